@@ -0,0 +1,149 @@
+//! APDU logging helpers, captures exchanged APDUs for export in the
+//! Ledger Live log format so traces can be shared with existing tooling.
+//!
+//! Entries are emitted as a JSON array of `{ "type": "apdu", "message": "=> e001000000", "date": "..." }`
+//! objects, with `=>` marking commands sent to the device and `<=` marking responses.
+//! Failed exchanges (such as transport errors or timeouts) are recorded as
+//! `{ "type": "error", "message": "...", "date": "..." }` entries following the command.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use hex::ToHex;
+use serde_json::json;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use ledger_lib::{Error, Exchange, LedgerInfo, Timeouts, Transport};
+
+/// Shared APDU log, records exchanges from all [LoggedExchange] handles
+#[derive(Clone, Debug, Default)]
+pub struct ApduLog {
+    entries: Arc<Mutex<Vec<serde_json::Value>>>,
+}
+
+/// APDU direction for log entries
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Direction {
+    /// Command sent to the device
+    Out,
+    /// Response received from the device
+    In,
+}
+
+impl ApduLog {
+    /// Append an APDU to the log
+    fn push(&self, dir: Direction, data: &[u8]) {
+        let prefix = match dir {
+            Direction::Out => "=>",
+            Direction::In => "<=",
+        };
+
+        self.append("apdu", format!("{prefix} {}", data.encode_hex::<String>()));
+    }
+
+    /// Append an exchange result to the log, recording errors so that
+    /// commands are always followed by a matching entry
+    fn push_result(&self, r: &Result<Vec<u8>, Error>) {
+        match r {
+            Ok(resp) => self.push(Direction::In, resp),
+            Err(e) => self.append("error", e.to_string()),
+        }
+    }
+
+    /// Append a timestamped entry to the log
+    fn append(&self, kind: &str, message: String) {
+        let date = OffsetDateTime::now_utc()
+            .format(&Rfc3339)
+            .unwrap_or_default();
+
+        let entry = json!({
+            "type": kind,
+            "message": message,
+            "date": date,
+        });
+
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// Write log entries to the specified file
+    pub fn write(&self, path: &str) -> anyhow::Result<()> {
+        let entries = self.entries.lock().unwrap();
+        let s = serde_json::to_string_pretty(&*entries)?;
+        std::fs::write(path, s)?;
+        Ok(())
+    }
+}
+
+/// [Transport] wrapper, returns [LoggedExchange] devices recording to a shared [ApduLog]
+pub struct LoggedTransport<T> {
+    inner: T,
+    log: ApduLog,
+}
+
+impl<T> LoggedTransport<T> {
+    /// Wrap a transport for APDU logging
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            log: ApduLog::default(),
+        }
+    }
+
+    /// Fetch the shared log for this transport
+    pub fn log(&self) -> &ApduLog {
+        &self.log
+    }
+}
+
+impl<T: Transport> Transport for LoggedTransport<T> {
+    type Filters = <T as Transport>::Filters;
+    type Info = <T as Transport>::Info;
+    type Device = LoggedExchange<<T as Transport>::Device>;
+
+    async fn list(&mut self, filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
+        self.inner.list(filters).await
+    }
+
+    async fn connect(&mut self, info: Self::Info) -> Result<Self::Device, Error> {
+        let inner = self.inner.connect(info).await?;
+
+        Ok(LoggedExchange {
+            inner,
+            log: self.log.clone(),
+        })
+    }
+}
+
+/// [Exchange] wrapper recording commands and responses to an [ApduLog]
+pub struct LoggedExchange<E> {
+    inner: E,
+    log: ApduLog,
+}
+
+impl<E: Exchange> Exchange for LoggedExchange<E> {
+    async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        self.log.push(Direction::Out, command);
+
+        let resp = self.inner.exchange(command, timeout).await;
+
+        self.log.push_result(&resp);
+
+        resp
+    }
+
+    async fn exchange_timeouts(
+        &mut self,
+        command: &[u8],
+        timeouts: Timeouts,
+    ) -> Result<Vec<u8>, Error> {
+        self.log.push(Direction::Out, command);
+
+        let resp = self.inner.exchange_timeouts(command, timeouts).await;
+
+        self.log.push_result(&resp);
+
+        resp
+    }
+}
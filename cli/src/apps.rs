@@ -0,0 +1,37 @@
+//! Application management helpers, providing a manager-like view of
+//! installed applications using the BOLOS dashboard APDUs.
+
+use hex::ToHex;
+
+use ledger_lib::info::InstalledApp;
+use ledger_proto::apdus::MemoryInfoResp;
+
+/// Fetch table columns for an installed application
+pub fn columns(a: &InstalledApp) -> Vec<String> {
+    vec![
+        a.name.clone(),
+        format!("0x{:04x}", a.flags),
+        a.hash.encode_hex::<String>(),
+    ]
+}
+
+/// Fetch table rows (used, free and total) for device flash and application slots
+pub fn storage(m: &MemoryInfoResp) -> Vec<Vec<String>> {
+    let used = m.system_size as u64 + m.apps_size as u64;
+    let slots_free = m.total_app_slots.saturating_sub(m.used_app_slots);
+
+    vec![
+        vec![
+            "flash (bytes)".to_string(),
+            used.to_string(),
+            m.free_size.to_string(),
+            (used + m.free_size as u64).to_string(),
+        ],
+        vec![
+            "app slots".to_string(),
+            m.used_app_slots.to_string(),
+            slots_free.to_string(),
+            m.total_app_slots.to_string(),
+        ],
+    ]
+}
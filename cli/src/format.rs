@@ -0,0 +1,73 @@
+//! Output formatting helpers for tabular command output
+
+/// Tabular output format
+#[derive(Copy, Clone, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    /// Aligned table for human consumption
+    #[default]
+    Table,
+    /// Comma separated values for spreadsheets and scripts
+    Csv,
+}
+
+impl Format {
+    /// Print rows with the provided headers using the selected format
+    pub fn print(&self, headers: &[&str], rows: &[Vec<String>]) {
+        match self {
+            Format::Table => print!("{}", table(headers, rows)),
+            Format::Csv => print!("{}", csv(headers, rows)),
+        }
+    }
+}
+
+/// Render rows as an aligned table
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    // Compute column widths
+    let mut widths: Vec<_> = headers.iter().map(|h| h.len()).collect();
+    for r in rows {
+        for (w, v) in widths.iter_mut().zip(r.iter()) {
+            *w = (*w).max(v.len());
+        }
+    }
+
+    // Pad and join cells for each line
+    let line = |cells: Vec<String>| {
+        let padded: Vec<_> = cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(v, w)| format!("{v:w$}"))
+            .collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+
+    let mut s = line(headers.iter().map(|h| h.to_uppercase()).collect());
+    for r in rows {
+        s.push_str(&line(r.clone()));
+    }
+
+    s
+}
+
+/// Render rows as CSV
+fn csv(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let line = |cells: Vec<String>| {
+        let escaped: Vec<_> = cells.iter().map(|v| csv_escape(v)).collect();
+        format!("{}\n", escaped.join(","))
+    };
+
+    let mut s = line(headers.iter().map(|h| h.to_string()).collect());
+    for r in rows {
+        s.push_str(&line(r.clone()));
+    }
+
+    s
+}
+
+/// Escape CSV values containing separators or quotes
+fn csv_escape(v: &str) -> String {
+    if v.contains([',', '"', '\n']) {
+        format!("\"{}\"", v.replace('"', "\"\""))
+    } else {
+        v.to_string()
+    }
+}
@@ -2,17 +2,37 @@
 //!
 //! See [ledger_lib] for APIs used in this application.
 
-use std::str::FromStr;
+use std::{
+    net::SocketAddr,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
-use clap::Parser;
+use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
 use hex::ToHex;
 use tracing::{debug, error};
 use tracing_subscriber::{filter::LevelFilter, EnvFilter, FmtSubscriber};
 
 use ledger_lib::{
-    launch_app, Device, Error, Filters, LedgerHandle, LedgerInfo, LedgerProvider, Transport,
+    launch_app, server::ApduServer, transport::TransportFilters, Device, Error, Filters,
+    LedgerInfo, LedgerProvider, Timeouts, Transport,
+};
+use ledger_proto::{
+    apdus::{ExitAppReq, GetMemoryInfoReq, MemoryInfoResp, WalletIdReq, WalletIdResp},
+    GenericApdu, StatusCode,
 };
-use ledger_proto::{ApduHeader, GenericApdu, StatusCode};
+
+mod apdu_log;
+use apdu_log::LoggedTransport;
+
+mod select;
+use select::select_device;
+
+mod format;
+use format::Format;
+
+mod apps;
 
 /// Ledger Hardware Wallet Command Line Interface
 #[derive(Clone, Debug, PartialEq, Parser)]
@@ -21,31 +41,58 @@ pub struct Args {
     cmd: Command,
 
     /// Device index where multiple devices are available
-    #[clap(long, default_value = "0")]
-    index: usize,
+    /// (prompts for selection if unset and multiple devices are found)
+    #[clap(long)]
+    index: Option<usize>,
+
+    /// Disable interactive prompts, using the first device where multiple are found
+    #[clap(long)]
+    non_interactive: bool,
 
     /// Filters for use when connecting to devices
     #[clap(long, default_value = "any")]
     filters: Filters,
 
+    #[clap(flatten)]
+    transport_filters: TransportFilters,
+
     /// Timeout for device requests
     #[clap(long, default_value = "3s")]
     timeout: humantime::Duration,
 
+    /// Wait for the device to be unlocked prior to executing commands,
+    /// with an optional timeout (defaults to 5m)
+    #[clap(long, num_args = 0..=1, default_missing_value = "5m")]
+    wait_unlock: Option<humantime::Duration>,
+
     /// Enable verbose logging
     #[clap(long, default_value = "debug")]
     log_level: LevelFilter,
+
+    /// Write exchanged APDUs to the specified file (Ledger Live log format)
+    #[clap(long)]
+    apdu_log: Option<String>,
 }
 
 /// CLI subcommands
 #[derive(Clone, Debug, PartialEq, Parser)]
 pub enum Command {
     /// List available ledger devices
-    List,
+    List {
+        /// Output format
+        #[clap(long, value_enum, default_value_t)]
+        format: Format,
+
+        /// Skip connecting to each device to report state (running application or lock state)
+        #[clap(long)]
+        no_state: bool,
+    },
     /// Fetch application info
     AppInfo,
     /// Fetch device info
     DeviceInfo,
+    /// Fetch the seed-derived wallet identifier
+    WalletId,
     /// Exchange a raw APDU with the device
     Apdu {
         /// APDU class
@@ -79,6 +126,50 @@ pub enum Command {
         #[clap(long)]
         app_name: String,
     },
+    /// Manage installed applications
+    Apps {
+        #[clap(subcommand)]
+        cmd: AppsCommand,
+    },
+    /// Expose the device over the Speculos TCP APDU protocol
+    Serve {
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:1237")]
+        addr: SocketAddr,
+    },
+    /// Generate shell completions
+    Completions {
+        /// Shell to generate completions for
+        #[clap(value_enum)]
+        shell: Shell,
+    },
+    /// Generate man page
+    #[clap(hide = true)]
+    Man,
+}
+
+/// Application subcommands
+#[derive(Clone, Debug, PartialEq, Parser)]
+pub enum AppsCommand {
+    /// List installed applications
+    List {
+        /// Output format
+        #[clap(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Show device storage (flash and app slots) and storage used by installed applications
+    Storage {
+        /// Output format
+        #[clap(long, value_enum, default_value_t)]
+        format: Format,
+    },
+    /// Open an application by name
+    Open {
+        /// Application name
+        name: String,
+    },
+    /// Quit the running application
+    Quit,
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
@@ -118,64 +209,160 @@ async fn main() -> anyhow::Result<()> {
         .without_time()
         .with_max_level(args.log_level)
         .with_env_filter(filter)
+        .with_writer(std::io::stderr)
         .try_init();
 
     debug!("args: {:?}", args);
 
+    // Handle commands not requiring device access
+    match &args.cmd {
+        Command::Completions { shell } => {
+            let mut cmd = Args::command();
+            let name = cmd.get_name().to_string();
+            clap_complete::generate(*shell, &mut cmd, name, &mut std::io::stdout());
+            return Ok(());
+        }
+        Command::Man => {
+            clap_mangen::Man::new(Args::command()).render(&mut std::io::stdout())?;
+            return Ok(());
+        }
+        _ => (),
+    }
+
     // Initialise provider
-    let mut p = LedgerProvider::init().await;
+    let mut provider = LedgerProvider::init()
+        .await
+        .with_filters(args.transport_filters.clone());
+
+    // Execute command, wrapping the provider to capture exchanged APDUs only where
+    // an APDU log is requested
+    let Some(path) = args.apdu_log.clone() else {
+        return execute(args, &mut provider).await;
+    };
+
+    let mut p = LoggedTransport::new(provider);
+    let res = execute(args, &mut p).await;
+
+    // Write APDU log (including on command failure)
+    p.log().write(&path)?;
 
+    res
+}
+
+/// Execute a device command
+async fn execute<T>(args: Args, p: &mut T) -> anyhow::Result<()>
+where
+    T: Transport<Info = LedgerInfo, Filters = Filters>,
+{
     // Fetch list of available devices
     let devices = p.list(args.filters).await?;
 
     // Handle commands
-    match args.cmd {
-        Command::List => {
-            println!("devices:");
+    match args.cmd.clone() {
+        Command::List { format, no_state } => {
+            let mut headers = vec!["index", "model", "connection", "path", "serial"];
+            if !no_state {
+                headers.push("state");
+            }
+
+            let mut rows = vec![];
             for (i, d) in devices.iter().enumerate() {
-                println!("  {i} {} ({})", d.model, d.conn);
+                let mut row = vec![
+                    i.to_string(),
+                    d.model.to_string(),
+                    d.kind().to_string(),
+                    select::path(d),
+                    select::serial(d).unwrap_or_default().to_string(),
+                ];
+                if !no_state {
+                    row.push(select::device_state(p, d, &args).await);
+                }
+                rows.push(row);
             }
+
+            format.print(&headers, &rows);
         }
         Command::AppInfo => {
-            let mut d = connect(&mut p, &devices, args.index).await?;
+            let mut d = connect(p, &devices, &args).await?;
             let i = d.app_info(args.timeout.into()).await?;
 
             println!("app info: {:?}", i);
         }
         Command::DeviceInfo => {
-            let mut d = connect(&mut p, &devices, args.index).await?;
+            let mut d = connect(p, &devices, &args).await?;
             let i = d.device_info(args.timeout.into()).await?;
 
             println!("device info: {:?}", i);
         }
-        Command::Run { app_name } => {
-            // Check we have at least one device
-            if devices.is_empty() {
-                return Err(anyhow::Error::from(Error::NoDevices));
-            }
-
-            // Check we have a device matching the index specified
-            if args.index > devices.len() {
-                return Err(anyhow::Error::from(Error::InvalidDeviceIndex(args.index)));
-            }
+        Command::WalletId => {
+            let mut d = connect(p, &devices, &args).await?;
 
-            let info = devices[args.index].clone();
+            let mut buff = [0u8; 256];
+            let r = d
+                .request::<WalletIdResp>(WalletIdReq {}, &mut buff, *args.timeout)
+                .await?;
 
-            println!("launch app: {app_name}");
+            println!("wallet id: {}", r.id.encode_hex::<String>());
+        }
+        Command::Run { app_name } => run_app(p, &devices, &args, &app_name).await?,
+        Command::Apps { cmd } => match cmd {
+            AppsCommand::List { format } => {
+                let mut d = connect(p, &devices, &args).await?;
+                let apps = d.list_apps(args.timeout.into()).await?;
+
+                let rows: Vec<_> = apps.iter().map(apps::columns).collect();
+                format.print(&["name", "flags", "hash"], &rows);
+            }
+            AppsCommand::Storage { format } => {
+                let mut d = connect(p, &devices, &args).await?;
+                let apps = d.list_apps(args.timeout.into()).await?;
+
+                let mut rows: Vec<_> = apps
+                    .iter()
+                    .map(|a| vec![a.name.clone(), a.blocks.to_string()])
+                    .collect();
+
+                let total: u32 = apps.iter().map(|a| a.blocks as u32).sum();
+                rows.push(vec!["total".to_string(), total.to_string()]);
+
+                let mut buff = [0u8; 256];
+                let memory = d
+                    .request::<MemoryInfoResp>(GetMemoryInfoReq::new(), &mut buff, *args.timeout)
+                    .await?;
+
+                format.print(&["name", "blocks"], &rows);
+                println!();
+                format.print(
+                    &["storage", "used", "free", "total"],
+                    &apps::storage(&memory),
+                );
+            }
+            AppsCommand::Open { name } => run_app(p, &devices, &args, &name).await?,
+            AppsCommand::Quit => {
+                let mut d = connect(p, &devices, &args).await?;
+
+                let mut buff = [0u8; 256];
+                match d
+                    .request::<GenericApdu>(ExitAppReq::new(), &mut buff, *args.timeout)
+                    .await
+                {
+                    Ok(_) | Err(Error::Status(StatusCode::Ok)) => println!("app exited"),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        },
+        Command::Serve { addr } => {
+            let d = connect(p, &devices, &args).await?;
 
-            let mut d = launch_app(
-                &mut p,
-                info,
-                &app_name,
-                &Default::default(),
-                args.timeout.into(),
-            )
-            .await?;
+            let server = ApduServer::bind(addr, d)
+                .await?
+                .with_timeouts(Timeouts::new(*args.timeout).wait_for_user());
 
-            let i = d.app_info(args.timeout.into()).await?;
+            println!("Serving APDUs on {}", server.local_addr()?);
 
-            println!("running app: {i:?}");
+            server.run().await?;
         }
+        Command::Completions { .. } | Command::Man => unreachable!(),
         Command::Apdu {
             cla,
             ins,
@@ -183,16 +370,19 @@ async fn main() -> anyhow::Result<()> {
             p2,
             data,
         } => {
-            let req = GenericApdu {
-                header: ApduHeader { cla, ins, p1, p2 },
-                data: data.0,
-            };
+            let req = GenericApdu::build()
+                .cla(cla)
+                .ins(ins)
+                .p1(p1)
+                .p2(p2)
+                .data(data.0)
+                .finish();
 
-            let mut d = connect(&mut p, &devices, args.index).await?;
+            let mut d = connect(p, &devices, &args).await?;
 
             let mut buff = [0u8; 256];
             let resp = d
-                .request::<GenericApdu>(req, &mut buff, args.timeout.into())
+                .request::<GenericApdu>(req, &mut buff, *args.timeout)
                 .await?;
 
             println!("Response: {}", resp.data.encode_hex::<String>());
@@ -203,13 +393,13 @@ async fn main() -> anyhow::Result<()> {
             let apdu_seq: Vec<GenericApdu> = serde_json::from_str(data.as_str())?;
 
             // Connect to device
-            let mut d = connect(&mut p, &devices, args.index).await?;
+            let mut d = connect(p, &devices, &args).await?;
             let mut buff = [0u8; 256];
 
             // Execute APDU sequence
             for apdu_input in apdu_seq {
                 let resp = d
-                    .request::<GenericApdu>(apdu_input, &mut buff, args.timeout.into())
+                    .request::<GenericApdu>(apdu_input, &mut buff, *args.timeout)
                     .await;
 
                 match resp {
@@ -225,31 +415,92 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Connect to a device with the provided index
-async fn connect(
-    p: &mut LedgerProvider,
+/// Launch an application on the selected device
+async fn run_app<T>(
+    p: &mut T,
     devices: &[LedgerInfo],
-    index: usize,
-) -> Result<LedgerHandle, Error> {
-    // Check we have at least one device
-    if devices.is_empty() {
-        return Err(Error::NoDevices);
+    args: &Args,
+    app_name: &str,
+) -> anyhow::Result<()>
+where
+    T: Transport<Info = LedgerInfo, Filters = Filters>,
+{
+    let info = select_device(p, devices, args).await?;
+
+    // Wait for unlock prior to launching app if enabled
+    if let Some(t) = args.wait_unlock {
+        let mut d = p.connect(info.clone()).await?;
+        wait_unlock(&mut d, t.into(), args.timeout.into()).await?;
     }
 
-    // Check we have a device matching the index specified
-    if index > devices.len() {
-        return Err(Error::InvalidDeviceIndex(index));
-    }
+    println!("launch app: {app_name}");
+
+    let mut d = launch_app(
+        &mut *p,
+        info,
+        app_name,
+        &Default::default(),
+        args.timeout.into(),
+    )
+    .await?;
+
+    let i = d.app_info(args.timeout.into()).await?;
+
+    println!("running app: {i:?}");
 
-    let d = &devices[index];
+    Ok(())
+}
+
+/// Select and connect to a device
+async fn connect<T>(
+    p: &mut T,
+    devices: &[LedgerInfo],
+    args: &Args,
+) -> anyhow::Result<<T as Transport>::Device>
+where
+    T: Transport<Info = LedgerInfo>,
+{
+    let d = &select_device(p, devices, args).await?;
     debug!("Connecting to device: {:?}", d);
 
-    // Connect to the device using the index offset
-    match p.connect(d.clone()).await {
-        Ok(v) => Ok(v),
+    // Connect to the selected device
+    let mut h = match p.connect(d.clone()).await {
+        Ok(v) => v,
         Err(e) => {
             error!("Failed to connect to device {:?}: {:?}", d, e);
-            Err(e)
+            return Err(e.into());
+        }
+    };
+
+    // Wait for unlock if enabled
+    if let Some(t) = args.wait_unlock {
+        wait_unlock(&mut h, t.into(), args.timeout.into()).await?;
+    }
+
+    Ok(h)
+}
+
+/// Poll a device until it is unlocked or the provided timeout elapses
+async fn wait_unlock(d: &mut impl Device, wait: Duration, timeout: Duration) -> anyhow::Result<()> {
+    let start = Instant::now();
+    let mut hinted = false;
+
+    loop {
+        match d.app_info(timeout).await {
+            Err(Error::Status(StatusCode::LockedDevice)) => (),
+            Ok(_) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+
+        if !hinted {
+            eprintln!("Device locked, please enter your PIN to continue...");
+            hinted = true;
+        }
+
+        if start.elapsed() > wait {
+            return Err(anyhow::anyhow!("Timeout waiting for device unlock"));
         }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
     }
 }
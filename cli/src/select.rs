@@ -0,0 +1,126 @@
+//! Device selection helpers, prompting the user where multiple devices are available
+//! to avoid silently operating on the wrong device.
+
+use std::io::{BufRead, IsTerminal, Write};
+
+use tracing::{debug, warn};
+
+use ledger_lib::{info::ConnInfo, Device, Error, LedgerInfo, Transport};
+use ledger_proto::StatusCode;
+
+use crate::Args;
+
+/// Select a device from the available list.
+///
+/// Uses `--index` where provided, otherwise prompts the user to select from
+/// the available devices unless `--non-interactive` is set or stdin is not a terminal.
+pub async fn select_device<T>(
+    p: &mut T,
+    devices: &[LedgerInfo],
+    args: &Args,
+) -> anyhow::Result<LedgerInfo>
+where
+    T: Transport<Info = LedgerInfo>,
+{
+    // Check we have at least one device
+    if devices.is_empty() {
+        return Err(Error::NoDevices.into());
+    }
+
+    // Use the specified index if provided
+    if let Some(index) = args.index {
+        return match devices.get(index) {
+            Some(d) => Ok(d.clone()),
+            None => Err(Error::InvalidDeviceIndex(index).into()),
+        };
+    }
+
+    // Use the only device where available
+    if devices.len() == 1 {
+        return Ok(devices[0].clone());
+    }
+
+    // Fall back to the first device where we can't prompt
+    if args.non_interactive || !std::io::stdin().is_terminal() {
+        warn!(
+            "{} devices found, using the first (set --index to select)",
+            devices.len()
+        );
+        return Ok(devices[0].clone());
+    }
+
+    // Otherwise list devices and prompt for selection
+    eprintln!("Multiple devices found:");
+    for (i, d) in devices.iter().enumerate() {
+        let state = device_state(p, d, args).await;
+
+        eprintln!(
+            "  {i}: {} ({}) serial: {} state: {state}",
+            d.model,
+            d.conn,
+            serial(d).unwrap_or("-"),
+        );
+    }
+
+    loop {
+        eprint!("Select device [0-{}]: ", devices.len() - 1);
+        std::io::stderr().flush()?;
+
+        let mut line = String::new();
+        if std::io::stdin().lock().read_line(&mut line)? == 0 {
+            return Err(anyhow::anyhow!("No device selected"));
+        }
+
+        match line
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| devices.get(i))
+        {
+            Some(d) => return Ok(d.clone()),
+            None => eprintln!("Invalid selection: {}", line.trim()),
+        }
+    }
+}
+
+/// Fetch the serial number for a device where reported
+pub fn serial(info: &LedgerInfo) -> Option<&str> {
+    match &info.conn {
+        ConnInfo::Usb(i) => i.serial.as_deref(),
+        _ => None,
+    }
+}
+
+/// Fetch the path or address for a device
+pub fn path(info: &LedgerInfo) -> String {
+    match &info.conn {
+        ConnInfo::Usb(i) => i.path.clone().unwrap_or_default(),
+        ConnInfo::Tcp(i) => i.addr.to_string(),
+        ConnInfo::Ble(i) => i.addr.to_string(),
+        #[allow(unreachable_patterns)]
+        _ => info.conn.to_string(),
+    }
+}
+
+/// Fetch the device state (running application or lock state, best effort)
+pub async fn device_state<T>(p: &mut T, info: &LedgerInfo, args: &Args) -> String
+where
+    T: Transport<Info = LedgerInfo>,
+{
+    let mut d = match p.connect(info.clone()).await {
+        Ok(d) => d,
+        Err(e) => {
+            debug!("Failed to connect to {info}: {e:?}");
+            return "unavailable".to_string();
+        }
+    };
+
+    match d.app_info(args.timeout.into()).await {
+        Ok(i) => i.name,
+        Err(Error::Status(StatusCode::LockedDevice)) => "locked".to_string(),
+        Err(e) => {
+            debug!("Failed to fetch app info for {info}: {e:?}");
+            "unknown".to_string()
+        }
+    }
+}
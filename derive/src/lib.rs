@@ -0,0 +1,173 @@
+//! Derive macros for Ledger APDU definitions, re-exported by `ledger-proto`
+//! with the `derive` feature.
+//!
+//! `#[derive(ApduStatic)]` implements `ledger_proto::ApduStatic` using the
+//! `#[apdu(...)]` attribute, where `cla` and `ins` are required and `p1` / `p2`
+//! are optional expressions evaluated against `self` (defaulting to `0`).
+//! `le` optionally sets the expected response length (`Option<usize>`, defaulting to `None`).
+//!
+//! ```
+//! use ledger_proto::ApduStatic;
+//!
+//! #[derive(ApduStatic)]
+//! #[apdu(cla = 0xe0, ins = 0x02, p1 = self.confirm as u8)]
+//! pub struct GetAddressReq {
+//!     confirm: bool,
+//! }
+//!
+//! let r = GetAddressReq { confirm: true };
+//! assert_eq!((GetAddressReq::CLA, GetAddressReq::INS), (0xe0, 0x02));
+//! assert_eq!((r.p1(), r.p2()), (0x01, 0x00));
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Attribute, DeriveInput, Expr};
+
+/// Derive `ApduStatic` from `#[apdu(cla = .., ins = .., p1 = .., p2 = .., le = ..)]` attributes
+#[proc_macro_derive(ApduStatic, attributes(apdu))]
+pub fn derive_apdu_static(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_apdu_static(input) {
+        Ok(t) => t.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Parsed `#[apdu(...)]` attributes
+struct ApduAttrs {
+    cla: Expr,
+    ins: Expr,
+    p1: Option<Expr>,
+    p2: Option<Expr>,
+    le: Option<Expr>,
+}
+
+impl ApduAttrs {
+    /// Parse `#[apdu(...)]` attributes, requiring `cla` and `ins`
+    fn parse(ident: &syn::Ident, attrs: &[Attribute]) -> syn::Result<Self> {
+        let (mut cla, mut ins, mut p1, mut p2, mut le) = (None, None, None, None, None);
+
+        for a in attrs.iter().filter(|a| a.path().is_ident("apdu")) {
+            a.parse_nested_meta(|meta| {
+                let v = match meta.path.get_ident().map(|i| i.to_string()).as_deref() {
+                    Some("cla") => &mut cla,
+                    Some("ins") => &mut ins,
+                    Some("p1") => &mut p1,
+                    Some("p2") => &mut p2,
+                    Some("le") => &mut le,
+                    _ => return Err(meta.error("expected one of `cla`, `ins`, `p1`, `p2`, `le`")),
+                };
+
+                if v.is_some() {
+                    return Err(meta.error("duplicate apdu attribute"));
+                }
+                *v = Some(meta.value()?.parse::<Expr>()?);
+
+                Ok(())
+            })?;
+        }
+
+        let missing =
+            |f: &str| syn::Error::new(ident.span(), format!("missing `#[apdu({f} = ..)]`"));
+
+        Ok(Self {
+            cla: cla.ok_or_else(|| missing("cla"))?,
+            ins: ins.ok_or_else(|| missing("ins"))?,
+            p1,
+            p2,
+            le,
+        })
+    }
+}
+
+/// Expand `ApduStatic` implementation
+fn expand_apdu_static(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let ApduAttrs {
+        cla,
+        ins,
+        p1,
+        p2,
+        le,
+    } = ApduAttrs::parse(ident, &input.attrs)?;
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let p1 = p1.map(|v| {
+        quote! {
+            fn p1(&self) -> u8 {
+                #v
+            }
+        }
+    });
+    let p2 = p2.map(|v| {
+        quote! {
+            fn p2(&self) -> u8 {
+                #v
+            }
+        }
+    });
+
+    let le = le.map(|v| {
+        quote! {
+            fn le(&self) -> Option<usize> {
+                #v
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::ledger_proto::ApduStatic for #ident #ty_generics #where_clause {
+            const CLA: u8 = #cla;
+            const INS: u8 = #ins;
+
+            #p1
+            #p2
+            #le
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_attrs() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[apdu(cla = 0xe0, ins = 0x10, p2 = self.kind as u8, le = Some(32))]
+            struct Req<'a> { kind: Kind, data: &'a [u8] }
+        };
+
+        let t = expand_apdu_static(input).unwrap().to_string();
+        assert!(t.contains("const INS : u8 = 0x10"));
+        assert!(t.contains("fn p2"));
+        assert!(!t.contains("fn p1"));
+        assert!(t.contains("fn le"));
+        assert!(t.contains("impl < 'a >"));
+    }
+
+    #[test]
+    fn parse_attr_errors() {
+        let input: DeriveInput = syn::parse_quote! {
+            #[apdu(cla = 0xe0)]
+            struct Req {}
+        };
+        assert!(expand_apdu_static(input).is_err());
+
+        let input: DeriveInput = syn::parse_quote! {
+            #[apdu(cla = 0xe0, ins = 0x01, ins = 0x02)]
+            struct Req {}
+        };
+        assert!(expand_apdu_static(input).is_err());
+
+        let input: DeriveInput = syn::parse_quote! {
+            #[apdu(cla = 0xe0, ins = 0x01, p3 = 0x00)]
+            struct Req {}
+        };
+        assert!(expand_apdu_static(input).is_err());
+    }
+}
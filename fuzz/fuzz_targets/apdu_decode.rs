@@ -0,0 +1,18 @@
+//! Fuzz shared APDU decoders with device-supplied data
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use ledger_proto::{
+    apdus::{AppInfoResp, DeviceInfoResp, RunAppReq, WalletIdResp},
+    ApduHeader, Decode, DecodeOwned, GenericApdu,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ApduHeader::decode(data);
+    let _ = GenericApdu::decode_owned(data);
+    let _ = AppInfoResp::decode(data);
+    let _ = DeviceInfoResp::decode(data);
+    let _ = WalletIdResp::decode(data);
+    let _ = RunAppReq::decode(data);
+});
@@ -0,0 +1,113 @@
+//! Fuzz shared APDU encode / decode round-trips with structurally valid objects
+#![no_main]
+
+use core::fmt::Debug;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+use ledger_proto::{
+    apdus::{
+        AppData, AppInfoReq, AppInfoResp, AppListResp, CommitAppReq, CommitEndorsementReq,
+        CreateAppReq, DeleteAppByHashReq, DeleteAppReq, DeviceCert, DeviceInfoReq, DeviceInfoResp,
+        EndorsementKeyResp, ExitAppReq, FlushReq, GetBatteryStatusResp, GetMemoryInfoReq,
+        InitAuthReq, InitAuthResp, LoadSegmentReq, McuResetReq, McuValidateReq, McuVersionReq,
+        McuVersionResp, MemoryInfoResp, RunAppReq, SelectSegmentReq, Tlv, ValidateTargetIdReq,
+        WalletIdReq, WalletIdResp,
+    },
+    ApduError, ApduHeader, Decode, Encode,
+};
+
+/// Shared APDU objects for round-trip checks, excluding requests with header-carried
+/// parameters (eg. `AppListReq`) as these are not encoded in the APDU body
+#[derive(Debug, Arbitrary)]
+enum Apdu<'a> {
+    Header(ApduHeader),
+    AppInfoReq(AppInfoReq),
+    AppInfoResp(AppInfoResp<'a>),
+    DeviceInfoReq(DeviceInfoReq),
+    DeviceInfoResp(DeviceInfoResp<'a>),
+    AppData(AppData<'a>),
+    AppListResp(AppListResp<'a>),
+    RunAppReq(RunAppReq<'a>),
+    ExitAppReq(ExitAppReq),
+    WalletIdReq(WalletIdReq),
+    WalletIdResp(WalletIdResp<'a>),
+    GetBatteryStatusResp(GetBatteryStatusResp<'a>),
+    ValidateTargetIdReq(ValidateTargetIdReq),
+    InitAuthReq(InitAuthReq),
+    InitAuthResp(InitAuthResp),
+    DeviceCert(DeviceCert<'a>),
+    EndorsementKeyResp(EndorsementKeyResp<'a>),
+    CommitEndorsementReq(CommitEndorsementReq<'a>),
+    GetMemoryInfoReq(GetMemoryInfoReq),
+    MemoryInfoResp(MemoryInfoResp),
+    McuVersionReq(McuVersionReq),
+    McuVersionResp(McuVersionResp<'a>),
+    McuValidateReq(McuValidateReq),
+    McuResetReq(McuResetReq),
+    CreateAppReq(CreateAppReq),
+    SelectSegmentReq(SelectSegmentReq),
+    LoadSegmentReq(LoadSegmentReq<'a>),
+    FlushReq(FlushReq),
+    CommitAppReq(CommitAppReq<'a>),
+    DeleteAppReq(DeleteAppReq<'a>),
+    DeleteAppByHashReq(DeleteAppByHashReq),
+    Tlv(Tlv<'a>),
+}
+
+/// Encode an object then check decoding returns the same object and length
+fn round_trip<'a, T>(v: &T, buff: &'a mut [u8])
+where
+    T: Encode<Error = ApduError> + Decode<'a, Output = T, Error = ApduError> + PartialEq + Debug,
+{
+    // Objects that can not be encoded (eg. oversized fields) are skipped
+    let n = match v.encode(buff) {
+        Ok(n) => n,
+        Err(_) => return,
+    };
+
+    let (d, m) = T::decode(&buff[..n]).expect("decode failed");
+    assert_eq!(&d, v);
+    assert_eq!(m, n);
+}
+
+fuzz_target!(|apdu: Apdu<'_>| {
+    let mut buff = [0u8; 1024];
+    let b = &mut buff[..];
+
+    match &apdu {
+        Apdu::Header(v) => round_trip(v, b),
+        Apdu::AppInfoReq(v) => round_trip(v, b),
+        Apdu::AppInfoResp(v) => round_trip(v, b),
+        Apdu::DeviceInfoReq(v) => round_trip(v, b),
+        Apdu::DeviceInfoResp(v) => round_trip(v, b),
+        Apdu::AppData(v) => round_trip(v, b),
+        Apdu::AppListResp(v) => round_trip(v, b),
+        Apdu::RunAppReq(v) => round_trip(v, b),
+        Apdu::ExitAppReq(v) => round_trip(v, b),
+        Apdu::WalletIdReq(v) => round_trip(v, b),
+        Apdu::WalletIdResp(v) => round_trip(v, b),
+        Apdu::GetBatteryStatusResp(v) => round_trip(v, b),
+        Apdu::ValidateTargetIdReq(v) => round_trip(v, b),
+        Apdu::InitAuthReq(v) => round_trip(v, b),
+        Apdu::InitAuthResp(v) => round_trip(v, b),
+        Apdu::DeviceCert(v) => round_trip(v, b),
+        Apdu::EndorsementKeyResp(v) => round_trip(v, b),
+        Apdu::CommitEndorsementReq(v) => round_trip(v, b),
+        Apdu::GetMemoryInfoReq(v) => round_trip(v, b),
+        Apdu::MemoryInfoResp(v) => round_trip(v, b),
+        Apdu::McuVersionReq(v) => round_trip(v, b),
+        Apdu::McuVersionResp(v) => round_trip(v, b),
+        Apdu::McuValidateReq(v) => round_trip(v, b),
+        Apdu::McuResetReq(v) => round_trip(v, b),
+        Apdu::CreateAppReq(v) => round_trip(v, b),
+        Apdu::SelectSegmentReq(v) => round_trip(v, b),
+        Apdu::LoadSegmentReq(v) => round_trip(v, b),
+        Apdu::FlushReq(v) => round_trip(v, b),
+        Apdu::CommitAppReq(v) => round_trip(v, b),
+        Apdu::DeleteAppReq(v) => round_trip(v, b),
+        Apdu::DeleteAppByHashReq(v) => round_trip(v, b),
+        Apdu::Tlv(v) => round_trip(v, b),
+    }
+});
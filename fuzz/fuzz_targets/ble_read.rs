@@ -0,0 +1,19 @@
+//! Fuzz BLE response reassembly using length-prefixed notifications from the input
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use ledger_lib::fuzzing::ble_read_apdu;
+
+fuzz_target!(|data: &[u8]| {
+    // Split input into notifications, each prefixed by a length byte
+    let mut notifications = vec![];
+    let mut d = data;
+    while let Some((&n, rest)) = d.split_first() {
+        let n = (n as usize).min(rest.len());
+        notifications.push(rest[..n].to_vec());
+        d = &rest[n..];
+    }
+
+    let _ = futures::executor::block_on(ble_read_apdu(futures::stream::iter(notifications)));
+});
@@ -0,0 +1,11 @@
+//! Fuzz TCP length-prefixed response parsing
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use ledger_lib::fuzzing::tcp_read_apdu;
+
+fuzz_target!(|data: &[u8]| {
+    let mut r = data;
+    let _ = futures::executor::block_on(tcp_read_apdu(&mut r));
+});
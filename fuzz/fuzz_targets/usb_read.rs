@@ -0,0 +1,26 @@
+//! Fuzz USB HID response reassembly using 64-byte packets from the input
+#![no_main]
+
+use std::time::Duration;
+
+use libfuzzer_sys::fuzz_target;
+
+use ledger_lib::{fuzzing::usb_read_apdu, Error};
+use ledger_proto::framing::hid::HID_DEFAULT_CHANNEL;
+
+fuzz_target!(|data: &[u8]| {
+    let mut packets = data.chunks(64);
+
+    let _ = usb_read_apdu(
+        |buff, _timeout_ms| match packets.next() {
+            Some(p) => {
+                let n = p.len().min(buff.len());
+                buff[..n].copy_from_slice(&p[..n]);
+                Ok(n)
+            }
+            None => Err(Error::Timeout),
+        },
+        HID_DEFAULT_CHANNEL,
+        Duration::from_millis(100),
+    );
+});
@@ -0,0 +1,58 @@
+//! Benchmarks for request encoding and transport framing,
+//! requires the `fuzzing` feature to expose internal helpers.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use ledger_lib::fuzzing::encode_request;
+use ledger_proto::{
+    apdus::{AppInfoReq, RunAppReq},
+    framing::{ble::BleEncoder, hid::HidEncoder},
+};
+
+/// APDU payload lengths for framing benchmarks
+const LENGTHS: &[usize] = &[16, 128, 255];
+
+/// Helper to build an APDU for framing
+fn apdu(len: usize) -> Vec<u8> {
+    vec![0xaa; len]
+}
+
+/// Helper to consume chunks without these being optimised out
+fn drop_chunk(c: Vec<u8>) {
+    black_box(c);
+}
+
+fn request(c: &mut Criterion) {
+    let mut buff = [0u8; 256];
+
+    c.bench_function("encode_request AppInfoReq", |b| {
+        b.iter(|| encode_request(AppInfoReq {}, black_box(&mut buff)).unwrap())
+    });
+
+    c.bench_function("encode_request RunAppReq", |b| {
+        b.iter(|| {
+            encode_request(RunAppReq::new(black_box("Ethereum")), black_box(&mut buff)).unwrap()
+        })
+    });
+}
+
+fn chunking(c: &mut Criterion) {
+    let mut g = c.benchmark_group("chunking");
+
+    for len in LENGTHS {
+        let data = apdu(*len);
+
+        g.bench_with_input(BenchmarkId::new("usb", len), &data, |b, d| {
+            b.iter(|| HidEncoder::new(black_box(d)).for_each(drop_chunk))
+        });
+
+        g.bench_with_input(BenchmarkId::new("ble", len), &data, |b, d| {
+            b.iter(|| BleEncoder::new(0x05, black_box(d), 153).for_each(drop_chunk))
+        });
+    }
+
+    g.finish();
+}
+
+criterion_group!(benches, request, chunking);
+criterion_main!(benches);
@@ -0,0 +1,205 @@
+//! Synchronous (blocking) device interface, enabled with the `blocking_tcp` feature.
+//!
+//! This provides a std-only TCP client for Speculos APDU sockets without any async runtime,
+//! intended for small test utilities. Build with `default-features = false` and
+//! `features = [ "blocking_tcp" ]` to avoid pulling in `hidapi`, `btleplug` and `tokio`.
+//!
+//! ```no_run
+//! use ledger_lib::{blocking::{BlockingDevice, TcpDevice}, DEFAULT_TIMEOUT};
+//!
+//! let mut d = TcpDevice::connect("127.0.0.1:1237".parse().unwrap()).unwrap();
+//!
+//! let info = d.app_info(DEFAULT_TIMEOUT).unwrap();
+//! println!("info: {info:?}");
+//! ```
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    time::Duration,
+};
+
+use encdec::EncDec;
+use tracing::{debug, error};
+
+use ledger_proto::{
+    apdus::{AppInfoReq, AppInfoResp, DeviceInfoReq, DeviceInfoResp},
+    ApduError, ApduReq,
+};
+
+use crate::{
+    device::{app_info, decode_response, device_info, encode_request, APDU_BUFF_LEN},
+    info::{AppInfo, DeviceInfo},
+    wipe::Scratch,
+    Error,
+};
+
+/// Blocking equivalent of [Exchange](crate::Exchange) for byte-wise APDU exchange
+pub trait BlockingExchange {
+    fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error>;
+}
+
+/// Blocking equivalent of [Device](crate::Device), automatically implemented for [BlockingExchange] types
+pub trait BlockingDevice {
+    /// Issue a request APDU, returning a reponse APDU
+    fn request<'a, 'b, RESP: EncDec<'b, ApduError>>(
+        &mut self,
+        request: impl ApduReq<'a>,
+        buff: &'b mut [u8],
+        timeout: Duration,
+    ) -> Result<RESP, Error>;
+
+    /// Fetch application information
+    fn app_info(&mut self, timeout: Duration) -> Result<AppInfo, Error> {
+        let mut buff = [0u8; APDU_BUFF_LEN];
+
+        let r = self.request::<AppInfoResp>(AppInfoReq {}, &mut buff[..], timeout)?;
+
+        Ok(app_info(r))
+    }
+
+    /// Fetch device information
+    fn device_info(&mut self, timeout: Duration) -> Result<DeviceInfo, Error> {
+        let mut buff = [0u8; APDU_BUFF_LEN];
+
+        let r = self.request::<DeviceInfoResp>(DeviceInfoReq {}, &mut buff[..], timeout)?;
+
+        Ok(device_info(r))
+    }
+}
+
+/// Generic [BlockingDevice] implementation for types supporting [BlockingExchange]
+impl<T: BlockingExchange> BlockingDevice for T {
+    fn request<'a, 'b, RESP: EncDec<'b, ApduError>>(
+        &mut self,
+        req: impl ApduReq<'a>,
+        buff: &'b mut [u8],
+        timeout: Duration,
+    ) -> Result<RESP, Error> {
+        debug!("TX: {req:?}");
+
+        // Encode request
+        let n = encode_request(req, buff)?;
+
+        // Send request to device
+        let resp_bytes = Scratch::new(self.exchange(&buff[..n], timeout)?);
+
+        // Decode response
+        let resp = decode_response::<RESP>(&resp_bytes, buff)?;
+
+        debug!("RX: {resp:?}");
+
+        Ok(resp)
+    }
+}
+
+/// Maximum TCP response length (extended APDU data + status)
+const TCP_MAX_RESP_LEN: usize = 65536 + 2;
+
+/// Blocking TCP device, for interacting with Speculos via the TCP APDU socket
+pub struct TcpDevice {
+    s: TcpStream,
+    pub addr: SocketAddr,
+}
+
+impl TcpDevice {
+    /// Connect to a Speculos APDU socket at the provided address
+    pub fn connect(addr: SocketAddr) -> Result<Self, Error> {
+        debug!("Connecting to: {addr}");
+
+        let s = TcpStream::connect(addr).map_err(|e| {
+            error!("TCP connection failed: {e:?}");
+            io_error(e)
+        })?;
+
+        Ok(Self { s, addr })
+    }
+}
+
+/// [BlockingExchange] implementation for the blocking TCP device
+impl BlockingExchange for TcpDevice {
+    fn exchange(&mut self, req: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        // Write length-prefixed APDU request
+        let mut buff = Scratch::new(Vec::with_capacity(4 + req.len()));
+        buff.extend_from_slice(&(req.len() as u32).to_be_bytes());
+        buff.extend_from_slice(req);
+
+        debug!("TX: {:02x?}", *buff);
+
+        self.s.write_all(&buff).map_err(io_error)?;
+
+        // Read response length (u32 big endian + 2 bytes for status)
+        self.s.set_read_timeout(Some(timeout)).map_err(io_error)?;
+
+        let mut len = [0u8; 4];
+        self.s.read_exact(&mut len).map_err(io_error)?;
+
+        let n = u32::from_be_bytes(len) as usize + 2;
+        if n > TCP_MAX_RESP_LEN {
+            error!("Invalid response APDU length: {n}");
+            return Err(Error::UnexpectedResponse);
+        }
+
+        // Read response data
+        let mut resp = Scratch::new(vec![0u8; n]);
+        self.s.read_exact(&mut resp[..]).map_err(io_error)?;
+
+        debug!("RX: {:02x?}", *resp);
+
+        Ok(std::mem::take(&mut *resp))
+    }
+}
+
+/// Map IO errors, reporting read timeouts as [Error::Timeout]
+fn io_error(e: std::io::Error) -> Error {
+    use std::io::ErrorKind;
+
+    match e.kind() {
+        ErrorKind::TimedOut | ErrorKind::WouldBlock => Error::Timeout,
+        _ => Error::Tcp(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocking_tcp_app_info() {
+        // Mock speculos APDU socket, responding with BOLOS app info
+        let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = l.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (mut s, _) = l.accept().unwrap();
+
+            let mut buff = [0u8; 9];
+            s.read_exact(&mut buff).unwrap();
+
+            let resp = [
+                0x01, 0x05, b'B', b'O', b'L', b'O', b'S', 0x05, b'1', b'.', b'0', b'.', b'0', 0x90,
+                0x00,
+            ];
+            s.write_all(&((resp.len() - 2) as u32).to_be_bytes())
+                .unwrap();
+            s.write_all(&resp).unwrap();
+        });
+
+        let mut d = TcpDevice::connect(addr).unwrap();
+        let info = d.app_info(Duration::from_secs(1)).unwrap();
+
+        assert_eq!(info.name, "BOLOS");
+        assert_eq!(info.version, "1.0.0");
+    }
+
+    #[test]
+    fn blocking_tcp_timeout() {
+        let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = l.local_addr().unwrap();
+
+        let mut d = TcpDevice::connect(addr).unwrap();
+        let r = d.exchange(&[0xe0, 0x01, 0x00, 0x00, 0x00], Duration::from_millis(10));
+
+        assert!(matches!(r, Err(Error::Timeout)));
+    }
+}
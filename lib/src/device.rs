@@ -2,32 +2,57 @@
 
 use std::time::Duration;
 
-use encdec::{EncDec, Encode};
-use tracing::{debug, error};
+use encdec::EncDec;
+use tracing::{debug, error, trace};
 
 use ledger_proto::{
-    apdus::{AppInfoReq, AppInfoResp, DeviceInfoReq, DeviceInfoResp},
-    ApduError, ApduReq, StatusCode,
+    apdus::{
+        AppInfoReq, AppInfoResp, AppListResp, BatteryStatus, BatteryStatusKind, DeviceCert,
+        DeviceInfoReq, DeviceInfoResp, GetBatteryStatusReq, GetBatteryStatusResp, GetCertReq,
+        InitAuthReq, InitAuthResp, ListAppsSession, ValidateCertReq, ValidateTargetIdReq,
+    },
+    fmt::{CommandDump, ResponseDump},
+    split_status, ApduEncoder, ApduError, ApduReq, ApduResp, GenericApdu, StatusCode,
 };
 
 use crate::{
-    info::{AppInfo, DeviceInfo},
-    Error, Exchange,
+    genuine::{AuthChallenge, GenuineInfo, GenuineVerifier},
+    info::{AppInfo, ConnState, DeviceInfo, InstalledApp, PingInfo},
+    rt,
+    wipe::Scratch,
+    Error, Exchange, Timeouts,
 };
 
-const APDU_BUFF_LEN: usize = 256;
+pub(crate) const APDU_BUFF_LEN: usize = 256;
 
 /// [Device] provides a high-level interface exchanging APDU objects with implementers of [Exchange]
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+///
+/// Note `Send` bounds are not applied to returned futures, these are `Send` where the implementing type is.
+#[allow(async_fn_in_trait)]
 pub trait Device {
     /// Issue a request APDU, returning a reponse APDU
+    ///
+    /// `timeout` accepts a [Duration] for requests bounded by the transport timeout, or
+    /// [Timeouts] for requests awaiting user confirmation (such as signing).
     async fn request<'a, 'b, RESP: EncDec<'b, ApduError>>(
         &mut self,
-        request: impl ApduReq<'a> + Send,
+        request: impl ApduReq<'a>,
         buff: &'b mut [u8],
-        timeout: Duration,
+        timeout: impl Into<Timeouts>,
     ) -> Result<RESP, Error>;
 
+    /// Issue a request APDU, returning a response APDU with the trailing status word.
+    ///
+    /// Unlike [Device::request] this returns responses with warning statuses (`0x62xx`, `0x63xx`)
+    /// and status-only `0x9000` responses as [ApduResp] rather than errors, other
+    /// status-only responses are returned as [Error::Status].
+    async fn request_with_status<'a, 'b, RESP: EncDec<'b, ApduError>>(
+        &mut self,
+        request: impl ApduReq<'a>,
+        buff: &'b mut [u8],
+        timeout: impl Into<Timeouts>,
+    ) -> Result<ApduResp<RESP>, Error>;
+
     /// Fetch application information
     async fn app_info(&mut self, timeout: Duration) -> Result<AppInfo, Error> {
         let mut buff = [0u8; APDU_BUFF_LEN];
@@ -36,11 +61,40 @@ pub trait Device {
             .request::<AppInfoResp>(AppInfoReq {}, &mut buff[..], timeout)
             .await?;
 
-        Ok(AppInfo {
-            name: r.name.to_string(),
-            version: r.version.to_string(),
-            flags: r.flags,
-        })
+        Ok(app_info(r))
+    }
+
+    /// Check the device is responsive, issuing a no-op (app info) request and
+    /// reporting the round-trip latency with the connection state.
+    ///
+    /// Timeouts and transport failures are reported as [ConnState]s rather than errors,
+    /// providing a uniform liveness check across transports.
+    async fn ping(&mut self, timeout: Duration) -> Result<PingInfo, Error> {
+        let start = rt::Instant::now();
+        let r = self.app_info(timeout).await;
+        let latency = start.elapsed();
+
+        let state = match r {
+            Ok(app) => ConnState::Ready(app),
+            Err(e) => match e.root() {
+                Error::Status(s) if s.is_locked() => ConnState::Locked,
+                Error::Timeout => ConnState::Unresponsive,
+                Error::Closed => ConnState::Disconnected,
+                #[cfg(feature = "transport_usb")]
+                Error::Hid(_) => ConnState::Disconnected,
+                #[cfg(any(feature = "transport_tcp", feature = "blocking_tcp"))]
+                Error::Tcp(_) => ConnState::Disconnected,
+                #[cfg(feature = "transport_ble")]
+                Error::Ble(_) => ConnState::Disconnected,
+                #[cfg(feature = "transport_webhid")]
+                Error::WebHid(_) => ConnState::Disconnected,
+                _ => return Err(e),
+            },
+        };
+
+        debug!("Ping: {state:?} ({latency:?})");
+
+        Ok(PingInfo { latency, state })
     }
 
     /// Fetch device information
@@ -51,94 +105,261 @@ pub trait Device {
             .request::<DeviceInfoResp>(DeviceInfoReq {}, &mut buff[..], timeout)
             .await?;
 
-        Ok(DeviceInfo {
-            target_id: r.target_id,
-            se_version: r.se_version.to_string(),
-            mcu_version: r.mcu_version.to_string(),
-            flags: r.flags.to_vec(),
+        Ok(device_info(r))
+    }
+
+    /// Fetch installed applications (must be issued from the dashboard)
+    async fn list_apps(&mut self, timeout: Duration) -> Result<Vec<InstalledApp>, Error> {
+        let mut buff = [0u8; EXT_BUFF_LEN];
+        let mut s = ListAppsSession::new();
+
+        while let Some(req) = s.next_req() {
+            // Status-only (or empty) responses indicate the end of the list
+            match self
+                .request::<AppListResp>(req, &mut buff[..], timeout)
+                .await
+            {
+                Ok(r) => s.push(&r),
+                Err(Error::Status(StatusCode::Ok)) => s.push(&AppListResp::default()),
+                Err(e) => return Err(e),
+            };
+        }
+
+        Ok(s.finish().into_iter().map(InstalledApp::from).collect())
+    }
+
+    /// Fetch battery status (Nano X and Stax)
+    async fn battery_status(
+        &mut self,
+        kind: BatteryStatusKind,
+        timeout: Duration,
+    ) -> Result<BatteryStatus, Error> {
+        let mut buff = [0u8; APDU_BUFF_LEN];
+
+        let r = self
+            .request::<GetBatteryStatusResp>(GetBatteryStatusReq::new(kind), &mut buff[..], timeout)
+            .await?;
+
+        Ok(r.status(kind)?)
+    }
+
+    /// Check the device is genuine, authenticating with the signer certificates issued
+    /// by the provided [GenuineVerifier] then verifying the returned device certificate chain.
+    ///
+    /// This must be called from the dashboard, returns [Error::NotGenuine] where the
+    /// device certificates are rejected by the verifier.
+    async fn genuine_check(
+        &mut self,
+        verifier: &mut impl GenuineVerifier,
+        timeout: Duration,
+    ) -> Result<GenuineInfo, Error> {
+        let mut buff = [0u8; EXT_BUFF_LEN];
+
+        // Identify the device
+        let target_id = self.device_info(timeout).await?.target_id;
+        match self
+            .request::<GenericApdu>(ValidateTargetIdReq::new(target_id), &mut buff[..], timeout)
+            .await
+        {
+            Ok(_) | Err(Error::Status(StatusCode::Ok)) => (),
+            Err(e) => return Err(e),
+        }
+
+        // Exchange nonces
+        let host_nonce = verifier.host_nonce();
+        let r = self
+            .request::<InitAuthResp>(InitAuthReq::new(host_nonce), &mut buff[..], timeout)
+            .await?;
+        let challenge = AuthChallenge {
+            target_id,
+            host_nonce,
+            batch_serial: r.batch_serial,
+            device_nonce: r.device_nonce,
+        };
+
+        // Submit the signer certificate chain
+        let certs = verifier.signer_certs(&challenge).await?;
+        for (i, c) in certs.iter().enumerate() {
+            let req = ValidateCertReq::new(c, i == certs.len() - 1);
+            match self
+                .request::<GenericApdu>(req, &mut buff[..], timeout)
+                .await
+            {
+                Ok(_) | Err(Error::Status(StatusCode::Ok)) => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Fetch the device certificate chain (device then ephemeral certificates)
+        let mut certificates = vec![];
+        for next in [false, true] {
+            match self
+                .request::<DeviceCert>(GetCertReq::new(next), &mut buff[..], timeout)
+                .await
+            {
+                Ok(c) if !c.is_empty() => certificates.push(c.into()),
+                Ok(_) | Err(Error::Status(StatusCode::Ok)) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        // Verify the chain
+        if !verifier.verify(&challenge, &certificates).await? {
+            return Err(Error::NotGenuine);
+        }
+
+        Ok(GenuineInfo {
+            challenge,
+            certificates,
         })
     }
 }
 
+/// Buffer length for APDUs with maximum length payloads (app lists, certificates)
+const EXT_BUFF_LEN: usize = 512;
+
+/// Convert an [AppInfoResp] APDU to an owned [AppInfo]
+pub(crate) fn app_info(r: AppInfoResp) -> AppInfo {
+    AppInfo {
+        name: r.name.to_string(),
+        version: r.version.to_string(),
+        flags: r.flags,
+    }
+}
+
+/// Convert a [DeviceInfoResp] APDU to an owned [DeviceInfo]
+pub(crate) fn device_info(r: DeviceInfoResp) -> DeviceInfo {
+    DeviceInfo {
+        target_id: r.target_id,
+        se_version: r.se_version.to_string(),
+        mcu_version: r.mcu_version.to_string(),
+        flags: r.flags.to_vec(),
+        bootloader_version: match r.bootloader_version {
+            "" => None,
+            v => Some(v.to_string()),
+        },
+        hardware_revision: r.hardware_revision,
+        language_id: r.language_id,
+    }
+}
+
 /// Generic [Device] implementation for types supporting [Exchange]
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
-impl<T: Exchange + Send> Device for T {
+impl<T: Exchange> Device for T {
     /// Issue a request APDU to a device, encoding and decoding internally then returning a response APDU
     async fn request<'a, 'b, RESP: EncDec<'b, ApduError>>(
         &mut self,
-        req: impl ApduReq<'a> + Send,
+        req: impl ApduReq<'a>,
         buff: &'b mut [u8],
-        timeout: Duration,
+        timeout: impl Into<Timeouts>,
     ) -> Result<RESP, Error> {
         debug!("TX: {req:?}");
 
         // Encode request
         let n = encode_request(req, buff)?;
+        trace!("TX APDU:\n{}", CommandDump::new(&buff[..n]));
 
         // Send request to device
-        let resp_bytes = self.exchange(&buff[..n], timeout).await?;
-
-        // Copy response back to buffer prior to decode
-        // (these hijinks are required to allow devices to avoid ownership of APDU data)
-        let n = resp_bytes.len();
-        if n > buff.len() {
-            error!(
-                "Response length exceeds buffer length ({} > {})",
-                n,
-                buff.len()
-            );
-            return Err(ApduError::InvalidLength.into());
-        }
-        buff[..n].copy_from_slice(&resp_bytes[..]);
-
-        // Handle error responses (2 bytes long, only a status)
-        if n == 2 {
-            // Return status code if matched, unknown otherwise
-            let v = u16::from_be_bytes([resp_bytes[0], resp_bytes[1]]);
-            match StatusCode::try_from(v) {
-                Ok(c) => return Err(Error::Status(c)),
-                Err(_) => return Err(Error::UnknownStatus(resp_bytes[0], resp_bytes[1])),
-            }
-        }
+        let resp_bytes = Scratch::new(self.exchange_timeouts(&buff[..n], timeout.into()).await?);
+        trace!("RX APDU:\n{}", ResponseDump::new(&resp_bytes));
 
-        // Decode response data - status bytes
-        let (resp, _) = RESP::decode(&buff[..n - 2])?;
+        // Decode response
+        let resp = decode_response::<RESP>(&resp_bytes, buff)?;
 
         debug!("RX: {resp:?}");
 
         // Return decode response
         Ok(resp)
     }
+
+    /// Issue a request APDU to a device, returning the decoded response with status
+    async fn request_with_status<'a, 'b, RESP: EncDec<'b, ApduError>>(
+        &mut self,
+        req: impl ApduReq<'a>,
+        buff: &'b mut [u8],
+        timeout: impl Into<Timeouts>,
+    ) -> Result<ApduResp<RESP>, Error> {
+        debug!("TX: {req:?}");
+
+        // Encode request
+        let n = encode_request(req, buff)?;
+        trace!("TX APDU:\n{}", CommandDump::new(&buff[..n]));
+
+        // Send request to device
+        let resp_bytes = Scratch::new(self.exchange_timeouts(&buff[..n], timeout.into()).await?);
+        trace!("RX APDU:\n{}", ResponseDump::new(&resp_bytes));
+
+        // Decode response and status
+        let resp = decode_response_with_status::<RESP>(&resp_bytes, buff)?;
+
+        debug!("RX: {resp:?}");
+
+        Ok(resp)
+    }
 }
 
-/// Helper to perform APDU request encoding including the header, length, and body
-fn encode_request<'a, REQ: ApduReq<'a>>(req: REQ, buff: &mut [u8]) -> Result<usize, Error> {
-    let mut index = 0;
+/// Helper to decode a response APDU, returning status-only responses as errors
+pub(crate) fn decode_response<'b, RESP: EncDec<'b, ApduError>>(
+    resp_bytes: &[u8],
+    buff: &'b mut [u8],
+) -> Result<RESP, Error> {
+    let (body, status) = split_status(resp_bytes)?;
 
-    let data_len = req.encode_len()?;
+    // Handle error responses (status only, unrecognised codes map to StatusCode::Unknown)
+    if body.is_empty() {
+        return Err(Error::Status(status));
+    }
 
-    // Check buffer length is reasonable
-    if buff.len() < 5 + data_len {
+    // Copy body back to buffer prior to decode
+    // (these hijinks are required to allow devices to avoid ownership of APDU data)
+    if body.len() > buff.len() {
+        error!(
+            "Response length exceeds buffer length ({} > {})",
+            body.len(),
+            buff.len()
+        );
         return Err(ApduError::InvalidLength.into());
     }
+    buff[..body.len()].copy_from_slice(body);
 
-    // Encode request object
+    // Decode response data
+    let (resp, _) = RESP::decode(&buff[..body.len()])?;
 
-    // First the header
-    let h = req.header();
-    index += h.encode(&mut buff[index..])?;
+    Ok(resp)
+}
 
-    // Then the data length
-    if data_len > u8::MAX as usize {
+/// Helper to decode a response APDU with the trailing status word, returning
+/// status-only error responses as errors
+pub(crate) fn decode_response_with_status<'b, RESP: EncDec<'b, ApduError>>(
+    resp_bytes: &[u8],
+    buff: &'b mut [u8],
+) -> Result<ApduResp<RESP>, Error> {
+    let (body, status) = split_status(resp_bytes)?;
+
+    // Return status-only errors (excluding OK and warning statuses)
+    if body.is_empty() && !status.is_success() && !status.is_warning() {
+        return Err(Error::Status(status));
+    }
+
+    // Copy body back to buffer prior to decode
+    if body.len() > buff.len() {
+        error!(
+            "Response length exceeds buffer length ({} > {})",
+            body.len(),
+            buff.len()
+        );
         return Err(ApduError::InvalidLength.into());
     }
-    buff[index] = data_len as u8;
-    index += 1;
+    buff[..body.len()].copy_from_slice(body);
 
-    // Then finally the data
-    index += req.encode(&mut buff[index..])?;
+    let (body, _) = RESP::decode(&buff[..body.len()])?;
 
-    Ok(index)
+    Ok(ApduResp::new(body, u16::from(status)))
+}
+
+/// Helper to perform APDU request encoding including the header, length, and body
+/// (see [ApduEncoder] for framing details)
+pub fn encode_request<'a, REQ: ApduReq<'a>>(req: REQ, buff: &mut [u8]) -> Result<usize, Error> {
+    Ok(ApduEncoder::encode(&req, buff)?)
 }
 
 #[cfg(test)]
@@ -159,4 +380,195 @@ mod tests {
             &[AppInfoReq::CLA, AppInfoReq::INS, 0x00, 0x00, 0x00]
         );
     }
+
+    #[test]
+    fn test_decode_response_with_status() {
+        use ledger_proto::{apdus::WalletIdResp, ApduResp, StatusCode};
+
+        use super::decode_response_with_status;
+        use crate::Error;
+
+        let mut buff = [0u8; 16];
+
+        // Warning statuses are returned with the response
+        let r =
+            decode_response_with_status::<WalletIdResp>(&[0xab, 0x63, 0xc2], &mut buff).unwrap();
+        assert_eq!(r, ApduResp::new(WalletIdResp::new(&[0xab]), 0x63c2));
+
+        // Status-only errors are returned as errors
+        let r = decode_response_with_status::<WalletIdResp>(&[0x69, 0x85], &mut buff);
+        assert!(matches!(
+            r,
+            Err(Error::Status(StatusCode::ConditionsOfUseNotSatisfied))
+        ));
+    }
+
+    #[test]
+    fn test_decode_response() {
+        use ledger_proto::{apdus::WalletIdResp, ApduError, StatusCode};
+
+        use super::decode_response;
+        use crate::Error;
+
+        let mut buff = [0u8; 16];
+
+        let r = decode_response::<WalletIdResp>(&[0xab, 0x90, 0x00], &mut buff).unwrap();
+        assert_eq!(r, WalletIdResp::new(&[0xab]));
+
+        // Status-only and truncated responses are returned as errors
+        let r = decode_response::<WalletIdResp>(&[0x55, 0x15], &mut buff);
+        assert!(matches!(r, Err(Error::Status(StatusCode::LockedDevice))));
+
+        let r = decode_response::<WalletIdResp>(&[0x90], &mut buff);
+        assert!(matches!(r, Err(Error::Apdu(ApduError::InvalidLength))));
+    }
+
+    #[test]
+    fn test_encode_requests_le() {
+        use ledger_proto::{ApduError, ApduStatic, Decode, Encode};
+
+        #[derive(Clone, Debug, PartialEq, Encode, Decode)]
+        #[encdec(error = "ApduError")]
+        struct LeReq {
+            v: u8,
+        }
+
+        impl ApduStatic for LeReq {
+            const CLA: u8 = 0xe0;
+            const INS: u8 = 0x10;
+
+            fn le(&self) -> Option<usize> {
+                Some(256)
+            }
+        }
+
+        // Buffer must fit header, length, data and le
+        assert!(encode_request(LeReq { v: 0xaa }, &mut [0u8; 6]).is_err());
+
+        let mut buff = [0u8; 16];
+        let n = encode_request(LeReq { v: 0xaa }, &mut buff).unwrap();
+        assert_eq!(&buff[..n], &[0xe0, 0x10, 0x00, 0x00, 0x01, 0xaa, 0x00]);
+    }
+
+    #[test]
+    fn test_encode_extended_requests() {
+        use ledger_proto::GenericApdu;
+
+        let req = GenericApdu::build()
+            .cla(0xe0)
+            .ins(0x04)
+            .data([0xaa; 300])
+            .finish();
+
+        // Buffer must fit header, extended length and data
+        assert!(encode_request(req.clone(), &mut [0u8; 306]).is_err());
+
+        let mut buff = [0u8; 307];
+        let n = encode_request(req, &mut buff).unwrap();
+        assert_eq!(n, 307);
+        assert_eq!(&buff[..7], &[0xe0, 0x04, 0x00, 0x00, 0x00, 0x01, 0x2c]);
+        assert_eq!(&buff[7..], &[0xaa; 300]);
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        use std::{collections::VecDeque, time::Duration};
+
+        use ledger_proto::{
+            apdus::{AppFlags, AppInfoResp},
+            ApduError, Encode,
+        };
+
+        use crate::{
+            info::{AppInfo, ConnState},
+            Device, Error, Exchange,
+        };
+
+        /// Mock device returning queued exchange results
+        struct Scripted(VecDeque<Result<Vec<u8>, Error>>);
+
+        impl Exchange for Scripted {
+            async fn exchange(
+                &mut self,
+                _command: &[u8],
+                _timeout: Duration,
+            ) -> Result<Vec<u8>, Error> {
+                self.0.pop_front().unwrap()
+            }
+        }
+
+        let mut app = [0u8; 64];
+        let n = AppInfoResp::new("BOLOS", "1.0.0", AppFlags::empty())
+            .encode(&mut app)
+            .unwrap();
+        let app = [&app[..n], &[0x90, 0x00]].concat();
+
+        let mut d = Scripted(VecDeque::from([
+            Ok(app),
+            Ok(vec![0x55, 0x15]),
+            Err(Error::Timeout),
+            Err(Error::Closed),
+            Err(ApduError::InvalidLength.into()),
+        ]));
+
+        let p = d.ping(Duration::from_secs(1)).await.unwrap();
+        assert_eq!(
+            p.state,
+            ConnState::Ready(AppInfo {
+                name: "BOLOS".to_string(),
+                version: "1.0.0".to_string(),
+                flags: AppFlags::empty(),
+            })
+        );
+        assert!(p.state.is_alive());
+
+        // Device and transport failures are reported as connection states
+        let states = [
+            ConnState::Locked,
+            ConnState::Unresponsive,
+            ConnState::Disconnected,
+        ];
+        for s in states {
+            assert_eq!(d.ping(Duration::from_secs(1)).await.unwrap().state, s);
+        }
+
+        // Other failures are returned as errors
+        assert!(matches!(
+            d.ping(Duration::from_secs(1)).await,
+            Err(Error::Apdu(ApduError::InvalidLength))
+        ));
+    }
+
+    #[cfg(feature = "transport_loopback")]
+    #[tokio::test]
+    async fn test_loopback_request() {
+        use ledger_proto::{ApduHeader, GenericApdu};
+
+        use crate::{
+            transport::{LoopbackInfo, LoopbackTransport},
+            Device, Transport, DEFAULT_TIMEOUT,
+        };
+
+        let mut t = LoopbackTransport::new().unwrap();
+        let mut d = t.connect(LoopbackInfo::default()).await.unwrap();
+
+        let req = GenericApdu {
+            header: ApduHeader {
+                cla: 0xe0,
+                ins: 0x02,
+                p1: 0x01,
+                p2: 0x02,
+            },
+            data: vec![0xaa, 0xbb],
+        };
+
+        // Loopback responses contain the encoded request, less the status word
+        let mut buff = [0u8; 256];
+        let resp = d
+            .request::<GenericApdu>(req, &mut buff, DEFAULT_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(&resp.data, &[0xe0, 0x02, 0x01, 0x02, 0x02, 0xaa, 0xbb]);
+    }
 }
@@ -1,6 +1,10 @@
 //! Ledger interface [Error] type and conversions
 
-use ledger_proto::{ApduError, StatusCode};
+use ledger_proto::{
+    apdus::RunAppError, framing::FrameError, ApduError, AppStatus, Status, StatusCode,
+};
+
+use crate::info::{ConnInfo, ConnType, LedgerInfo, Model};
 
 /// Ledger interface error type
 #[derive(Debug, thiserror::Error)]
@@ -9,20 +13,34 @@ pub enum Error {
     #[error(transparent)]
     Hid(#[from] hidapi::HidError),
 
-    #[cfg(feature = "transport_tcp")]
+    #[cfg(any(feature = "transport_tcp", feature = "blocking_tcp"))]
     #[error(transparent)]
-    Tcp(#[from] tokio::io::Error),
+    Tcp(#[from] std::io::Error),
 
     #[cfg(feature = "transport_ble")]
     #[error(transparent)]
     Ble(#[from] btleplug::Error),
 
+    /// BLE pairing not completed on first connection to a device
+    #[cfg(feature = "transport_ble")]
+    #[error("BLE pairing not completed, confirm the pairing request on the device")]
+    PairingRequired,
+
+    /// WebHID errors (JavaScript exceptions and promise rejections)
+    #[cfg(feature = "transport_webhid")]
+    #[error("WebHID error: {0}")]
+    WebHid(String),
+
     #[error("Unknown ledger model: {0}")]
     UnknownModel(u16),
 
     #[error("Unknown error")]
     Unknown,
 
+    /// Transport not initialised (see [GenericTransport::init](crate::transport::GenericTransport::init))
+    #[error("{0} transport not initialised")]
+    TransportUnavailable(ConnType),
+
     #[error("No devices found")]
     NoDevices,
 
@@ -32,17 +50,17 @@ pub enum Error {
     #[error("Apdu encode/decode error: {0}")]
     Apdu(#[from] ApduError),
 
-    /// Recognised status codes (see [StatusCode])
+    /// Device status codes (see [StatusCode], unrecognised codes map to [StatusCode::Unknown])
     #[error("Status: {0}")]
     Status(StatusCode),
 
-    /// Unrecognised status codes
-    #[error("Status: 0x{0:02x}{1:02x} (unrecognised)")]
-    UnknownStatus(u8, u8),
-
     #[error("Request timeout")]
     Timeout,
 
+    /// User confirmation deadline elapsed (see [Timeouts](crate::Timeouts))
+    #[error("Timeout awaiting user confirmation")]
+    UserTimeout,
+
     #[error("Device or transport closed")]
     Closed,
 
@@ -52,15 +70,287 @@ pub enum Error {
     #[error("Unexpected response payload")]
     UnexpectedResponse,
 
-    #[error("Device in use")]
-    DeviceInUse,
+    /// Device in use, by another handle or by the `holder` process
+    /// (see `UsbTransport::with_device_lock`)
+    #[error("Device in use{}", in_use_by(.holder))]
+    DeviceInUse { holder: Option<String> },
+
+    /// Insufficient permissions to open a device, with a remediation `hint`
+    /// for display (for example, installing udev rules on Linux)
+    #[error("Permission denied opening device {path} ({hint})")]
+    PermissionDenied { path: String, hint: String },
+
+    /// Device rejected by the genuine check (see [Device::genuine_check](crate::Device::genuine_check))
+    #[error("Device failed genuine check")]
+    NotGenuine,
+
+    /// Application launch failure (see [launch_app](crate::launch_app))
+    #[error("Run application failed: {0}")]
+    RunApp(#[from] RunAppError),
 
     #[error("Already running application ({0})")]
     ApplicationLoaded(String),
+
+    #[cfg(feature = "trace")]
+    #[error("Trace encode/decode error: {0}")]
+    Trace(String),
+
+    /// Error raised by a transport or the provider, with context identifying
+    /// the operation and device (see [Error::context] and [Error::root])
+    #[error("{ctx}: {source}")]
+    Context {
+        ctx: Box<ErrorContext>,
+        source: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Attach context to an error, filling missing fields where context is already attached
+    pub fn with_context(self, ctx: ErrorContext) -> Self {
+        match self {
+            Self::Context { ctx: mut c, source } => {
+                c.kind = c.kind.or(ctx.kind);
+                c.conn = c.conn.or(ctx.conn);
+                c.model = c.model.or(ctx.model);
+
+                Self::Context { ctx: c, source }
+            }
+            e => Self::Context {
+                ctx: Box::new(ctx),
+                source: Box::new(e),
+            },
+        }
+    }
+
+    /// Fetch attached [ErrorContext] where available
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Context { ctx, .. } => Some(ctx),
+            _ => None,
+        }
+    }
+
+    /// Fetch the underlying error, stripping any attached context
+    pub fn root(&self) -> &Error {
+        match self {
+            Self::Context { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    /// Fetch the operation that failed where context is available
+    pub fn operation(&self) -> Option<Operation> {
+        self.context().map(|c| c.op)
+    }
+
+    /// Fetch the transport kind associated with an error where available
+    pub fn conn_type(&self) -> Option<ConnType> {
+        self.context().and_then(|c| c.kind)
+    }
+
+    /// Fetch device information associated with an error where available
+    pub fn device(&self) -> Option<LedgerInfo> {
+        self.context().and_then(|c| c.info())
+    }
+
+    /// Resolve status errors to application-specific codes (see [AppStatus]),
+    /// returning `None` for non-status errors
+    pub fn app_status<A: AppStatus>(&self) -> Option<Status<A>> {
+        match self.root() {
+            Self::Status(s) => Some(A::resolve(u16::from(*s))),
+            _ => None,
+        }
+    }
+}
+
+/// Helper to format [Error::DeviceInUse] holders
+fn in_use_by(holder: &Option<String>) -> String {
+    match holder {
+        Some(h) => format!(" by {h}"),
+        None => String::new(),
+    }
+}
+
+/// Operations annotated in [ErrorContext]
+#[derive(Copy, Clone, PartialEq, Debug, strum::Display)]
+pub enum Operation {
+    /// Device listing
+    #[strum(serialize = "list")]
+    List,
+    /// Device connection
+    #[strum(serialize = "connect")]
+    Connect,
+    /// APDU exchange
+    #[strum(serialize = "exchange")]
+    Exchange,
+}
+
+/// Context attached to errors raised by transports and the provider
+#[derive(Clone, PartialEq, Debug)]
+pub struct ErrorContext {
+    /// Operation that failed
+    pub op: Operation,
+    /// Transport kind, where known
+    pub kind: Option<ConnType>,
+    /// Device connection information, where known
+    pub conn: Option<ConnInfo>,
+    /// Device model, where known
+    pub model: Option<Model>,
 }
 
+impl ErrorContext {
+    /// Create a new context for the provided operation
+    pub fn new(op: Operation) -> Self {
+        Self {
+            op,
+            kind: None,
+            conn: None,
+            model: None,
+        }
+    }
+
+    /// Set the transport kind
+    pub fn with_kind(mut self, kind: ConnType) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Set device connection information (and the associated transport kind)
+    pub fn with_conn(mut self, conn: ConnInfo) -> Self {
+        self.kind = Some(conn.kind());
+        self.conn = Some(conn);
+        self
+    }
+
+    /// Set device information (connection and model)
+    pub fn with_info(mut self, info: LedgerInfo) -> Self {
+        self.model = Some(info.model);
+        self.with_conn(info.conn)
+    }
+
+    /// Fetch device information where both connection and model are known
+    #[cfg_attr(
+        not(any(
+            feature = "transport_usb",
+            feature = "transport_tcp",
+            feature = "transport_ble",
+            feature = "transport_loopback",
+            feature = "transport_webhid"
+        )),
+        allow(unreachable_code)
+    )]
+    pub fn info(&self) -> Option<LedgerInfo> {
+        match (&self.conn, &self.model) {
+            (Some(conn), Some(model)) => Some(LedgerInfo {
+                model: model.clone(),
+                conn: conn.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} failed", self.op)?;
+
+        match (&self.model, &self.conn, &self.kind) {
+            (Some(m), Some(c), _) => write!(f, " for {m} ({c})"),
+            (None, Some(c), _) => write!(f, " for {c}"),
+            (_, None, Some(k)) => write!(f, " via {k}"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Map framing errors to response errors
+impl From<FrameError> for Error {
+    fn from(e: FrameError) -> Self {
+        match e {
+            FrameError::Empty => Self::EmptyResponse,
+            _ => Self::UnexpectedResponse,
+        }
+    }
+}
+
+#[cfg(feature = "runtime_tokio")]
 impl From<tokio::time::error::Elapsed> for Error {
     fn from(_e: tokio::time::error::Elapsed) -> Self {
         Self::Timeout
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_display() {
+        let e = Error::Unknown.with_context(ErrorContext::new(Operation::List));
+        assert_eq!(e.to_string(), "list failed: Unknown error");
+
+        let e = Error::Closed
+            .with_context(ErrorContext::new(Operation::Connect).with_kind(ConnType::Tcp));
+        assert_eq!(
+            e.to_string(),
+            "connect failed via Tcp: Device or transport closed"
+        );
+        assert_eq!(e.conn_type(), Some(ConnType::Tcp));
+        assert!(matches!(e.root(), Error::Closed));
+    }
+
+    #[test]
+    fn app_status() {
+        #[derive(Copy, Clone, PartialEq, Debug)]
+        struct InvalidPath;
+
+        impl TryFrom<u16> for InvalidPath {
+            type Error = ();
+
+            fn try_from(sw: u16) -> Result<Self, ()> {
+                match sw {
+                    0x6a15 => Ok(Self),
+                    _ => Err(()),
+                }
+            }
+        }
+
+        impl AppStatus for InvalidPath {}
+
+        let e = Error::Status(StatusCode::from(0x6a15))
+            .with_context(ErrorContext::new(Operation::Exchange));
+        assert_eq!(e.app_status(), Some(Status::App(InvalidPath)));
+
+        let e = Error::Status(StatusCode::LockedDevice);
+        assert_eq!(
+            e.app_status::<InvalidPath>(),
+            Some(Status::Shared(StatusCode::LockedDevice))
+        );
+        assert_eq!(Error::Closed.app_status::<InvalidPath>(), None);
+    }
+
+    #[cfg(feature = "transport_loopback")]
+    #[test]
+    fn context_merge() {
+        use crate::transport::LoopbackInfo;
+
+        let info = LedgerInfo {
+            model: Model::NanoX,
+            conn: LoopbackInfo::default().into(),
+        };
+
+        // Transport context is extended with device information by the provider
+        let e = Error::EmptyResponse
+            .with_context(ErrorContext::new(Operation::Exchange).with_conn(info.conn.clone()))
+            .with_context(ErrorContext::new(Operation::Exchange).with_info(info.clone()));
+
+        assert_eq!(e.operation(), Some(Operation::Exchange));
+        assert_eq!(e.conn_type(), Some(ConnType::Loopback));
+        assert_eq!(e.device(), Some(info.clone()));
+        assert!(matches!(e.root(), Error::EmptyResponse));
+        assert_eq!(
+            e.to_string(),
+            format!("exchange failed for {info}: Empty response payload")
+        );
+    }
+}
@@ -0,0 +1,12 @@
+//! Internal framing helpers exposed for fuzzing and benchmarks, these are _not_ part of the public API
+
+pub use crate::device::encode_request;
+
+#[cfg(feature = "transport_usb")]
+pub use crate::transport::usb::read_apdu as usb_read_apdu;
+
+#[cfg(feature = "transport_ble")]
+pub use crate::transport::ble::read_apdu as ble_read_apdu;
+
+#[cfg(feature = "transport_tcp")]
+pub use crate::transport::tcp::read_apdu as tcp_read_apdu;
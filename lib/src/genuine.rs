@@ -0,0 +1,70 @@
+//! Genuine check types, see [Device::genuine_check](crate::Device::genuine_check)
+//!
+//! The device side of the genuine check is implemented by this crate, while certificate
+//! issuance and verification against the Ledger issuer key are delegated to a
+//! [GenuineVerifier] (typically backed by the Ledger HSM).
+
+use ledger_proto::apdus::DeviceCert;
+
+use crate::Error;
+
+/// Authentication challenge, exchanged with the device at the start of a genuine check
+#[derive(Clone, PartialEq, Debug)]
+pub struct AuthChallenge {
+    /// Device target ID
+    pub target_id: [u8; 4],
+    /// Host nonce
+    pub host_nonce: [u8; 8],
+    /// Serial of the batch signer key used to issue the device certificate
+    pub batch_serial: [u8; 4],
+    /// Device nonce
+    pub device_nonce: [u8; 8],
+}
+
+/// Device certificate, owned version of [DeviceCert]
+#[derive(Clone, PartialEq, Debug)]
+pub struct DeviceCertificate {
+    /// Certificate header (role and serial)
+    pub header: Vec<u8>,
+    /// Certified public key
+    pub public_key: Vec<u8>,
+    /// Issuer signature over the header and public key
+    pub signature: Vec<u8>,
+}
+
+impl<'a> From<DeviceCert<'a>> for DeviceCertificate {
+    fn from(c: DeviceCert<'a>) -> Self {
+        Self {
+            header: c.header.to_vec(),
+            public_key: c.public_key.to_vec(),
+            signature: c.signature.to_vec(),
+        }
+    }
+}
+
+/// Genuine check result, returned for devices accepted by the [GenuineVerifier]
+#[derive(Clone, PartialEq, Debug)]
+pub struct GenuineInfo {
+    /// Authentication challenge
+    pub challenge: AuthChallenge,
+    /// Device certificate chain
+    pub certificates: Vec<DeviceCertificate>,
+}
+
+/// Host side of the genuine check, issuing the signer certificate chain submitted to
+/// the device and verifying the returned device certificates
+#[allow(async_fn_in_trait)]
+pub trait GenuineVerifier {
+    /// Fetch a fresh host nonce
+    fn host_nonce(&mut self) -> [u8; 8];
+
+    /// Issue the signer certificate chain for the provided challenge, root first
+    async fn signer_certs(&mut self, challenge: &AuthChallenge) -> Result<Vec<Vec<u8>>, Error>;
+
+    /// Verify the device certificate chain, returning `true` for genuine devices
+    async fn verify(
+        &mut self,
+        challenge: &AuthChallenge,
+        certificates: &[DeviceCertificate],
+    ) -> Result<bool, Error>;
+}
@@ -4,10 +4,18 @@ use strum::{Display, EnumString};
 
 use crate::Filters;
 
+#[cfg(any(
+    feature = "transport_usb",
+    feature = "transport_tcp",
+    feature = "transport_ble",
+    feature = "transport_loopback",
+    feature = "transport_webhid"
+))]
 use super::transport;
 
 /// Ledger device information
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LedgerInfo {
     /// Device Model
     pub model: Model,
@@ -25,19 +33,64 @@ impl std::fmt::Display for LedgerInfo {
 impl LedgerInfo {
     /// Fetch connection kind enumeration
     pub fn kind(&self) -> ConnType {
-        match &self.conn {
+        self.conn.kind()
+    }
+}
+
+impl ConnInfo {
+    /// Fetch connection kind enumeration
+    pub fn kind(&self) -> ConnType {
+        match self {
             #[cfg(feature = "transport_usb")]
             ConnInfo::Usb(_) => ConnType::Usb,
             #[cfg(feature = "transport_tcp")]
             ConnInfo::Tcp(_) => ConnType::Tcp,
             #[cfg(feature = "transport_ble")]
             ConnInfo::Ble(_) => ConnType::Ble,
+            #[cfg(feature = "transport_loopback")]
+            ConnInfo::Loopback(_) => ConnType::Loopback,
+            #[cfg(feature = "transport_webhid")]
+            ConnInfo::WebHid(_) => ConnType::WebHid,
+            #[cfg(not(any(
+                feature = "transport_usb",
+                feature = "transport_tcp",
+                feature = "transport_ble",
+                feature = "transport_loopback",
+                feature = "transport_webhid"
+            )))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// Fetch the device path for connections where available, USB device paths
+    /// or socket / peripheral addresses for TCP and BLE devices
+    pub fn path(&self) -> Option<String> {
+        match self {
+            #[cfg(feature = "transport_usb")]
+            ConnInfo::Usb(i) => i.path.clone(),
+            #[cfg(feature = "transport_tcp")]
+            ConnInfo::Tcp(i) => Some(i.addr.to_string()),
+            #[cfg(feature = "transport_ble")]
+            ConnInfo::Ble(i) => Some(i.addr.to_string()),
+            #[cfg(feature = "transport_loopback")]
+            ConnInfo::Loopback(_) => None,
+            #[cfg(feature = "transport_webhid")]
+            ConnInfo::WebHid(_) => None,
+            #[cfg(not(any(
+                feature = "transport_usb",
+                feature = "transport_tcp",
+                feature = "transport_ble",
+                feature = "transport_loopback",
+                feature = "transport_webhid"
+            )))]
+            _ => unreachable!(),
         }
     }
 }
 
 /// Ledger device models
 #[derive(Clone, PartialEq, Debug, Display, EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Model {
     /// Nano S
     NanoS,
@@ -47,29 +100,105 @@ pub enum Model {
     NanoX,
     /// Stax
     Stax,
+    /// Blue
+    Blue,
+    /// Flex
+    Flex,
     /// Unknown model
     Unknown(u16),
 }
 
+/// Ledger USB vendor ID (VID)
+#[cfg(any(feature = "transport_usb", feature = "transport_webhid"))]
+pub(crate) const LEDGER_VID: u16 = 0x2c97;
+
+/// USB product ID mapping for a device [Model]
+struct UsbPid {
+    model: Model,
+    /// Full PID reported by legacy firmware (and bootloaders)
+    legacy: u16,
+    /// PID top byte, with the low byte varying with the device interfaces
+    prefix: u8,
+}
+
+/// USB product IDs for known models, new models only require an entry here
+const USB_PIDS: &[UsbPid] = &[
+    UsbPid {
+        model: Model::Blue,
+        legacy: 0x0000,
+        prefix: 0x00,
+    },
+    UsbPid {
+        model: Model::NanoS,
+        legacy: 0x0001,
+        prefix: 0x10,
+    },
+    UsbPid {
+        model: Model::NanoX,
+        legacy: 0x0004,
+        prefix: 0x40,
+    },
+    UsbPid {
+        model: Model::NanoSPlus,
+        legacy: 0x0005,
+        prefix: 0x50,
+    },
+    UsbPid {
+        model: Model::Stax,
+        legacy: 0x0006,
+        prefix: 0x60,
+    },
+    UsbPid {
+        model: Model::Flex,
+        legacy: 0x0007,
+        prefix: 0x70,
+    },
+];
+
+/// Target IDs for known models (as reported in [DeviceInfo::target_id])
+const TARGET_IDS: &[(u32, Model)] = &[
+    (0x3100_0002, Model::Blue),
+    (0x3101_0004, Model::Blue),
+    (0x3110_0002, Model::NanoS),
+    (0x3110_0003, Model::NanoS),
+    (0x3110_0004, Model::NanoS),
+    (0x3300_0004, Model::NanoX),
+    (0x3310_0004, Model::NanoSPlus),
+    (0x3320_0004, Model::Stax),
+    (0x3330_0004, Model::Flex),
+];
+
 impl Model {
+    /// Convert a device target ID (see [DeviceInfo::target_id]) to a [Model] kind
+    pub fn from_target_id(target_id: [u8; 4]) -> Model {
+        let id = u32::from_be_bytes(target_id);
+
+        TARGET_IDS
+            .iter()
+            .find(|(t, _m)| *t == id)
+            .map(|(_t, m)| m.clone())
+            .unwrap_or(Model::Unknown(0))
+    }
+
     /// Convert a USB PID to a [Model] kind
     ///
-    /// Note that ledger PIDs vary depending on the device state so only the top byte is used
-    /// for matching.
+    /// Note that ledger PIDs vary depending on the device state so legacy PIDs are matched
+    /// exactly, with only the top byte used for matching otherwise.
     pub fn from_pid(pid: u16) -> Model {
-        match pid & 0xFF00 {
-            // TODO: support all the models
-            //0x0001 => Ok(Model::NanoS),
-            0x4000 => Model::NanoX,
-            0x5000 => Model::NanoSPlus,
-            //0x0006 => Ok(Model::Stax),
-            _ => Model::Unknown(pid),
-        }
+        let [prefix, _] = pid.to_be_bytes();
+
+        USB_PIDS
+            .iter()
+            .find(|p| p.legacy == pid)
+            .or_else(|| USB_PIDS.iter().find(|p| p.prefix == prefix))
+            .map(|p| p.model.clone())
+            .unwrap_or(Model::Unknown(pid))
     }
 }
 
 /// Ledger connection information
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConnInfo {
     #[cfg(feature = "transport_usb")]
     Usb(transport::UsbInfo),
@@ -77,14 +206,20 @@ pub enum ConnInfo {
     Tcp(transport::TcpInfo),
     #[cfg(feature = "transport_ble")]
     Ble(transport::BleInfo),
+    #[cfg(feature = "transport_loopback")]
+    Loopback(transport::LoopbackInfo),
+    #[cfg(feature = "transport_webhid")]
+    WebHid(transport::WebHidInfo),
 }
 
 /// Ledger connection types
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Display)]
 pub enum ConnType {
     Usb,
     Tcp,
     Ble,
+    Loopback,
+    WebHid,
 }
 
 impl From<ConnType> for Filters {
@@ -94,11 +229,23 @@ impl From<ConnType> for Filters {
             ConnType::Usb => Filters::Hid,
             ConnType::Tcp => Filters::Tcp,
             ConnType::Ble => Filters::Ble,
+            ConnType::Loopback => Filters::Loopback,
+            ConnType::WebHid => Filters::Hid,
         }
     }
 }
 
 impl std::fmt::Display for ConnInfo {
+    #[cfg_attr(
+        not(any(
+            feature = "transport_usb",
+            feature = "transport_tcp",
+            feature = "transport_ble",
+            feature = "transport_loopback",
+            feature = "transport_webhid"
+        )),
+        allow(unused_variables)
+    )]
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             #[cfg(feature = "transport_usb")]
@@ -107,6 +254,18 @@ impl std::fmt::Display for ConnInfo {
             Self::Tcp(i) => write!(f, "TCP {}", i),
             #[cfg(feature = "transport_ble")]
             Self::Ble(i) => write!(f, "BLE {}", i),
+            #[cfg(feature = "transport_loopback")]
+            Self::Loopback(i) => write!(f, "Loopback {}", i),
+            #[cfg(feature = "transport_webhid")]
+            Self::WebHid(i) => write!(f, "WebHID {}", i),
+            #[cfg(not(any(
+                feature = "transport_usb",
+                feature = "transport_tcp",
+                feature = "transport_ble",
+                feature = "transport_loopback",
+                feature = "transport_webhid"
+            )))]
+            _ => unreachable!(),
         }
     }
 }
@@ -118,6 +277,19 @@ impl From<transport::UsbInfo> for ConnInfo {
     }
 }
 
+#[cfg(feature = "transport_usb")]
+impl TryFrom<LedgerInfo> for transport::UsbInfo {
+    type Error = crate::Error;
+
+    fn try_from(value: LedgerInfo) -> Result<Self, Self::Error> {
+        match value.conn {
+            ConnInfo::Usb(i) => Ok(i),
+            #[allow(unreachable_patterns)]
+            _ => Err(crate::Error::Unknown),
+        }
+    }
+}
+
 #[cfg(feature = "transport_tcp")]
 impl From<transport::TcpInfo> for ConnInfo {
     fn from(value: transport::TcpInfo) -> Self {
@@ -125,6 +297,19 @@ impl From<transport::TcpInfo> for ConnInfo {
     }
 }
 
+#[cfg(feature = "transport_tcp")]
+impl TryFrom<LedgerInfo> for transport::TcpInfo {
+    type Error = crate::Error;
+
+    fn try_from(value: LedgerInfo) -> Result<Self, Self::Error> {
+        match value.conn {
+            ConnInfo::Tcp(i) => Ok(i),
+            #[allow(unreachable_patterns)]
+            _ => Err(crate::Error::Unknown),
+        }
+    }
+}
+
 #[cfg(feature = "transport_ble")]
 impl From<transport::BleInfo> for ConnInfo {
     fn from(value: transport::BleInfo) -> Self {
@@ -132,8 +317,62 @@ impl From<transport::BleInfo> for ConnInfo {
     }
 }
 
+#[cfg(feature = "transport_ble")]
+impl TryFrom<LedgerInfo> for transport::BleInfo {
+    type Error = crate::Error;
+
+    fn try_from(value: LedgerInfo) -> Result<Self, Self::Error> {
+        match value.conn {
+            ConnInfo::Ble(i) => Ok(i),
+            #[allow(unreachable_patterns)]
+            _ => Err(crate::Error::Unknown),
+        }
+    }
+}
+
+#[cfg(feature = "transport_loopback")]
+impl From<transport::LoopbackInfo> for ConnInfo {
+    fn from(value: transport::LoopbackInfo) -> Self {
+        Self::Loopback(value)
+    }
+}
+
+#[cfg(feature = "transport_loopback")]
+impl TryFrom<LedgerInfo> for transport::LoopbackInfo {
+    type Error = crate::Error;
+
+    fn try_from(value: LedgerInfo) -> Result<Self, Self::Error> {
+        match value.conn {
+            ConnInfo::Loopback(i) => Ok(i),
+            #[allow(unreachable_patterns)]
+            _ => Err(crate::Error::Unknown),
+        }
+    }
+}
+
+#[cfg(feature = "transport_webhid")]
+impl From<transport::WebHidInfo> for ConnInfo {
+    fn from(value: transport::WebHidInfo) -> Self {
+        Self::WebHid(value)
+    }
+}
+
+#[cfg(feature = "transport_webhid")]
+impl TryFrom<LedgerInfo> for transport::WebHidInfo {
+    type Error = crate::Error;
+
+    fn try_from(value: LedgerInfo) -> Result<Self, Self::Error> {
+        match value.conn {
+            ConnInfo::WebHid(i) => Ok(i),
+            #[allow(unreachable_patterns)]
+            _ => Err(crate::Error::Unknown),
+        }
+    }
+}
+
 /// Application info object
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AppInfo {
     pub name: String,
     pub version: String,
@@ -142,9 +381,137 @@ pub struct AppInfo {
 
 /// Device info object
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceInfo {
     pub target_id: [u8; 4],
     pub se_version: String,
     pub mcu_version: String,
     pub flags: Vec<u8>,
+    /// MCU bootloader version, where reported by the firmware
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bootloader_version: Option<String>,
+    /// Hardware revision, where reported by the firmware
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub hardware_revision: Option<u8>,
+    /// Language ID, where reported by the firmware
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub language_id: Option<u8>,
+}
+
+/// Device liveness check result, see [Device::ping](crate::Device::ping)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PingInfo {
+    /// Round-trip latency for the ping request (or until failure)
+    pub latency: std::time::Duration,
+    /// Device connection state
+    pub state: ConnState,
+}
+
+/// Device connection state, see [Device::ping](crate::Device::ping)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConnState {
+    /// Device responded, with the running application
+    Ready(AppInfo),
+    /// Device responded but is locked (PIN entry required)
+    Locked,
+    /// Device did not respond prior to the timeout, for example while
+    /// awaiting user input for another request
+    Unresponsive,
+    /// Device or transport disconnected
+    Disconnected,
+}
+
+impl ConnState {
+    /// Check whether the device responded
+    pub fn is_alive(&self) -> bool {
+        matches!(self, Self::Ready(_) | Self::Locked)
+    }
+}
+
+/// Installed application object, see [Device::list_apps](crate::Device::list_apps)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstalledApp {
+    pub name: String,
+    pub flags: u16,
+    pub blocks: u16,
+    pub hash_code_data: [u8; 32],
+    pub hash: [u8; 32],
+}
+
+/// Convert an owned application list entry to an [InstalledApp]
+impl From<ledger_proto::apdus::AppDataOwned> for InstalledApp {
+    fn from(a: ledger_proto::apdus::AppDataOwned) -> Self {
+        Self {
+            name: a.name,
+            flags: a.flags,
+            blocks: a.blocks,
+            hash_code_data: a.hash_code_data,
+            hash: a.hash,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde", feature = "transport_tcp"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_from_pid() {
+        let tests = [
+            (0x0000, Model::Blue),
+            (0x0001, Model::NanoS),
+            (0x1011, Model::NanoS),
+            (0x0004, Model::NanoX),
+            (0x4011, Model::NanoX),
+            (0x0005, Model::NanoSPlus),
+            (0x5015, Model::NanoSPlus),
+            (0x0006, Model::Stax),
+            (0x6011, Model::Stax),
+            (0x0007, Model::Flex),
+            (0x7015, Model::Flex),
+            (0xa011, Model::Unknown(0xa011)),
+        ];
+
+        for (pid, model) in tests {
+            assert_eq!(Model::from_pid(pid), model, "pid: 0x{pid:04x}");
+        }
+    }
+
+    #[test]
+    fn model_from_target_id() {
+        let tests = [
+            ([0x31, 0x10, 0x00, 0x04], Model::NanoS),
+            ([0x33, 0x00, 0x00, 0x04], Model::NanoX),
+            ([0x33, 0x10, 0x00, 0x04], Model::NanoSPlus),
+            ([0x33, 0x20, 0x00, 0x04], Model::Stax),
+            ([0x33, 0x30, 0x00, 0x04], Model::Flex),
+            ([0x33, 0x40, 0x00, 0x04], Model::Unknown(0)),
+        ];
+
+        for (target_id, model) in tests {
+            assert_eq!(
+                Model::from_target_id(target_id),
+                model,
+                "target: {target_id:02x?}"
+            );
+        }
+    }
+
+    #[test]
+    fn serde_json_info() {
+        let i = LedgerInfo {
+            model: Model::NanoSPlus,
+            conn: transport::TcpInfo::default().into(),
+        };
+
+        let s = serde_json::to_string(&i).unwrap();
+        assert_eq!(
+            s,
+            r#"{"model":"NanoSPlus","conn":{"Tcp":{"addr":"127.0.0.1:1237"}}}"#
+        );
+        assert_eq!(serde_json::from_str::<LedgerInfo>(&s).unwrap(), i);
+    }
 }
@@ -6,19 +6,65 @@
 //!
 //! [LedgerProvider] and [LedgerHandle] provide a high-level tokio-compatible [Transport]
 //! for application integration, supporting connecting to and interacting with ledger devices.
-//! This uses a pinned thread to avoid thread safety issues with `hidapi` and async executors.
+//! This uses a pinned thread to avoid thread safety issues with `hidapi` and async executors,
+//! and is gated by the (default) `provider` feature.
+//!
+//! [LocalProvider] provides a single-threaded alternative for local executors such as the browser,
+//! sharing a [GenericTransport](transport::GenericTransport) between cloned handles.
+//! Where a single device is required, [PinnedExchange] (also gated by `provider`) confines
+//! any [Exchange] to a worker thread behind a `Send` handle.
+//!
+//! ## WASM
+//!
+//! `ledger-lib` builds for `wasm32` targets with default features disabled, using browser timers
+//! in place of `tokio`. Native transports and the `provider` feature are not available on `wasm32`,
+//! however [Device] and [Exchange] may be used with any compatible [Exchange] implementation.
 //!
 //! Low-level [Transport] implementations are provided for [USB/HID](transport::UsbTransport),
 //! [BLE](transport::BleTransport) and [TCP](transport::TcpTransport), with a [Generic](transport::GenericTransport)
 //! implementation providing a common interface over all enabled transports.
 //!
-//! ## Safety
+//! Adapters for the Zondax [ledger-transport](https://docs.rs/ledger-transport) traits are
+//! available in the `zondax` module with the `ledger_transport` feature, and a versioned
+//! JSON / CBOR APDU trace event schema is provided in the `trace` module with the `trace` feature.
+//!
+//! A synchronous std-only TCP client for Speculos is available in the `blocking` module with
+//! the `blocking_tcp` feature, which may be used with default features disabled for minimal builds.
+//!
+//! [ApduServer](server::ApduServer) (with the `transport_tcp` feature) exposes any [Exchange]
+//! over the Speculos TCP APDU protocol, allowing Speculos-oriented tooling to use real devices.
+//!
+//! ## Timeouts
+//!
+//! [Device::request] accepts either a [Duration](std::time::Duration) bounding the whole exchange,
+//! or [Timeouts] separating transport inactivity from user confirmation time for requests such as
+//! signing, which may await the user indefinitely with [Timeouts::wait_for_user]. Expiry of the
+//! user deadline is reported as [Error::UserTimeout], distinct from transport [Error::Timeout]s.
 //!
-//! Transports are currently marked as `Send` due to limitations of [async_trait] and are NOT all
-//! thread safe. If you're calling this from an async context, please use [LedgerProvider].
+//! ## Runtimes
 //!
-//! This will be corrected when the unstable async trait feature is stabilised,
-//! which until then can be opted-into using the `unstable_async_trait` feature
+//! Timers and the TCP transport use `tokio` by default (`runtime_tokio` feature), alternatively
+//! `runtime_async_io` may be enabled with default features disabled for use with `smol`,
+//! `async-std` or other executors. The USB transport requires only a timer so works with any
+//! executor, while [LedgerProvider] and the BLE transport (via `btleplug`) require `tokio`.
+//!
+//! ## Thread Safety
+//!
+//! [Transport], [Exchange] and [Device] use native `async fn` in traits, so whether a returned
+//! future is `Send` is determined by the concrete implementation rather than the trait.
+//! All provided transports and devices are `Send`, however `hidapi` requires a single
+//! [UsbTransport](transport::UsbTransport) instance per application, so [LedgerProvider]
+//! should be used for shared access from multi-threaded async contexts.
+//!
+//! ## Zeroize
+//!
+//! The `zeroize` feature wipes intermediate APDU buffers once they are no longer required,
+//! covering [Device::request] response copies, [LedgerProvider] request channels, and
+//! USB / BLE / TCP framing buffers. This does _not_ cover:
+//!
+//! - Caller-owned buffers passed to [Device::request] or responses returned from [Exchange::exchange]
+//! - Buffers internal to `hidapi`, `btleplug`, `tokio` or the operating system
+//! - APDU data emitted via `debug` / `trace` logging, which should be disabled where this is a concern
 //!
 //! ## Examples
 //!
@@ -49,15 +95,19 @@
 //! }
 //! ```
 
-#![cfg_attr(feature = "unstable_async_trait", feature(async_fn_in_trait))]
-#![cfg_attr(feature = "unstable_async_trait", feature(negative_impls))]
+#[cfg(all(
+    feature = "transport_tcp",
+    not(target_arch = "wasm32"),
+    not(any(feature = "runtime_tokio", feature = "runtime_async_io"))
+))]
+compile_error!("`transport_tcp` requires either the `runtime_tokio` or `runtime_async_io` feature");
 
 use std::time::Duration;
 
 use tracing::debug;
 
 use ledger_proto::{
-    apdus::{ExitAppReq, RunAppReq},
+    apdus::{ExitAppReq, RunAppError, RunAppReq},
     GenericApdu, StatusCode,
 };
 
@@ -65,26 +115,59 @@ pub mod info;
 pub use info::LedgerInfo;
 
 mod error;
-pub use error::Error;
+pub use error::{Error, ErrorContext, Operation};
 
 pub mod transport;
 pub use transport::Transport;
 
+#[cfg(feature = "provider")]
 mod provider;
+#[cfg(feature = "provider")]
 pub use provider::{LedgerHandle, LedgerProvider};
 
-mod device;
+#[cfg(feature = "provider")]
+mod pinned;
+#[cfg(feature = "provider")]
+pub use pinned::PinnedExchange;
+
+mod local;
+pub use local::LocalProvider;
+
+#[cfg(feature = "blocking_tcp")]
+pub mod blocking;
+
+#[cfg(feature = "transport_tcp")]
+pub mod server;
+
+mod rt;
+
+mod wipe;
+
+#[cfg(feature = "ledger_transport")]
+pub mod zondax;
+
+#[cfg(feature = "trace")]
+pub mod trace;
+
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzzing;
+
+pub(crate) mod device;
 pub use device::Device;
 
+pub mod genuine;
+
 /// Default timeout helper for use with [Device] and [Exchange]
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
 
 /// Device discovery filter
-#[derive(Copy, Clone, Debug, PartialEq, strum::Display)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, strum::Display)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 #[non_exhaustive]
 pub enum Filters {
     /// List all devices available using supported transport
+    #[default]
     Any,
     /// List only HID devices
     Hid,
@@ -92,26 +175,119 @@ pub enum Filters {
     Tcp,
     /// List only BLE device
     Ble,
+    /// List only loopback devices (requires `transport_loopback` feature)
+    Loopback,
 }
 
-impl Default for Filters {
+/// Upper bound applied where transports cannot wait indefinitely for user confirmation
+const USER_WAIT_MAX: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Request timeouts, separating transport inactivity from user confirmation time.
+///
+/// Signing APDUs may legitimately wait minutes for the user to review a transaction,
+/// so where [Timeouts::user] is set the wait for the first response data is bounded by the
+/// user deadline (returning [Error::UserTimeout]), while the remainder of the exchange is
+/// bounded by the transport timeout (returning [Error::Timeout]).
+///
+/// A plain [Duration] converts to a transport-only timeout, matching the behaviour of [Exchange::exchange].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Timeouts {
+    /// Transport inactivity timeout
+    pub transport: Duration,
+
+    /// User confirmation wait, where the request requires user interaction
+    pub user: UserWait,
+}
+
+/// User confirmation wait for [Timeouts]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum UserWait {
+    /// Request does not await the user, responses are bounded by the transport timeout
+    #[default]
+    None,
+    /// Await the user for up to the provided duration
+    Deadline(Duration),
+    /// Await the user indefinitely
+    Indefinite,
+}
+
+impl Timeouts {
+    /// Create transport-only timeouts
+    pub fn new(transport: Duration) -> Self {
+        Self {
+            transport,
+            user: UserWait::None,
+        }
+    }
+
+    /// Await user confirmation for up to the provided duration
+    pub fn with_user_deadline(mut self, deadline: Duration) -> Self {
+        self.user = UserWait::Deadline(deadline);
+        self
+    }
+
+    /// Await user confirmation indefinitely
+    pub fn wait_for_user(mut self) -> Self {
+        self.user = UserWait::Indefinite;
+        self
+    }
+}
+
+impl Default for Timeouts {
     fn default() -> Self {
-        Self::Any
+        Self::new(DEFAULT_TIMEOUT)
+    }
+}
+
+impl From<Duration> for Timeouts {
+    fn from(transport: Duration) -> Self {
+        Self::new(transport)
     }
 }
 
 /// [Exchange] trait provides a low-level interface for byte-wise exchange of APDU commands with a ledger devices
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+///
+/// Note `Send` bounds are not applied to returned futures, these are `Send` where the implementing type is.
+#[allow(async_fn_in_trait)]
 pub trait Exchange {
     async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error>;
+
+    /// Exchange an APDU with separate transport and user confirmation [Timeouts].
+    ///
+    /// The default implementation applies the user deadline to the whole exchange (reporting
+    /// [Error::UserTimeout] on expiry), transports able to detect stalls once a response has
+    /// started override this to apply the transport timeout separately.
+    async fn exchange_timeouts(
+        &mut self,
+        command: &[u8],
+        timeouts: Timeouts,
+    ) -> Result<Vec<u8>, Error> {
+        let deadline = match timeouts.user {
+            UserWait::None => return self.exchange(command, timeouts.transport).await,
+            UserWait::Deadline(d) => d,
+            UserWait::Indefinite => USER_WAIT_MAX,
+        };
+
+        self.exchange(command, deadline).await.map_err(|e| match e {
+            Error::Timeout => Error::UserTimeout,
+            _ => e,
+        })
+    }
 }
 
 /// Blanket [Exchange] impl for mutable references
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
-impl<T: Exchange + Send> Exchange for &mut T {
+impl<T: Exchange> Exchange for &mut T {
     async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
         <T as Exchange>::exchange(self, command, timeout).await
     }
+
+    async fn exchange_timeouts(
+        &mut self,
+        command: &[u8],
+        timeouts: Timeouts,
+    ) -> Result<Vec<u8>, Error> {
+        <T as Exchange>::exchange_timeouts(self, command, timeouts).await
+    }
 }
 
 /// Launch an application by name and return a device handle.
@@ -125,6 +301,16 @@ impl<T: Exchange + Send> Exchange for &mut T {
 /// contexts, and the lack of reported serial numbers by ledger devices,
 /// this is not incredibly reliable. Use at your own risk.
 ///
+#[cfg_attr(
+    not(any(
+        feature = "transport_usb",
+        feature = "transport_tcp",
+        feature = "transport_ble",
+        feature = "transport_loopback",
+        feature = "transport_webhid"
+    )),
+    allow(unused_variables, unused_mut)
+)]
 pub async fn launch_app<T>(
     mut t: T,
     info: <T as Transport>::Info,
@@ -133,8 +319,7 @@ pub async fn launch_app<T>(
     timeout: Duration,
 ) -> Result<<T as Transport>::Device, Error>
 where
-    T: Transport<Info = LedgerInfo, Filters = Filters> + Send,
-    <T as Transport>::Device: Send,
+    T: Transport<Info = LedgerInfo, Filters = Filters>,
 {
     let mut buff = [0u8; 256];
 
@@ -167,7 +352,7 @@ where
         // Close and re-connect to the device
         drop(d);
 
-        tokio::time::sleep(Duration::from_secs(opts.reconnect_delay_s as u64)).await;
+        rt::sleep(Duration::from_secs(opts.reconnect_delay_s as u64)).await;
 
         d = reconnect(&mut t, info.clone(), opts).await?;
     }
@@ -177,26 +362,39 @@ where
         debug!("Issuing run request ({i}/10)");
 
         let resp = d
-            .request::<GenericApdu>(RunAppReq::new(app_name), &mut buff, timeout)
+            .request_with_status::<GenericApdu>(RunAppReq::new(app_name), &mut buff, timeout)
             .await;
 
         // Handle responses
         match resp {
-            // Ok response or status, app opened
-            Ok(_) | Err(Error::Status(StatusCode::Ok)) => {
+            // Error status (with or without payload), launch failed
+            Ok(r) if r.sw != u16::from(StatusCode::Ok) => {
+                return Err(RunAppError::from_status(r.sw)
+                    .unwrap_or(RunAppError::Status(r.status()))
+                    .into());
+            }
+            // Ok status, app opened
+            Ok(_) => {
                 debug!("Run request complete, reconnecting to {info:?}");
 
                 // Re-connect to the device following app loading
                 drop(d);
 
-                tokio::time::sleep(Duration::from_secs(opts.reconnect_delay_s as u64)).await;
+                rt::sleep(Duration::from_secs(opts.reconnect_delay_s as u64)).await;
 
                 d = reconnect(&mut t, info.clone(), opts).await?;
 
                 return Ok(d);
             }
             // Empty response, pending reply
-            Err(Error::EmptyResponse) => tokio::time::sleep(Duration::from_secs(1)).await,
+            Err(e) if matches!(e.root(), Error::EmptyResponse) => {
+                rt::sleep(Duration::from_secs(1)).await
+            }
+            // Status-only error response, decode launch failure
+            Err(Error::Status(s)) => match RunAppError::from_status(u16::from(s)) {
+                Some(e) => return Err(e.into()),
+                None => return Err(Error::Status(s)),
+            },
             // Error response, something failed
             Err(e) => return Err(e),
         }
@@ -226,6 +424,16 @@ impl Default for LaunchAppOpts {
 }
 
 /// Helper to reconnect to devices
+#[cfg_attr(
+    not(any(
+        feature = "transport_usb",
+        feature = "transport_tcp",
+        feature = "transport_ble",
+        feature = "transport_loopback",
+        feature = "transport_webhid"
+    )),
+    allow(unused_mut)
+)]
 async fn reconnect<T: Transport<Info = LedgerInfo, Filters = Filters>>(
     mut t: T,
     info: LedgerInfo,
@@ -256,7 +464,7 @@ async fn reconnect<T: Transport<Info = LedgerInfo, Filters = Filters>>(
                 new_info = Some(i.clone());
                 break;
             }
-            None => tokio::time::sleep(Duration::from_secs(1)).await,
+            None => rt::sleep(Duration::from_secs(1)).await,
         };
     }
 
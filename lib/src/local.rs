@@ -0,0 +1,90 @@
+//! [LocalProvider] provides a single-threaded interface for interacting with ledger devices,
+//! intended for local executors such as the browser (`wasm_bindgen_futures`) or tokio `LocalSet`s.
+
+use std::rc::Rc;
+
+use futures::lock::Mutex;
+
+use crate::{
+    error::Error,
+    info::LedgerInfo,
+    transport::{GenericDevice, GenericTransport, Transport, TransportFilters},
+    Filters,
+};
+
+/// Local (single-threaded) ledger provider, shares a [GenericTransport]
+/// between cloned handles without requiring a pinned thread.
+///
+/// This is intentionally `!Send`, use [LedgerProvider](crate::LedgerProvider)
+/// for multi-threaded contexts where available.
+#[derive(Clone)]
+pub struct LocalProvider {
+    t: Rc<Mutex<GenericTransport>>,
+
+    /// Per-transport filters applied to list operations
+    filters: TransportFilters,
+}
+
+impl LocalProvider {
+    /// Create a new local provider using all enabled transports
+    pub async fn init() -> Result<Self, Error> {
+        let t = GenericTransport::new().await?;
+        Ok(Self::new(t))
+    }
+
+    /// Create a local provider using an existing [GenericTransport]
+    pub fn new(t: GenericTransport) -> Self {
+        Self {
+            t: Rc::new(Mutex::new(t)),
+            filters: TransportFilters::default(),
+        }
+    }
+
+    /// Set per-transport filters for list operations using this provider handle
+    pub fn with_filters(mut self, filters: TransportFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+}
+
+/// [Transport] implementation for [LocalProvider]
+impl Transport for LocalProvider {
+    type Device = GenericDevice;
+    type Info = LedgerInfo;
+    type Filters = Filters;
+
+    /// List available devices using the specified filter
+    async fn list(&mut self, filters: Filters) -> Result<Vec<LedgerInfo>, Error> {
+        let mut t = self.t.lock().await;
+        t.list_filtered(filters, &self.filters).await
+    }
+
+    /// Connect to an available device
+    async fn connect(&mut self, info: LedgerInfo) -> Result<GenericDevice, Error> {
+        let mut t = self.t.lock().await;
+        t.connect(info).await
+    }
+}
+
+#[cfg(all(test, feature = "transport_loopback"))]
+mod tests {
+    use super::*;
+    use crate::{Exchange, DEFAULT_TIMEOUT};
+
+    #[tokio::test]
+    async fn local_provider_loopback() {
+        let t = GenericTransport::init(Filters::Loopback).await.unwrap();
+        let mut p = LocalProvider::new(t);
+
+        // Clone to check transport is shared between handles
+        let mut p2 = p.clone();
+
+        let devices = p.list(Filters::Loopback).await.unwrap();
+        assert_eq!(devices.len(), 1);
+
+        let mut d = p2.connect(devices[0].clone()).await.unwrap();
+        let resp = d.exchange(&[0xe0, 0x01], DEFAULT_TIMEOUT).await.unwrap();
+
+        assert_eq!(&resp, &[0xe0, 0x01, 0x90, 0x00]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Thread-confined [Exchange] wrapper, allowing `!Send` devices (or devices whose
+//! libraries are not okay with changing threads) to be used from multi-threaded executors.
+//!
+//! The inner [Exchange] is created and driven on a dedicated worker thread,
+//! with requests and responses passed over channels. The worker exits once
+//! the [PinnedExchange] is dropped.
+//!
+//! ```no_run
+//! use ledger_lib::{transport::{GenericTransport, Transport}, Exchange, PinnedExchange, DEFAULT_TIMEOUT};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     // Connect to a device on the worker thread
+//!     let mut d = PinnedExchange::new(|| async {
+//!         let mut t = GenericTransport::new().await?;
+//!         t.connect_any(Default::default()).await
+//!     })
+//!     .await?;
+//!
+//!     // Use the `Send` handle from any task (here requesting app info)
+//!     let cmd = [0xb0, 0x01, 0x00, 0x00, 0x00];
+//!     let r = tokio::spawn(async move { d.exchange(&cmd, DEFAULT_TIMEOUT).await }).await??;
+//!     println!("response: {r:02x?}");
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::{future::Future, time::Duration};
+
+use tokio::{
+    runtime::Builder,
+    sync::{
+        mpsc::{unbounded_channel, UnboundedSender},
+        oneshot,
+    },
+};
+use tracing::{debug, error};
+
+use crate::{Error, Exchange, Timeouts};
+
+/// Request to the worker thread, containing the command, timeouts and response channel
+type PinnedReq = (Vec<u8>, Timeouts, oneshot::Sender<Result<Vec<u8>, Error>>);
+
+/// `Send` handle to an [Exchange] confined to a dedicated worker thread
+#[derive(Debug)]
+pub struct PinnedExchange {
+    req_tx: UnboundedSender<PinnedReq>,
+}
+
+impl PinnedExchange {
+    /// Create a [PinnedExchange], calling `f` on a new worker thread to construct
+    /// the inner [Exchange] and returning any error from this
+    pub async fn new<E, F, Fut>(f: F) -> Result<Self, Error>
+    where
+        E: Exchange + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<E, Error>> + 'static,
+    {
+        let (req_tx, mut req_rx) = unbounded_channel::<PinnedReq>();
+        let (ready_tx, ready_rx) = oneshot::channel::<Result<(), Error>>();
+
+        // Setup runtime for the worker thread, allowing use of timers and IO in the inner exchange
+        let rt = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| {
+                error!("Failed to create pinned exchange runtime: {e:?}");
+                Error::Unknown
+            })?;
+
+        std::thread::spawn(move || {
+            rt.block_on(async move {
+                // Create the inner exchange on this thread
+                let mut e = match f().await {
+                    Ok(e) => {
+                        let _ = ready_tx.send(Ok(()));
+                        e
+                    }
+                    Err(e) => {
+                        error!("Failed to create pinned exchange: {e:?}");
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+
+                // Handle requests until all handles are dropped
+                while let Some((command, timeouts, resp_tx)) = req_rx.recv().await {
+                    let r = e.exchange_timeouts(&command, timeouts).await;
+                    let _ = resp_tx.send(r);
+                }
+
+                debug!("Pinned exchange closed");
+            });
+        });
+
+        ready_rx.await.map_err(|_| Error::Unknown)??;
+
+        Ok(Self { req_tx })
+    }
+}
+
+/// [Exchange] implementation for [PinnedExchange], forwarding requests to the worker thread
+impl Exchange for PinnedExchange {
+    async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        self.exchange_timeouts(command, timeout.into()).await
+    }
+
+    async fn exchange_timeouts(
+        &mut self,
+        command: &[u8],
+        timeouts: Timeouts,
+    ) -> Result<Vec<u8>, Error> {
+        let (tx, rx) = oneshot::channel();
+
+        self.req_tx
+            .send((command.to_vec(), timeouts, tx))
+            .map_err(|_| Error::Unknown)?;
+
+        rx.await.map_err(|_| Error::Unknown)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    /// `!Send` exchange recording commands and echoing these as responses
+    struct LocalEcho(Rc<RefCell<Vec<Vec<u8>>>>);
+
+    impl Exchange for LocalEcho {
+        async fn exchange(&mut self, command: &[u8], _timeout: Duration) -> Result<Vec<u8>, Error> {
+            self.0.borrow_mut().push(command.to_vec());
+            Ok(command.to_vec())
+        }
+    }
+
+    #[tokio::test]
+    async fn pinned_exchange_send() {
+        let mut e = PinnedExchange::new(|| async { Ok(LocalEcho(Default::default())) })
+            .await
+            .unwrap();
+
+        // Handles can be moved across `tokio::spawn` boundaries
+        let r = tokio::spawn(async move {
+            let a = e.exchange(&[0xaa], Duration::from_secs(1)).await?;
+            let b = e
+                .exchange_timeouts(&[0xbb, 0xcc], Timeouts::default().wait_for_user())
+                .await?;
+            Ok::<_, Error>((a, b))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(r.unwrap(), (vec![0xaa], vec![0xbb, 0xcc]));
+    }
+
+    #[tokio::test]
+    async fn pinned_exchange_init_error() {
+        let r = PinnedExchange::new(|| async { Err::<LocalEcho, _>(Error::NoDevices) }).await;
+        assert!(matches!(r, Err(Error::NoDevices)));
+    }
+}
@@ -11,7 +11,8 @@ use crate::{
     error::Error,
     provider::{LedgerReq, LedgerResp, ReqChannel},
     transport::{GenericDevice, GenericTransport, Transport},
-    Exchange,
+    wipe::Scratch,
+    Exchange, Filters,
 };
 
 /// Context for provider task
@@ -33,8 +34,9 @@ pub struct ProviderContext {
 }
 
 impl ProviderContext {
-    /// Create a new provider context with a thread-pinned task for managing ledger operations
-    pub async fn new() -> Self {
+    /// Create a new provider context with a thread-pinned task for managing ledger operations,
+    /// using enabled transports matching `filters`
+    pub async fn new(filters: Filters) -> Self {
         // Setup channel for interacting with the pinned provider task
         let (req_tx, req_rx) = unbounded_channel::<(LedgerReq, UnboundedSender<LedgerResp>)>();
 
@@ -55,7 +57,7 @@ impl ProviderContext {
             // (HidApi and other libraries are not thread safe / okay with changing threads)
             local.spawn_local(async move {
                 // Setup ledger provider task
-                let mut p = match ProviderImpl::new(req_rx).await {
+                let mut p = match ProviderImpl::new(filters, req_rx).await {
                     Ok(v) => v,
                     Err(e) => {
                         error!("Failed to initialise ledger task: {:?}", e);
@@ -82,10 +84,11 @@ impl ProviderContext {
 impl ProviderImpl {
     /// Create provider instance
     pub async fn new(
+        filters: Filters,
         req_rx: UnboundedReceiver<(LedgerReq, UnboundedSender<LedgerResp>)>,
     ) -> Result<Self, Error> {
         // Setup transport
-        let t = match GenericTransport::new().await {
+        let t = match GenericTransport::init(filters).await {
             Ok(v) => v,
             Err(e) => {
                 error!("Failed to create transport: {}", e);
@@ -109,7 +112,14 @@ impl ProviderImpl {
         while let Some((req, tx)) = self.req_rx.recv().await {
             debug!("LedgerProvider request: {:02x?}", req);
 
-            if let Some(resp) = self.handle_req(&req).await {
+            let resp = self.handle_req(&req).await;
+
+            // Wipe request data once handled
+            if let LedgerReq::Req(_, apdu, _) = req {
+                drop(Scratch::new(apdu));
+            }
+
+            if let Some(resp) = resp {
                 debug!("LedgerProvider response: {:02x?}", resp);
 
                 if let Err(e) = tx.send(resp) {
@@ -122,10 +132,20 @@ impl ProviderImpl {
     }
 
     /// Handle incoming requests and generate responses
+    #[cfg_attr(
+        not(any(
+            feature = "transport_usb",
+            feature = "transport_tcp",
+            feature = "transport_ble",
+            feature = "transport_loopback",
+            feature = "transport_webhid"
+        )),
+        allow(unused_variables)
+    )]
     async fn handle_req(&mut self, req: &LedgerReq) -> Option<LedgerResp> {
         let resp = match req {
             // List devices using the provided filters
-            LedgerReq::List(filters) => match self.t.list(*filters).await {
+            LedgerReq::List(filters, opts) => match self.t.list_filtered(*filters, opts).await {
                 Ok(i) => LedgerResp::Devices(i),
                 Err(e) => LedgerResp::Error(e),
             },
@@ -143,7 +163,7 @@ impl ProviderImpl {
                         // If the handle is available and in-use, return an error
                         Ok(true) => {
                             warn!("Device {k} already in use");
-                            return Some(LedgerResp::Error(Error::DeviceInUse));
+                            return Some(LedgerResp::Error(Error::DeviceInUse { holder: None }));
                         }
                         // Otherwise, drop the handle and continue connection
                         Ok(false) => {
@@ -177,7 +197,7 @@ impl ProviderImpl {
                 // Return device handle
                 LedgerResp::Handle(index)
             }
-            LedgerReq::Req(index, apdu, timeout) => {
+            LedgerReq::Req(index, apdu, timeouts) => {
                 // Fetch device handle
                 let d = match self.devices.get_mut(index) {
                     Some(d) => d,
@@ -188,15 +208,19 @@ impl ProviderImpl {
                 };
 
                 // Issue APDU request to device and return response
-                match Exchange::exchange(d, apdu, *timeout).await {
+                match Exchange::exchange_timeouts(d, apdu, *timeouts).await {
                     Ok(r) => LedgerResp::Resp(r),
                     Err(e) => LedgerResp::Error(e),
                 }
             }
             LedgerReq::Close(index) => {
-                // Drop device handle
+                // Close and drop device handle
                 if let Some(d) = self.devices.remove(index) {
-                    debug!("Closed device {index}: {:?}", d.info());
+                    let info = d.info();
+                    match d.close().await {
+                        Ok(_) => debug!("Closed device {index}: {info:?}"),
+                        Err(e) => warn!("Failed to close device {index} ({info:?}): {e:?}"),
+                    }
                 }
 
                 // no response for close message (channel no longer exists)
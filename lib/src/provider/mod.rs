@@ -3,6 +3,7 @@
 
 use std::time::Duration;
 
+use futures::Stream;
 use tokio::sync::{
     mpsc::{unbounded_channel, UnboundedSender},
     OnceCell,
@@ -11,11 +12,19 @@ use tokio::sync::{
 mod context;
 use context::ProviderContext;
 
-use crate::{error::Error, info::LedgerInfo, transport::Transport, Exchange, Filters};
+use crate::{
+    error::{Error, ErrorContext, Operation},
+    info::LedgerInfo,
+    transport::{watch, DeviceEvent, Transport, TransportFilters},
+    Exchange, Filters, Timeouts,
+};
 
 /// Ledger provider manages device discovery and connection
 pub struct LedgerProvider {
     req_tx: ReqChannel,
+
+    /// Per-transport filters applied to list operations
+    filters: TransportFilters,
 }
 
 /// Ledger device handle for interacting with [LedgerProvider] backed devices
@@ -34,13 +43,13 @@ pub struct LedgerHandle {
 #[derive(Clone, Debug, PartialEq)]
 pub enum LedgerReq {
     /// List available devices
-    List(Filters),
+    List(Filters, TransportFilters),
 
     /// Connect to a specific device
     Connect(LedgerInfo),
 
     /// APDU request issued to a device handle
-    Req(usize, Vec<u8>, Duration),
+    Req(usize, Vec<u8>, Timeouts),
 
     /// Close the device handle
     Close(usize),
@@ -73,18 +82,45 @@ impl LedgerProvider {
     pub async fn init() -> Self {
         // Fetch or create the provider context
         let ctx = PROVIDER_CTX
-            .get_or_init(|| async { ProviderContext::new().await })
+            .get_or_init(|| async { ProviderContext::new(Filters::Any).await })
             .await;
 
         // Return handle to request channel
         Self {
             req_tx: ctx.req_tx(),
+            filters: TransportFilters::default(),
+        }
+    }
+
+    /// Create a standalone ledger provider (rather than connecting to the shared instance),
+    /// using only enabled transports matching `filters` (see [GenericTransport::init](crate::transport::GenericTransport::init))
+    pub async fn standalone(filters: Filters) -> Self {
+        let ctx = ProviderContext::new(filters).await;
+
+        Self {
+            req_tx: ctx.req_tx(),
+            filters: TransportFilters::default(),
         }
     }
+
+    /// Set per-transport filters for list operations using this provider handle
+    pub fn with_filters(mut self, filters: TransportFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Watch for devices being connected or disconnected, listing devices every `interval`
+    /// (see [watch](crate::transport::watch))
+    pub fn watch(
+        &mut self,
+        filters: Filters,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<DeviceEvent, Error>> + '_ {
+        watch(self, filters, interval)
+    }
 }
 
 /// [Transport] implementation for high-level [LedgerProvider]
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Transport for LedgerProvider {
     type Device = LedgerHandle;
     type Info = LedgerInfo;
@@ -94,27 +130,41 @@ impl Transport for LedgerProvider {
     async fn list(&mut self, filters: Filters) -> Result<Vec<LedgerInfo>, Error> {
         let (tx, mut rx) = unbounded_channel::<LedgerResp>();
 
+        let ctx = ErrorContext::new(Operation::List);
+
         // Send control request
         self.req_tx
-            .send((LedgerReq::List(filters), tx))
-            .map_err(|_| Error::Unknown)?;
+            .send((LedgerReq::List(filters, self.filters.clone()), tx))
+            .map_err(|_| Error::Unknown.with_context(ctx.clone()))?;
 
         // Await resposne
         match rx.recv().await {
             Some(LedgerResp::Devices(i)) => Ok(i),
-            Some(LedgerResp::Error(e)) => Err(e),
-            _ => Err(Error::Unknown),
+            Some(LedgerResp::Error(e)) => Err(e.with_context(ctx)),
+            _ => Err(Error::Unknown.with_context(ctx)),
         }
     }
 
     /// Connect to an available device
+    #[cfg_attr(
+        not(any(
+            feature = "transport_usb",
+            feature = "transport_tcp",
+            feature = "transport_ble",
+            feature = "transport_loopback",
+            feature = "transport_webhid"
+        )),
+        allow(unused_variables, unused_mut)
+    )]
     async fn connect(&mut self, info: LedgerInfo) -> Result<LedgerHandle, Error> {
         let (tx, mut rx) = unbounded_channel::<LedgerResp>();
 
+        let ctx = ErrorContext::new(Operation::Connect).with_info(info.clone());
+
         // Send control request
         self.req_tx
             .send((LedgerReq::Connect(info.clone()), tx))
-            .map_err(|_| Error::Unknown)?;
+            .map_err(|_| Error::Unknown.with_context(ctx.clone()))?;
 
         // Await resposne
         match rx.recv().await {
@@ -123,28 +173,47 @@ impl Transport for LedgerProvider {
                 index,
                 req_tx: self.req_tx.clone(),
             }),
-            Some(LedgerResp::Error(e)) => Err(e),
-            _ => Err(Error::Unknown),
+            Some(LedgerResp::Error(e)) => Err(e.with_context(ctx)),
+            _ => Err(Error::Unknown.with_context(ctx)),
         }
     }
 }
 
 /// [Exchange] implementation for [LedgerProvider] backed [LedgerHandle]
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Exchange for LedgerHandle {
     async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        self.exchange_timeouts(command, timeout.into()).await
+    }
+
+    #[cfg_attr(
+        not(any(
+            feature = "transport_usb",
+            feature = "transport_tcp",
+            feature = "transport_ble",
+            feature = "transport_loopback",
+            feature = "transport_webhid"
+        )),
+        allow(unused_variables, unused_mut)
+    )]
+    async fn exchange_timeouts(
+        &mut self,
+        command: &[u8],
+        timeouts: Timeouts,
+    ) -> Result<Vec<u8>, Error> {
         let (tx, mut rx) = unbounded_channel::<LedgerResp>();
 
+        let ctx = ErrorContext::new(Operation::Exchange).with_info(self.info.clone());
+
         // Send APDU request
         self.req_tx
-            .send((LedgerReq::Req(self.index, command.to_vec(), timeout), tx))
-            .map_err(|_| Error::Unknown)?;
+            .send((LedgerReq::Req(self.index, command.to_vec(), timeouts), tx))
+            .map_err(|_| Error::Unknown.with_context(ctx.clone()))?;
 
         // Await APDU response
         match rx.recv().await {
             Some(LedgerResp::Resp(data)) => Ok(data),
-            Some(LedgerResp::Error(e)) => Err(e),
-            _ => Err(Error::Unknown),
+            Some(LedgerResp::Error(e)) => Err(e.with_context(ctx)),
+            _ => Err(Error::Unknown.with_context(ctx)),
         }
     }
 }
@@ -156,3 +225,23 @@ impl Drop for LedgerHandle {
         let _ = self.req_tx.send((LedgerReq::Close(self.index), tx));
     }
 }
+
+#[cfg(all(test, feature = "transport_loopback"))]
+mod tests {
+    use super::*;
+    use crate::{info::ConnType, DEFAULT_TIMEOUT};
+
+    #[tokio::test]
+    async fn provider_loopback() {
+        let mut p = LedgerProvider::standalone(Filters::Loopback).await;
+
+        let devices = p.list(Filters::Loopback).await.unwrap();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].kind(), ConnType::Loopback);
+
+        let mut d = p.connect(devices[0].clone()).await.unwrap();
+        let resp = d.exchange(&[0xe0, 0x01], DEFAULT_TIMEOUT).await.unwrap();
+
+        assert_eq!(&resp, &[0xe0, 0x01, 0x90, 0x00]);
+    }
+}
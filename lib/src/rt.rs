@@ -0,0 +1,212 @@
+//! Runtime shim providing timers independent of the async executor in use.
+//!
+//! Timers are selected by feature, with `runtime_tokio` taking precedence where multiple are enabled:
+//!
+//! - `runtime_tokio` (default) uses `tokio` timers, requiring a `tokio` runtime with time enabled
+//! - `runtime_async_io` uses `async-io` timers, compatible with `smol`, `async-std` and other executors
+//! - wasm32 targets use browser timers (via `gloo-timers`)
+//! - otherwise a thread-backed timer is used, allowing use with any executor at the cost of a thread per timer
+//!
+//! The [LedgerProvider](crate::LedgerProvider) always runs on its own `tokio` runtime, so
+//! this is only relevant to direct use of transports and [LocalProvider](crate::LocalProvider).
+
+use std::time::Duration;
+
+#[cfg(any(
+    feature = "transport_tcp",
+    feature = "transport_ble",
+    feature = "transport_webhid"
+))]
+use std::{future::Future, pin::pin};
+
+#[cfg(any(
+    feature = "transport_tcp",
+    feature = "transport_ble",
+    feature = "transport_webhid"
+))]
+use futures::future::{select, Either};
+
+#[cfg(any(
+    feature = "transport_usb",
+    feature = "transport_tcp",
+    feature = "transport_ble",
+    feature = "transport_webhid"
+))]
+use crate::Error;
+
+/// Sleep for the provided duration
+#[cfg(all(not(target_arch = "wasm32"), feature = "runtime_tokio"))]
+pub async fn sleep(d: Duration) {
+    tokio::time::sleep(d).await
+}
+
+/// Sleep for the provided duration
+#[cfg(all(
+    not(target_arch = "wasm32"),
+    not(feature = "runtime_tokio"),
+    feature = "runtime_async_io"
+))]
+pub async fn sleep(d: Duration) {
+    async_io::Timer::after(d).await;
+}
+
+/// Sleep for the provided duration
+#[cfg(all(
+    not(target_arch = "wasm32"),
+    not(feature = "runtime_tokio"),
+    not(feature = "runtime_async_io")
+))]
+pub async fn sleep(d: Duration) {
+    let (tx, rx) = futures::channel::oneshot::channel();
+
+    std::thread::spawn(move || {
+        std::thread::sleep(d);
+        let _ = tx.send(());
+    });
+
+    let _ = rx.await;
+}
+
+/// Sleep for the provided duration
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(d: Duration) {
+    gloo_timers::future::sleep(d).await
+}
+
+/// Monotonic clock reading, for measuring elapsed time
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+/// Clock reading for measuring elapsed time, as [std::time::Instant] panics on wasm32 targets
+#[cfg(target_arch = "wasm32")]
+#[derive(Copy, Clone, Debug)]
+pub struct Instant(f64);
+
+#[cfg(target_arch = "wasm32")]
+impl Instant {
+    /// Fetch the current time
+    pub fn now() -> Self {
+        Self(js_sys::Date::now())
+    }
+
+    /// Fetch the time elapsed since this reading
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_secs_f64((js_sys::Date::now() - self.0).max(0.0) / 1000.0)
+    }
+}
+
+/// Run a blocking operation on the `tokio` blocking thread pool, avoiding stalling the executor.
+///
+/// Returns [Error::Unknown] if the operation panics.
+#[cfg(all(feature = "transport_usb", feature = "runtime_tokio"))]
+pub async fn blocking<T, F>(f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|_| Error::Unknown)
+}
+
+/// Run a blocking operation on the `blocking` thread pool, avoiding stalling the executor.
+///
+/// Returns [Error::Unknown] if the operation panics.
+#[cfg(all(
+    feature = "transport_usb",
+    not(feature = "runtime_tokio"),
+    feature = "runtime_async_io"
+))]
+pub async fn blocking<T, F>(f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    // `blocking` propagates panics to the awaiting task, catch these to match other runtimes
+    blocking::unblock(move || std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)))
+        .await
+        .map_err(|_| Error::Unknown)
+}
+
+/// Run a blocking operation on a dedicated thread, avoiding stalling the executor.
+///
+/// As with [sleep] this costs a thread per call, enable `runtime_tokio` or `runtime_async_io`
+/// to use a thread pool instead.
+///
+/// Returns [Error::Unknown] if the operation panics.
+#[cfg(all(
+    feature = "transport_usb",
+    not(feature = "runtime_tokio"),
+    not(feature = "runtime_async_io")
+))]
+pub async fn blocking<T, F>(f: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (tx, rx) = futures::channel::oneshot::channel();
+
+    std::thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+
+    rx.await.map_err(|_| Error::Unknown)
+}
+
+/// Await a future with a timeout, returning [Error::Timeout] if this elapses
+#[cfg(any(
+    feature = "transport_tcp",
+    feature = "transport_ble",
+    feature = "transport_webhid"
+))]
+pub async fn timeout<F: Future>(d: Duration, f: F) -> Result<F::Output, Error> {
+    match select(pin!(f), pin!(sleep(d))).await {
+        Either::Left((v, _)) => Ok(v),
+        Either::Right(_) => Err(Error::Timeout),
+    }
+}
+
+#[cfg(all(
+    test,
+    any(
+        feature = "transport_usb",
+        feature = "transport_tcp",
+        feature = "transport_ble",
+        feature = "transport_webhid"
+    )
+))]
+mod tests {
+    use super::*;
+
+    #[cfg(any(
+        feature = "transport_tcp",
+        feature = "transport_ble",
+        feature = "transport_webhid"
+    ))]
+    #[tokio::test]
+    async fn timeout_elapsed() {
+        let r = timeout(Duration::from_millis(10), sleep(Duration::from_secs(10))).await;
+        assert!(matches!(r, Err(Error::Timeout)));
+
+        let r = timeout(Duration::from_secs(10), async { 1 }).await;
+        assert!(matches!(r, Ok(1)));
+    }
+
+    #[cfg(feature = "transport_usb")]
+    #[tokio::test(flavor = "current_thread")]
+    async fn blocking_progress() {
+        // Blocking operations do not stall other tasks on the executor
+        let (tx, rx) = std::sync::mpsc::channel();
+        let b = blocking(move || rx.recv_timeout(Duration::from_secs(10)));
+        let t = async {
+            sleep(Duration::from_millis(10)).await;
+            tx.send(1).unwrap();
+        };
+
+        let (r, _) = futures::join!(b, t);
+        assert_eq!(r.unwrap(), Ok(1));
+
+        let r = blocking(|| panic!("blocking")).await;
+        assert!(matches!(r, Err(Error::Unknown)));
+    }
+}
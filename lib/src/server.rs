@@ -0,0 +1,201 @@
+//! APDU server exposing an [Exchange] (such as a USB device) over the Speculos TCP APDU protocol.
+//!
+//! This allows tooling written for Speculos (and [TcpTransport](crate::transport::TcpTransport)s
+//! on remote machines) to interact with real hardware.
+//!
+//! Requests are length-prefixed (`u32` big endian) APDUs, with responses length-prefixed by
+//! the response data length (excluding the two byte status word). As with Speculos, a single
+//! client is served at a time, with further connections queued until the client disconnects.
+//!
+//! ```no_run
+//! use ledger_lib::{server::ApduServer, transport::{GenericTransport, Transport}};
+//!
+//! #[tokio::main]
+//! async fn main() -> anyhow::Result<()> {
+//!     let mut t = GenericTransport::new().await?;
+//!     let device = t.connect_any(Default::default()).await?;
+//!
+//!     let server = ApduServer::bind("127.0.0.1:1237".parse()?, device).await?;
+//!     server.run().await?;
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use std::net::SocketAddr;
+
+#[cfg(feature = "runtime_tokio")]
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+#[cfg(not(feature = "runtime_tokio"))]
+use async_net::{TcpListener, TcpStream};
+#[cfg(not(feature = "runtime_tokio"))]
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, error, warn};
+
+use crate::{wipe::Scratch, Error, Exchange, Timeouts};
+
+/// Maximum request length (extended APDU header and data)
+const MAX_REQ_LEN: usize = 7 + 65535;
+
+/// APDU server, forwarding requests from TCP clients to an [Exchange]
+pub struct ApduServer<E: Exchange> {
+    l: TcpListener,
+    device: E,
+    timeouts: Timeouts,
+}
+
+impl<E: Exchange> ApduServer<E> {
+    /// Bind an [ApduServer] to the provided address, forwarding requests to `device`
+    pub async fn bind(addr: SocketAddr, device: E) -> Result<Self, Error> {
+        let l = TcpListener::bind(addr).await?;
+
+        debug!("APDU server listening on {}", l.local_addr()?);
+
+        Ok(Self {
+            l,
+            device,
+            timeouts: Timeouts::default().wait_for_user(),
+        })
+    }
+
+    /// Set [Timeouts] for forwarded requests (defaults to awaiting user confirmation
+    /// indefinitely, as clients may issue signing requests)
+    pub fn with_timeouts(mut self, timeouts: impl Into<Timeouts>) -> Self {
+        self.timeouts = timeouts.into();
+        self
+    }
+
+    /// Fetch the bound address (for servers bound to port 0)
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.l.local_addr()?)
+    }
+
+    /// Run the server, accepting and serving clients until an accept error occurs
+    pub async fn run(mut self) -> Result<(), Error> {
+        loop {
+            let (s, addr) = self.l.accept().await?;
+
+            debug!("Client {addr} connected");
+
+            match self.serve(s).await {
+                Ok(_) => debug!("Client {addr} disconnected"),
+                Err(e) => warn!("Client {addr} failed: {e:?}"),
+            }
+        }
+    }
+
+    /// Serve a single client connection, forwarding requests until the client disconnects.
+    ///
+    /// Device failures close the connection, returning the failure.
+    pub async fn serve(&mut self, mut s: TcpStream) -> Result<(), Error> {
+        while let Some(req) = read_request(&mut s).await? {
+            let resp = match self.device.exchange_timeouts(&req, self.timeouts).await {
+                Ok(v) => Scratch::new(v),
+                Err(e) => {
+                    error!("Device exchange failed: {e:?}");
+                    return Err(e);
+                }
+            };
+
+            // Responses include the status word, excluded from the length prefix
+            if resp.len() < 2 {
+                error!("Invalid response from device: {:02x?}", *resp);
+                return Err(Error::UnexpectedResponse);
+            }
+
+            let mut buff = Scratch::new(Vec::with_capacity(4 + resp.len()));
+            buff.extend_from_slice(&(resp.len() as u32 - 2).to_be_bytes());
+            buff.extend_from_slice(&resp);
+
+            s.write_all(&buff).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a length-prefixed request APDU, returning `None` where the client disconnected
+async fn read_request(s: &mut TcpStream) -> Result<Option<Scratch<Vec<u8>>>, Error> {
+    let mut len = [0u8; 4];
+
+    match s.read_exact(&mut len).await {
+        Ok(_) => (),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let n = u32::from_be_bytes(len) as usize;
+    if n > MAX_REQ_LEN {
+        error!("Invalid request APDU length: {n}");
+        return Err(Error::UnexpectedResponse);
+    }
+
+    let mut req = Scratch::new(vec![0u8; n]);
+    s.read_exact(&mut req[..]).await?;
+
+    debug!("Request: {:02x?}", *req);
+
+    Ok(Some(req))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::transport::{TcpInfo, TcpTransport, Transport};
+
+    /// Mock device, echoing commands with a success status
+    struct Echo;
+
+    impl Exchange for Echo {
+        async fn exchange(&mut self, command: &[u8], _timeout: Duration) -> Result<Vec<u8>, Error> {
+            match command.is_empty() {
+                true => Err(Error::Closed),
+                false => Ok([command, &[0x90, 0x00]].concat()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn apdu_server() {
+        let server = ApduServer::bind("127.0.0.1:0".parse().unwrap(), Echo)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+
+        tokio::spawn(server.run());
+
+        // Clients are served in sequence using the speculos protocol
+        for _ in 0..2 {
+            let mut t = TcpTransport::new().unwrap();
+            let mut d = t
+                .connect(TcpInfo {
+                    addr,
+                    ..Default::default()
+                })
+                .await
+                .unwrap();
+
+            for req in [vec![0xe0, 0x01, 0x00, 0x00, 0x00], vec![0xaa; 300]] {
+                let resp = d.exchange(&req, Duration::from_secs(1)).await.unwrap();
+                assert_eq!(resp, [&req[..], &[0x90, 0x00]].concat());
+            }
+        }
+
+        // Device failures close the client connection
+        let mut t = TcpTransport::new().unwrap();
+        let mut d = t
+            .connect(TcpInfo {
+                addr,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert!(d.exchange(&[], Duration::from_secs(1)).await.is_err());
+    }
+}
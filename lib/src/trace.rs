@@ -0,0 +1,260 @@
+//! Structured APDU trace events (enabled with the `trace` feature), providing a versioned
+//! schema for recording exchanges in a form external analyzers can consume.
+//!
+//! Each [TraceEvent] describes a single command or response, serialised to JSON
+//! (one event per line for streams) or CBOR:
+//!
+//! ```json
+//! {"v":1,"ts":1700000000000000,"dir":"command","device":"NanoX (HID ...)","header":{"cla":224,"ins":1,"p1":0,"p2":0},"data":"","status":null,"latency_us":null}
+//! {"v":1,"ts":1700000000012000,"dir":"response","device":"NanoX (HID ...)","header":null,"data":"0105424f4c4f53","status":36864,"latency_us":12000}
+//! ```
+//!
+//! Payloads may be replaced with a SHA-256 hash (`"sha256"` in place of `"data"`)
+//! where APDU contents should not be retained.
+
+use std::{
+    io::{Read, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use ledger_proto::{length::decode_lc, split_status, ApduHeader};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::Error;
+
+/// Current trace event schema version
+pub const TRACE_VERSION: u16 = 1;
+
+/// APDU trace event
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TraceEvent {
+    /// Schema version (see [TRACE_VERSION])
+    #[serde(rename = "v")]
+    pub version: u16,
+
+    /// Event timestamp in microseconds since the unix epoch
+    #[serde(rename = "ts")]
+    pub timestamp_us: u64,
+
+    /// APDU direction
+    #[serde(rename = "dir")]
+    pub direction: TraceDirection,
+
+    /// Device identifier, where known
+    pub device: Option<String>,
+
+    /// Command header (commands only)
+    pub header: Option<ApduHeader>,
+
+    /// Command or response payload (excluding header, length and status)
+    #[serde(flatten)]
+    pub payload: TracePayload,
+
+    /// Response status word (responses only)
+    pub status: Option<u16>,
+
+    /// Time since the associated command in microseconds (responses only)
+    pub latency_us: Option<u64>,
+}
+
+/// APDU direction for [TraceEvent]s
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceDirection {
+    /// Command sent to the device
+    Command,
+    /// Response received from the device
+    Response,
+}
+
+/// APDU payload for [TraceEvent]s, either the raw data or a SHA-256 hash
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TracePayload {
+    /// Raw payload data
+    #[serde(with = "hex::serde")]
+    Data(Vec<u8>),
+    /// SHA-256 hash of payload data
+    #[serde(with = "hex::serde")]
+    Sha256([u8; 32]),
+}
+
+impl TracePayload {
+    /// Build a payload, hashing data where `hash` is set
+    pub fn new(data: &[u8], hash: bool) -> Self {
+        match hash {
+            true => Self::Sha256(Sha256::digest(data).into()),
+            false => Self::Data(data.to_vec()),
+        }
+    }
+}
+
+impl TraceEvent {
+    /// Build a command event from an encoded APDU (header, short or extended length, data)
+    pub fn command(apdu: &[u8], hash: bool) -> Result<Self, Error> {
+        if apdu.len() < 5 {
+            return Err(ledger_proto::ApduError::InvalidLength.into());
+        }
+
+        let (_, lc_len) = decode_lc(&apdu[4..])?;
+
+        let header = ApduHeader {
+            cla: apdu[0],
+            ins: apdu[1],
+            p1: apdu[2],
+            p2: apdu[3],
+        };
+
+        Ok(Self {
+            version: TRACE_VERSION,
+            timestamp_us: now_us(),
+            direction: TraceDirection::Command,
+            device: None,
+            header: Some(header),
+            payload: TracePayload::new(&apdu[4 + lc_len..], hash),
+            status: None,
+            latency_us: None,
+        })
+    }
+
+    /// Build a response event from a raw response (data, status) and the exchange latency
+    pub fn response(resp: &[u8], latency: Duration, hash: bool) -> Result<Self, Error> {
+        let (data, status) = split_status(resp).map_err(|_| Error::EmptyResponse)?;
+
+        Ok(Self {
+            version: TRACE_VERSION,
+            timestamp_us: now_us(),
+            direction: TraceDirection::Response,
+            device: None,
+            header: None,
+            payload: TracePayload::new(data, hash),
+            status: Some(u16::from(status)),
+            latency_us: Some(latency.as_micros() as u64),
+        })
+    }
+
+    /// Set the device identifier for an event
+    pub fn with_device(mut self, device: impl ToString) -> Self {
+        self.device = Some(device.to_string());
+        self
+    }
+
+    /// Encode an event to a single line of JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string(self).map_err(|e| Error::Trace(e.to_string()))
+    }
+
+    /// Decode an event from JSON
+    pub fn from_json(s: &str) -> Result<Self, Error> {
+        let e: Self = serde_json::from_str(s).map_err(|e| Error::Trace(e.to_string()))?;
+        e.check_version()
+    }
+
+    /// Write an event as CBOR
+    pub fn write_cbor(&self, w: impl Write) -> Result<(), Error> {
+        ciborium::into_writer(self, w).map_err(|e| Error::Trace(e.to_string()))
+    }
+
+    /// Read an event from CBOR
+    pub fn read_cbor(r: impl Read) -> Result<Self, Error> {
+        let e: Self = ciborium::from_reader(r).map_err(|e| Error::Trace(e.to_string()))?;
+        e.check_version()
+    }
+
+    /// Reject events from newer (unsupported) schema versions
+    fn check_version(self) -> Result<Self, Error> {
+        match self.version <= TRACE_VERSION {
+            true => Ok(self),
+            false => Err(Error::Trace(format!(
+                "unsupported trace version {}",
+                self.version
+            ))),
+        }
+    }
+}
+
+/// Helper to fetch the current time in microseconds since the unix epoch
+fn now_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_command() {
+        let e = TraceEvent::command(&[0xe0, 0x01, 0x00, 0x00, 0x01, 0xaa], false)
+            .unwrap()
+            .with_device("test");
+
+        assert_eq!(e.direction, TraceDirection::Command);
+        assert_eq!(
+            e.header,
+            Some(ApduHeader {
+                cla: 0xe0,
+                ins: 0x01,
+                p1: 0x00,
+                p2: 0x00
+            })
+        );
+        assert_eq!(e.payload, TracePayload::Data(vec![0xaa]));
+        assert_eq!(e.device.as_deref(), Some("test"));
+
+        assert!(TraceEvent::command(&[0xe0, 0x01], false).is_err());
+
+        // Extended length commands
+        let e =
+            TraceEvent::command(&[0xe0, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0xaa], false).unwrap();
+        assert_eq!(e.payload, TracePayload::Data(vec![0xaa]));
+    }
+
+    #[test]
+    fn trace_response() {
+        let e = TraceEvent::response(&[0xaa, 0x90, 0x00], Duration::from_millis(12), true).unwrap();
+
+        assert_eq!(e.direction, TraceDirection::Response);
+        assert_eq!(e.status, Some(0x9000));
+        assert_eq!(e.latency_us, Some(12_000));
+        assert_eq!(
+            e.payload,
+            TracePayload::Sha256(Sha256::digest([0xaa]).into())
+        );
+    }
+
+    #[test]
+    fn trace_json() {
+        let e = TraceEvent::response(&[0xaa, 0x90, 0x00], Duration::from_millis(1), false).unwrap();
+
+        let s = e.to_json().unwrap();
+        assert!(s.contains(r#""v":1"#));
+        assert!(s.contains(r#""dir":"response""#));
+        assert!(s.contains(r#""data":"aa""#));
+        assert!(!s.contains('\n'));
+
+        assert_eq!(TraceEvent::from_json(&s).unwrap(), e);
+    }
+
+    #[test]
+    fn trace_cbor() {
+        let e = TraceEvent::command(&[0xe0, 0x01, 0x00, 0x00, 0x00], true).unwrap();
+
+        let mut buff = vec![];
+        e.write_cbor(&mut buff).unwrap();
+
+        assert_eq!(TraceEvent::read_cbor(&buff[..]).unwrap(), e);
+    }
+
+    #[test]
+    fn trace_version() {
+        let mut e = TraceEvent::command(&[0xe0, 0x01, 0x00, 0x00, 0x00], false).unwrap();
+        e.version = TRACE_VERSION + 1;
+
+        let s = serde_json::to_string(&e).unwrap();
+        assert!(TraceEvent::from_json(&s).is_err());
+    }
+}
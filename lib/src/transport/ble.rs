@@ -1,21 +1,21 @@
 //! Bluetooth Low Energy (BLE) transport
 
-use std::{fmt::Display, pin::Pin, time::Duration};
+use std::{collections::HashMap, fmt::Display, time::Duration};
 
 use btleplug::{
-    api::{
-        BDAddr, Central as _, Characteristic, Manager as _, Peripheral, ScanFilter,
-        ValueNotification, WriteType,
-    },
+    api::{BDAddr, Central as _, Characteristic, Manager as _, Peripheral, ScanFilter, WriteType},
     platform::Manager,
 };
 use futures::{stream::StreamExt, Stream};
+use ledger_proto::framing::ble::{BleDecoder, BleEncoder, BLE_CMD_APDU, BLE_CMD_MTU};
 use tracing::{debug, error, trace, warn};
 use uuid::{uuid, Uuid};
 
 use super::{Exchange, Transport};
 use crate::{
     info::{ConnInfo, LedgerInfo, Model},
+    rt,
+    wipe::Scratch,
     Error,
 };
 
@@ -23,43 +23,182 @@ use crate::{
 pub struct BleTransport {
     manager: Manager,
     peripherals: Vec<(LedgerInfo, btleplug::platform::Peripheral)>,
+    pairing: Option<Box<PairingHandler>>,
+    specs: Vec<BleSpec>,
+    mtu: Option<u8>,
 }
 
-/// BLE specific device information
+/// Pairing event handler, see [BleTransport::with_pairing_handler]
+pub type PairingHandler = dyn Fn(PairingEvent) + Send + Sync;
+
+/// BLE pairing events, issued while connecting to devices requiring pairing
 #[derive(Clone, Debug, PartialEq)]
+pub enum PairingEvent {
+    /// Pairing required, the user should confirm the passkey shown on the device
+    /// using the platform pairing prompt
+    Required(BleInfo),
+    /// Pairing complete
+    Paired(BleInfo),
+    /// Pairing not completed prior to the timeout
+    Failed(BleInfo),
+}
+
+/// BLE specific device information
+///
+/// Peripheral metadata (`rssi`, `connected` and `manufacturer_data`) is populated by
+/// list operations and is not considered when comparing devices, so listings of the
+/// same device compare equal as signal strength changes.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BleInfo {
-    name: String,
-    addr: BDAddr,
+    /// Device name
+    pub name: String,
+    /// Device address
+    #[cfg_attr(feature = "serde", serde(with = "bdaddr_str"))]
+    pub addr: BDAddr,
+    /// Received signal strength (dBm) from the last advertisement, if available
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rssi: Option<i16>,
+    /// Whether the peripheral was connected to this host when listed
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub connected: bool,
+    /// Advertised manufacturer data, keyed by company identifier
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub manufacturer_data: HashMap<u16, Vec<u8>>,
+}
+
+impl PartialEq for BleInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.addr == other.addr
+    }
+}
+
+/// Serde helpers for [BDAddr], using the `AA:BB:CC:DD:EE:FF` string representation
+#[cfg(feature = "serde")]
+mod bdaddr_str {
+    use btleplug::api::BDAddr;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &BDAddr, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(v)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<BDAddr, D::Error> {
+        let s = String::deserialize(d)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl BleInfo {
+    /// Create device info for connecting by address, without a prior list operation
+    pub fn from_addr(addr: BDAddr) -> Self {
+        Self {
+            addr,
+            ..Default::default()
+        }
+    }
+
+    /// Create device info for connecting by name, without a prior list operation
+    /// (for platforms not reporting device addresses, such as macOS)
+    pub fn from_name(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Check whether this info matches a discovered device, by address where set
+    /// and otherwise by name
+    pub fn matches(&self, other: &BleInfo) -> bool {
+        match self.addr != BDAddr::default() {
+            true => self.addr == other.addr,
+            false => !self.name.is_empty() && self.name == other.name,
+        }
+    }
 }
 
 impl Display for BleInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.name)
+        match self.name.is_empty() {
+            true => write!(f, "{}", self.addr),
+            false => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// BLE device discovery filters
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct BleFilters {
+    #[cfg_attr(feature = "clap", clap(long = "ble-scan-ms", default_value_t = 1000))]
+    /// BLE scan duration in milliseconds
+    pub scan_ms: u64,
+}
+
+impl Default for BleFilters {
+    fn default() -> Self {
+        Self { scan_ms: 1000 }
     }
 }
 
 /// BLE connected ledger device
+///
+/// Devices should be closed with [BleDevice::close] when no longer required, with a
+/// best-effort disconnect on drop (requiring a `tokio` runtime).
 pub struct BleDevice {
     pub info: BleInfo,
     mtu: u8,
+    mtu_override: bool,
+    mtu_pending: bool,
     p: btleplug::platform::Peripheral,
     c_write: Characteristic,
+    c_write_cmd: Option<Characteristic>,
     c_read: Characteristic,
+    write_without_response: bool,
+    closed: bool,
 }
 
-/// Bluetooth spec for ledger devices
+/// Scan duration for locating devices not found by a previous list operation
+const CONNECT_SCAN_DURATION: Duration = Duration::from_secs(3);
+
+/// Timeout awaiting the platform pairing flow on first connection
+const PAIRING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Interval between connection attempts while awaiting pairing
+const PAIRING_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Minimum BLE MTU, used where MTU negotiation fails
+const DEFAULT_MTU: u8 = 23;
+
+/// Timeout awaiting MTU responses
+const MTU_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// MTU request attempts on connection, allowing the connection to settle
+const MTU_ATTEMPTS: usize = 3;
+
+/// Interval between MTU request attempts
+const MTU_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Bluetooth spec for ledger devices, see [BleTransport::with_spec] for adding specs
+/// for models not yet supported by this library.
+///
 /// see: https://github.com/LedgerHQ/ledger-live/blob/develop/libs/ledgerjs/packages/devices/src/index.ts#L32
 #[derive(Clone, PartialEq, Debug)]
-struct BleSpec {
+pub struct BleSpec {
+    /// Device model
     pub model: Model,
+    /// Advertised service UUID, used for discovery and model detection
     pub service_uuid: Uuid,
+    /// Notify (response) characteristic UUID
     pub notify_uuid: Uuid,
+    /// Write (command) characteristic UUID
     pub write_uuid: Uuid,
+    /// Write without response characteristic UUID
     pub write_cmd_uuid: Uuid,
 }
 
 /// Spec for types of bluetooth device
-const BLE_SPECS: &[BleSpec] = &[
+pub const BLE_SPECS: &[BleSpec] = &[
     BleSpec {
         model: Model::NanoX,
         service_uuid: uuid!("13d63400-2c97-0004-0000-4c6564676572"),
@@ -74,8 +213,21 @@ const BLE_SPECS: &[BleSpec] = &[
         write_uuid: uuid!("13d63400-2c97-6004-0002-4c6564676572"),
         write_cmd_uuid: uuid!("13d63400-2c97-6004-0003-4c6564676572"),
     },
+    BleSpec {
+        model: Model::Flex,
+        service_uuid: uuid!("13d63400-2c97-3004-0000-4c6564676572"),
+        notify_uuid: uuid!("13d63400-2c97-3004-0001-4c6564676572"),
+        write_uuid: uuid!("13d63400-2c97-3004-0002-4c6564676572"),
+        write_cmd_uuid: uuid!("13d63400-2c97-3004-0003-4c6564676572"),
+    },
 ];
 
+/// Match a peripheral to a [BleSpec] using advertised service UUIDs
+/// (from the service list or service data)
+fn match_spec<'a>(specs: &'a [BleSpec], services: &[Uuid]) -> Option<&'a BleSpec> {
+    specs.iter().find(|s| services.contains(&s.service_uuid))
+}
+
 impl BleTransport {
     pub async fn new() -> Result<Self, Error> {
         // Setup connection manager
@@ -84,9 +236,36 @@ impl BleTransport {
         Ok(Self {
             manager,
             peripherals: vec![],
+            pairing: None,
+            specs: BLE_SPECS.to_vec(),
+            mtu: None,
         })
     }
 
+    /// Register an additional [BleSpec], for discovering and connecting to models
+    /// not yet supported by this library (registered specs are matched first)
+    pub fn with_spec(mut self, spec: BleSpec) -> Self {
+        self.specs.insert(0, spec);
+        self
+    }
+
+    /// Override the MTU for connected devices, skipping MTU negotiation
+    /// (for platforms or devices where negotiation is unreliable)
+    pub fn with_mtu(mut self, mtu: u8) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
+
+    /// Set a handler for [PairingEvent]s, allowing applications to prompt the user
+    /// to confirm pairing on first connection to a device
+    pub fn with_pairing_handler(
+        mut self,
+        f: impl Fn(PairingEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.pairing = Some(Box::new(f));
+        self
+    }
+
     /// Helper to perform scan for available BLE devices, used in [list] and [connect].
     async fn scan_internal(
         &self,
@@ -97,8 +276,10 @@ impl BleTransport {
         // Grab adapter list
         let adapters = self.manager.adapters().await?;
 
-        // TODO: load filters?
-        let f = ScanFilter { services: vec![] };
+        // Filter for known ledger services
+        let f = ScanFilter {
+            services: self.specs.iter().map(|s| s.service_uuid).collect(),
+        };
 
         // Search using adapters
         for adapter in adapters.iter() {
@@ -108,7 +289,7 @@ impl BleTransport {
             // Start scan with adaptor
             adapter.start_scan(f.clone()).await?;
 
-            tokio::time::sleep(duration).await;
+            rt::sleep(duration).await;
 
             // Fetch peripheral list
             let mut peripherals = adapter.peripherals().await?;
@@ -120,7 +301,7 @@ impl BleTransport {
             // Load peripheral information
             for p in peripherals.drain(..) {
                 // Fetch peripheral properties
-                let (properties, _connected) = (p.properties().await?, p.is_connected().await?);
+                let (properties, connected) = (p.properties().await?, p.is_connected().await?);
 
                 // Skip peripherals where we couldn't fetch properties
                 let properties = match properties {
@@ -131,30 +312,30 @@ impl BleTransport {
                     }
                 };
 
-                // Skip peripherals without a local name (NanoX should report this)
-                let name = match &properties.local_name {
-                    Some(v) => v,
-                    None => continue,
-                };
-
                 debug!("Peripheral: {p:?} props: {properties:?}");
 
-                // Match on peripheral names
-                let model = if name.contains("Nano X") {
-                    Model::NanoX
-                } else if name.contains("Stax") {
-                    Model::Stax
-                } else {
-                    continue;
+                // Match on advertised services, skipping non-ledger peripherals
+                let services: Vec<_> = properties
+                    .services
+                    .iter()
+                    .chain(properties.service_data.keys())
+                    .cloned()
+                    .collect();
+                let spec = match match_spec(&self.specs, &services) {
+                    Some(v) => v,
+                    None => continue,
                 };
 
                 // Add to device list
                 matched.push((
                     LedgerInfo {
-                        model: model.clone(),
+                        model: spec.model.clone(),
                         conn: BleInfo {
-                            name: name.clone(),
+                            name: properties.local_name.clone().unwrap_or_default(),
                             addr: properties.address,
+                            rssi: properties.rssi,
+                            connected,
+                            manufacturer_data: properties.manufacturer_data.clone(),
                         }
                         .into(),
                     },
@@ -165,19 +346,54 @@ impl BleTransport {
 
         Ok(matched)
     }
+
+    /// Locate a peripheral matching the provided info (see [BleInfo::matches]), using
+    /// peripherals from the previous list operation where available, otherwise checking
+    /// peripherals known to the adapters then scanning.
+    async fn find(
+        &mut self,
+        info: &BleInfo,
+    ) -> Result<(LedgerInfo, btleplug::platform::Peripheral), Error> {
+        let matches = |d: &LedgerInfo| match &d.conn {
+            ConnInfo::Ble(i) => info.matches(i),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        };
+
+        if let Some(v) = self.peripherals.iter().find(|(d, _p)| matches(d)) {
+            return Ok(v.clone());
+        }
+
+        for duration in [Duration::ZERO, CONNECT_SCAN_DURATION] {
+            debug!("Scanning for {info} ({duration:?})");
+
+            let devices = self.scan_internal(duration).await?;
+            let found = devices.iter().find(|(d, _p)| matches(d)).cloned();
+
+            self.peripherals = devices;
+
+            if let Some(v) = found {
+                return Ok(v);
+            }
+        }
+
+        warn!("No device found matching: {info:?}");
+        Err(Error::NoDevices)
+    }
 }
 
 /// [Transport] implementation for [BleTransport]
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Transport for BleTransport {
-    type Filters = ();
+    type Filters = BleFilters;
     type Info = BleInfo;
     type Device = BleDevice;
 
     /// List BLE connected ledger devices
-    async fn list(&mut self, _filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
+    async fn list(&mut self, filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
         // Scan for available devices
-        let devices = self.scan_internal(Duration::from_millis(1000)).await?;
+        let devices = self
+            .scan_internal(Duration::from_millis(filters.scan_ms))
+            .await?;
 
         // Filter to return info list
         let info: Vec<_> = devices.iter().map(|d| d.0.clone()).collect();
@@ -190,20 +406,12 @@ impl Transport for BleTransport {
 
     /// Connect to a specific ledger device
     ///
-    /// Note: this _must_ follow a [Self::list] operation to match `info` with known peripherals
+    /// `info` is matched with peripherals from the previous [Self::list] operation,
+    /// falling back to a targeted scan so saved (or address / name only, see
+    /// [BleInfo::from_addr] and [BleInfo::from_name]) info may be used without listing.
     async fn connect(&mut self, info: Self::Info) -> Result<Self::Device, Error> {
         // Match known peripherals using provided device info
-        let (d, p) = match self
-            .peripherals
-            .iter()
-            .find(|(d, _p)| d.conn == info.clone().into())
-        {
-            Some(v) => v,
-            None => {
-                warn!("No device found matching: {info:?}");
-                return Err(Error::NoDevices);
-            }
-        };
+        let (d, p) = self.find(&info).await?;
         let i = match &d.conn {
             ConnInfo::Ble(i) => i,
             _ => unreachable!(),
@@ -211,12 +419,15 @@ impl Transport for BleTransport {
 
         let name = &i.name;
 
+        // Pairing is triggered by the platform on first connection (or characteristic access)
+        let mut pairing = Pairing::new(i, self.pairing.as_deref(), PAIRING_TIMEOUT);
+
         // Fetch properties
         let properties = p.properties().await?;
 
         // Connect to device and subscribe to characteristics
         // Fetch specs for matched model (contains characteristic identifiers)
-        let specs = match BLE_SPECS.iter().find(|s| s.model == d.model) {
+        let specs = match self.specs.iter().find(|s| s.model == d.model) {
             Some(v) => v,
             None => {
                 warn!("No specs for model: {:?}", d.model);
@@ -226,9 +437,15 @@ impl Transport for BleTransport {
 
         // If we're not connected, attempt to connect
         if !p.is_connected().await? {
-            if let Err(e) = p.connect().await {
-                warn!("Failed to connect to {name}: {e:?}");
-                return Err(Error::Unknown);
+            loop {
+                match p.connect().await {
+                    Ok(_) => break,
+                    Err(e) if is_pairing_required(&e) => pairing.wait().await?,
+                    Err(e) => {
+                        warn!("Failed to connect to {name}: {e:?}");
+                        return Err(Error::Unknown);
+                    }
+                }
             }
 
             if !p.is_connected().await? {
@@ -248,6 +465,10 @@ impl Transport for BleTransport {
 
         let c_write = characteristics.iter().find(|c| c.uuid == specs.write_uuid);
         let c_read = characteristics.iter().find(|c| c.uuid == specs.notify_uuid);
+        let c_write_cmd = characteristics
+            .iter()
+            .find(|c| c.uuid == specs.write_cmd_uuid)
+            .cloned();
 
         let (c_write, c_read) = match (c_write, c_read) {
             (Some(w), Some(r)) => (w, r),
@@ -259,120 +480,179 @@ impl Transport for BleTransport {
 
         // Create device instance
         let mut d = BleDevice {
-            info: info.clone(),
-            mtu: 23,
+            info: i.clone(),
+            mtu: self.mtu.unwrap_or(DEFAULT_MTU),
+            mtu_override: self.mtu.is_some(),
+            mtu_pending: self.mtu.is_none(),
             p: p.clone(),
             c_write: c_write.clone(),
+            write_without_response: c_write_cmd.is_some(),
+            c_write_cmd,
             c_read: c_read.clone(),
+            closed: false,
         };
 
-        // Request MTU (cmd 0x08, seq: 0x0000, len: 0x0000)
-        match d.fetch_mtu().await {
-            Ok(mtu) => d.mtu = mtu,
-            Err(e) => {
-                warn!("Failed to fetch MTU: {:?}", e);
+        // Request MTU (cmd 0x08, seq: 0x0000, len: 0x0000), retrying while the connection settles
+        let mut attempts = 0;
+        while d.mtu_pending {
+            attempts += 1;
+
+            match d.fetch_mtu().await {
+                Ok(mtu) => {
+                    d.mtu = mtu;
+                    d.mtu_pending = false;
+                }
+                Err(Error::Ble(e)) if is_pairing_required(&e) => pairing.wait().await?,
+                Err(e) if attempts < MTU_ATTEMPTS => {
+                    debug!("Failed to fetch MTU (attempt {attempts}): {e:?}");
+                    rt::sleep(MTU_RETRY_INTERVAL).await;
+                }
+                Err(e) => {
+                    warn!("Failed to fetch MTU, renegotiating on next exchange: {e:?}");
+                    break;
+                }
             }
         }
 
+        pairing.complete();
+
         debug!("using MTU: {}", d.mtu);
 
         Ok(d)
     }
 }
 
-const BLE_HEADER_LEN: usize = 3;
-
-impl BleDevice {
-    /// Helper to write commands as chunks based on device MTU
-    async fn write_command(&mut self, cmd: u8, payload: &[u8]) -> Result<(), Error> {
-        // Setup outgoing data (adds 2-byte big endian length prefix)
-        let mut data = Vec::with_capacity(payload.len() + 2);
-        data.extend_from_slice(&(payload.len() as u16).to_be_bytes()); // Data length
-        data.extend_from_slice(payload); // Data
-
-        debug!("TX cmd: 0x{cmd:02x} payload: {data:02x?}");
-
-        // Write APDU in chunks
-        for (i, c) in data.chunks(self.mtu as usize - BLE_HEADER_LEN).enumerate() {
-            // Setup chunk buffer
-            let mut buff = Vec::with_capacity(self.mtu as usize);
-            let cmd = match i == 0 {
-                true => cmd,
-                false => 0x03,
-            };
-
-            buff.push(cmd); // Command
-            buff.extend_from_slice(&(i as u16).to_be_bytes()); // Sequence ID
-            buff.extend_from_slice(c);
+/// Check whether a BLE failure was due to the device requiring pairing (or bonding).
+///
+/// `btleplug` does not expose pairing state, so failures are matched by kind and message.
+fn is_pairing_required(e: &btleplug::Error) -> bool {
+    match e {
+        btleplug::Error::PermissionDenied => true,
+        btleplug::Error::Other(e) => {
+            let m = e.to_string().to_lowercase();
+            [
+                "authentication",
+                "notauthorized",
+                "not authorized",
+                "insufficient",
+                "encryption",
+                "not paired",
+            ]
+            .iter()
+            .any(|s| m.contains(s))
+        }
+        _ => false,
+    }
+}
 
-            debug!("Write chunk {i}: {:02x?}", buff);
+/// Pairing flow state for a connection attempt
+struct Pairing<'a> {
+    info: &'a BleInfo,
+    handler: Option<&'a PairingHandler>,
+    timeout: Duration,
+    started: Option<rt::Instant>,
+}
 
-            self.p
-                .write(&self.c_write, &buff, WriteType::WithResponse)
-                .await?;
+impl<'a> Pairing<'a> {
+    fn new(info: &'a BleInfo, handler: Option<&'a PairingHandler>, timeout: Duration) -> Self {
+        Self {
+            info,
+            handler,
+            timeout,
+            started: None,
         }
+    }
 
-        Ok(())
+    fn notify(&self, f: fn(BleInfo) -> PairingEvent) {
+        if let Some(h) = self.handler {
+            h(f(self.info.clone()))
+        }
     }
 
-    /// Helper to read response packet from notification channel
-    async fn read_data(
-        &mut self,
-        mut notifications: Pin<Box<dyn Stream<Item = ValueNotification> + Send>>,
-    ) -> Result<Vec<u8>, Error> {
-        // Await first response
-        let v = match notifications.next().await {
-            Some(v) => v.value,
+    /// Await the platform pairing flow following a pairing failure, returning
+    /// [Error::PairingRequired] once the pairing timeout has elapsed
+    async fn wait(&mut self) -> Result<(), Error> {
+        match self.started {
             None => {
-                return Err(Error::Closed);
+                debug!("Pairing required for {}", self.info);
+                self.notify(PairingEvent::Required);
+                self.started = Some(rt::Instant::now());
             }
-        };
+            Some(s) if s.elapsed() >= self.timeout => {
+                warn!("Pairing not completed for {}", self.info);
+                self.notify(PairingEvent::Failed);
+                return Err(Error::PairingRequired);
+            }
+            Some(_) => (),
+        }
 
-        debug!("RX: {:02x?}", v);
+        rt::sleep(PAIRING_RETRY_INTERVAL.min(self.timeout)).await;
 
-        // Check response length is reasonable
-        if v.len() < 5 {
-            error!("response too short");
-            return Err(Error::UnexpectedResponse);
-        } else if v[0] != 0x05 {
-            error!("unexpected response type: {:?}", v[0]);
-            return Err(Error::UnexpectedResponse);
-        }
+        Ok(())
+    }
 
-        // Read out full response length
-        let len = v[4] as usize;
-        if len == 0 {
-            return Err(Error::EmptyResponse);
+    /// Complete the pairing flow, notifying the handler where pairing was required
+    fn complete(&self) {
+        if self.started.is_some() {
+            debug!("Paired with {}", self.info);
+            self.notify(PairingEvent::Paired);
         }
+    }
+}
+
+/// Select the write type for a command chunk, continuation chunks may be written
+/// without response to improve throughput for large APDUs
+fn write_type(index: usize, without_response: bool) -> WriteType {
+    match index > 0 && without_response {
+        true => WriteType::WithoutResponse,
+        false => WriteType::WithResponse,
+    }
+}
+
+/// Parse an MTU response notification (command, sequence, length, MTU),
+/// returning `None` for other or malformed notifications
+fn parse_mtu(value: &[u8]) -> Option<u8> {
+    match value {
+        [BLE_CMD_MTU, _, _, _, _, mtu] => Some(*mtu),
+        _ => None,
+    }
+}
 
-        trace!("Expecting response length: {}", len);
+impl BleDevice {
+    /// Fetch the MTU in use for the device
+    pub fn mtu(&self) -> u8 {
+        self.mtu
+    }
 
-        // Setup response buffer
-        let mut buff = Vec::with_capacity(len);
-        buff.extend_from_slice(&v[5..]);
+    /// Enable or disable writing continuation chunks without response (using the
+    /// write command characteristic), enabled by default where supported by the device.
+    ///
+    /// This has no effect where the device does not expose a write command characteristic.
+    pub fn with_write_without_response(mut self, enabled: bool) -> Self {
+        self.write_without_response = enabled && self.c_write_cmd.is_some();
+        self
+    }
 
-        // Read further responses
-        // TODO: check this is correct with larger packets
-        while buff.len() < len {
-            // Await response notification
-            let v = match notifications.next().await {
-                Some(v) => v.value,
-                None => {
-                    error!("Failed to fetch next chunk from peripheral");
-                    self.p.unsubscribe(&self.c_read).await?;
-                    return Err(Error::Closed);
-                }
-            };
+    /// Helper to write commands as chunks based on device MTU
+    async fn write_command(&mut self, cmd: u8, payload: &[u8]) -> Result<(), Error> {
+        debug!("TX cmd: 0x{cmd:02x} payload: {:02x?}", payload);
 
-            debug!("RX: {v:02x?}");
+        // Write APDU in chunks
+        for (i, buff) in BleEncoder::new(cmd, payload, self.mtu as usize).enumerate() {
+            let buff = Scratch::new(buff);
 
-            // TODO: check sequence index?
+            let t = write_type(i, self.write_without_response);
+            let c = match (t, &self.c_write_cmd) {
+                (WriteType::WithoutResponse, Some(c)) => c,
+                _ => &self.c_write,
+            };
 
-            // add received data to buffer
-            buff.extend_from_slice(&v[5..]);
+            debug!("Write chunk {i} ({t:?}): {:02x?}", *buff);
+
+            self.p.write(c, &buff, t).await?;
         }
 
-        Ok(buff)
+        Ok(())
     }
 
     /// Helper to fetch the available MTU from a bluetooth device
@@ -382,18 +662,20 @@ impl BleDevice {
         let mut n = self.p.notifications().await?;
 
         // Write get mtu command
-        self.write_command(0x08, &[]).await?;
+        self.write_command(BLE_CMD_MTU, &[]).await?;
 
         // Await MTU response
-        let mtu = match n.next().await {
-            Some(r) if r.value[0] == 0x08 && r.value.len() == 6 => {
-                debug!("RX: {:02x?}", r);
-                r.value[5]
-            }
-            Some(r) => {
-                warn!("Unexpected MTU response: {r:02x?}");
-                return Err(Error::Unknown);
-            }
+        let mtu = match rt::timeout(MTU_TIMEOUT, n.next()).await? {
+            Some(r) => match parse_mtu(&r.value) {
+                Some(mtu) => {
+                    debug!("RX: {:02x?}", r);
+                    mtu
+                }
+                None => {
+                    warn!("Unexpected MTU response: {r:02x?}");
+                    return Err(Error::Unknown);
+                }
+            },
             None => {
                 warn!("Failed to request MTU");
                 return Err(Error::Unknown);
@@ -406,41 +688,298 @@ impl BleDevice {
         Ok(mtu)
     }
 
+    /// Renegotiate the MTU where the previous negotiation failed or the device has
+    /// since disconnected, retaining the current MTU on failure
+    async fn renegotiate_mtu(&mut self) {
+        if !self.mtu_pending || !matches!(self.p.is_connected().await, Ok(true)) {
+            return;
+        }
+
+        match self.fetch_mtu().await {
+            Ok(mtu) => {
+                debug!("Renegotiated MTU: {mtu}");
+                self.mtu = mtu;
+                self.mtu_pending = false;
+            }
+            Err(e) => debug!("Failed to renegotiate MTU: {e:?}"),
+        }
+    }
+
+    /// Handle exchange failures, scheduling MTU renegotiation where the device
+    /// has disconnected (and may be reconnected by the platform)
+    async fn exchange_failed(&mut self, e: Error) -> Error {
+        if !self.mtu_override && !matches!(self.p.is_connected().await, Ok(true)) {
+            debug!("Device disconnected, renegotiating MTU on next exchange");
+            self.mtu_pending = true;
+        }
+
+        let _ = self.p.unsubscribe(&self.c_read).await;
+
+        e
+    }
+
     pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
         let c = self.p.is_connected().await?;
         Ok(c)
     }
+
+    /// Close the device, unsubscribing from notifications and disconnecting the
+    /// peripheral so the device is available to other applications
+    pub async fn close(&mut self) -> Result<(), Error> {
+        if self.closed {
+            return Ok(());
+        }
+        self.closed = true;
+
+        debug!("Disconnecting from {}", self.info);
+
+        // Subscriptions may remain following cancelled exchanges
+        if let Err(e) = self.p.unsubscribe(&self.c_read).await {
+            trace!("Failed to unsubscribe: {e:?}");
+        }
+
+        if self.p.is_connected().await? {
+            self.p.disconnect().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Best-effort disconnect for devices dropped without [BleDevice::close]
+impl Drop for BleDevice {
+    fn drop(&mut self) {
+        if self.closed {
+            return;
+        }
+
+        let (p, c) = (self.p.clone(), self.c_read.clone());
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(h) => {
+                h.spawn(async move {
+                    let _ = p.unsubscribe(&c).await;
+                    let _ = p.disconnect().await;
+                });
+            }
+            Err(_) => warn!("No runtime to disconnect {} on drop", self.info),
+        }
+    }
 }
 
 /// [Exchange] impl for BLE backed devices
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Exchange for BleDevice {
     async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        // Renegotiate MTU following failed negotiation or reconnection
+        self.renegotiate_mtu().await;
+
         // Fetch notification channel for responses
         self.p.subscribe(&self.c_read).await?;
         let notifications = self.p.notifications().await?;
 
         // Write command data
-        if let Err(e) = self.write_command(0x05, command).await {
-            self.p.unsubscribe(&self.c_read).await?;
-            return Err(e);
+        if let Err(e) = self.write_command(BLE_CMD_APDU, command).await {
+            return Err(self.exchange_failed(e).await);
         }
 
         debug!("Await response");
 
         // Wait for response
-        let buff = match tokio::time::timeout(timeout, self.read_data(notifications)).await {
+        let notifications = notifications.map(|n| n.value);
+        let buff = match rt::timeout(timeout, read_apdu(notifications)).await {
             Ok(Ok(v)) => v,
-            Ok(Err(e)) => {
-                self.p.unsubscribe(&self.c_read).await?;
-                return Err(e);
-            }
+            Ok(Err(e)) | Err(e) => return Err(self.exchange_failed(e).await),
+        };
+
+        Ok(buff)
+    }
+}
+
+/// Read and reassemble a chunked BLE APDU response from a stream of notification values
+pub async fn read_apdu(
+    mut notifications: impl Stream<Item = Vec<u8>> + Unpin,
+) -> Result<Vec<u8>, Error> {
+    let mut decoder = BleDecoder::new();
+
+    // Await notifications until the response is complete
+    while let Some(v) = notifications.next().await {
+        let v = Scratch::new(v);
+
+        debug!("RX: {:02x?}", *v);
+
+        match decoder.push(&v) {
+            Ok(Some(r)) => return Ok(r),
+            Ok(None) => (),
             Err(e) => {
-                self.p.unsubscribe(&self.c_read).await?;
+                error!("Invalid response chunk: {e}");
                 return Err(e.into());
             }
+        }
+    }
+
+    error!("Failed to fetch next chunk from peripheral");
+    Err(Error::Closed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ble_info_matches() {
+        let addr = BDAddr::from([0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+        let d = BleInfo {
+            name: "Nano X 1A2B".to_string(),
+            addr,
+            rssi: Some(-60),
+            connected: true,
+            manufacturer_data: HashMap::from([(0x02c5, vec![0x01])]),
         };
 
-        Ok(buff)
+        // Addresses are preferred where set, with names used otherwise
+        assert!(BleInfo::from_addr(addr).matches(&d));
+        assert!(!BleInfo::from_addr(BDAddr::from([0x01; 6])).matches(&d));
+        assert!(BleInfo::from_name("Nano X 1A2B").matches(&d));
+        assert!(!BleInfo::from_name("Nano X 3C4D").matches(&d));
+        assert!(!BleInfo::from_name("").matches(&d));
+        assert!(d.clone().matches(&d));
+
+        // Peripheral metadata is not considered in comparisons
+        let mut e = d.clone();
+        e.rssi = Some(-80);
+        e.connected = false;
+        e.manufacturer_data.clear();
+        assert_eq!(d, e);
+        assert_ne!(d, BleInfo::from_addr(addr));
+
+        assert_eq!(BleInfo::from_addr(addr).to_string(), "01:02:03:04:05:06");
+        assert_eq!(d.to_string(), "Nano X 1A2B");
+    }
+
+    #[test]
+    fn ble_pairing_required() {
+        assert!(is_pairing_required(&btleplug::Error::PermissionDenied));
+        assert!(is_pairing_required(&btleplug::Error::Other(
+            "org.bluez.Error.AuthenticationFailed".into()
+        )));
+        assert!(is_pairing_required(&btleplug::Error::Other(
+            "Insufficient Encryption".into()
+        )));
+        assert!(!is_pairing_required(&btleplug::Error::NotConnected));
+        assert!(!is_pairing_required(&btleplug::Error::Other(
+            "org.bluez.Error.Failed".into()
+        )));
+    }
+
+    #[tokio::test]
+    async fn ble_pairing_events() {
+        let info = BleInfo::from_name("Nano X 1A2B");
+        let events = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let e = events.clone();
+        let handler = move |ev| e.lock().unwrap().push(ev);
+
+        // Pairing completes following a pairing failure
+        let mut p = Pairing::new(&info, Some(&handler), Duration::from_secs(1));
+        p.complete();
+        assert!(events.lock().unwrap().is_empty());
+
+        p.wait().await.unwrap();
+        p.complete();
+
+        // Or times out
+        let mut p = Pairing::new(&info, Some(&handler), Duration::ZERO);
+        p.wait().await.unwrap();
+        assert!(matches!(p.wait().await, Err(Error::PairingRequired)));
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                PairingEvent::Required(info.clone()),
+                PairingEvent::Paired(info.clone()),
+                PairingEvent::Required(info.clone()),
+                PairingEvent::Failed(info.clone()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_apdu_large() {
+        // Responses over 255 bytes use the full u16 length
+        for len in [255usize, 256, 300, 1000] {
+            let resp: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+            let mut n: Vec<_> = BleEncoder::new(BLE_CMD_APDU, &resp, 156).collect();
+            assert_eq!(&n[0][3..5], &(len as u16).to_be_bytes());
+
+            // Devices tag continuation chunks with the APDU command
+            n.iter_mut().skip(1).for_each(|c| c[0] = BLE_CMD_APDU);
+
+            let r = read_apdu(futures::stream::iter(n)).await.unwrap();
+            assert_eq!(r, resp, "len: {len}");
+        }
+
+        // Truncated responses report closure
+        let n: Vec<_> = BleEncoder::new(BLE_CMD_APDU, &[0xaa; 300], 156)
+            .take(1)
+            .collect();
+        let r = read_apdu(futures::stream::iter(n)).await;
+        assert!(matches!(r, Err(Error::Closed)));
+    }
+
+    #[test]
+    fn ble_write_type() {
+        // Initial chunks are always written with response
+        assert_eq!(write_type(0, false), WriteType::WithResponse);
+        assert_eq!(write_type(0, true), WriteType::WithResponse);
+
+        // Continuation chunks are written without response where enabled
+        assert_eq!(write_type(1, false), WriteType::WithResponse);
+        assert_eq!(write_type(1, true), WriteType::WithoutResponse);
+        assert_eq!(write_type(7, true), WriteType::WithoutResponse);
+    }
+
+    #[test]
+    fn ble_parse_mtu() {
+        assert_eq!(
+            parse_mtu(&[BLE_CMD_MTU, 0x00, 0x00, 0x00, 0x01, 0x99]),
+            Some(0x99)
+        );
+
+        // Empty, truncated and other notifications are rejected
+        assert_eq!(parse_mtu(&[]), None);
+        assert_eq!(parse_mtu(&[BLE_CMD_MTU]), None);
+        assert_eq!(
+            parse_mtu(&[BLE_CMD_APDU, 0x00, 0x00, 0x00, 0x01, 0x99]),
+            None
+        );
+        assert_eq!(
+            parse_mtu(&[BLE_CMD_MTU, 0x00, 0x00, 0x00, 0x01, 0x99, 0x00]),
+            None
+        );
+    }
+
+    #[test]
+    fn ble_match_spec() {
+        let other = uuid!("0000180f-0000-1000-8000-00805f9b34fb");
+
+        // Models are matched on advertised services
+        for spec in BLE_SPECS {
+            let s = match_spec(BLE_SPECS, &[other, spec.service_uuid]);
+            assert_eq!(s, Some(spec));
+        }
+        assert_eq!(match_spec(BLE_SPECS, &[other]), None);
+        assert_eq!(match_spec(BLE_SPECS, &[]), None);
+
+        // Including registered specs
+        let future = BleSpec {
+            model: Model::Unknown(0x8004),
+            service_uuid: uuid!("13d63400-2c97-8004-0000-4c6564676572"),
+            notify_uuid: uuid!("13d63400-2c97-8004-0001-4c6564676572"),
+            write_uuid: uuid!("13d63400-2c97-8004-0002-4c6564676572"),
+            write_cmd_uuid: uuid!("13d63400-2c97-8004-0003-4c6564676572"),
+        };
+        let specs = [std::slice::from_ref(&future), BLE_SPECS].concat();
+        let s = match_spec(&specs, &[future.service_uuid]);
+        assert_eq!(s, Some(&future));
     }
 }
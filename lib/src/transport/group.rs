@@ -0,0 +1,178 @@
+//! Cross-transport device grouping, correlating connections to the same physical device
+//!
+//! Devices such as the Nano X may be connected over both USB and BLE, and are then
+//! listed once per transport. Ledger devices do not report a shared identifier over
+//! USB and BLE prior to connecting (USB serials are not unique and BLE names are not
+//! reported over USB), so [group_devices] only correlates connections where this is
+//! unambiguous: a single USB and a single BLE device of the same model. Virtual devices
+//! (TCP and loopback) are never grouped.
+
+use crate::info::{ConnInfo, ConnType, LedgerInfo, Model};
+
+/// Physical device, grouping available connections across transports
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceGroup {
+    /// Device model
+    pub model: Model,
+
+    /// Available connections, ordered by preference (USB over BLE)
+    pub conns: Vec<ConnInfo>,
+}
+
+impl DeviceGroup {
+    /// Fetch the preferred connection for the device
+    pub fn preferred(&self) -> LedgerInfo {
+        LedgerInfo {
+            model: self.model.clone(),
+            conn: self.conns[0].clone(),
+        }
+    }
+
+    /// Fetch device information for each available connection
+    pub fn infos(&self) -> impl Iterator<Item = LedgerInfo> + '_ {
+        self.conns.iter().map(|c| LedgerInfo {
+            model: self.model.clone(),
+            conn: c.clone(),
+        })
+    }
+}
+
+/// Group listed devices by physical device, preserving list order.
+///
+/// See [module docs](self) for correlation rules.
+#[cfg_attr(
+    not(any(
+        feature = "transport_usb",
+        feature = "transport_tcp",
+        feature = "transport_ble",
+        feature = "transport_loopback",
+        feature = "transport_webhid"
+    )),
+    allow(unreachable_code)
+)]
+pub fn group_devices(devices: Vec<LedgerInfo>) -> Vec<DeviceGroup> {
+    // Devices may only be correlated where each physical transport lists at most one of a model
+    let unique = |m: &Model| {
+        PHYSICAL.iter().all(|k| {
+            devices
+                .iter()
+                .filter(|d| &d.model == m && d.kind() == *k)
+                .count()
+                <= 1
+        })
+    };
+
+    let mut groups: Vec<DeviceGroup> = vec![];
+
+    for d in &devices {
+        let physical = PHYSICAL.contains(&d.kind());
+
+        let existing = match physical && unique(&d.model) {
+            true => groups.iter_mut().find(|g| {
+                g.model == d.model && g.conns.iter().all(|c| PHYSICAL.contains(&c.kind()))
+            }),
+            false => None,
+        };
+
+        match existing {
+            Some(g) => {
+                g.conns.push(d.conn.clone());
+                g.conns.sort_by_key(|c| preference(c.kind()));
+            }
+            None => groups.push(DeviceGroup {
+                model: d.model.clone(),
+                conns: vec![d.conn.clone()],
+            }),
+        }
+    }
+
+    groups
+}
+
+/// Physical transports, connections over which may refer to the same device
+const PHYSICAL: [ConnType; 2] = [ConnType::Usb, ConnType::Ble];
+
+/// Connection preference, lower values preferred
+fn preference(kind: ConnType) -> u8 {
+    match kind {
+        ConnType::Usb | ConnType::WebHid => 0,
+        ConnType::Ble => 1,
+        ConnType::Tcp => 2,
+        ConnType::Loopback => 3,
+    }
+}
+
+#[cfg(all(
+    test,
+    feature = "transport_usb",
+    feature = "transport_ble",
+    feature = "transport_tcp"
+))]
+mod tests {
+    use btleplug::api::BDAddr;
+
+    use super::*;
+    use crate::transport::{BleInfo, TcpInfo, UsbInfo};
+
+    fn usb(model: Model, path: &str) -> LedgerInfo {
+        LedgerInfo {
+            model,
+            conn: UsbInfo {
+                vid: 0x2c97,
+                pid: 0x4011,
+                path: Some(path.to_string()),
+                serial: Some("0001".to_string()),
+            }
+            .into(),
+        }
+    }
+
+    fn ble(model: Model, name: &str) -> LedgerInfo {
+        LedgerInfo {
+            model,
+            conn: BleInfo {
+                name: name.to_string(),
+                addr: BDAddr::from([0x01, 0x02, 0x03, 0x04, 0x05, name.len() as u8]),
+                ..Default::default()
+            }
+            .into(),
+        }
+    }
+
+    #[test]
+    fn group_usb_ble() {
+        let (u, b) = (usb(Model::NanoX, "a"), ble(Model::NanoX, "Nano X 1A2B"));
+        let tcp = LedgerInfo {
+            model: Model::NanoX,
+            conn: TcpInfo::default().into(),
+        };
+
+        // Single USB and BLE devices of the same model are grouped, preferring USB
+        let g = group_devices(vec![b.clone(), tcp.clone(), u.clone()]);
+        assert_eq!(g.len(), 2);
+        assert_eq!(g[0].conns, vec![u.conn.clone(), b.conn.clone()]);
+        assert_eq!(g[0].preferred(), u);
+        assert_eq!(g[0].infos().collect::<Vec<_>>(), vec![u.clone(), b.clone()]);
+
+        // Virtual devices are not grouped
+        assert_eq!(g[1].conns, vec![tcp.conn.clone()]);
+
+        // Different models are not grouped
+        let s = usb(Model::NanoSPlus, "b");
+        let g = group_devices(vec![s.clone(), b.clone()]);
+        assert_eq!(g.len(), 2);
+        assert_eq!(g[0].preferred(), s);
+    }
+
+    #[test]
+    fn group_ambiguous() {
+        let (u1, u2) = (usb(Model::NanoX, "a"), usb(Model::NanoX, "b"));
+        let b = ble(Model::NanoX, "Nano X 1A2B");
+
+        // Multiple devices of a model over one transport cannot be correlated
+        let g = group_devices(vec![u1, u2, b]);
+        assert_eq!(g.len(), 3);
+        assert!(g.iter().all(|g| g.conns.len() == 1));
+    }
+}
@@ -0,0 +1,166 @@
+//! Cross-process advisory device locks, preventing multiple processes interleaving
+//! frames on the same device.
+//!
+//! Locks are held on per-device files in the system temporary directory, containing
+//! the holder process for reporting via [Error::DeviceInUse]. As these are advisory
+//! they only apply between processes using this crate (with the same temporary directory).
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use hidapi::HidError;
+use tracing::debug;
+
+use crate::Error;
+
+/// Advisory lock for a device, released on drop
+#[derive(Debug)]
+pub(crate) struct DeviceLock {
+    _file: File,
+}
+
+impl DeviceLock {
+    /// Acquire the lock for a device path, returning [Error::DeviceInUse]
+    /// naming the holder where this is already held
+    pub fn acquire(device: &str) -> Result<Self, Error> {
+        let path = lock_path(device);
+        debug!("Acquiring device lock: {}", path.display());
+
+        let mut file = match try_lock(&path) {
+            Ok(Some(f)) => f,
+            Ok(None) => {
+                return Err(Error::DeviceInUse {
+                    holder: read_holder(&path),
+                })
+            }
+            Err(e) => return Err(io_error(e)),
+        };
+
+        // Record holder for reporting to other processes
+        file.set_len(0).map_err(io_error)?;
+        file.rewind().map_err(io_error)?;
+        write!(file, "{}", holder()).map_err(io_error)?;
+
+        Ok(Self { _file: file })
+    }
+}
+
+/// Open and lock a file, returning `None` if this is locked by another handle
+#[cfg(unix)]
+fn try_lock(path: &Path) -> std::io::Result<Option<File>> {
+    use std::os::fd::AsRawFd;
+
+    let file = open(path)?;
+
+    // SAFETY: `flock` is called on a valid file descriptor owned by `file`
+    match unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } {
+        0 => Ok(Some(file)),
+        _ => match std::io::Error::last_os_error() {
+            e if e.kind() == std::io::ErrorKind::WouldBlock => Ok(None),
+            e => Err(e),
+        },
+    }
+}
+
+/// Open and lock a file, returning `None` if this is locked by another handle.
+///
+/// Files are opened without write sharing, so other handles may read but not lock them.
+#[cfg(windows)]
+fn try_lock(path: &Path) -> std::io::Result<Option<File>> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    /// `FILE_SHARE_READ` share mode
+    const FILE_SHARE_READ: u32 = 0x01;
+    /// `ERROR_SHARING_VIOLATION` error code
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+
+    match options().share_mode(FILE_SHARE_READ).open(path) {
+        Ok(f) => Ok(Some(f)),
+        Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Open a lock file, locking is not supported on this platform
+#[cfg(not(any(unix, windows)))]
+fn try_lock(path: &Path) -> std::io::Result<Option<File>> {
+    open(path).map(Some)
+}
+
+/// Open (or create) a lock file
+#[cfg(not(windows))]
+fn open(path: &Path) -> std::io::Result<File> {
+    options().open(path)
+}
+
+/// Options for opening lock files
+fn options() -> OpenOptions {
+    let mut o = OpenOptions::new();
+    o.read(true).write(true).create(true).truncate(false);
+    o
+}
+
+/// Read the holder of a locked device
+fn read_holder(path: &Path) -> Option<String> {
+    let mut holder = String::new();
+    File::open(path).ok()?.read_to_string(&mut holder).ok()?;
+
+    Some(holder.trim().to_string()).filter(|h| !h.is_empty())
+}
+
+/// Build the lock file path for a device
+fn lock_path(device: &str) -> PathBuf {
+    let name: String = device
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+
+    std::env::temp_dir().join(format!("ledger-lib-{name}.lock"))
+}
+
+/// Describe the current process for lock holder reporting
+fn holder() -> String {
+    let exe = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    format!("{exe} (pid {})", std::process::id())
+}
+
+/// Map lock file IO errors
+fn io_error(error: std::io::Error) -> Error {
+    HidError::IoError { error }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_lock() {
+        let device = format!("/dev/test-lock-{}", std::process::id());
+
+        let l = DeviceLock::acquire(&device).unwrap();
+
+        // Second acquisition fails, naming the holder
+        match DeviceLock::acquire(&device) {
+            Err(Error::DeviceInUse { holder: Some(h) }) => {
+                assert!(h.contains(&format!("pid {}", std::process::id())), "{h}")
+            }
+            r => panic!("unexpected result: {r:?}"),
+        }
+
+        // Lock is released on drop
+        drop(l);
+        let _l = DeviceLock::acquire(&device).unwrap();
+
+        let _ = std::fs::remove_file(lock_path(&device));
+    }
+}
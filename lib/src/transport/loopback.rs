@@ -0,0 +1,167 @@
+//! Loopback transport, echoing commands back as responses for deterministic testing
+//! of [Device](crate::Device) implementations, [Exchange] wrappers and providers
+//! without hardware or simulators.
+
+use std::{fmt::Display, time::Duration};
+
+use tracing::debug;
+
+use crate::{
+    info::{LedgerInfo, Model},
+    Error,
+};
+
+use super::{Exchange, Transport};
+
+/// Default status word appended to loopback responses (`0x9000`, OK)
+pub const LOOPBACK_STATUS_OK: u16 = 0x9000;
+
+/// Loopback transport, provides a single [LoopbackDevice] echoing commands
+pub struct LoopbackTransport {
+    status: Option<u16>,
+}
+
+/// Loopback device, responds to each command with the command bytes
+/// followed by the configured status word (if set)
+pub struct LoopbackDevice {
+    pub info: LoopbackInfo,
+}
+
+/// Loopback device information
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoopbackInfo {
+    /// Status word appended to echoed responses
+    pub status: Option<u16>,
+}
+
+impl Default for LoopbackInfo {
+    fn default() -> Self {
+        Self {
+            status: Some(LOOPBACK_STATUS_OK),
+        }
+    }
+}
+
+impl Display for LoopbackInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            Some(s) => write!(f, "loopback (0x{s:04x})"),
+            None => write!(f, "loopback"),
+        }
+    }
+}
+
+impl Default for LoopbackTransport {
+    fn default() -> Self {
+        Self {
+            status: Some(LOOPBACK_STATUS_OK),
+        }
+    }
+}
+
+impl LoopbackTransport {
+    /// Create a new [LoopbackTransport] appending an OK status to responses
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self::default())
+    }
+
+    /// Set the status word appended to responses, `None` echoes commands unmodified
+    pub fn with_status(mut self, status: Option<u16>) -> Self {
+        self.status = status;
+        self
+    }
+}
+
+impl Transport for LoopbackTransport {
+    type Filters = ();
+    type Info = LoopbackInfo;
+    type Device = LoopbackDevice;
+
+    /// List loopback devices (always returns a single device)
+    async fn list(&mut self, _filters: ()) -> Result<Vec<LedgerInfo>, Error> {
+        Ok(vec![LedgerInfo {
+            model: Model::Unknown(0),
+            conn: LoopbackInfo {
+                status: self.status,
+            }
+            .into(),
+        }])
+    }
+
+    /// Connect to a loopback device
+    async fn connect(&mut self, info: LoopbackInfo) -> Result<LoopbackDevice, Error> {
+        debug!("Connecting to {info}");
+
+        Ok(LoopbackDevice { info })
+    }
+}
+
+impl LoopbackDevice {
+    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+}
+
+/// [Exchange] impl for [LoopbackDevice], echoes commands with the configured status
+impl Exchange for LoopbackDevice {
+    async fn exchange(&mut self, command: &[u8], _timeout: Duration) -> Result<Vec<u8>, Error> {
+        let mut resp = command.to_vec();
+
+        if let Some(s) = self.info.status {
+            resp.extend_from_slice(&s.to_be_bytes());
+        }
+
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn loopback_echo() {
+        let mut t = LoopbackTransport::new().unwrap();
+
+        let devices = t.list(()).await.unwrap();
+        assert_eq!(devices.len(), 1);
+
+        let mut d = t.connect(LoopbackInfo::default()).await.unwrap();
+        let resp = d
+            .exchange(&[0xe0, 0x01, 0x00, 0x00], Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        assert_eq!(&resp, &[0xe0, 0x01, 0x00, 0x00, 0x90, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn loopback_no_status() {
+        let mut t = LoopbackTransport::new().unwrap().with_status(None);
+
+        let info = LoopbackInfo { status: None };
+        let mut d = t.connect(info).await.unwrap();
+        let resp = d
+            .exchange(&[0xaa, 0xbb], Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        assert_eq!(&resp, &[0xaa, 0xbb]);
+    }
+
+    #[tokio::test]
+    async fn loopback_connect_any() {
+        let mut t = LoopbackTransport::new().unwrap().with_status(None);
+
+        // Connect without a prior list call
+        let mut d = t.connect_any(()).await.unwrap();
+        assert_eq!(d.info, LoopbackInfo { status: None });
+
+        let resp = d.exchange(&[0xaa], Duration::from_secs(1)).await.unwrap();
+        assert_eq!(&resp, &[0xaa]);
+
+        // Loopback devices have no path
+        assert!(matches!(t.connect_path("a").await, Err(Error::NoDevices)));
+    }
+}
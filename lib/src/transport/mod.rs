@@ -3,44 +3,80 @@
 //! Transports are gated by `transport_X` features, while [GenericTransport] and
 //! [GenericDevice] provide an abstraction over enabled transports.
 //!
-//! # Safety
-//! [UsbTransport] (and thus [GenericTransport] when `transport_usb` feature is enabled)
-//! is _not_ `Send` or `Sync`, however this is marked as such to appease `async_trait`...
-//!
-//! Once `async_trait` has stabilised transports can be marked correctly.
-//! (This is also implemented under the `unstable_async_trait` feature)
-//! Until then, use [LedgerProvider](crate::LedgerProvider) for a `Sync + Send` interface or
-//!  be _super sure_ you're not going to call transports from a multi-threaded context.
+//! # Thread Safety
+//! Transports and devices are `Send` but not `Sync`, and `hidapi` only supports a single
+//! [UsbTransport] instance per application. Use [LedgerProvider](crate::LedgerProvider)
+//! for a shared `Sync + Send` interface backed by a pinned thread, or
+//! [PinnedExchange](crate::PinnedExchange) to confine a single device to a worker thread.
 
 use std::{fmt::Debug, time::Duration};
 
-#[cfg(feature = "transport_ble")]
-use tracing::warn;
+use futures::Stream;
 
-use tracing::debug;
+use tracing::{debug, warn};
 
 #[cfg(feature = "transport_usb")]
-mod usb;
+pub(crate) mod usb;
+#[cfg(feature = "transport_usb")]
+pub use usb::{HidChannel, UsbDevice, UsbFilters, UsbInfo, UsbTransport};
 #[cfg(feature = "transport_usb")]
-pub use usb::{UsbDevice, UsbInfo, UsbTransport};
+mod lock;
 
 #[cfg(feature = "transport_ble")]
-mod ble;
+pub(crate) mod ble;
 #[cfg(feature = "transport_ble")]
-pub use ble::{BleDevice, BleInfo, BleTransport};
+pub use ble::{
+    BleDevice, BleFilters, BleInfo, BleSpec, BleTransport, PairingEvent, PairingHandler, BLE_SPECS,
+};
 
 #[cfg(feature = "transport_tcp")]
-mod tcp;
+pub(crate) mod tcp;
 #[cfg(feature = "transport_tcp")]
-pub use tcp::{TcpDevice, TcpInfo, TcpTransport};
-
+pub use tcp::{
+    PortRange, ReconnectPolicy, TcpDevice, TcpFilters, TcpInfo, TcpTransport, TCP_ADDRS_ENV,
+};
+/// Re-export of `rustls` for TLS configuration (see [TcpTransport::with_tls_config])
+#[cfg(feature = "transport_tcp_tls")]
+pub use tokio_rustls::rustls;
+
+#[cfg(feature = "transport_loopback")]
+mod loopback;
+#[cfg(feature = "transport_loopback")]
+pub use loopback::{LoopbackDevice, LoopbackInfo, LoopbackTransport, LOOPBACK_STATUS_OK};
+
+#[cfg(all(feature = "transport_webhid", not(target_arch = "wasm32")))]
+compile_error!("`transport_webhid` is only supported on wasm32 targets");
+#[cfg(all(feature = "transport_webhid", not(web_sys_unstable_apis)))]
+compile_error!("`transport_webhid` requires building with `RUSTFLAGS=--cfg=web_sys_unstable_apis`");
+
+#[cfg(feature = "transport_webhid")]
+pub(crate) mod webhid;
+#[cfg(feature = "transport_webhid")]
+pub use webhid::{WebHidDevice, WebHidInfo, WebHidTransport};
+
+mod watch;
+pub use watch::{watch, DeviceEvent, DEFAULT_WATCH_INTERVAL};
+
+mod group;
+pub use group::{group_devices, DeviceGroup};
+
+#[cfg(any(
+    feature = "transport_usb",
+    feature = "transport_tcp",
+    feature = "transport_ble",
+    feature = "transport_loopback",
+    feature = "transport_webhid"
+))]
+use crate::info::ConnType;
 use crate::{
     info::{ConnInfo, LedgerInfo},
-    Error, Exchange, Filters,
+    Error, ErrorContext, Exchange, Filters, Operation, Timeouts,
 };
 
 /// [Transport] trait provides an abstract interface for transport implementations
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
+///
+/// Note `Send` bounds are not applied to returned futures, these are `Send` where the implementing type is.
+#[allow(async_fn_in_trait)]
 pub trait Transport {
     /// Connection filters
     type Filters: Default + Debug;
@@ -54,16 +90,48 @@ pub trait Transport {
 
     /// Connect to a device using info from a previous list operation
     async fn connect(&mut self, info: Self::Info) -> Result<Self::Device, Error>;
+
+    /// Connect to the first available device matching the provided filters,
+    /// listing devices prior to connecting
+    async fn connect_any(&mut self, filters: Self::Filters) -> Result<Self::Device, Error>
+    where
+        Self::Info: TryFrom<LedgerInfo>,
+    {
+        let devices = self.list(filters).await?;
+
+        let info = devices.into_iter().next().ok_or(Error::NoDevices)?;
+        debug!("Connecting to first available device: {:?}", info);
+
+        let info = Self::Info::try_from(info).map_err(|_| Error::Unknown)?;
+        self.connect(info).await
+    }
+
+    /// Connect to the device with the provided path (see [ConnInfo::path]),
+    /// listing devices with default filters prior to connecting
+    async fn connect_path(&mut self, path: &str) -> Result<Self::Device, Error>
+    where
+        Self::Info: TryFrom<LedgerInfo>,
+    {
+        let devices = self.list(Self::Filters::default()).await?;
+
+        let info = match devices
+            .into_iter()
+            .find(|d| d.conn.path().as_deref() == Some(path))
+        {
+            Some(i) => i,
+            None => {
+                warn!("No device found with path: {path}");
+                return Err(Error::NoDevices);
+            }
+        };
+
+        let info = Self::Info::try_from(info).map_err(|_| Error::Unknown)?;
+        self.connect(info).await
+    }
 }
 
 /// Blanket [Transport] implementation for references types
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
-impl<T: Transport + Send> Transport for &mut T
-where
-    <T as Transport>::Device: Send,
-    <T as Transport>::Filters: Send,
-    <T as Transport>::Info: Send,
-{
+impl<T: Transport> Transport for &mut T {
     type Filters = <T as Transport>::Filters;
     type Info = <T as Transport>::Info;
     type Device = <T as Transport>::Device;
@@ -76,17 +144,42 @@ where
     }
 }
 
+/// Per-transport discovery filters, applied alongside [Filters] when listing devices
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct TransportFilters {
+    #[cfg(feature = "transport_usb")]
+    #[cfg_attr(feature = "clap", clap(flatten))]
+    pub usb: UsbFilters,
+
+    #[cfg(feature = "transport_ble")]
+    #[cfg_attr(feature = "clap", clap(flatten))]
+    pub ble: BleFilters,
+
+    #[cfg(feature = "transport_tcp")]
+    #[cfg_attr(feature = "clap", clap(flatten))]
+    pub tcp: TcpFilters,
+}
+
 /// [GenericTransport] for device communication, abstracts underlying transport types
 ///
+/// Transports are only present where enabled by features and selected on
+/// initialisation (see [GenericTransport::init]).
 pub struct GenericTransport {
     #[cfg(feature = "transport_usb")]
-    usb: UsbTransport,
+    usb: Option<UsbTransport>,
 
     #[cfg(feature = "transport_ble")]
-    ble: BleTransport,
+    ble: Option<BleTransport>,
 
     #[cfg(feature = "transport_tcp")]
-    tcp: TcpTransport,
+    tcp: Option<TcpTransport>,
+
+    #[cfg(feature = "transport_loopback")]
+    loopback: Option<LoopbackTransport>,
+
+    #[cfg(feature = "transport_webhid")]
+    webhid: Option<WebHidTransport>,
 }
 
 /// [GenericDevice] for communication with ledger devices, abstracts underlying transport types
@@ -100,48 +193,144 @@ pub enum GenericDevice {
 
     #[cfg(feature = "transport_tcp")]
     Tcp(TcpDevice),
+
+    #[cfg(feature = "transport_loopback")]
+    Loopback(LoopbackDevice),
+
+    #[cfg(feature = "transport_webhid")]
+    WebHid(WebHidDevice),
 }
 
 impl GenericTransport {
     /// Create a new [GenericTransport] with all endabled transports
     pub async fn new() -> Result<Self, Error> {
-        debug!("Initialising GenericTransport");
+        Self::init(Filters::Any).await
+    }
+
+    /// Create a new [GenericTransport] using only enabled transports matching `filters`,
+    /// avoiding initialisation of unused transports (eg. HID or BLE when using
+    /// [Filters::Tcp])
+    pub async fn init(filters: Filters) -> Result<Self, Error> {
+        debug!("Initialising GenericTransport ({filters:?})");
 
         Ok(Self {
             #[cfg(feature = "transport_usb")]
-            usb: UsbTransport::new()?,
+            usb: match selected(filters, Filters::Hid) {
+                true => Some(UsbTransport::new()?),
+                false => None,
+            },
 
             #[cfg(feature = "transport_ble")]
-            ble: BleTransport::new().await?,
+            ble: match selected(filters, Filters::Ble) {
+                true => Some(BleTransport::new().await?),
+                false => None,
+            },
 
             #[cfg(feature = "transport_tcp")]
-            tcp: TcpTransport::new()?,
+            tcp: match selected(filters, Filters::Tcp) {
+                true => Some(TcpTransport::new()?),
+                false => None,
+            },
+
+            #[cfg(feature = "transport_loopback")]
+            loopback: match selected(filters, Filters::Loopback) {
+                true => Some(LoopbackTransport::new()?),
+                false => None,
+            },
+
+            #[cfg(feature = "transport_webhid")]
+            webhid: match selected(filters, Filters::Hid) {
+                true => Some(WebHidTransport::new()?),
+                false => None,
+            },
         })
     }
-}
 
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
-impl Transport for GenericTransport {
-    type Filters = Filters;
-    type Info = LedgerInfo;
-    type Device = GenericDevice;
+    /// Set a handler for BLE [PairingEvent]s (see [BleTransport::with_pairing_handler])
+    #[cfg(feature = "transport_ble")]
+    pub fn with_ble_pairing_handler(
+        mut self,
+        f: impl Fn(PairingEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.ble = self.ble.map(|t| t.with_pairing_handler(f));
+        self
+    }
 
-    /// List available ledger devices using all enabled transports
-    async fn list(&mut self, filters: Filters) -> Result<Vec<LedgerInfo>, Error> {
+    /// Override the BLE MTU, skipping MTU negotiation (see [BleTransport::with_mtu])
+    #[cfg(feature = "transport_ble")]
+    pub fn with_ble_mtu(mut self, mtu: u8) -> Self {
+        self.ble = self.ble.map(|t| t.with_mtu(mtu));
+        self
+    }
+
+    /// Set a [ReconnectPolicy] for TCP devices (see [TcpTransport::with_reconnect])
+    #[cfg(feature = "transport_tcp")]
+    pub fn with_tcp_reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.tcp = self.tcp.map(|t| t.with_reconnect(policy));
+        self
+    }
+
+    /// Set the TLS configuration for TCP devices (see [TcpTransport::with_tls_config])
+    #[cfg(feature = "transport_tcp_tls")]
+    pub fn with_tcp_tls_config(mut self, config: std::sync::Arc<rustls::ClientConfig>) -> Self {
+        self.tcp = self.tcp.map(|t| t.with_tls_config(config));
+        self
+    }
+
+    /// List available ledger devices using all enabled transports,
+    /// applying the provided per-transport filters
+    #[cfg_attr(
+        not(any(
+            feature = "transport_usb",
+            feature = "transport_tcp",
+            feature = "transport_ble"
+        )),
+        allow(unused_variables)
+    )]
+    #[cfg_attr(
+        not(any(
+            feature = "transport_usb",
+            feature = "transport_tcp",
+            feature = "transport_ble",
+            feature = "transport_loopback",
+            feature = "transport_webhid"
+        )),
+        allow(unused_mut)
+    )]
+    pub async fn list_filtered(
+        &mut self,
+        filters: Filters,
+        opts: &TransportFilters,
+    ) -> Result<Vec<LedgerInfo>, Error> {
         let mut devices = vec![];
 
         #[cfg(feature = "transport_usb")]
-        if filters == Filters::Any || filters == Filters::Hid {
-            let mut d = self.usb.list(()).await?;
+        if let Some(t) = self
+            .usb
+            .as_mut()
+            .filter(|_| selected(filters, Filters::Hid))
+        {
+            let mut d = t
+                .list(opts.usb.clone())
+                .await
+                .map_err(|e| e.with_context(list_context(ConnType::Usb)))?;
             devices.append(&mut d);
         }
 
         #[cfg(feature = "transport_ble")]
-        if filters == Filters::Any || filters == Filters::Ble {
+        if let Some(t) = self
+            .ble
+            .as_mut()
+            .filter(|_| selected(filters, Filters::Ble))
+        {
             // BLE discovery is allowed to fail if not exclusively selected
             // as dbus does not always provide the relevant service (eg. under WSL)
             // TODO: work out whether we can detect this to separate no BLE from discovery failure
-            match self.ble.list(()).await {
+            match t
+                .list(opts.ble.clone())
+                .await
+                .map_err(|e| e.with_context(list_context(ConnType::Ble)))
+            {
                 Ok(mut d) => devices.append(&mut d),
                 Err(e) if filters == Filters::Any => {
                     warn!("BLE discovery failed: {e:?}");
@@ -151,29 +340,166 @@ impl Transport for GenericTransport {
         }
 
         #[cfg(feature = "transport_tcp")]
-        if filters == Filters::Any || filters == Filters::Tcp {
-            let mut d = self.tcp.list(()).await?;
+        if let Some(t) = self
+            .tcp
+            .as_mut()
+            .filter(|_| selected(filters, Filters::Tcp))
+        {
+            let mut d = t
+                .list(opts.tcp.clone())
+                .await
+                .map_err(|e| e.with_context(list_context(ConnType::Tcp)))?;
+            devices.append(&mut d);
+        }
+
+        #[cfg(feature = "transport_loopback")]
+        if let Some(t) = self
+            .loopback
+            .as_mut()
+            .filter(|_| selected(filters, Filters::Loopback))
+        {
+            let mut d = t
+                .list(())
+                .await
+                .map_err(|e| e.with_context(list_context(ConnType::Loopback)))?;
+            devices.append(&mut d);
+        }
+
+        #[cfg(feature = "transport_webhid")]
+        if let Some(t) = self
+            .webhid
+            .as_mut()
+            .filter(|_| selected(filters, Filters::Hid))
+        {
+            let mut d = t
+                .list(())
+                .await
+                .map_err(|e| e.with_context(list_context(ConnType::WebHid)))?;
             devices.append(&mut d);
         }
 
         Ok(devices)
     }
 
+    /// List available ledger devices using all enabled transports, grouping
+    /// connections to the same physical device (see [group_devices])
+    pub async fn list_grouped(
+        &mut self,
+        filters: Filters,
+        opts: &TransportFilters,
+    ) -> Result<Vec<DeviceGroup>, Error> {
+        let devices = self.list_filtered(filters, opts).await?;
+        Ok(group_devices(devices))
+    }
+
+    /// Watch enabled transports for devices being connected or disconnected,
+    /// listing devices every `interval` (see [watch])
+    pub fn watch(
+        &mut self,
+        filters: Filters,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<DeviceEvent, Error>> + '_ {
+        watch(self, filters, interval)
+    }
+}
+
+/// Helper to check whether a transport of `kind` is selected by `filters`
+#[cfg(any(
+    feature = "transport_usb",
+    feature = "transport_ble",
+    feature = "transport_tcp",
+    feature = "transport_loopback",
+    feature = "transport_webhid"
+))]
+fn selected(filters: Filters, kind: Filters) -> bool {
+    filters == Filters::Any || filters == kind
+}
+
+/// Helper to fetch an initialised transport for connection
+#[cfg(any(
+    feature = "transport_usb",
+    feature = "transport_ble",
+    feature = "transport_tcp",
+    feature = "transport_loopback",
+    feature = "transport_webhid"
+))]
+fn transport<T>(t: &mut Option<T>, kind: ConnType) -> Result<&mut T, Error> {
+    t.as_mut().ok_or(Error::TransportUnavailable(kind))
+}
+
+/// Helper to build [ErrorContext] for per-transport list operations
+#[cfg(any(
+    feature = "transport_usb",
+    feature = "transport_tcp",
+    feature = "transport_ble",
+    feature = "transport_loopback",
+    feature = "transport_webhid"
+))]
+fn list_context(kind: ConnType) -> ErrorContext {
+    ErrorContext::new(Operation::List).with_kind(kind)
+}
+
+impl Transport for GenericTransport {
+    type Filters = Filters;
+    type Info = LedgerInfo;
+    type Device = GenericDevice;
+
+    /// List available ledger devices using all enabled transports with default
+    /// per-transport filters (see [GenericTransport::list_filtered])
+    async fn list(&mut self, filters: Filters) -> Result<Vec<LedgerInfo>, Error> {
+        self.list_filtered(filters, &TransportFilters::default())
+            .await
+    }
+
     /// Connect to a ledger device using available transports
     ///
+    #[cfg_attr(
+        not(any(
+            feature = "transport_usb",
+            feature = "transport_tcp",
+            feature = "transport_ble",
+            feature = "transport_loopback",
+            feature = "transport_webhid"
+        )),
+        allow(unused_variables)
+    )]
     async fn connect(&mut self, info: LedgerInfo) -> Result<GenericDevice, Error> {
         debug!("Connecting to device: {:?}", info);
 
-        let d = match info.conn {
-            #[cfg(feature = "transport_usb")]
-            ConnInfo::Usb(i) => self.usb.connect(i).await.map(GenericDevice::Usb)?,
-            #[cfg(feature = "transport_tcp")]
-            ConnInfo::Tcp(i) => self.tcp.connect(i).await.map(GenericDevice::Tcp)?,
-            #[cfg(feature = "transport_ble")]
-            ConnInfo::Ble(i) => self.ble.connect(i).await.map(GenericDevice::Ble)?,
-        };
+        let ctx = ErrorContext::new(Operation::Connect).with_info(info.clone());
+
+        let d: Result<GenericDevice, Error> = async {
+            match info.conn {
+                #[cfg(feature = "transport_usb")]
+                ConnInfo::Usb(i) => transport(&mut self.usb, ConnType::Usb)?
+                    .connect(i)
+                    .await
+                    .map(GenericDevice::Usb),
+                #[cfg(feature = "transport_tcp")]
+                ConnInfo::Tcp(i) => transport(&mut self.tcp, ConnType::Tcp)?
+                    .connect(i)
+                    .await
+                    .map(GenericDevice::Tcp),
+                #[cfg(feature = "transport_ble")]
+                ConnInfo::Ble(i) => transport(&mut self.ble, ConnType::Ble)?
+                    .connect(i)
+                    .await
+                    .map(GenericDevice::Ble),
+                #[cfg(feature = "transport_loopback")]
+                ConnInfo::Loopback(i) => transport(&mut self.loopback, ConnType::Loopback)?
+                    .connect(i)
+                    .await
+                    .map(GenericDevice::Loopback),
+                #[cfg(feature = "transport_webhid")]
+                ConnInfo::WebHid(i) => transport(&mut self.webhid, ConnType::WebHid)?
+                    .connect(i)
+                    .await
+                    .map(GenericDevice::WebHid),
+            }
+        }
+        .await;
 
-        Ok(d)
+        d.map_err(|e| e.with_context(ctx))
     }
 }
 
@@ -187,10 +513,23 @@ impl GenericDevice {
             GenericDevice::Ble(d) => d.info.clone().into(),
             #[cfg(feature = "transport_tcp")]
             GenericDevice::Tcp(d) => d.info.clone().into(),
+            #[cfg(feature = "transport_loopback")]
+            GenericDevice::Loopback(d) => d.info.clone().into(),
+            #[cfg(feature = "transport_webhid")]
+            GenericDevice::WebHid(d) => d.info.clone().into(),
+            #[cfg(not(any(
+                feature = "transport_usb",
+                feature = "transport_tcp",
+                feature = "transport_ble",
+                feature = "transport_loopback",
+                feature = "transport_webhid"
+            )))]
+            _ => unreachable!(),
         }
     }
 
-    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
+    /// Check whether the underlying device is still connected
+    pub async fn is_connected(&self) -> Result<bool, Error> {
         match self {
             #[cfg(feature = "transport_usb")]
             GenericDevice::Usb(d) => d.is_connected().await,
@@ -198,22 +537,80 @@ impl GenericDevice {
             GenericDevice::Ble(d) => d.is_connected().await,
             #[cfg(feature = "transport_tcp")]
             GenericDevice::Tcp(d) => d.is_connected().await,
+            #[cfg(feature = "transport_loopback")]
+            GenericDevice::Loopback(d) => d.is_connected().await,
+            #[cfg(feature = "transport_webhid")]
+            GenericDevice::WebHid(d) => d.is_connected().await,
+            #[cfg(not(any(
+                feature = "transport_usb",
+                feature = "transport_tcp",
+                feature = "transport_ble",
+                feature = "transport_loopback",
+                feature = "transport_webhid"
+            )))]
+            _ => unreachable!(),
+        }
+    }
+
+    /// Close the device, disconnecting BLE peripherals (see [BleDevice::close]),
+    /// with other transports closed on drop
+    pub async fn close(self) -> Result<(), Error> {
+        #[cfg(feature = "transport_ble")]
+        if let GenericDevice::Ble(mut d) = self {
+            return d.close().await;
         }
+
+        Ok(())
     }
 }
 
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Exchange for GenericDevice {
     /// Exchange an APDU with the [GenericDevice]
     async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
-        match self {
+        self.exchange_timeouts(command, timeout.into()).await
+    }
+
+    /// Exchange an APDU with the [GenericDevice] using separate transport and user [Timeouts]
+    #[cfg_attr(
+        not(any(
+            feature = "transport_usb",
+            feature = "transport_tcp",
+            feature = "transport_ble",
+            feature = "transport_loopback",
+            feature = "transport_webhid"
+        )),
+        allow(unused_variables, unreachable_code)
+    )]
+    async fn exchange_timeouts(
+        &mut self,
+        command: &[u8],
+        timeouts: Timeouts,
+    ) -> Result<Vec<u8>, Error> {
+        let r: Result<Vec<u8>, Error> = match self {
             #[cfg(feature = "transport_usb")]
-            Self::Usb(d) => d.exchange(command, timeout).await,
+            Self::Usb(d) => d.exchange_timeouts(command, timeouts).await,
             #[cfg(feature = "transport_ble")]
-            Self::Ble(d) => d.exchange(command, timeout).await,
+            Self::Ble(d) => d.exchange_timeouts(command, timeouts).await,
             #[cfg(feature = "transport_tcp")]
-            Self::Tcp(d) => d.exchange(command, timeout).await,
-        }
+            Self::Tcp(d) => d.exchange_timeouts(command, timeouts).await,
+            #[cfg(feature = "transport_loopback")]
+            Self::Loopback(d) => d.exchange_timeouts(command, timeouts).await,
+            #[cfg(feature = "transport_webhid")]
+            Self::WebHid(d) => d.exchange_timeouts(command, timeouts).await,
+            #[cfg(not(any(
+                feature = "transport_usb",
+                feature = "transport_tcp",
+                feature = "transport_ble",
+                feature = "transport_loopback",
+                feature = "transport_webhid"
+            )))]
+            _ => unreachable!(),
+        };
+
+        r.map_err(|e| {
+            let ctx = ErrorContext::new(Operation::Exchange).with_conn(self.info());
+            e.with_context(ctx)
+        })
     }
 }
 
@@ -237,3 +634,17 @@ impl From<BleDevice> for GenericDevice {
         Self::Ble(value)
     }
 }
+
+#[cfg(feature = "transport_loopback")]
+impl From<LoopbackDevice> for GenericDevice {
+    fn from(value: LoopbackDevice) -> Self {
+        Self::Loopback(value)
+    }
+}
+
+#[cfg(feature = "transport_webhid")]
+impl From<WebHidDevice> for GenericDevice {
+    fn from(value: WebHidDevice) -> Self {
+        Self::WebHid(value)
+    }
+}
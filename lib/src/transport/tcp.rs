@@ -1,88 +1,408 @@
 use std::{
     fmt::Display,
     net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    str::FromStr,
     time::Duration,
 };
 
+#[cfg(feature = "runtime_tokio")]
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt, Interest},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, Interest},
     net::{TcpListener, TcpStream},
 };
+
+#[cfg(not(feature = "runtime_tokio"))]
+use async_net::{TcpListener, TcpStream};
+use futures::future::{join, join_all};
+#[cfg(not(feature = "runtime_tokio"))]
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tracing::{debug, error};
 
+#[cfg(feature = "transport_tcp_tls")]
+use std::sync::Arc;
+#[cfg(feature = "transport_tcp_tls")]
+use tokio_rustls::{
+    rustls::{self, pki_types::ServerName, ClientConfig, RootCertStore},
+    TlsConnector,
+};
+
 use crate::{
-    info::{LedgerInfo, Model},
-    Error,
+    info::{AppInfo, LedgerInfo, Model},
+    rt,
+    wipe::Scratch,
+    Error, Timeouts, UserWait,
 };
 
 use super::{Exchange, Transport};
 
+mod speculos;
+
 /// TCP transport implementation for interacting with Speculos via the TCP APDU socket
 #[derive(Default)]
-pub struct TcpTransport {}
+pub struct TcpTransport {
+    connector: Connector,
+    reconnect: Option<ReconnectPolicy>,
+}
 
 /// TCP based device
 pub struct TcpDevice {
-    s: TcpStream,
+    s: Box<dyn Conn>,
     pub info: TcpInfo,
+    connector: Connector,
+    reconnect: Option<ReconnectPolicy>,
+}
+
+/// Device connection stream, plain TCP or TLS
+trait Conn: AsyncRead + AsyncWrite + Unpin + Send + Sync {
+    /// Fetch the underlying TCP stream
+    fn tcp(&self) -> &TcpStream;
+}
+
+impl Conn for TcpStream {
+    fn tcp(&self) -> &TcpStream {
+        self
+    }
+}
+
+#[cfg(feature = "transport_tcp_tls")]
+impl Conn for tokio_rustls::client::TlsStream<TcpStream> {
+    fn tcp(&self) -> &TcpStream {
+        self.get_ref().0
+    }
+}
+
+/// Connection helper, applying TLS where requested by the [TcpInfo]
+#[derive(Clone, Default)]
+struct Connector {
+    #[cfg(feature = "transport_tcp_tls")]
+    tls: Option<Arc<ClientConfig>>,
+}
+
+impl Connector {
+    async fn connect(&self, info: &TcpInfo) -> Result<Box<dyn Conn>, Error> {
+        let s = TcpStream::connect(info.addr).await?;
+
+        match &info.tls {
+            None => Ok(Box::new(s)),
+            #[cfg(feature = "transport_tcp_tls")]
+            Some(name) => self.connect_tls(s, name).await,
+            #[cfg(not(feature = "transport_tcp_tls"))]
+            Some(_) => Err(Error::Tcp(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "TLS connections require the `transport_tcp_tls` feature",
+            ))),
+        }
+    }
+
+    #[cfg(feature = "transport_tcp_tls")]
+    async fn connect_tls(&self, s: TcpStream, name: &str) -> Result<Box<dyn Conn>, Error> {
+        let config = match &self.tls {
+            Some(c) => c.clone(),
+            None => default_tls_config()?,
+        };
+
+        let name = ServerName::try_from(name.to_string())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+        let s = TlsConnector::from(config).connect(name, s).await?;
+
+        Ok(Box::new(s))
+    }
+}
+
+/// Build the default TLS configuration, verifying servers using the webpki root certificates
+#[cfg(feature = "transport_tcp_tls")]
+fn default_tls_config() -> Result<Arc<ClientConfig>, Error> {
+    let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .map_err(std::io::Error::other)?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+    Ok(Arc::new(config))
+}
+
+/// Reconnection policy for [TcpDevice]s, retrying exchanges following connection
+/// errors (for example where speculos is restarted between tests).
+///
+/// Commands are re-sent following reconnection, so may be executed twice where the
+/// connection fails after the command was received.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ReconnectPolicy {
+    /// Maximum reconnection attempts per exchange
+    pub max_attempts: usize,
+    /// Delay prior to the first reconnection attempt, doubled for each subsequent attempt
+    pub backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Compute the delay prior to a reconnection attempt
+    fn delay(&self, attempt: usize) -> Duration {
+        self.backoff.saturating_mul(1 << attempt.min(16))
+    }
 }
 
 /// TCP device information
-#[derive(Clone, PartialEq, Debug)]
+///
+/// The running `app` is populated by list operations where the Speculos HTTP API is
+/// reachable (see [TcpFilters::http_port]) and is not considered when comparing devices.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TcpInfo {
     pub addr: SocketAddr,
+    /// TLS server name, connecting using TLS where set (requires the `transport_tcp_tls` feature)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub tls: Option<String>,
+    /// Running application, where reported by the Speculos HTTP API
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub app: Option<AppInfo>,
+}
+
+impl PartialEq for TcpInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.addr == other.addr && self.tls == other.tls
+    }
 }
 
 impl Default for TcpInfo {
     fn default() -> Self {
         Self {
             addr: SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 1237)),
+            tls: None,
+            app: None,
+        }
+    }
+}
+
+/// Environment variable listing additional endpoints to probe, as comma separated `host:port` values
+pub const TCP_ADDRS_ENV: &str = "LEDGER_TCP_ADDRS";
+
+/// Timeout connecting to endpoints when probing for devices
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// TCP device discovery filters
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct TcpFilters {
+    #[cfg_attr(
+        feature = "clap",
+        clap(long = "tcp-ports", value_delimiter = ',', default_values_t = [1237])
+    )]
+    /// Local ports to probe for speculos APDU sockets
+    pub ports: Vec<u16>,
+
+    #[cfg_attr(feature = "clap", clap(long = "tcp-port-range"))]
+    /// Range of local ports to probe (`START-END`, inclusive), for multiple simulator instances
+    pub port_range: Option<PortRange>,
+
+    #[cfg_attr(feature = "clap", clap(long = "tcp-addrs", value_delimiter = ','))]
+    /// Additional `host:port` endpoints to probe (for remote or multiple simulators),
+    /// extended by endpoints listed in `LEDGER_TCP_ADDRS`. Prefix with `tls://` for TLS endpoints
+    pub addrs: Vec<String>,
+
+    #[cfg_attr(feature = "clap", clap(long = "tcp-http-port", default_value_t = 5000))]
+    /// Speculos HTTP API port, queried on each device host for the model and running app
+    /// (0 to disable, not queried for TLS endpoints)
+    pub http_port: u16,
+}
+
+impl Default for TcpFilters {
+    fn default() -> Self {
+        Self {
+            ports: vec![1237],
+            port_range: None,
+            addrs: vec![],
+            http_port: 5000,
+        }
+    }
+}
+
+impl TcpFilters {
+    /// Fetch local ports to probe, combining listed ports with the port range
+    fn ports(&self) -> Vec<u16> {
+        let range = self.port_range.iter().flat_map(|r| r.start..=r.end);
+
+        let mut ports = vec![];
+        for p in self.ports.iter().cloned().chain(range) {
+            if !ports.contains(&p) {
+                ports.push(p);
+            }
+        }
+
+        ports
+    }
+
+    /// Fetch endpoints to probe, combining configured endpoints with those from
+    /// the `LEDGER_TCP_ADDRS` environment variable
+    fn endpoints(&self) -> Vec<String> {
+        let env = std::env::var(TCP_ADDRS_ENV).unwrap_or_default();
+
+        let mut addrs = self.addrs.clone();
+        for a in parse_addrs(&env) {
+            if !addrs.contains(&a) {
+                addrs.push(a);
+            }
         }
+
+        addrs
     }
 }
 
+/// Inclusive range of ports for discovery, parsed from `START-END`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl FromStr for PortRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("Invalid port range '{s}', expected START-END"))?;
+
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<u16>()
+                .map_err(|e| format!("Invalid port '{v}': {e}"))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+
+        if start > end {
+            return Err(format!("Invalid port range '{s}', start exceeds end"));
+        }
+
+        Ok(Self { start, end })
+    }
+}
+
+impl Display for PortRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Split an endpoint into the address to connect to and the TLS server name
+/// for `tls://host:port` endpoints
+fn parse_endpoint(endpoint: &str) -> (&str, Option<String>) {
+    match endpoint.strip_prefix("tls://") {
+        Some(addr) => {
+            let host = addr.rsplit_once(':').map(|(h, _p)| h).unwrap_or(addr);
+            (addr, Some(host.trim_matches(['[', ']']).to_string()))
+        }
+        None => (endpoint, None),
+    }
+}
+
+/// Parse a comma separated list of endpoints
+fn parse_addrs(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 impl Display for TcpInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.addr)
+        match &self.tls {
+            Some(name) => write!(f, "{} (TLS: {name})", self.addr),
+            None => write!(f, "{}", self.addr),
+        }
     }
 }
 
 impl TcpTransport {
     /// Create a new [TcpTransport] instance
     pub fn new() -> Result<Self, Error> {
-        Ok(Self {})
+        Ok(Self::default())
+    }
+
+    /// Set the TLS configuration for TLS connections (see [TcpInfo::tls]), for example
+    /// to trust self-signed certificates, otherwise the webpki root certificates are used
+    #[cfg(feature = "transport_tcp_tls")]
+    pub fn with_tls_config(mut self, config: Arc<ClientConfig>) -> Self {
+        self.connector.tls = Some(config);
+        self
+    }
+
+    /// Set a [ReconnectPolicy] for devices connected using this transport
+    pub fn with_reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = Some(policy);
+        self
     }
 }
 
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Transport for TcpTransport {
-    type Filters = ();
+    type Filters = TcpFilters;
     type Info = TcpInfo;
     type Device = TcpDevice;
 
     /// List available devices using the [TcpTransport]
     ///
-    /// (This looks for a speculos socket on the default port and returns a device if found,
-    /// if you want to connect to a specific device use [TcpTransport::connect])
-    async fn list(&mut self, _filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
-        let mut devices = vec![];
-
-        // Check whether a speculos socket is open on the default port
-        let addr = SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 1237);
-
-        // We can't -connect- to speculos as this does not handle multiple TCP connections
-        // so instead we attempt to bind to the socket we expect speculos to occupy.
-        match TcpListener::bind(addr).await {
-            Ok(_) => (),
-            // A failure indicates this is in use and we should report a device available for connection
-            Err(_) => {
-                devices.push(LedgerInfo {
-                    conn: TcpInfo { addr }.into(),
-                    model: Model::Unknown(0),
-                });
+    /// (This looks for speculos sockets on the filtered ports and endpoints, probed concurrently,
+    /// and returns a device for each found, if you want to connect to a specific device use
+    /// [TcpTransport::connect])
+    async fn list(&mut self, filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
+        let (ports, endpoints) = (filters.ports(), filters.endpoints());
+
+        let (local, remote) = join(
+            join_all(ports.iter().map(|p| probe_port(*p))),
+            join_all(endpoints.iter().map(|a| probe_addr(a))),
+        )
+        .await;
+
+        let mut found: Vec<TcpInfo> = vec![];
+        for conn in local.into_iter().chain(remote).flatten() {
+            if !found.contains(&conn) {
+                found.push(conn);
             }
         }
 
+        // Fill in models and running apps using the Speculos HTTP API where available
+        let details = join_all(found.iter().map(|i| query_api(i, filters.http_port))).await;
+
+        let devices: Vec<_> = found
+            .into_iter()
+            .zip(details)
+            .map(|(mut conn, details)| {
+                let model = match details {
+                    Some((model, app)) => {
+                        conn.app = Some(app);
+                        model
+                    }
+                    None => Model::Unknown(0),
+                };
+
+                LedgerInfo {
+                    conn: conn.into(),
+                    model,
+                }
+            })
+            .collect();
+
+        debug!("devices: {:?}", devices);
+
         Ok(devices)
     }
 
@@ -91,24 +411,147 @@ impl Transport for TcpTransport {
         debug!("Connecting to: {:?}", info);
 
         // Connect to provided TCP socket
-        let s = match TcpStream::connect(info.addr).await {
+        let s = match self.connector.connect(&info).await {
             Ok(v) => v,
             Err(e) => {
                 error!("TCP connection failed: {:?}", e);
-                return Err(e.into());
+                return Err(e);
             }
         };
 
         // Return TCP device handle
-        Ok(TcpDevice { s, info })
+        Ok(TcpDevice {
+            s,
+            info,
+            connector: self.connector.clone(),
+            reconnect: self.reconnect,
+        })
+    }
+}
+
+/// Query the Speculos HTTP API on the device host for the model and running app
+async fn query_api(info: &TcpInfo, port: u16) -> Option<(Model, AppInfo)> {
+    if port == 0 || info.tls.is_some() {
+        return None;
+    }
+
+    // Locally probed devices are reported using the unspecified address
+    let ip = match info.addr.ip() {
+        ip if ip.is_unspecified() => Ipv4Addr::LOCALHOST.into(),
+        ip => ip,
+    };
+
+    match speculos::query(SocketAddr::new(ip, port)).await {
+        Ok(v) => Some(v),
+        Err(e) => {
+            debug!("Speculos API unavailable for {info}: {e:?}");
+            None
+        }
+    }
+}
+
+/// Check whether a speculos socket is open on a local port
+async fn probe_port(port: u16) -> Option<TcpInfo> {
+    let addr = SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), port);
+
+    // We can't -connect- to speculos as this does not handle multiple TCP connections
+    // so instead we attempt to bind to the socket we expect speculos to occupy.
+    match TcpListener::bind(addr).await {
+        Ok(_) => None,
+        // A failure indicates this is in use and we should report a device available for connection
+        Err(_) => Some(TcpInfo {
+            addr,
+            tls: None,
+            app: None,
+        }),
+    }
+}
+
+/// Check whether an endpoint is accepting connections, returning the resolved device info.
+///
+/// Endpoints may be remote so cannot be probed by binding, connections are closed
+/// immediately so the endpoint is available for subsequent connection.
+async fn probe_addr(endpoint: &str) -> Option<TcpInfo> {
+    let (addr, tls) = parse_endpoint(endpoint);
+
+    match rt::timeout(PROBE_TIMEOUT, TcpStream::connect(addr)).await {
+        Ok(Ok(s)) => s.peer_addr().ok().map(|addr| TcpInfo {
+            addr,
+            tls,
+            app: None,
+        }),
+        Ok(Err(e)) => {
+            debug!("No device at {addr}: {e:?}");
+            None
+        }
+        Err(_) => {
+            debug!("Timeout probing {addr}");
+            None
+        }
+    }
+}
+
+/// Check whether an error indicates the connection was lost
+fn is_disconnect(e: &Error) -> bool {
+    use std::io::ErrorKind::*;
+
+    match e {
+        Error::Tcp(e) => matches!(
+            e.kind(),
+            ConnectionReset
+                | ConnectionAborted
+                | ConnectionRefused
+                | BrokenPipe
+                | NotConnected
+                | UnexpectedEof
+        ),
+        _ => false,
     }
 }
 
 impl TcpDevice {
+    /// Set a [ReconnectPolicy] for this device, or `None` to disable reconnection
+    pub fn with_reconnect(mut self, policy: Option<ReconnectPolicy>) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Recover from an exchange failure, reconnecting where the connection was lost
+    /// and permitted by the [ReconnectPolicy], otherwise returning the failure
+    async fn recover(&mut self, e: Error, attempt: &mut usize) -> Result<(), Error> {
+        let policy = match self.reconnect {
+            Some(p) if is_disconnect(&e) && *attempt < p.max_attempts => p,
+            _ => return Err(e),
+        };
+
+        debug!("Connection to {} lost: {e:?}", self.info);
+
+        loop {
+            rt::sleep(policy.delay(*attempt)).await;
+            *attempt += 1;
+
+            debug!("Reconnecting to {} (attempt {attempt})", self.info);
+
+            match self.connector.connect(&self.info).await {
+                Ok(s) => {
+                    self.s = s;
+                    return Ok(());
+                }
+                Err(e) if *attempt < policy.max_attempts => {
+                    debug!("Reconnection failed: {e:?}");
+                }
+                Err(e) => {
+                    error!("Reconnection to {} failed: {e:?}", self.info);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
     /// Internal helper to write command data
     async fn write_command(&mut self, req: &[u8]) -> Result<(), Error> {
         // Setup data buffer to send
-        let mut buff = vec![0; 4 + req.len()];
+        let mut buff = Scratch::new(vec![0; 4 + req.len()]);
 
         // Write APDU length
         buff[0..4].copy_from_slice(&(req.len() as u32).to_be_bytes());
@@ -116,7 +559,7 @@ impl TcpDevice {
         // Write APDU data
         buff[4..].copy_from_slice(req);
 
-        debug!("TX: {:02x?}", buff);
+        debug!("TX: {:02x?}", *buff);
 
         // Send APDU request
         if let Err(e) = self.s.write_all(&buff).await {
@@ -127,53 +570,414 @@ impl TcpDevice {
         Ok(())
     }
 
-    /// Internal helper to read response data
-    async fn read_data(&mut self) -> Result<Vec<u8>, Error> {
-        let mut buff = vec![0u8; 4];
+    #[cfg(feature = "runtime_tokio")]
+    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
+        let r = self.s.tcp().ready(Interest::WRITABLE).await?;
+        Ok(!r.is_read_closed() || !r.is_write_closed())
+    }
 
-        // Read response length (u32 big endian + 2 bytes for status)
-        let n = match self.s.read_exact(&mut buff[..4]).await {
-            Ok(_) => u32::from_be_bytes(buff[..4].try_into().unwrap()) as usize + 2,
-            Err(e) => {
-                error!("Failed to read response APDU length: {:?}", e);
-                return Err(e.into());
-            }
-        };
+    #[cfg(not(feature = "runtime_tokio"))]
+    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
+        Ok(self.s.tcp().peer_addr().is_ok())
+    }
+}
 
-        // Read response data
-        buff.resize(n + 4, 0);
-        if let Err(e) = self.s.read_exact(&mut buff[4..][..n]).await {
-            error!("Failed to read response APDU data: {:?}", e);
-            return Err(e.into());
-        }
+impl TcpDevice {
+    /// Exchange an APDU, without reconnection
+    async fn exchange_once(&mut self, req: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        // Write APDU request
+        self.write_command(req).await?;
 
-        debug!("RX: {:02x?}", buff);
+        // Await APDU response with timeout
+        let d = rt::timeout(timeout, read_apdu(&mut self.s)).await??;
 
         // Return response data
-        Ok(buff[4..].to_vec())
+        Ok(d)
     }
 
-    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
-        let r = self.s.ready(Interest::WRITABLE).await?;
-        Ok(!r.is_read_closed() || !r.is_write_closed())
+    /// Exchange an APDU with user and transport timeouts, without reconnection
+    async fn exchange_timeouts_once(
+        &mut self,
+        req: &[u8],
+        timeouts: Timeouts,
+    ) -> Result<Vec<u8>, Error> {
+        if timeouts.user == UserWait::None {
+            return self.exchange_once(req, timeouts.transport).await;
+        }
+
+        // Write APDU request
+        self.write_command(req).await?;
+
+        // Await response length, waiting for the user where required
+        let n = match timeouts.user {
+            UserWait::Deadline(d) => rt::timeout(d, read_len(&mut self.s))
+                .await
+                .map_err(|_| Error::UserTimeout)??,
+            _ => read_len(&mut self.s).await?,
+        };
+
+        // Await response data with timeout
+        rt::timeout(timeouts.transport, read_data(&mut self.s, n)).await?
     }
 }
 
 /// [Exchange] implementation for the TCP transport
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Exchange for TcpDevice {
     async fn exchange(&mut self, req: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
-        // Write APDU request
-        self.write_command(req).await?;
+        let mut attempt = 0;
+        loop {
+            match self.exchange_once(req, timeout).await {
+                Err(e) => self.recover(e, &mut attempt).await?,
+                r => return r,
+            }
+        }
+    }
 
-        // Await APDU response with timeout
-        let d = match tokio::time::timeout(timeout, self.read_data()).await {
-            Ok(Ok(d)) => d,
-            Ok(Err(e)) => return Err(e),
-            Err(e) => return Err(e.into()),
+    /// Exchange an APDU, bounding the response length by the user deadline
+    /// and the response data by the transport timeout
+    async fn exchange_timeouts(
+        &mut self,
+        req: &[u8],
+        timeouts: Timeouts,
+    ) -> Result<Vec<u8>, Error> {
+        let mut attempt = 0;
+        loop {
+            match self.exchange_timeouts_once(req, timeouts).await {
+                Err(e) => self.recover(e, &mut attempt).await?,
+                r => return r,
+            }
+        }
+    }
+}
+
+/// Maximum TCP response length (extended APDU data + status)
+const TCP_MAX_RESP_LEN: usize = 65536 + 2;
+
+/// Read a length-prefixed APDU response
+pub async fn read_apdu<R: AsyncRead + Unpin>(r: &mut R) -> Result<Vec<u8>, Error> {
+    let n = read_len(r).await?;
+    read_data(r, n).await
+}
+
+/// Read a response length prefix, returning the response length including status
+async fn read_len<R: AsyncRead + Unpin>(r: &mut R) -> Result<usize, Error> {
+    let mut len = [0u8; 4];
+
+    // Read response length (u32 big endian + 2 bytes for status)
+    let n = match r.read_exact(&mut len).await {
+        Ok(_) => u32::from_be_bytes(len) as usize + 2,
+        Err(e) => {
+            error!("Failed to read response APDU length: {:?}", e);
+            return Err(e.into());
+        }
+    };
+
+    // Check response length prior to allocation
+    if n > TCP_MAX_RESP_LEN {
+        error!("Invalid response APDU length: {n}");
+        return Err(Error::UnexpectedResponse);
+    }
+
+    Ok(n)
+}
+
+/// Read `n` bytes of response data
+async fn read_data<R: AsyncRead + Unpin>(r: &mut R, n: usize) -> Result<Vec<u8>, Error> {
+    // Read response data
+    let mut buff = Scratch::new(vec![0u8; n]);
+    if let Err(e) = r.read_exact(&mut buff[..]).await {
+        error!("Failed to read response APDU data: {:?}", e);
+        return Err(e.into());
+    }
+
+    debug!("RX: {:02x?}", *buff);
+
+    // Return response data
+    Ok(std::mem::take(&mut *buff))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn tcp_exchange() {
+        // Mock speculos APDU socket, responding with a fixed status
+        let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = l.local_addr().unwrap();
+
+        let h = std::thread::spawn(move || {
+            let (mut s, _) = l.accept().unwrap();
+
+            let mut buff = [0u8; 9];
+            s.read_exact(&mut buff).unwrap();
+            s.write_all(&[0x00, 0x00, 0x00, 0x01, 0xaa, 0x90, 0x00])
+                .unwrap();
+
+            buff
+        });
+
+        let mut t = TcpTransport::new().unwrap();
+        let mut d = t
+            .connect(TcpInfo {
+                addr,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let resp = d
+            .exchange(&[0xe0, 0x01, 0x00, 0x00, 0x00], Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        assert_eq!(&resp, &[0xaa, 0x90, 0x00]);
+        assert_eq!(
+            h.join().unwrap(),
+            [0x00, 0x00, 0x00, 0x05, 0xe0, 0x01, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn tcp_parse_addrs() {
+        assert_eq!(
+            parse_addrs("127.0.0.1:1237, speculos:9999,,"),
+            vec!["127.0.0.1:1237".to_string(), "speculos:9999".to_string()]
+        );
+        assert!(parse_addrs("").is_empty());
+    }
+
+    #[test]
+    fn tcp_parse_endpoint() {
+        assert_eq!(parse_endpoint("127.0.0.1:1237"), ("127.0.0.1:1237", None));
+        assert_eq!(
+            parse_endpoint("tls://speculos.example.com:443"),
+            (
+                "speculos.example.com:443",
+                Some("speculos.example.com".to_string())
+            )
+        );
+        assert_eq!(
+            parse_endpoint("tls://[::1]:9999"),
+            ("[::1]:9999", Some("::1".to_string()))
+        );
+    }
+
+    #[cfg(not(feature = "transport_tcp_tls"))]
+    #[tokio::test]
+    async fn tcp_tls_unsupported() {
+        let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let info = TcpInfo {
+            addr: l.local_addr().unwrap(),
+            tls: Some("localhost".to_string()),
+            app: None,
         };
 
-        // Return response data
-        Ok(d)
+        let r = TcpTransport::new().unwrap().connect(info).await;
+        assert!(matches!(r, Err(Error::Tcp(e)) if e.kind() == std::io::ErrorKind::Unsupported));
+    }
+
+    #[cfg(feature = "transport_tcp_tls")]
+    #[tokio::test]
+    async fn tcp_tls_exchange() {
+        use tokio_rustls::{
+            rustls::{pki_types::PrivateKeyDer, ServerConfig},
+            TlsAcceptor,
+        };
+
+        // Self-signed certificate for the mock endpoint
+        let c = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert = c.cert.der().clone();
+        let key = PrivateKeyDer::try_from(c.key_pair.serialize_der()).unwrap();
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let server = ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.clone()], key)
+            .unwrap();
+
+        let mut roots = RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let client = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        // Mock TLS APDU endpoint, responding with a fixed status
+        let l = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = l.local_addr().unwrap();
+
+        let h = tokio::spawn(async move {
+            let (s, _) = l.accept().await.unwrap();
+            let mut s = TlsAcceptor::from(Arc::new(server)).accept(s).await.unwrap();
+
+            let mut buff = [0u8; 9];
+            s.read_exact(&mut buff).await.unwrap();
+            s.write_all(&[0x00, 0x00, 0x00, 0x00, 0x90, 0x00])
+                .await
+                .unwrap();
+            s.flush().await.unwrap();
+
+            buff
+        });
+
+        let mut t = TcpTransport::new()
+            .unwrap()
+            .with_tls_config(Arc::new(client));
+        let info = TcpInfo {
+            addr,
+            tls: Some("localhost".to_string()),
+            app: None,
+        };
+        let mut d = t.connect(info).await.unwrap();
+
+        let resp = d
+            .exchange(&[0xe0, 0x01, 0x00, 0x00, 0x00], Duration::from_secs(1))
+            .await
+            .unwrap();
+
+        assert_eq!(&resp, &[0x90, 0x00]);
+        assert_eq!(
+            h.await.unwrap(),
+            [0x00, 0x00, 0x00, 0x05, 0xe0, 0x01, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn tcp_port_range() {
+        assert_eq!(
+            "1237-1239".parse::<PortRange>(),
+            Ok(PortRange {
+                start: 1237,
+                end: 1239
+            })
+        );
+        assert!("1239-1237".parse::<PortRange>().is_err());
+        assert!("1237".parse::<PortRange>().is_err());
+        assert!("a-b".parse::<PortRange>().is_err());
+
+        // Ranges extend listed ports, without duplicates
+        let filters = TcpFilters {
+            ports: vec![1237, 5000],
+            port_range: Some("1237-1239".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(filters.ports(), vec![1237, 5000, 1238, 1239]);
+    }
+
+    #[tokio::test]
+    async fn tcp_list_addrs() {
+        // Mock simulator socket, with an endpoint not accepting connections
+        let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = l.local_addr().unwrap();
+
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed_addr = closed.local_addr().unwrap();
+        drop(closed);
+
+        let filters = TcpFilters {
+            ports: vec![],
+            port_range: None,
+            addrs: vec![addr.to_string(), closed_addr.to_string(), addr.to_string()],
+            http_port: 0,
+        };
+
+        let mut t = TcpTransport::new().unwrap();
+        let devices = t.list(filters).await.unwrap();
+
+        assert_eq!(
+            devices,
+            vec![LedgerInfo {
+                conn: TcpInfo {
+                    addr,
+                    ..Default::default()
+                }
+                .into(),
+                model: Model::Unknown(0),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn tcp_reconnect() {
+        // Mock speculos APDU socket, closing the first connection (as on restart)
+        let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = l.local_addr().unwrap();
+
+        let h = std::thread::spawn(move || {
+            let (s, _) = l.accept().unwrap();
+            drop(s);
+
+            let (mut s, _) = l.accept().unwrap();
+            let mut buff = [0u8; 9];
+            s.read_exact(&mut buff).unwrap();
+            s.write_all(&[0x00, 0x00, 0x00, 0x00, 0x90, 0x00]).unwrap();
+
+            s
+        });
+
+        let req = [0xe0, 0x01, 0x00, 0x00, 0x00];
+        let policy = ReconnectPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_millis(1),
+        };
+
+        let mut t = TcpTransport::new().unwrap().with_reconnect(policy);
+        let mut d = t
+            .connect(TcpInfo {
+                addr,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // Exchanges are retried following reconnection
+        let resp = d.exchange(&req, Duration::from_secs(1)).await.unwrap();
+        assert_eq!(&resp, &[0x90, 0x00]);
+
+        // Failures are returned where reconnection is disabled
+        drop(h.join().unwrap());
+        let mut d = d.with_reconnect(None);
+        let r = d.exchange(&req, Duration::from_secs(1)).await;
+        assert!(matches!(r, Err(e) if is_disconnect(&e)));
+    }
+
+    #[test]
+    fn tcp_reconnect_delay() {
+        let p = ReconnectPolicy::default();
+        assert_eq!(p.delay(0), Duration::from_millis(200));
+        assert_eq!(p.delay(2), Duration::from_millis(800));
+        assert_eq!(p.delay(usize::MAX), p.backoff * (1 << 16));
+    }
+
+    #[tokio::test]
+    async fn tcp_user_timeout() {
+        // Mock speculos APDU socket, never responding
+        let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = l.local_addr().unwrap();
+
+        let mut t = TcpTransport::new().unwrap();
+        let mut d = t
+            .connect(TcpInfo {
+                addr,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let timeouts =
+            Timeouts::new(Duration::from_secs(1)).with_user_deadline(Duration::from_millis(10));
+        let r = d
+            .exchange_timeouts(&[0xe0, 0x04, 0x00, 0x00, 0x00], timeouts)
+            .await;
+
+        assert!(matches!(r, Err(Error::UserTimeout)));
+
+        drop(l);
     }
 }
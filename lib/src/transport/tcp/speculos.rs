@@ -0,0 +1,296 @@
+//! Speculos HTTP API helpers, used to fill in model and app information when listing
+//! TCP devices.
+//!
+//! The API does not report the emulated model, so this is inferred from the display
+//! resolution of screenshots. The Nano X and Nano S Plus share a resolution, so for
+//! these the model is resolved from the target ID in a device info request (reported
+//! as [Model::Unknown] where this is not available). App info is fetched via the
+//! API `/apdu` endpoint, as the APDU socket only accepts a single connection.
+//!
+//! see: https://github.com/LedgerHQ/speculos/blob/master/speculos/api/static/swagger/swagger.json
+
+use std::net::SocketAddr;
+
+use ledger_proto::{
+    apdus::{AppInfoReq, AppInfoResp, DeviceInfoReq, DeviceInfoResp},
+    ApduReq,
+};
+use tracing::debug;
+
+use super::{AsyncReadExt, AsyncWriteExt, TcpStream, PROBE_TIMEOUT};
+use crate::{
+    device::{app_info, decode_response, encode_request},
+    info::{AppInfo, Model},
+    rt, Error,
+};
+
+/// Maximum HTTP response length (screenshots for large display devices)
+const HTTP_MAX_RESP_LEN: usize = 1 << 20;
+
+/// PNG file signature
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Display resolutions (width, height) for emulated models, where models sharing
+/// a resolution are resolved by target ID
+const DISPLAYS: &[((u32, u32), &[Model])] = &[
+    ((128, 32), &[Model::NanoS]),
+    ((128, 64), &[Model::NanoX, Model::NanoSPlus]),
+    ((400, 672), &[Model::Stax]),
+    ((480, 600), &[Model::Flex]),
+];
+
+/// Query the Speculos HTTP API at `addr` for the emulated model and running app
+pub(crate) async fn query(addr: SocketAddr) -> Result<(Model, AppInfo), Error> {
+    let png = request(addr, "GET", "/screenshot", None).await?;
+    let model = match models_from_png(&png) {
+        [m] => m.clone(),
+        _ => model_from_target(addr).await,
+    };
+
+    let mut buff = [0u8; 256];
+    let data = apdu(addr, AppInfoReq {}).await?;
+    let app = app_info(decode_response::<AppInfoResp>(&data, &mut buff)?);
+
+    debug!("Speculos at {addr}: {model} running {}", app.name);
+
+    Ok((model, app))
+}
+
+/// Resolve the emulated model from the target ID reported in a device info request,
+/// returning [Model::Unknown] where this is not available (for example, depending on
+/// the running app)
+async fn model_from_target(addr: SocketAddr) -> Model {
+    let mut buff = [0u8; 256];
+    let r = match apdu(addr, DeviceInfoReq {}).await {
+        Ok(data) => decode_response::<DeviceInfoResp>(&data, &mut buff),
+        Err(e) => Err(e),
+    };
+
+    match r {
+        Ok(i) => Model::from_target_id(i.target_id),
+        Err(e) => {
+            debug!("Speculos device info unavailable: {e:?}");
+            Model::Unknown(0)
+        }
+    }
+}
+
+/// Issue an APDU request via the API, returning the response (data and status)
+async fn apdu<'a>(addr: SocketAddr, req: impl ApduReq<'a>) -> Result<Vec<u8>, Error> {
+    let mut buff = [0u8; 16];
+    let n = encode_request(req, &mut buff)?;
+    let req = format!(r#"{{"data": "{}"}}"#, to_hex(&buff[..n]));
+
+    let resp = request(addr, "POST", "/apdu", Some(&req)).await?;
+    let resp = std::str::from_utf8(&resp).map_err(|_| Error::UnexpectedResponse)?;
+
+    json_str(resp, "data")
+        .and_then(from_hex)
+        .ok_or(Error::UnexpectedResponse)
+}
+
+/// Issue an HTTP/1.0 request (avoiding chunked responses), returning the body of
+/// successful responses
+async fn request(
+    addr: SocketAddr,
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+) -> Result<Vec<u8>, Error> {
+    let body = body.unwrap_or_default();
+    let req = format!(
+        "{method} {path} HTTP/1.0\r\nHost: {addr}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+
+    let resp = rt::timeout(PROBE_TIMEOUT, async {
+        let mut s = TcpStream::connect(addr).await?;
+        s.write_all(req.as_bytes()).await?;
+
+        let mut resp = vec![];
+        (&mut s)
+            .take(HTTP_MAX_RESP_LEN as u64)
+            .read_to_end(&mut resp)
+            .await?;
+
+        Ok::<_, Error>(resp)
+    })
+    .await??;
+
+    parse_response(&resp).map(|b| b.to_vec())
+}
+
+/// Split an HTTP response, returning the body for successful (2xx) responses
+fn parse_response(resp: &[u8]) -> Result<&[u8], Error> {
+    let n = resp
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or(Error::UnexpectedResponse)?;
+
+    let status = resp[..n]
+        .split(|c| *c == b' ')
+        .nth(1)
+        .ok_or(Error::UnexpectedResponse)?;
+
+    if status.first() != Some(&b'2') {
+        debug!("HTTP request failed: {}", String::from_utf8_lossy(status));
+        return Err(Error::UnexpectedResponse);
+    }
+
+    Ok(&resp[n + 4..])
+}
+
+/// Infer candidate emulated models from the display resolution of a PNG screenshot,
+/// returning an empty slice for unrecognised images
+fn models_from_png(png: &[u8]) -> &'static [Model] {
+    // PNG signature followed by the IHDR chunk (length, type, width, height)
+    if png.len() < 24 || !png.starts_with(PNG_MAGIC) || &png[12..16] != b"IHDR" {
+        return &[];
+    }
+
+    let [width, height] =
+        [&png[16..20], &png[20..24]].map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+
+    DISPLAYS
+        .iter()
+        .find(|(d, _m)| *d == (width, height))
+        .map(|(_d, m)| *m)
+        .unwrap_or_default()
+}
+
+/// Fetch a string value by key from a flat JSON object
+fn json_str<'a>(json: &'a str, key: &str) -> Option<&'a str> {
+    let (_, v) = json.split_once(&format!(r#""{key}""#))?;
+    let v = v.trim_start().strip_prefix(':')?.trim_start();
+    let v = v.strip_prefix('"')?;
+    v.split_once('"').map(|(v, _)| v)
+}
+
+fn to_hex(v: &[u8]) -> String {
+    v.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger_proto::apdus::AppFlags;
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut v = PNG_MAGIC.to_vec();
+        v.extend_from_slice(b"\x00\x00\x00\x0dIHDR");
+        v.extend_from_slice(&width.to_be_bytes());
+        v.extend_from_slice(&height.to_be_bytes());
+        v
+    }
+
+    #[test]
+    fn speculos_model() {
+        assert_eq!(models_from_png(&png(128, 32)), &[Model::NanoS]);
+        assert_eq!(models_from_png(&png(400, 672)), &[Model::Stax]);
+        assert_eq!(models_from_png(&png(480, 600)), &[Model::Flex]);
+        assert_eq!(
+            models_from_png(&png(128, 64)),
+            &[Model::NanoX, Model::NanoSPlus]
+        );
+        assert!(models_from_png(&png(64, 64)).is_empty());
+        assert!(models_from_png(b"GIF89a").is_empty());
+
+        // Full PNG signature is required
+        let mut p = png(128, 32);
+        p[0] = b'x';
+        assert!(models_from_png(&p).is_empty());
+    }
+
+    #[test]
+    fn speculos_parse() {
+        let r = b"HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{\"data\": \"9000\"}";
+        assert_eq!(parse_response(r).unwrap(), br#"{"data": "9000"}"#);
+        assert!(parse_response(b"HTTP/1.0 404 NOT FOUND\r\n\r\n").is_err());
+        assert!(parse_response(b"HTTP/1.0 200 OK").is_err());
+
+        assert_eq!(json_str(r#"{"data": "9000"}"#, "data"), Some("9000"));
+        assert_eq!(json_str(r#"{"data":"ab"}"#, "data"), Some("ab"));
+        assert_eq!(json_str(r#"{"other": "ab"}"#, "data"), None);
+
+        assert_eq!(from_hex("0aff"), Some(vec![0x0a, 0xff]));
+        assert_eq!(from_hex("0af"), None);
+        assert_eq!(from_hex("zz"), None);
+        assert_eq!(to_hex(&[0xb0, 0x01]), "b001");
+    }
+
+    /// Mock Speculos HTTP API, serving the provided response bodies in order
+    fn serve(bodies: Vec<Vec<u8>>) -> (SocketAddr, std::thread::JoinHandle<()>) {
+        use std::io::{Read, Write};
+
+        let l = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = l.local_addr().unwrap();
+
+        let h = std::thread::spawn(move || {
+            for body in bodies {
+                let (mut s, _) = l.accept().unwrap();
+
+                let mut buff = [0u8; 512];
+                let _ = s.read(&mut buff).unwrap();
+
+                s.write_all(b"HTTP/1.0 200 OK\r\n\r\n").unwrap();
+                s.write_all(&body).unwrap();
+            }
+        });
+
+        (addr, h)
+    }
+
+    /// Encode an APDU response as an API `/apdu` response body
+    fn apdu_resp(resp: impl encdec::Encode<Error = ledger_proto::ApduError>) -> Vec<u8> {
+        let mut buff = [0u8; 64];
+        let n = resp.encode(&mut buff).unwrap();
+        format!(r#"{{"data": "{}9000"}}"#, to_hex(&buff[..n])).into_bytes()
+    }
+
+    #[tokio::test]
+    async fn speculos_query() {
+        let app = || apdu_resp(AppInfoResp::new("Bitcoin", "2.1.0", AppFlags::empty()));
+
+        // Models are inferred from the display resolution where this is unique
+        let (addr, h) = serve(vec![png(400, 672), app()]);
+        let (model, info) = query(addr).await.unwrap();
+        h.join().unwrap();
+
+        assert_eq!(model, Model::Stax);
+        assert_eq!(info.name, "Bitcoin");
+        assert_eq!(info.version, "2.1.0");
+
+        // Otherwise resolved by target ID
+        let device = apdu_resp(DeviceInfoResp::new(
+            [0x33, 0x10, 0x00, 0x04],
+            "1.1.1",
+            "5.24",
+            &[0xa6],
+        ));
+        let (addr, h) = serve(vec![png(128, 64), device, app()]);
+        let (model, _) = query(addr).await.unwrap();
+        h.join().unwrap();
+
+        assert_eq!(model, Model::NanoSPlus);
+
+        // Falling back to unknown where device info is unavailable
+        let (addr, h) = serve(vec![png(128, 64), br#"{"data": "6d00"}"#.to_vec(), app()]);
+        let (model, info) = query(addr).await.unwrap();
+        h.join().unwrap();
+
+        assert_eq!(model, Model::Unknown(0));
+        assert_eq!(info.name, "Bitcoin");
+    }
+}
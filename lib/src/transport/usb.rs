@@ -1,26 +1,43 @@
 //! USB HID transport implementation
 //!
-//! # SAFETY
+//! # Thread Safety
 //!
-//! This is _not_ `Send` or thread safe, see [transport][crate::transport] docs for
-//! more details.
+//! This is `Send` but only one instance should exist in an application, see
+//! [transport][crate::transport] docs for more details.
 //!
 
-use std::{ffi::CString, fmt::Display, io::ErrorKind, time::Duration};
+use std::{
+    collections::hash_map::RandomState,
+    ffi::CString,
+    fmt::Display,
+    hash::{BuildHasher, Hasher},
+    io::ErrorKind,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
 
+#[cfg(target_os = "linux")]
+use futures::future::Either;
+use futures::Stream;
 use hidapi::{HidApi, HidDevice, HidError};
+use ledger_proto::framing::hid::{
+    HidDecoder, HidEncoder, HID_DEFAULT_CHANNEL, HID_HEADER_LEN, HID_PACKET_LEN,
+};
 use tracing::{debug, error, trace, warn};
 
 use crate::{
-    info::{LedgerInfo, Model},
-    Error,
+    info::{LedgerInfo, Model, LEDGER_VID},
+    rt,
+    wipe::{self, Scratch},
+    Error, Timeouts, UserWait,
 };
 
-use super::{Exchange, Transport};
+use super::{lock::DeviceLock, watch, DeviceEvent, Exchange, Transport};
 
 /// Basic USB device information
 #[derive(Clone, PartialEq, Debug)]
 #[cfg_attr(feature = "clap", derive(clap::Parser))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UsbInfo {
     #[cfg_attr(feature = "clap", clap(long, value_parser=u16_parse_hex))]
     /// USB Device Vendor ID (VID) in hex
@@ -33,6 +50,10 @@ pub struct UsbInfo {
     #[cfg_attr(feature = "clap", clap(long))]
     /// Device path
     pub path: Option<String>,
+
+    #[cfg_attr(feature = "clap", clap(long))]
+    /// Device serial number (where reported)
+    pub serial: Option<String>,
 }
 
 impl Display for UsbInfo {
@@ -41,6 +62,15 @@ impl Display for UsbInfo {
     }
 }
 
+/// USB device discovery filters
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "clap", derive(clap::Args))]
+pub struct UsbFilters {
+    #[cfg_attr(feature = "clap", clap(long = "usb-serial"))]
+    /// Match USB devices by serial number
+    pub serial: Option<String>,
+}
+
 /// Helper to pass VID/PID pairs from hex values
 #[cfg(feature = "clap")]
 fn u16_parse_hex(s: &str) -> Result<u16, std::num::ParseIntError> {
@@ -49,58 +79,113 @@ fn u16_parse_hex(s: &str) -> Result<u16, std::num::ParseIntError> {
 
 /// USB HID based transport
 ///
-/// # Safety
-/// Due to `hidapi` this is not thread safe an only one instance must exist in an application.
+/// # Thread Safety
+/// Due to `hidapi` only one instance must exist in an application.
 /// If you don't need low-level control see [crate::LedgerProvider] for a tokio based wrapper.
 pub struct UsbTransport {
     hid_api: HidApi,
+    channel: HidChannel,
+    lock: bool,
+}
+
+/// HID channel ID selection for [UsbDevice] connections.
+///
+/// Devices echo the channel ID in responses, using a per-connection random channel
+/// allows responses intended for other processes sharing a device to be rejected.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum HidChannel {
+    /// Fixed channel ID
+    Fixed(u16),
+    /// Random channel ID, selected per connection
+    Random,
+}
+
+impl Default for HidChannel {
+    fn default() -> Self {
+        Self::Fixed(HID_DEFAULT_CHANNEL)
+    }
+}
+
+impl HidChannel {
+    /// Resolve the channel ID for a new connection
+    fn resolve(&self) -> u16 {
+        match self {
+            Self::Fixed(c) => *c,
+            Self::Random => RandomState::new().build_hasher().finish() as u16,
+        }
+    }
 }
 
 /// USB HID based device
+///
+/// `hidapi` calls block, so [Exchange] operations run on a dedicated thread
+/// to avoid stalling the executor.
 pub struct UsbDevice {
     pub info: UsbInfo,
-    device: HidDevice,
+    device: Arc<Mutex<HidDevice>>,
+    channel: u16,
+    /// Report buffer, reused across commands to avoid per-exchange allocation
+    reports: Scratch<Vec<u8>>,
+    _lock: Option<DeviceLock>,
 }
 
-/// Ledger USB VID
-pub const LEDGER_VID: u16 = 0x2c97;
-
 impl UsbTransport {
     /// Create a new [UsbTransport]
     pub fn new() -> Result<Self, Error> {
         Ok(Self {
             hid_api: HidApi::new()?,
+            channel: HidChannel::default(),
+            lock: false,
         })
     }
-}
 
-// With the unstable_async_trait feature we can (correctly) mark this as non-send
-// however [async_trait] can't easily differentiate between send and non-send so we're
-// exposing this as Send for the moment
+    /// Set the HID channel ID used for new connections
+    pub fn with_channel(mut self, channel: HidChannel) -> Self {
+        self.channel = channel;
+        self
+    }
 
-#[cfg(feature = "unstable_async_trait")]
-impl !Send for UsbDevice {}
-#[cfg(feature = "unstable_async_trait")]
-impl !Sync for UsbDevice {}
+    /// Enable cross-process advisory locking of devices on connection, returning
+    /// [Error::DeviceInUse] (naming the holder) where another process has
+    /// the device open
+    pub fn with_device_lock(mut self) -> Self {
+        self.lock = true;
+        self
+    }
 
-#[cfg(feature = "unstable_async_trait")]
-impl !Send for UsbTransport {}
-#[cfg(feature = "unstable_async_trait")]
-impl !Sync for UsbTransport {}
+    /// Watch for devices being connected or disconnected.
+    ///
+    /// On Linux devices are listed on udev hotplug events for HID devices, elsewhere
+    /// (or where udev is unavailable) `hidapi` does not provide hotplug notifications
+    /// so devices are listed every `interval` (see [watch](super::watch)).
+    pub fn watch(
+        &mut self,
+        filters: UsbFilters,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<DeviceEvent, Error>> + '_ {
+        #[cfg(target_os = "linux")]
+        let triggers = match hotplug() {
+            Ok(s) => Either::Left(s),
+            Err(e) => {
+                warn!("udev hotplug monitor unavailable, polling for devices: {e:?}");
+                Either::Right(watch::ticks(interval))
+            }
+        };
+        #[cfg(not(target_os = "linux"))]
+        let triggers = watch::ticks(interval);
 
-/// WARNING: THIS IS A LIE TO APPEASE `async_trait`
-#[cfg(not(feature = "unstable_async_trait"))]
-unsafe impl Send for UsbTransport {}
+        watch::watch_with(self, filters, triggers)
+    }
+}
 
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
 impl Transport for UsbTransport {
-    type Filters = ();
+    type Filters = UsbFilters;
     type Info = UsbInfo;
     type Device = UsbDevice;
 
     /// List available devices using the [UsbTransport]
-    async fn list(&mut self, _filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
-        debug!("Listing USB devices");
+    async fn list(&mut self, filters: Self::Filters) -> Result<Vec<LedgerInfo>, Error> {
+        debug!("Listing USB devices (filters: {filters:?})");
 
         // Refresh available devices
         // TODO: determine whether the refresh call is critical (or, useful?)
@@ -108,19 +193,24 @@ impl Transport for UsbTransport {
             warn!("Failed to refresh devices: {e:?}");
         }
 
-        tokio::time::sleep(Duration::from_millis(200)).await;
+        rt::sleep(Duration::from_millis(200)).await;
 
         // Fetch list of devices, filtering for ledgers
         let devices: Vec<_> = self
             .hid_api
             .device_list()
             .filter(|d| d.vendor_id() == LEDGER_VID)
+            .filter(|d| match &filters.serial {
+                Some(s) => d.serial_number() == Some(s.as_str()),
+                None => true,
+            })
             .map(|d| LedgerInfo {
                 model: Model::from_pid(d.product_id()),
                 conn: UsbInfo {
                     vid: d.vendor_id(),
                     pid: d.product_id(),
                     path: Some(d.path().to_string_lossy().to_string()),
+                    serial: d.serial_number().map(|s| s.to_string()),
                 }
                 .into(),
             })
@@ -135,6 +225,15 @@ impl Transport for UsbTransport {
     async fn connect(&mut self, info: UsbInfo) -> Result<UsbDevice, Error> {
         debug!("Connecting to USB device: {:?}", info);
 
+        // Claim the device prior to opening where enabled
+        let lock = match self.lock {
+            true => {
+                let key = info.path.clone().unwrap_or_else(|| info.to_string());
+                Some(DeviceLock::acquire(&key)?)
+            }
+            false => None,
+        };
+
         // If we have a path, use this to connect
         let d = if let Some(p) = &info.path {
             let p = CString::new(p.clone()).unwrap();
@@ -148,7 +247,20 @@ impl Transport for UsbTransport {
         match d {
             Ok(d) => {
                 debug!("Connected to USB device: {:?}", info);
-                Ok(UsbDevice { device: d, info })
+                Ok(UsbDevice {
+                    device: Arc::new(Mutex::new(d)),
+                    channel: self.channel.resolve(),
+                    reports: Scratch::new(Vec::new()),
+                    _lock: lock,
+                    info,
+                })
+            }
+            Err(e) if is_permission_denied(&e, info.path.as_deref()) => {
+                warn!("Permission denied connecting to USB device: {:?}", e);
+                Err(Error::PermissionDenied {
+                    path: info.path.clone().unwrap_or_else(|| info.to_string()),
+                    hint: PERMISSION_HINT.to_string(),
+                })
             }
             Err(e) => {
                 debug!("Failed to connect to USB device: {:?}", e);
@@ -158,147 +270,749 @@ impl Transport for UsbTransport {
     }
 }
 
-// HID packet length (header + data)
-const HID_PACKET_LEN: usize = 64;
+/// Remediation hint for USB permission errors
+#[cfg(target_os = "linux")]
+const PERMISSION_HINT: &str =
+    "install the Ledger udev rules (https://github.com/LedgerHQ/udev-rules) then reconnect the device";
+
+/// Remediation hint for USB permission errors
+#[cfg(not(target_os = "linux"))]
+const PERMISSION_HINT: &str = "check the device is not in use by another application";
+
+/// Check whether a device open failure was due to insufficient permissions.
+///
+/// `hidapi` reports most failures as messages (or not at all under `libusb`),
+/// so access to device nodes is also checked where a path is available.
+fn is_permission_denied(e: &HidError, path: Option<&str>) -> bool {
+    match e {
+        HidError::IoError { error } if error.kind() == ErrorKind::PermissionDenied => return true,
+        HidError::HidApiError { message } => {
+            let m = message.to_lowercase();
+            if ["permission denied", "access denied", "error_access"]
+                .iter()
+                .any(|s| m.contains(s))
+            {
+                return true;
+            }
+        }
+        _ => (),
+    }
+
+    match path {
+        Some(p) if p.starts_with("/dev/") => access_denied(p),
+        _ => false,
+    }
+}
+
+/// Check whether read / write access to a device node is denied, without opening
+/// the node (avoiding side effects or contention with other users of the device)
+#[cfg(unix)]
+fn access_denied(path: &str) -> bool {
+    // Missing device nodes are not permission failures
+    if let Err(e) = std::fs::metadata(path) {
+        return e.kind() == ErrorKind::PermissionDenied;
+    }
+
+    let Ok(p) = CString::new(path) else {
+        return false;
+    };
 
-// Five bytes: channnel (0x101), tag (0x05), sequence index
-const HID_HEADER_LEN: usize = 5;
+    // SAFETY: `access` is called with a valid nul-terminated path owned by `p`
+    match unsafe { libc::access(p.as_ptr(), libc::R_OK | libc::W_OK) } {
+        0 => false,
+        _ => std::io::Error::last_os_error().kind() == ErrorKind::PermissionDenied,
+    }
+}
+
+/// Check whether read / write access to a device node is denied
+#[cfg(not(unix))]
+fn access_denied(_path: &str) -> bool {
+    false
+}
 
 impl UsbDevice {
-    /// Write an APDU to the device
+    /// Fetch the HID channel ID for the connection
+    pub fn channel(&self) -> u16 {
+        self.channel
+    }
+
+    /// Write an APDU to the device, blocking until complete
     pub fn write(&mut self, apdu: &[u8]) -> Result<(), Error> {
-        debug!("Write APDU");
+        encode_reports(&mut self.reports, self.channel, apdu);
+        write_device(&lock(&self.device), &mut self.reports)
+    }
+
+    /// Read an APDU from the device, blocking until complete or `timeout` elapses
+    pub fn read(&mut self, timeout: Duration) -> Result<Vec<u8>, Error> {
+        debug!("Read APDU");
 
-        // Setup outgoing data buffer with length prefix
-        let mut data = Vec::with_capacity(apdu.len() + 2);
-        data.extend_from_slice(&(apdu.len() as u16).to_be_bytes());
-        data.extend_from_slice(apdu);
+        let device = lock(&self.device);
+        read_apdu(
+            |buff, timeout_ms| read_chunk(&device, buff, timeout_ms),
+            self.channel,
+            timeout,
+        )
+    }
+
+    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
+        Ok(lock(&self.device).get_device_info().is_ok())
+    }
 
-        debug!("TX: {:02x?}", data);
+    /// Write a command then read the response using `read` on a blocking thread.
+    ///
+    /// Reports are encoded prior to spawning, with the report buffer moved to the
+    /// blocking thread and returned on completion for reuse.
+    async fn exchange_blocking(
+        &mut self,
+        command: &[u8],
+        read: impl FnOnce(&HidDevice, u16) -> Result<Vec<u8>, Error> + Send + 'static,
+    ) -> Result<Vec<u8>, Error> {
+        let (device, channel) = (self.device.clone(), self.channel);
+
+        let mut reports = std::mem::replace(&mut self.reports, Scratch::new(Vec::new()));
+        encode_reports(&mut reports, channel, command);
+
+        let (resp, reports) = rt::blocking(move || {
+            let device = lock(&device);
+            let resp = write_device(&device, &mut reports).and_then(|_| read(&device, channel));
+            (resp, reports)
+        })
+        .await?;
 
-        // Write data in 64 byte chunks
-        for (i, c) in data.chunks(HID_PACKET_LEN - HID_HEADER_LEN).enumerate() {
-            trace!("Writing chunk {} of {} bytes", i, c.len());
+        self.reports = reports;
 
-            // Setup HID packet with header and data
-            let mut packet = Vec::with_capacity(HID_PACKET_LEN + 1);
+        resp
+    }
+}
 
-            // Zero prefix for unknown reasons
-            packet.push(0x00);
+/// Lock a shared device handle, ignoring poisoning as devices hold no invariants
+fn lock(device: &Mutex<HidDevice>) -> MutexGuard<'_, HidDevice> {
+    device.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
-            // Header channnel (0x101), tag (0x05), sequence index
-            packet.extend_from_slice(&[0x01, 0x01, 0x05]);
-            packet.extend_from_slice(&(i as u16).to_be_bytes());
-            // Remaining data
-            packet.extend_from_slice(c);
+/// Interval for [hotplug] worker threads to check whether the stream has been dropped
+#[cfg(target_os = "linux")]
+const HOTPLUG_CHECK_MS: i32 = 1000;
+
+/// Stream of udev hotplug notifications for HID devices, with events monitored on
+/// a worker thread and coalesced so each wakeup triggers a single notification
+#[cfg(target_os = "linux")]
+fn hotplug() -> std::io::Result<impl Stream<Item = ()>> {
+    use std::os::fd::AsRawFd;
+    use udev::{EventType, MonitorBuilder};
+
+    let socket = MonitorBuilder::new()?.match_subsystem("hidraw")?.listen()?;
+    let (tx, rx) = futures::channel::mpsc::unbounded();
+
+    std::thread::spawn(move || {
+        let mut fds = [libc::pollfd {
+            fd: socket.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+
+        // Forward events until the stream is dropped
+        while !tx.is_closed() {
+            // SAFETY: `poll` is called with a single valid pollfd referencing the socket fd
+            if unsafe { libc::poll(fds.as_mut_ptr(), 1, HOTPLUG_CHECK_MS) } < 0 {
+                match std::io::Error::last_os_error() {
+                    e if e.kind() == ErrorKind::Interrupted => continue,
+                    e => {
+                        error!("udev hotplug monitor failed: {e:?}");
+                        break;
+                    }
+                }
+            }
 
-            trace!("Write: 0x{:02x?}", packet);
+            let mut changed = false;
+            for e in socket.iter() {
+                debug!("Hotplug {:?}: {:?}", e.event_type(), e.devnode());
+                changed |= matches!(e.event_type(), EventType::Add | EventType::Remove);
+            }
 
-            // Write HID packet
-            self.device.write(&packet)?;
+            if changed {
+                let _ = tx.unbounded_send(());
+            }
         }
 
-        Ok(())
+        debug!("udev hotplug monitor closed");
+    });
+
+    Ok(rx)
+}
+
+/// Maximum number of pending reports discarded prior to writing a command
+const DRAIN_MAX_REPORTS: usize = 64;
+
+/// Discard pending reports (for example, responses to previous timed-out exchanges)
+fn drain(device: &HidDevice) {
+    let mut buff = Scratch::new([0u8; HID_REPORT_LEN]);
+    for _ in 0..DRAIN_MAX_REPORTS {
+        match device.read_timeout(&mut buff[..], 0) {
+            Ok(n) if n > 0 => trace!("Discarding stale report: {:02x?}", &buff[..n]),
+            _ => break,
+        }
     }
+}
 
-    /// Read an APDU from the device
-    pub fn read(&mut self, timeout: Duration) -> Result<Vec<u8>, Error> {
-        debug!("Read APDU");
+/// Write encoded reports to a device, discarding any stale pending reports
+/// and clearing the report buffer once complete
+fn write_device(device: &HidDevice, reports: &mut Vec<u8>) -> Result<(), Error> {
+    drain(device);
 
-        let mut buff = [0u8; HID_PACKET_LEN + 1];
-
-        // Read first chunk of response
-        // Timeout argument applied here as once the reply has started timeout bounds should be more consistent
-        let n = match self
-            .device
-            .read_timeout(&mut buff, timeout.as_millis() as i32)
-        {
-            Ok(n) => n,
-            Err(HidError::IoError { error }) if error.kind() == ErrorKind::TimedOut => {
-                return Err(Error::Timeout)
-            }
-            Err(e) => return Err(e.into()),
+    debug!("Write APDU");
+
+    let r = write_reports(|report| Ok(device.write(report)?), reports);
+    wipe::clear(reports);
+    r
+}
+
+/// Read a single HID packet, `timeout_ms` of -1 blocks indefinitely
+fn read_chunk(device: &HidDevice, buff: &mut [u8], timeout_ms: i32) -> Result<usize, Error> {
+    match device.read_timeout(buff, timeout_ms) {
+        // hidapi reports timeouts as zero-length reads
+        Ok(0) => Err(Error::Timeout),
+        Ok(n) => Ok(n),
+        Err(HidError::IoError { error }) if error.kind() == ErrorKind::TimedOut => {
+            Err(Error::Timeout)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// [Exchange] impl for sending APDUs to a [UsbDevice]
+impl Exchange for UsbDevice {
+    async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        // Write APDU command then read response, chunked for HID transport
+        self.exchange_blocking(command, move |device, channel| {
+            read_apdu(
+                |buff, timeout_ms| read_chunk(device, buff, timeout_ms),
+                channel,
+                timeout,
+            )
+        })
+        .await
+    }
+
+    /// Exchange an APDU, bounding the first response packet by the user deadline
+    /// and following packets by the transport timeout
+    async fn exchange_timeouts(
+        &mut self,
+        command: &[u8],
+        timeouts: Timeouts,
+    ) -> Result<Vec<u8>, Error> {
+        let first_ms = match timeouts.user {
+            UserWait::None => return self.exchange(command, timeouts.transport).await,
+            UserWait::Deadline(d) => d.as_millis().min(i32::MAX as u128) as i32,
+            UserWait::Indefinite => -1,
         };
 
-        // Check read length is valid for following operations
-        if n == 0 {
-            error!("Empty response");
-            return Err(Error::EmptyResponse);
-        } else if n < 7 {
-            error!("Unexpected read length {n}");
+        self.exchange_blocking(command, move |device, channel| {
+            read_chunks(
+                |buff, timeout_ms| read_chunk(device, buff, timeout_ms),
+                channel,
+                first_ms,
+                timeouts.transport.as_millis().min(i32::MAX as u128) as i32,
+                true,
+            )
+        })
+        .await
+    }
+}
+
+/// HID report length (report ID + packet)
+const HID_REPORT_LEN: usize = HID_PACKET_LEN + 1;
+
+/// Encode a chunked HID APDU command for the HID `channel` into `reports`, replacing
+/// any existing contents while retaining buffer capacity.
+///
+/// All reports are encoded up front into a single buffer with a fixed (zero padded)
+/// stride, to be written back-to-back by [write_reports] without per-report allocation
+/// or logging between writes. `hidapi` does not support multi-report writes and devices
+/// only respond once the full command is received, so the read path starts immediately
+/// after the last report is written.
+fn encode_reports(reports: &mut Vec<u8>, channel: u16, apdu: &[u8]) {
+    debug!("TX: {:02x?}", apdu);
+
+    wipe::clear(reports);
+    reports.reserve((apdu.len() + 2).div_ceil(HID_PACKET_LEN - HID_HEADER_LEN) * HID_REPORT_LEN);
+
+    let mut encoder = HidEncoder::new(apdu).with_channel(channel);
+    while encoder.write_next(reports) {
+        let n = reports.len().next_multiple_of(HID_REPORT_LEN);
+        reports.resize(n, 0);
+    }
+
+    trace!(
+        "Encoded {} reports: 0x{:02x?}",
+        reports.len() / HID_REPORT_LEN,
+        reports
+    );
+}
+
+/// Write reports from [encode_reports], checking for short writes.
+///
+/// `write` is called with each report, returning the number of bytes written.
+fn write_reports(
+    mut write: impl FnMut(&[u8]) -> Result<usize, Error>,
+    reports: &[u8],
+) -> Result<(), Error> {
+    for (i, report) in reports.chunks(HID_REPORT_LEN).enumerate() {
+        let n = write(report)?;
+        if n < report.len() {
+            error!("Short write for chunk {i} ({n} of {} bytes)", report.len());
             return Err(Error::UnexpectedResponse);
         }
+    }
 
-        // Check header matches expectations
-        if buff[..5] != [0x01, 0x01, 0x05, 0x00, 0x00] {
-            error!("Unexpected response header: {:02x?}", &buff[..5]);
-            return Err(Error::UnexpectedResponse);
+    Ok(())
+}
+
+/// Read and reassemble a chunked HID APDU response.
+///
+/// `read` is called with a packet buffer and timeout in milliseconds, returning the number of bytes read
+/// (with zero-length reads treated as timeouts). Packets for HID channels other than `channel` are rejected.
+pub fn read_apdu(
+    read: impl FnMut(&mut [u8], i32) -> Result<usize, Error>,
+    channel: u16,
+    timeout: Duration,
+) -> Result<Vec<u8>, Error> {
+    // Timeout argument applied to the first chunk as once the reply has started timeout bounds should be more consistent,
+    // following chunks use a constant timeout as these should be sent end-to-end
+    let timeout_ms = timeout.as_millis().min(i32::MAX as u128) as i32;
+    read_chunks(read, channel, timeout_ms, 500, false)
+}
+
+/// Read and reassemble a chunked HID APDU response with separate first and following
+/// chunk timeouts, reporting first chunk timeouts as [Error::UserTimeout] where `user` is set.
+///
+/// Timeouts are applied as deadlines, set on start and refreshed only when a chunk of the
+/// response is accepted, so skipped stale or foreign packets cannot extend the exchange.
+fn read_chunks(
+    mut read: impl FnMut(&mut [u8], i32) -> Result<usize, Error>,
+    channel: u16,
+    first_ms: i32,
+    next_ms: i32,
+    user: bool,
+) -> Result<Vec<u8>, Error> {
+    let mut buff = Scratch::new([0u8; HID_PACKET_LEN + 1]);
+    let mut decoder = HidDecoder::new().with_channel(channel).with_resync();
+
+    // Apply the first chunk timeout until a response has started
+    let mut deadline = Deadline::after(first_ms);
+    let mut packets = decoder.packets();
+
+    // Reassemble response, skipping stale or foreign chunks
+    let resp = loop {
+        let r = match deadline.remaining_ms() {
+            // Zero-length reads indicate timeouts (as returned by hidapi)
+            Some(ms) => read(&mut buff[..], ms).and_then(|n| match n {
+                0 => Err(Error::Timeout),
+                n => Ok(n),
+            }),
+            None => Err(Error::Timeout),
+        };
+        let n = match r {
+            Err(Error::Timeout) if user && !decoder.in_progress() => {
+                return Err(Error::UserTimeout)
+            }
+            r => r?,
+        };
+
+        trace!("read: {:02x?}", &buff[..n]);
+
+        match decoder.push(&buff[..n]) {
+            Ok(Some(r)) => break r,
+            Ok(None) => (),
+            Err(e) => {
+                error!("Invalid response chunk: {e}");
+                return Err(e.into());
+            }
         }
 
-        trace!("initial read: {buff:02x?}");
+        // Apply the following chunk timeout from each accepted chunk
+        if decoder.packets() != packets {
+            packets = decoder.packets();
+            deadline = Deadline::after(next_ms);
+        }
+    };
 
-        // Parse response length
-        let len = u16::from_be_bytes([buff[5], buff[6]]) as usize;
+    debug!("RX: {:02x?}", resp);
 
-        trace!("Read len: {len}");
+    Ok(resp)
+}
 
-        // Setup response buffer and add any remaining data
-        let mut resp = Vec::with_capacity(len);
+/// Read deadline for [read_chunks], using `hidapi` millisecond timeout semantics
+/// where negative values block indefinitely
+#[derive(Copy, Clone, Debug)]
+struct Deadline(Option<rt::Instant>);
 
-        let data_len = len.min(n - 7);
-        resp.extend_from_slice(&buff[7..][..data_len]);
+impl Deadline {
+    /// Create a deadline `ms` milliseconds from now
+    fn after(ms: i32) -> Self {
+        let d = u64::try_from(ms).ok().map(Duration::from_millis);
+        Self(d.map(|d| rt::Instant::now() + d))
+    }
 
-        // Read following chunks if required
-        let mut seq_idx = 1;
-        while resp.len() < len {
-            let rem = len - resp.len();
+    /// Fetch the remaining time in milliseconds (`-1` if indefinite), `None` once elapsed
+    fn remaining_ms(&self) -> Option<i32> {
+        let Some(d) = self.0 else {
+            return Some(-1);
+        };
+
+        match d.saturating_duration_since(rt::Instant::now()).as_millis() {
+            0 => None,
+            ms => Some(ms.min(i32::MAX as u128) as i32),
+        }
+    }
+}
 
-            trace!("Read chunk {seq_idx} ({rem} bytes remaining)");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode then write an APDU using a fresh report buffer
+    fn write_apdu(
+        write: impl FnMut(&[u8]) -> Result<usize, Error>,
+        channel: u16,
+        apdu: &[u8],
+    ) -> Result<(), Error> {
+        let mut reports = vec![];
+        encode_reports(&mut reports, channel, apdu);
+        write_reports(write, &reports)
+    }
 
-            // Read next chunk, constant timeout as chunks should be sent end-to-end
-            let n = match self.device.read_timeout(&mut buff, 500) {
-                Ok(n) => n,
-                Err(e) => return Err(e.into()),
-            };
+    #[test]
+    fn write_apdu_reports() {
+        let apdu: Vec<u8> = (0..200u8).collect();
+        let mut reports = vec![];
+
+        write_apdu(
+            |r| {
+                reports.push(r.to_vec());
+                Ok(r.len())
+            },
+            HID_DEFAULT_CHANNEL,
+            &apdu,
+        )
+        .unwrap();
+
+        // Reports are fixed length with sequential headers
+        assert_eq!(reports.len(), 4);
+        for (i, r) in reports.iter().enumerate() {
+            assert_eq!(r.len(), HID_REPORT_LEN);
+            assert_eq!(&r[..6], &[0x00, 0x01, 0x01, 0x05, 0x00, i as u8]);
+        }
 
-            if n < 5 {
-                error!("Invalid chunk length {n}");
-                return Err(Error::UnexpectedResponse);
-            }
+        // Reports match the encoder output
+        for (r, p) in reports.iter().zip(HidEncoder::new(&apdu)) {
+            assert_eq!(&r[..p.len()], &p[..]);
+        }
+    }
+
+    #[test]
+    fn encode_reports_reuse() {
+        let mut reports = vec![];
+
+        // Buffers are replaced (not appended to) when reused, retaining capacity
+        encode_reports(&mut reports, HID_DEFAULT_CHANNEL, &[0xaa; 200]);
+        assert_eq!(reports.len(), 4 * HID_REPORT_LEN);
+        let capacity = reports.capacity();
+
+        encode_reports(&mut reports, 0x1234, &[0xbb; 10]);
+        assert_eq!(reports.len(), HID_REPORT_LEN);
+        assert_eq!(reports.capacity(), capacity);
+
+        let mut written = vec![];
+        write_apdu(
+            |r| {
+                written.extend_from_slice(r);
+                Ok(r.len())
+            },
+            0x1234,
+            &[0xbb; 10],
+        )
+        .unwrap();
+        assert_eq!(reports, written);
+    }
+
+    #[test]
+    fn write_apdu_short_write() {
+        let r = write_apdu(
+            |r| Ok(r.len() - 1),
+            HID_DEFAULT_CHANNEL,
+            &[0xe0, 0x01, 0x00, 0x00, 0x00],
+        );
+        assert!(matches!(r, Err(Error::UnexpectedResponse)));
+    }
+
+    #[test]
+    fn read_chunks_user_timeout() {
+        // First chunk timeouts are reported as user timeouts
+        let r = read_chunks(
+            |_, _| Err(Error::Timeout),
+            HID_DEFAULT_CHANNEL,
+            -1,
+            500,
+            true,
+        );
+        assert!(matches!(r, Err(Error::UserTimeout)));
+
+        // Following chunk timeouts are reported as transport timeouts
+        let first: Vec<u8> = HidEncoder::new(&[0xaa; 100]).next().unwrap();
+        let mut timeouts = vec![];
+        let r = read_chunks(
+            |buff, timeout_ms| {
+                timeouts.push(timeout_ms);
+                match timeouts.len() {
+                    1 => {
+                        buff[..first.len() - 1].copy_from_slice(&first[1..]);
+                        Ok(first.len() - 1)
+                    }
+                    _ => Err(Error::Timeout),
+                }
+            },
+            HID_DEFAULT_CHANNEL,
+            -1,
+            250,
+            true,
+        );
+        assert!(matches!(r, Err(Error::Timeout)));
+        assert_eq!(timeouts.len(), 2);
+        assert_eq!(timeouts[0], -1);
+        assert!((1..=250).contains(&timeouts[1]));
+    }
 
-            // Check header and sequence index
-            if buff[..3] != [0x01, 0x01, 0x05] {
-                error!("Unexpected response header: {:02x?}", &buff[..5]);
-                return Err(Error::UnexpectedResponse);
+    #[test]
+    fn read_chunks_zero_length() {
+        // Zero-length reads (hidapi timeouts) on the first chunk are reported as user timeouts
+        let r = read_chunks(|_, _| Ok(0), HID_DEFAULT_CHANNEL, 50, 500, true);
+        assert!(matches!(r, Err(Error::UserTimeout)));
+
+        let r = read_chunks(|_, _| Ok(0), HID_DEFAULT_CHANNEL, 50, 500, false);
+        assert!(matches!(r, Err(Error::Timeout)));
+
+        // And on following chunks as transport timeouts
+        let first: Vec<u8> = HidEncoder::new(&[0xaa; 100]).next().unwrap();
+        let mut reads = 0;
+        let r = read_chunks(
+            |buff, _| {
+                reads += 1;
+                match reads {
+                    1 => {
+                        buff[..first.len() - 1].copy_from_slice(&first[1..]);
+                        Ok(first.len() - 1)
+                    }
+                    _ => Ok(0),
+                }
+            },
+            HID_DEFAULT_CHANNEL,
+            -1,
+            250,
+            true,
+        );
+        assert!(matches!(r, Err(Error::Timeout)));
+        assert_eq!(reads, 2);
+    }
+
+    #[test]
+    fn read_chunks_skip_stale() {
+        let stale: Vec<_> = HidEncoder::new(&[0xaa; 100]).collect();
+        let foreign: Vec<_> = HidEncoder::new(&[0xcc; 10]).with_channel(0x1234).collect();
+        let resp: Vec<_> = HidEncoder::new(&[0xbb; 100]).collect();
+
+        let mut packets = [&stale[1], &foreign[0], &resp[0], &foreign[0], &resp[1]].into_iter();
+        let mut timeouts = vec![];
+        let r = read_chunks(
+            |buff, timeout_ms| {
+                timeouts.push(timeout_ms);
+                let Some(p) = packets.next() else {
+                    return Ok(0);
+                };
+                buff[..p.len() - 1].copy_from_slice(&p[1..]);
+                Ok(p.len() - 1)
+            },
+            HID_DEFAULT_CHANNEL,
+            -1,
+            250,
+            true,
+        );
+
+        // Stale and foreign chunks are skipped, retaining the first chunk timeout
+        // until the response starts
+        assert_eq!(r.unwrap(), vec![0xbb; 100]);
+        assert_eq!(&timeouts[..3], &[-1, -1, -1]);
+        assert!(timeouts[3..].iter().all(|t| (1..=250).contains(t)));
+
+        // Timeouts (zero-length reads) following skipped chunks are reported as
+        // user timeouts before the response starts, and transport timeouts after
+        for (started, p) in [
+            (false, &[&stale[1], &foreign[0]][..]),
+            (true, &[&resp[0], &foreign[0]]),
+        ] {
+            let mut packets = p.iter();
+            let r = read_chunks(
+                |buff, _| {
+                    let Some(p) = packets.next() else {
+                        return Ok(0);
+                    };
+                    buff[..p.len() - 1].copy_from_slice(&p[1..]);
+                    Ok(p.len() - 1)
+                },
+                HID_DEFAULT_CHANNEL,
+                -1,
+                250,
+                true,
+            );
+
+            match started {
+                false => assert!(matches!(r, Err(Error::UserTimeout))),
+                true => assert!(matches!(r, Err(Error::Timeout))),
             }
-            if u16::from_be_bytes([buff[3], buff[4]]) != seq_idx {
-                error!("Unexpected sequence index: {:02x?}", &buff[5..7]);
-                return Err(Error::UnexpectedResponse);
+        }
+    }
+
+    #[test]
+    fn read_chunks_deadline() {
+        let foreign: Vec<_> = HidEncoder::new(&[0xcc; 10]).with_channel(0x1234).collect();
+        let resp: Vec<_> = HidEncoder::new(&[0xbb; 100]).collect();
+
+        // Foreign packets arriving continuously do not extend the deadline
+        // for the first or following chunks
+        for (started, first_ms, next_ms) in [(false, 50, 500), (true, -1, 50)] {
+            let mut reads = 0;
+            let start = rt::Instant::now();
+            let r = read_chunks(
+                |buff, timeout_ms| {
+                    assert!(timeout_ms != 0);
+                    reads += 1;
+                    let p = match reads {
+                        1 if started => &resp[0],
+                        _ => {
+                            std::thread::sleep(Duration::from_millis(5));
+                            &foreign[0]
+                        }
+                    };
+                    buff[..p.len() - 1].copy_from_slice(&p[1..]);
+                    Ok(p.len() - 1)
+                },
+                HID_DEFAULT_CHANNEL,
+                first_ms,
+                next_ms,
+                true,
+            );
+
+            match started {
+                false => assert!(matches!(r, Err(Error::UserTimeout))),
+                true => assert!(matches!(r, Err(Error::Timeout))),
             }
+            assert!(start.elapsed() < Duration::from_millis(250));
+            assert!(reads < 50);
+        }
 
-            // Add to response buffer
-            let data_len = rem.min(n - 5);
-            resp.extend_from_slice(&buff[5..][..data_len]);
-            seq_idx += 1;
+        // Reads following skipped packets wait out only the remaining deadline,
+        // timing out as hidapi does with zero-length reads
+        let cases = [
+            (false, 100, 500, &[&foreign[0], &foreign[0]][..]),
+            (true, -1, 100, &[&resp[0], &foreign[0], &foreign[0]]),
+        ];
+        for (started, first_ms, next_ms, p) in cases {
+            let mut packets = p.iter();
+            let mut timeouts = vec![];
+            let start = rt::Instant::now();
+            let r = read_chunks(
+                |buff, timeout_ms| {
+                    timeouts.push(timeout_ms);
+                    let Some(p) = packets.next() else {
+                        std::thread::sleep(Duration::from_millis(timeout_ms as u64));
+                        return Ok(0);
+                    };
+                    std::thread::sleep(Duration::from_millis(20));
+                    buff[..p.len() - 1].copy_from_slice(&p[1..]);
+                    Ok(p.len() - 1)
+                },
+                HID_DEFAULT_CHANNEL,
+                first_ms,
+                next_ms,
+                true,
+            );
+
+            match started {
+                false => assert!(matches!(r, Err(Error::UserTimeout))),
+                true => assert!(matches!(r, Err(Error::Timeout))),
+            }
+            let last = *timeouts.last().unwrap();
+            assert!((1..=60).contains(&last), "remaining timeout {last}");
+            assert!(start.elapsed() < Duration::from_millis(250));
         }
+    }
 
-        debug!("RX: {:02x?}", resp);
+    #[test]
+    fn permission_denied() {
+        let io = |kind| HidError::IoError {
+            error: std::io::Error::from(kind),
+        };
+        let msg = |m: &str| HidError::HidApiError {
+            message: m.to_string(),
+        };
 
-        Ok(resp)
+        assert!(is_permission_denied(&io(ErrorKind::PermissionDenied), None));
+        assert!(!is_permission_denied(&io(ErrorKind::NotFound), None));
+        assert!(is_permission_denied(
+            &msg("Failed to open a device with path '/dev/hidraw0': Permission denied"),
+            None
+        ));
+        assert!(is_permission_denied(&msg("LIBUSB_ERROR_ACCESS"), None));
+        assert!(!is_permission_denied(&msg("No such device"), None));
+
+        // Missing device nodes are not permission failures
+        assert!(!is_permission_denied(
+            &HidError::HidApiErrorEmpty,
+            Some("/dev/hidraw-missing")
+        ));
+
+        // Accessible device nodes are not permission failures
+        #[cfg(unix)]
+        assert!(!is_permission_denied(
+            &HidError::HidApiErrorEmpty,
+            Some("/dev/null")
+        ));
     }
 
-    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
-        Ok(self.device.get_device_info().is_ok())
-    }
-}
+    #[test]
+    fn apdu_channel() {
+        assert_eq!(HidChannel::default().resolve(), HID_DEFAULT_CHANNEL);
+        assert_eq!(HidChannel::Fixed(0x1234).resolve(), 0x1234);
+
+        // Responses are read on the written channel
+        let channel = HidChannel::Random.resolve();
+        let mut reports = vec![];
+        write_apdu(
+            |r| {
+                reports.push(r.to_vec());
+                Ok(r.len())
+            },
+            channel,
+            &[0xbb; 100],
+        )
+        .unwrap();
+        assert!(reports.iter().all(|r| r[1..3] == channel.to_be_bytes()));
+
+        let read = |channel| {
+            let mut packets = reports.iter();
+            read_apdu(
+                |buff, _| {
+                    let r = packets.next().ok_or(Error::Timeout)?;
+                    buff[..r.len() - 1].copy_from_slice(&r[1..]);
+                    Ok(r.len() - 1)
+                },
+                channel,
+                Duration::from_millis(100),
+            )
+        };
+        assert_eq!(read(channel).unwrap(), vec![0xbb; 100]);
 
-/// [Exchange] impl for sending APDUs to a [UsbDevice]
-#[cfg_attr(not(feature = "unstable_async_trait"), async_trait::async_trait)]
-impl Exchange for UsbDevice {
-    async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
-        // Write APDU command, chunked for HID transport
-        self.write(command)?;
-        // Read APDU response, chunked for HID transport
-        self.read(timeout)
+        // Responses on other channels are skipped
+        assert!(matches!(read(channel.wrapping_add(1)), Err(Error::Timeout)));
     }
 }
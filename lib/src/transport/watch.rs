@@ -0,0 +1,251 @@
+//! Device connection event streams, for detecting devices being connected and disconnected
+//!
+//! Streams list devices on start and then each time they are triggered, reporting
+//! differences between successive lists as [DeviceEvent]s. [watch] is a polling helper
+//! triggering on a fixed interval, for transports without hotplug notifications (TCP, BLE),
+//! while [UsbTransport::watch](super::UsbTransport::watch) triggers on udev hotplug
+//! events on Linux.
+
+use std::{collections::VecDeque, pin::Pin, time::Duration};
+
+use futures::{stream, Stream, StreamExt};
+
+use super::Transport;
+use crate::{info::LedgerInfo, rt, Error};
+
+/// Default interval between device list operations when watching for device changes
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Device connection event, returned by [watch]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeviceEvent {
+    /// Device connected (or present when the watch started)
+    Connected(LedgerInfo),
+    /// Device disconnected
+    Disconnected(LedgerInfo),
+}
+
+impl DeviceEvent {
+    /// Fetch device information for the event
+    pub fn info(&self) -> &LedgerInfo {
+        match self {
+            DeviceEvent::Connected(i) | DeviceEvent::Disconnected(i) => i,
+        }
+    }
+}
+
+/// Watch a transport for device connection changes, polling `list` every `interval`.
+///
+/// Devices present when the watch starts are reported as [DeviceEvent::Connected].
+/// List failures are returned in the stream without ending it, with polling
+/// resumed following the next interval.
+///
+/// Note that each poll lists all devices for the transport, which for BLE includes
+/// a scan, so filters should be used to restrict watches to the required transports.
+pub fn watch<T: Transport>(
+    transport: T,
+    filters: T::Filters,
+    interval: Duration,
+) -> impl Stream<Item = Result<DeviceEvent, Error>>
+where
+    T::Filters: Clone,
+{
+    watch_with(transport, filters, ticks(interval))
+}
+
+/// Stream yielding every `interval`, for polling [watch] streams
+pub(crate) fn ticks(interval: Duration) -> impl Stream<Item = ()> {
+    stream::unfold((), move |_| async move {
+        rt::sleep(interval).await;
+        Some(((), ()))
+    })
+}
+
+/// Watch a transport for device connection changes, listing devices on start and
+/// each time `triggers` yields, ending once `triggers` ends (see [watch])
+pub(crate) fn watch_with<T: Transport, S: Stream<Item = ()>>(
+    transport: T,
+    filters: T::Filters,
+    triggers: S,
+) -> impl Stream<Item = Result<DeviceEvent, Error>>
+where
+    T::Filters: Clone,
+{
+    let state = WatchState {
+        transport,
+        filters,
+        triggers: Box::pin(triggers),
+        known: None,
+        pending: VecDeque::new(),
+    };
+
+    stream::unfold(state, |mut s| async move {
+        loop {
+            // Return queued events prior to polling again
+            if let Some(e) = s.pending.pop_front() {
+                return Some((Ok(e), s));
+            }
+
+            // Wait for a trigger between list operations, except on the first poll
+            if s.known.is_some() {
+                s.triggers.next().await?;
+            }
+
+            match s.transport.list(s.filters.clone()).await {
+                Ok(devices) => {
+                    let prev = s.known.take().unwrap_or_default();
+                    s.pending.extend(diff(&prev, &devices));
+                    s.known = Some(devices);
+                }
+                Err(e) => {
+                    s.known.get_or_insert_with(Vec::new);
+                    return Some((Err(e), s));
+                }
+            }
+        }
+    })
+}
+
+/// Internal state for [watch] streams
+struct WatchState<T: Transport, S> {
+    transport: T,
+    filters: T::Filters,
+    triggers: Pin<Box<S>>,
+    /// Devices from the last successful list, `None` prior to the first poll
+    known: Option<Vec<LedgerInfo>>,
+    /// Events awaiting return
+    pending: VecDeque<DeviceEvent>,
+}
+
+/// Compute connection events between two device lists, disconnections first
+pub(crate) fn diff(prev: &[LedgerInfo], next: &[LedgerInfo]) -> Vec<DeviceEvent> {
+    let removed = prev
+        .iter()
+        .filter(|d| !next.contains(d))
+        .map(|d| DeviceEvent::Disconnected(d.clone()));
+    let added = next
+        .iter()
+        .filter(|d| !prev.contains(d))
+        .map(|d| DeviceEvent::Connected(d.clone()));
+
+    removed.chain(added).collect()
+}
+
+#[cfg(all(test, feature = "transport_tcp"))]
+mod tests {
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{
+        info::{ConnInfo, Model},
+        transport::GenericDevice,
+        LedgerInfo,
+    };
+
+    /// Mock transport returning queued list results
+    struct MockTransport(VecDeque<Result<Vec<LedgerInfo>, Error>>);
+
+    impl Transport for MockTransport {
+        type Filters = ();
+        type Info = LedgerInfo;
+        type Device = GenericDevice;
+
+        async fn list(&mut self, _filters: ()) -> Result<Vec<LedgerInfo>, Error> {
+            self.0.pop_front().unwrap_or_else(|| Ok(vec![]))
+        }
+
+        async fn connect(&mut self, _info: LedgerInfo) -> Result<GenericDevice, Error> {
+            Err(Error::Unknown)
+        }
+    }
+
+    fn device(n: u16) -> LedgerInfo {
+        LedgerInfo {
+            model: Model::NanoSPlus,
+            conn: ConnInfo::Tcp(crate::transport::TcpInfo {
+                addr: ([127, 0, 0, 1], n).into(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn diff_lists() {
+        let (a, b, c) = (device(1), device(2), device(3));
+
+        let (prev, next) = ([a.clone(), b], [device(2), c.clone()]);
+
+        assert_eq!(diff(&[], &[]), vec![]);
+        assert_eq!(diff(&prev, &prev), vec![]);
+        assert_eq!(
+            diff(&prev, &next),
+            vec![
+                DeviceEvent::Disconnected(a),
+                DeviceEvent::Connected(c.clone())
+            ]
+        );
+        assert_eq!(diff(&prev[1..], &next), vec![DeviceEvent::Connected(c)]);
+    }
+
+    #[tokio::test]
+    async fn watch_events() {
+        let (a, b) = (device(1), device(2));
+
+        let t = MockTransport(VecDeque::from([
+            Ok(vec![a.clone()]),
+            Ok(vec![a.clone(), b.clone()]),
+            Err(Error::Timeout),
+            Ok(vec![b.clone()]),
+        ]));
+
+        let events: Vec<_> = watch(t, (), Duration::from_millis(1))
+            .take(5)
+            .collect()
+            .await;
+
+        assert_eq!(
+            events[0].as_ref().unwrap(),
+            &DeviceEvent::Connected(a.clone())
+        );
+        assert_eq!(
+            events[1].as_ref().unwrap(),
+            &DeviceEvent::Connected(b.clone())
+        );
+        assert!(matches!(events[2], Err(Error::Timeout)));
+        assert_eq!(
+            events[3].as_ref().unwrap(),
+            &DeviceEvent::Disconnected(a.clone())
+        );
+        assert_eq!(
+            events[4].as_ref().unwrap(),
+            &DeviceEvent::Disconnected(b.clone())
+        );
+    }
+
+    #[tokio::test]
+    async fn watch_triggers() {
+        let (a, b) = (device(1), device(2));
+
+        let t = MockTransport(VecDeque::from([
+            Ok(vec![a.clone()]),
+            Ok(vec![a.clone(), b.clone()]),
+            Ok(vec![b.clone()]),
+        ]));
+
+        // Devices are listed on start and each trigger, ending with the trigger stream
+        let events: Vec<_> = watch_with(t, (), stream::iter([(), ()]))
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(
+            events,
+            vec![
+                DeviceEvent::Connected(a.clone()),
+                DeviceEvent::Connected(b),
+                DeviceEvent::Disconnected(a),
+            ]
+        );
+    }
+}
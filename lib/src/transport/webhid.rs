@@ -0,0 +1,261 @@
+//! WebHID transport implementation, for browser (wasm32) targets
+//!
+//! WebHID bindings are unstable in `web-sys`, so this requires building with
+//! `RUSTFLAGS=--cfg=web_sys_unstable_apis`.
+//!
+//! Browsers only expose devices the user has granted access to, use
+//! [WebHidTransport::request] (from a user gesture such as a click handler) to
+//! prompt the user to select a device prior to listing or connecting.
+
+use std::{fmt::Display, time::Duration};
+
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver},
+    FutureExt, StreamExt,
+};
+use js_sys::Uint8Array;
+use ledger_proto::framing::hid::{HidDecoder, HidEncoder, HID_PACKET_LEN};
+use tracing::{debug, trace, warn};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{Hid, HidDevice, HidDeviceFilter, HidDeviceRequestOptions, HidInputReportEvent};
+
+use crate::{
+    info::{LedgerInfo, Model, LEDGER_VID},
+    rt, Error,
+};
+
+use super::{Exchange, Transport};
+
+/// Timeout for following response packets, once a response has started
+const NEXT_PACKET_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// WebHID device information
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WebHidInfo {
+    /// USB Device Vendor ID (VID)
+    pub vid: u16,
+    /// USB Device Product ID (PID)
+    pub pid: u16,
+    /// Product name
+    pub name: String,
+    /// Index in the list of granted devices, from the last list operation
+    pub index: usize,
+}
+
+impl Display for WebHidInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({:04x}:{:04x})", self.name, self.vid, self.pid)
+    }
+}
+
+/// WebHID based transport
+pub struct WebHidTransport {
+    hid: Hid,
+    devices: Vec<HidDevice>,
+}
+
+/// WebHID based device
+pub struct WebHidDevice {
+    pub info: WebHidInfo,
+    device: HidDevice,
+    reports: UnboundedReceiver<Vec<u8>>,
+    _on_report: Closure<dyn FnMut(HidInputReportEvent)>,
+}
+
+impl WebHidTransport {
+    /// Create a new [WebHidTransport], failing where WebHID is not supported by the browser
+    pub fn new() -> Result<Self, Error> {
+        let navigator = web_sys::window()
+            .ok_or_else(|| Error::WebHid("no window available".to_string()))?
+            .navigator();
+
+        if !js_sys::Reflect::has(&navigator, &JsValue::from_str("hid")).unwrap_or(false) {
+            return Err(Error::WebHid("WebHID not supported".to_string()));
+        }
+
+        Ok(Self {
+            hid: navigator.hid(),
+            devices: vec![],
+        })
+    }
+
+    /// Prompt the user to grant access to a ledger device, returning the selected devices.
+    ///
+    /// Browsers require this to be called from a user gesture (for example, a click handler).
+    pub async fn request(&mut self) -> Result<Vec<LedgerInfo>, Error> {
+        let filter = HidDeviceFilter::new();
+        filter.set_vendor_id(LEDGER_VID as u32);
+        let options = HidDeviceRequestOptions::new(&[filter]);
+
+        let selected = self.hid.request_device(&options).await?.to_vec();
+        debug!("Selected {} WebHID devices", selected.len());
+
+        // Refresh granted devices so selections can be connected
+        let devices = self.list(()).await?;
+
+        let selected = devices
+            .into_iter()
+            .zip(&self.devices)
+            .filter(|(_i, d)| selected.contains(d))
+            .map(|(i, _d)| i)
+            .collect();
+
+        Ok(selected)
+    }
+}
+
+impl Transport for WebHidTransport {
+    type Filters = ();
+    type Info = WebHidInfo;
+    type Device = WebHidDevice;
+
+    /// List granted devices using the [WebHidTransport]
+    async fn list(&mut self, _filters: ()) -> Result<Vec<LedgerInfo>, Error> {
+        // Fetch granted devices, filtering for ledgers
+        self.devices = self
+            .hid
+            .get_devices()
+            .await?
+            .to_vec()
+            .into_iter()
+            .filter(|d| d.vendor_id() == LEDGER_VID)
+            .collect();
+
+        let devices: Vec<_> = self
+            .devices
+            .iter()
+            .enumerate()
+            .map(|(index, d)| LedgerInfo {
+                model: Model::from_pid(d.product_id()),
+                conn: WebHidInfo {
+                    vid: d.vendor_id(),
+                    pid: d.product_id(),
+                    name: d.product_name(),
+                    index,
+                }
+                .into(),
+            })
+            .collect();
+
+        debug!("devices: {:?}", devices);
+
+        Ok(devices)
+    }
+
+    /// Connect to a device using info from a previous list operation
+    async fn connect(&mut self, info: WebHidInfo) -> Result<WebHidDevice, Error> {
+        debug!("Connecting to WebHID device: {:?}", info);
+
+        // Match listed devices using provided device info
+        let device = match self.devices.get(info.index) {
+            Some(d) if d.vendor_id() == info.vid && d.product_id() == info.pid => d.clone(),
+            _ => {
+                warn!("No device found matching: {info:?}");
+                return Err(Error::NoDevices);
+            }
+        };
+
+        if !device.opened() {
+            device.open().await?;
+        }
+
+        // Forward input reports to the device channel
+        let (tx, reports) = unbounded();
+        let on_report =
+            Closure::<dyn FnMut(HidInputReportEvent)>::new(move |e: HidInputReportEvent| {
+                let d = e.data();
+                let r = Uint8Array::new_with_byte_offset_and_length(
+                    &d.buffer(),
+                    d.byte_offset() as u32,
+                    d.byte_length() as u32,
+                );
+                let _ = tx.unbounded_send(r.to_vec());
+            });
+        device.set_oninputreport(Some(on_report.as_ref().unchecked_ref()));
+
+        debug!("Connected to WebHID device: {:?}", info);
+
+        Ok(WebHidDevice {
+            info,
+            device,
+            reports,
+            _on_report: on_report,
+        })
+    }
+}
+
+impl WebHidDevice {
+    /// Write an APDU to the device
+    async fn write(&mut self, apdu: &[u8]) -> Result<(), Error> {
+        debug!("TX: {:02x?}", apdu);
+
+        // Discard reports left over from previous (timed-out) exchanges
+        while let Some(Some(r)) = self.reports.next().now_or_never() {
+            trace!("Discarding stale report: {:02x?}", r);
+        }
+
+        for packet in HidEncoder::new(apdu) {
+            // Strip report ID and pad to the report length
+            let mut report = packet[1..].to_vec();
+            report.resize(HID_PACKET_LEN, 0);
+
+            self.device
+                .send_report_with_u8_slice(0, &mut report)?
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Read an APDU from the device
+    async fn read(&mut self, timeout: Duration) -> Result<Vec<u8>, Error> {
+        let mut decoder = HidDecoder::new().with_resync();
+
+        loop {
+            // Apply the request timeout until a response has started
+            let t = match decoder.in_progress() {
+                false => timeout,
+                true => NEXT_PACKET_TIMEOUT,
+            };
+
+            let report = rt::timeout(t, self.reports.next())
+                .await?
+                .ok_or(Error::Closed)?;
+
+            trace!("read: {:02x?}", report);
+
+            if let Some(r) = decoder.push(&report)? {
+                debug!("RX: {:02x?}", r);
+                return Ok(r);
+            }
+        }
+    }
+
+    pub(crate) async fn is_connected(&self) -> Result<bool, Error> {
+        Ok(self.device.opened())
+    }
+}
+
+/// [Exchange] impl for sending APDUs to a [WebHidDevice]
+impl Exchange for WebHidDevice {
+    async fn exchange(&mut self, command: &[u8], timeout: Duration) -> Result<Vec<u8>, Error> {
+        self.write(command).await?;
+        self.read(timeout).await
+    }
+}
+
+/// Close the device on drop, releasing the input report handler
+impl Drop for WebHidDevice {
+    fn drop(&mut self) {
+        self.device.set_oninputreport(None);
+        let _ = self.device.close();
+    }
+}
+
+/// Map JavaScript exceptions and promise rejections
+impl From<JsValue> for Error {
+    fn from(e: JsValue) -> Self {
+        Error::WebHid(e.as_string().unwrap_or_else(|| format!("{e:?}")))
+    }
+}
@@ -0,0 +1,47 @@
+//! Helpers for wiping intermediate APDU buffers when the `zeroize` feature is enabled,
+//! these are no-ops otherwise.
+
+#[cfg(not(feature = "zeroize"))]
+use std::ops::{Deref, DerefMut};
+
+/// Scratch buffer wrapper, wiping contents on drop when the `zeroize` feature is enabled
+#[cfg(feature = "zeroize")]
+pub(crate) use zeroize::Zeroizing as Scratch;
+
+/// Scratch buffer wrapper, wiping contents on drop when the `zeroize` feature is enabled
+#[cfg(not(feature = "zeroize"))]
+pub(crate) struct Scratch<T>(T);
+
+#[cfg(not(feature = "zeroize"))]
+impl<T> Scratch<T> {
+    pub fn new(v: T) -> Self {
+        Self(v)
+    }
+}
+
+#[cfg(not(feature = "zeroize"))]
+impl<T> Deref for Scratch<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(not(feature = "zeroize"))]
+impl<T> DerefMut for Scratch<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Clear a reusable buffer, retaining capacity and wiping contents when the `zeroize`
+/// feature is enabled
+#[cfg(feature = "transport_usb")]
+pub(crate) fn clear(buff: &mut Vec<u8>) {
+    #[cfg(feature = "zeroize")]
+    zeroize::Zeroize::zeroize(buff);
+
+    #[cfg(not(feature = "zeroize"))]
+    buff.clear();
+}
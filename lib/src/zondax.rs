@@ -0,0 +1,187 @@
+//! Adapters for the Zondax [ledger_transport] traits, allowing application crates
+//! written against [ledger_transport::Exchange] to run over `ledger-lib` devices.
+//!
+//! ```no_run
+//! use ledger_lib::{zondax::ZondaxExchange, LedgerProvider, Filters, Transport};
+//! use ledger_transport::{APDUCommand, Exchange};
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let mut p = LedgerProvider::init().await;
+//! let devices = p.list(Filters::Any).await?;
+//!
+//! // Wrap a connected device for use with Zondax-style apps
+//! let d = ZondaxExchange::new(p.connect(devices[0].clone()).await?);
+//!
+//! let cmd = APDUCommand { cla: 0xb0, ins: 0x01, p1: 0x00, p2: 0x00, data: vec![] };
+//! let answer = d.exchange(&cmd).await?;
+//!
+//! println!("retcode: 0x{:04x} data: {:02x?}", answer.retcode(), answer.data());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{ops::Deref, time::Duration};
+
+use futures::lock::Mutex;
+use ledger_proto::length::ApduLengths;
+use ledger_transport::{async_trait, APDUAnswer, APDUCommand};
+
+use crate::{Error, Exchange, DEFAULT_TIMEOUT};
+
+/// Adapter implementing [ledger_transport::Exchange] over `ledger-lib` devices.
+///
+/// The Zondax trait exchanges via `&self` and requires `Send` futures, so the wrapped
+/// device is held behind an async mutex. Status words are returned in the [APDUAnswer]
+/// as expected by Zondax apps, rather than mapped to [Error::Status].
+pub struct ZondaxExchange<D> {
+    d: Mutex<D>,
+    timeout: Duration,
+}
+
+impl<D> ZondaxExchange<D> {
+    /// Wrap a device for use with [ledger_transport::Exchange], using [DEFAULT_TIMEOUT]
+    pub fn new(d: D) -> Self {
+        Self {
+            d: Mutex::new(d),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Set the timeout applied to each exchange
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Unwrap the underlying device
+    pub fn into_inner(self) -> D {
+        self.d.into_inner()
+    }
+}
+
+/// Encode a Zondax [APDUCommand] for exchange, using extended lengths where required
+fn encode_command<I: Deref<Target = [u8]>>(command: &APDUCommand<I>) -> Result<Vec<u8>, Error> {
+    let data = command.data.deref();
+    let lengths = ApduLengths::new(data.len(), None)?;
+
+    let mut buff = vec![0u8; 4 + lengths.lc_len() + data.len()];
+    buff[..4].copy_from_slice(&[command.cla, command.ins, command.p1, command.p2]);
+    let n = 4 + lengths.encode_lc(&mut buff[4..])?;
+    buff[n..].copy_from_slice(data);
+
+    Ok(buff)
+}
+
+/// Implement [ledger_transport::Exchange] for [ZondaxExchange] over concrete device types.
+///
+/// This is not a blanket impl as native `async fn` futures on [Exchange] cannot be
+/// bounded as `Send` for generic devices.
+macro_rules! impl_zondax_exchange {
+    ($($(#[$m:meta])* $t:ty),* $(,)?) => {
+        $(
+            $(#[$m])*
+            #[async_trait]
+            impl ledger_transport::Exchange for ZondaxExchange<$t> {
+                type Error = Error;
+                type AnswerType = Vec<u8>;
+
+                async fn exchange<I>(
+                    &self,
+                    command: &APDUCommand<I>,
+                ) -> Result<APDUAnswer<Vec<u8>>, Error>
+                where
+                    I: Deref<Target = [u8]> + Send + Sync,
+                {
+                    let buff = encode_command(command)?;
+
+                    let mut d = self.d.lock().await;
+                    let resp = d.exchange(&buff, self.timeout).await?;
+
+                    APDUAnswer::from_answer(resp).map_err(|_| Error::EmptyResponse)
+                }
+            }
+        )*
+    };
+}
+
+impl_zondax_exchange!(
+    crate::transport::GenericDevice,
+    #[cfg(feature = "provider")]
+    crate::LedgerHandle,
+    #[cfg(feature = "transport_usb")]
+    crate::transport::UsbDevice,
+    #[cfg(feature = "transport_tcp")]
+    crate::transport::TcpDevice,
+    #[cfg(feature = "transport_ble")]
+    crate::transport::BleDevice,
+    #[cfg(feature = "transport_loopback")]
+    crate::transport::LoopbackDevice,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_zondax_command() {
+        let cmd = APDUCommand {
+            cla: 0xe0,
+            ins: 0x01,
+            p1: 0x02,
+            p2: 0x03,
+            data: vec![0xaa, 0xbb],
+        };
+
+        assert_eq!(
+            encode_command(&cmd).unwrap(),
+            &[0xe0, 0x01, 0x02, 0x03, 0x02, 0xaa, 0xbb]
+        );
+
+        let cmd = APDUCommand {
+            cla: 0xe0,
+            ins: 0x01,
+            p1: 0x00,
+            p2: 0x00,
+            data: vec![0u8; 256],
+        };
+        assert_eq!(
+            &encode_command(&cmd).unwrap()[..7],
+            &[0xe0, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00]
+        );
+
+        let cmd = APDUCommand {
+            cla: 0xe0,
+            ins: 0x01,
+            p1: 0x00,
+            p2: 0x00,
+            data: vec![0u8; 0x10000],
+        };
+        assert!(encode_command(&cmd).is_err());
+    }
+
+    #[cfg(feature = "transport_loopback")]
+    #[tokio::test]
+    async fn zondax_exchange_loopback() {
+        use crate::transport::{LoopbackInfo, LoopbackTransport, Transport};
+        use ledger_transport::Exchange as _;
+
+        let mut t = LoopbackTransport::new().unwrap();
+        let info = LoopbackInfo {
+            status: Some(0x6985),
+        };
+        let d = ZondaxExchange::new(t.connect(info).await.unwrap());
+
+        let cmd = APDUCommand {
+            cla: 0xe0,
+            ins: 0x01,
+            p1: 0x00,
+            p2: 0x00,
+            data: &[0xaa][..],
+        };
+        let answer = d.exchange(&cmd).await.unwrap();
+
+        // Loopback echoes the encoded command with the configured status
+        assert_eq!(answer.data(), &[0xe0, 0x01, 0x00, 0x00, 0x01, 0xaa]);
+        assert_eq!(answer.retcode(), 0x6985);
+    }
+}
@@ -0,0 +1,385 @@
+//! [MockDevice] implementation, handling BOLOS dashboard APDUs against shared device state
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use encdec::Encode;
+use tracing::debug;
+
+use ledger_lib::{info::Model, Error, Exchange};
+use ledger_proto::{
+    apdus::{
+        AppData, AppFlags, AppInfoReq, AppInfoResp, AppListReq, AppListResp, DeviceInfoReq,
+        DeviceInfoResp, ExitAppReq, RunAppReq,
+    },
+    length::decode_lc,
+    ApduStatic, StatusCode,
+};
+
+use crate::{Fault, MockConfig};
+
+/// Mock device emulating the BOLOS dashboard, cloned handles share device state
+#[derive(Clone)]
+pub struct MockDevice {
+    state: Arc<Mutex<MockState>>,
+}
+
+/// Shared mock device state
+struct MockState {
+    config: MockConfig,
+    /// Index of the running application (`None` for the dashboard)
+    running: Option<usize>,
+    /// Next app list entry index
+    list_index: usize,
+    /// Pending faults, optionally matched by instruction
+    faults: VecDeque<(Option<u8>, Fault)>,
+    /// Received commands
+    commands: Vec<Vec<u8>>,
+}
+
+impl MockDevice {
+    /// Create a new mock device in the dashboard using the provided configuration
+    pub fn new(config: MockConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockState {
+                config,
+                running: None,
+                list_index: 0,
+                faults: VecDeque::new(),
+                commands: vec![],
+            })),
+        }
+    }
+
+    /// Inject a fault for the next exchange
+    pub fn inject(&self, fault: Fault) {
+        self.state.lock().unwrap().faults.push_back((None, fault));
+    }
+
+    /// Inject a fault for the next exchange with the provided instruction
+    pub fn inject_for(&self, ins: u8, fault: Fault) {
+        self.state
+            .lock()
+            .unwrap()
+            .faults
+            .push_back((Some(ins), fault));
+    }
+
+    /// Lock or unlock the device
+    pub fn set_locked(&self, locked: bool) {
+        self.state.lock().unwrap().config.locked = locked;
+    }
+
+    /// Fetch the name of the running application (`None` for the dashboard)
+    pub fn running(&self) -> Option<String> {
+        let s = self.state.lock().unwrap();
+        s.running.map(|i| s.config.apps[i].name.clone())
+    }
+
+    /// Fetch the device model
+    pub fn model(&self) -> Model {
+        self.state.lock().unwrap().config.model.clone()
+    }
+
+    /// Fetch commands received by the device
+    pub fn commands(&self) -> Vec<Vec<u8>> {
+        self.state.lock().unwrap().commands.clone()
+    }
+}
+
+/// [Exchange] implementation for [MockDevice]
+impl Exchange for MockDevice {
+    async fn exchange(&mut self, command: &[u8], _timeout: Duration) -> Result<Vec<u8>, Error> {
+        let mut s = self.state.lock().unwrap();
+
+        debug!("RX: {command:02x?}");
+        s.commands.push(command.to_vec());
+
+        // Apply matching faults
+        let ins = command.get(1).copied();
+        if let Some(i) = s
+            .faults
+            .iter()
+            .position(|(f_ins, _)| f_ins.is_none() || *f_ins == ins)
+        {
+            let (_, f) = s.faults.remove(i).unwrap();
+
+            debug!("Injecting fault: {f:?}");
+
+            return match f {
+                Fault::Status(v) => Ok(v.to_be_bytes().to_vec()),
+                Fault::Timeout => Err(Error::Timeout),
+                Fault::Closed => Err(Error::Closed),
+                Fault::EmptyResponse => Err(Error::EmptyResponse),
+            };
+        }
+
+        let resp = s.handle(command);
+
+        debug!("TX: {resp:02x?}");
+
+        Ok(resp)
+    }
+}
+
+impl MockState {
+    /// Handle an incoming command, returning response data and status
+    fn handle(&mut self, command: &[u8]) -> Vec<u8> {
+        // Parse header and data (short or extended length, with optional trailing Le)
+        let data = match command.get(4..).map(decode_lc) {
+            Some(Ok((n, lc_len)))
+                if (0..=2).contains(&command.len().wrapping_sub(4 + lc_len + n)) =>
+            {
+                &command[4 + lc_len..][..n]
+            }
+            _ => return status(StatusCode::IncorrectLength),
+        };
+        let (cla, ins) = (command[0], command[1]);
+
+        if self.config.locked {
+            return status(StatusCode::LockedDevice);
+        }
+
+        match (cla, ins, self.running) {
+            // Application info, available in the dashboard and applications
+            (AppInfoReq::CLA, AppInfoReq::INS, _) => self.app_info(),
+            // Exit application
+            (ExitAppReq::CLA, ExitAppReq::INS, _) => {
+                self.running = None;
+                status(StatusCode::Ok)
+            }
+            // Dashboard-only instructions
+            (DeviceInfoReq::CLA, DeviceInfoReq::INS, None) => self.device_info(),
+            (RunAppReq::CLA, RunAppReq::INS, None) => self.run_app(data),
+            (AppListReq::CLA, AppListReq::INS_START, None) => {
+                self.list_index = 0;
+                self.list_apps()
+            }
+            (AppListReq::CLA, AppListReq::INS_NEXT, None) => self.list_apps(),
+            // Applications do not support dashboard instructions
+            (0xe0, _, Some(_)) => status(StatusCode::ClaNotSupported),
+            _ => status(StatusCode::InsNotSupported),
+        }
+    }
+
+    fn app_info(&self) -> Vec<u8> {
+        let r = match self.running {
+            Some(i) => {
+                let a = &self.config.apps[i];
+                AppInfoResp::new(&a.name, &a.version, a.flags.clone())
+            }
+            None => AppInfoResp::new("BOLOS", &self.config.se_version, AppFlags::empty()),
+        };
+
+        encode_ok(&r)
+    }
+
+    fn device_info(&self) -> Vec<u8> {
+        let c = &self.config;
+        let r = DeviceInfoResp::new(c.target_id, &c.se_version, &c.mcu_version, &c.flags);
+
+        encode_ok(&r)
+    }
+
+    fn run_app(&mut self, data: &[u8]) -> Vec<u8> {
+        let name = String::from_utf8_lossy(data);
+
+        match self.config.apps.iter().position(|a| a.name == name) {
+            Some(i) => {
+                self.running = Some(i);
+                status(StatusCode::Ok)
+            }
+            None => status(StatusCode::AppNotFound),
+        }
+    }
+
+    fn list_apps(&mut self) -> Vec<u8> {
+        let apps = &self.config.apps[self.list_index.min(self.config.apps.len())..];
+
+        // Status-only responses indicate the end of the list
+        if apps.is_empty() {
+            return status(StatusCode::Ok);
+        }
+
+        let mut entries = vec![];
+
+        for a in apps.iter().take(self.config.app_list_page.max(1)) {
+            let e = AppData {
+                name: &a.name,
+                flags: a.list_flags,
+                blocks: a.blocks,
+                hash_code_data: a.hash_code_data,
+                hash: a.hash,
+            };
+
+            let n = entries.len();
+            entries.resize(n + e.encode_len().unwrap(), 0);
+            e.encode(&mut entries[n..]).unwrap();
+
+            self.list_index += 1;
+        }
+
+        let r = AppListResp::new(&entries);
+        let mut resp = vec![0u8; r.encode_len().unwrap()];
+        r.encode(&mut resp).unwrap();
+
+        resp.extend_from_slice(&u16::from(StatusCode::Ok).to_be_bytes());
+        resp
+    }
+}
+
+/// Helper to build a status-only response
+fn status(v: impl Into<u16>) -> Vec<u8> {
+    v.into().to_be_bytes().to_vec()
+}
+
+/// Helper to encode a response APDU with an OK status
+fn encode_ok(r: &impl Encode) -> Vec<u8> {
+    let mut buff = [0u8; 256];
+
+    match r.encode(&mut buff) {
+        Ok(n) => {
+            let mut resp = buff[..n].to_vec();
+            resp.extend_from_slice(&u16::from(StatusCode::Ok).to_be_bytes());
+            resp
+        }
+        Err(_) => status(StatusCode::TechnicalProblem),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ledger_lib::{launch_app, Device, Filters, LaunchAppOpts, Transport, DEFAULT_TIMEOUT};
+    use ledger_proto::{apdus::RunAppError, GenericApdu};
+
+    use super::*;
+    use crate::{MockApp, MockTransport};
+
+    #[tokio::test]
+    async fn dashboard_info() {
+        let mut d = MockDevice::new(MockConfig::default());
+
+        let i = d.app_info(DEFAULT_TIMEOUT).await.unwrap();
+        assert_eq!(i.name, "BOLOS");
+        assert_eq!(i.version, "2.2.3");
+
+        let i = d.device_info(DEFAULT_TIMEOUT).await.unwrap();
+        assert_eq!(i.target_id, [0x33, 0x00, 0x00, 0x04]);
+        assert_eq!(i.se_version, "2.2.3");
+        assert_eq!(i.mcu_version, "2.30");
+    }
+
+    #[tokio::test]
+    async fn run_exit_app() {
+        let mut d = MockDevice::new(MockConfig::default());
+        let mut buff = [0u8; 256];
+
+        let r = d
+            .request::<GenericApdu>(RunAppReq::new("Ethereum"), &mut buff, DEFAULT_TIMEOUT)
+            .await;
+        assert!(matches!(r, Err(Error::Status(StatusCode::Ok))));
+        assert_eq!(d.running().as_deref(), Some("Ethereum"));
+
+        let i = d.app_info(DEFAULT_TIMEOUT).await.unwrap();
+        assert_eq!(i.name, "Ethereum");
+
+        // Dashboard instructions are rejected while an app is running
+        let r = d.device_info(DEFAULT_TIMEOUT).await;
+        assert!(matches!(r, Err(Error::Status(StatusCode::ClaNotSupported))));
+
+        let r = d
+            .request::<GenericApdu>(ExitAppReq::new(), &mut buff, DEFAULT_TIMEOUT)
+            .await;
+        assert!(matches!(r, Err(Error::Status(StatusCode::Ok))));
+        assert_eq!(d.running(), None);
+
+        // Unknown apps are not found
+        let r = d
+            .request::<GenericApdu>(RunAppReq::new("Unknown"), &mut buff, DEFAULT_TIMEOUT)
+            .await;
+        assert!(matches!(r, Err(Error::Status(StatusCode::AppNotFound))));
+    }
+
+    #[tokio::test]
+    async fn list_apps() {
+        let mut config = MockConfig::default();
+        config.apps.push(MockApp::new("Solana", "1.4.0"));
+
+        let mut d = MockDevice::new(config);
+
+        let apps = d.list_apps(DEFAULT_TIMEOUT).await.unwrap();
+        let names: Vec<_> = apps.iter().map(|a| a.name.as_str()).collect();
+
+        assert_eq!(&names, &["Bitcoin", "Ethereum", "Solana"]);
+    }
+
+    #[tokio::test]
+    async fn inject_faults() {
+        let mut d = MockDevice::new(MockConfig::default());
+
+        d.inject_for(RunAppReq::INS, Fault::Timeout);
+        d.inject(Fault::Status(StatusCode::UserRefusedOnDevice.into()));
+
+        // Unmatched instruction faults are skipped
+        let r = d.app_info(DEFAULT_TIMEOUT).await;
+        assert!(matches!(
+            r,
+            Err(Error::Status(StatusCode::UserRefusedOnDevice))
+        ));
+
+        // Faults are consumed once applied
+        assert!(d.device_info(DEFAULT_TIMEOUT).await.is_ok());
+
+        let r = d
+            .request::<GenericApdu>(RunAppReq::new("Bitcoin"), &mut [0u8; 256], DEFAULT_TIMEOUT)
+            .await;
+        assert!(matches!(r, Err(Error::Timeout)));
+        assert_eq!(d.running(), None);
+
+        d.set_locked(true);
+        let r = d.app_info(DEFAULT_TIMEOUT).await;
+        assert!(matches!(r, Err(Error::Status(StatusCode::LockedDevice))));
+    }
+
+    #[tokio::test]
+    async fn mock_launch_app() {
+        let d = MockDevice::new(MockConfig::default());
+        let mut t = MockTransport::new(d.clone());
+
+        // Start in another application to exercise exit and reconnect
+        let devices = t.list(Filters::Any).await.unwrap();
+        let mut h = t.connect(devices[0].clone()).await.unwrap();
+        let _ = h
+            .request::<GenericApdu>(RunAppReq::new("Bitcoin"), &mut [0u8; 256], DEFAULT_TIMEOUT)
+            .await;
+
+        let opts = LaunchAppOpts {
+            reconnect_delay_s: 0,
+            reconnect_timeout_s: 1,
+        };
+
+        let mut h = launch_app(t, devices[0].clone(), "Ethereum", &opts, DEFAULT_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert_eq!(d.running().as_deref(), Some("Ethereum"));
+        assert_eq!(h.app_info(DEFAULT_TIMEOUT).await.unwrap().name, "Ethereum");
+        drop(h);
+
+        // Launch failures decode to typed errors
+        let t = MockTransport::new(d.clone());
+        let r = launch_app(t, devices[0].clone(), "Unknown", &opts, DEFAULT_TIMEOUT).await;
+        assert!(matches!(r, Err(Error::RunApp(RunAppError::AppNotFound))));
+
+        d.inject_for(
+            RunAppReq::INS,
+            Fault::Status(StatusCode::UserRefusedOnDevice.into()),
+        );
+        let t = MockTransport::new(d.clone());
+        let r = launch_app(t, devices[0].clone(), "Bitcoin", &opts, DEFAULT_TIMEOUT).await;
+        assert!(matches!(r, Err(Error::RunApp(RunAppError::UserRefused))));
+    }
+}
@@ -0,0 +1,116 @@
+//! In-process Ledger device emulator, behaving like the BOLOS dashboard over the
+//! [Exchange](ledger_lib::Exchange) trait for testing application logic without hardware
+//! or a simulator.
+//!
+//! [MockDevice] responds to application info, device info, app list and run / exit app APDUs
+//! using a configurable [MockConfig] app table, with [Fault] injection for error paths.
+//! [MockTransport] provides a [Transport](ledger_lib::Transport) returning handles to a shared
+//! [MockDevice], for use with helpers such as [launch_app](ledger_lib::launch_app).
+//!
+//! ```
+//! use ledger_lib::{Device, DEFAULT_TIMEOUT};
+//! use ledger_mock::{MockConfig, MockDevice};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let mut d = MockDevice::new(MockConfig::default());
+//!
+//! let info = d.app_info(DEFAULT_TIMEOUT).await.unwrap();
+//! assert_eq!(info.name, "BOLOS");
+//! # }
+//! ```
+
+use ledger_lib::info::Model;
+use ledger_proto::apdus::AppFlags;
+
+mod device;
+pub use device::MockDevice;
+
+mod transport;
+pub use transport::MockTransport;
+
+/// Installed application configuration for [MockDevice]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockApp {
+    /// Application name
+    pub name: String,
+    /// Application version
+    pub version: String,
+    /// Application flags (reported via app info when running)
+    pub flags: AppFlags,
+    /// Application flags (reported via app list)
+    pub list_flags: u16,
+    /// Storage blocks used by the application
+    pub blocks: u16,
+    /// Application code hash
+    pub hash_code_data: [u8; 32],
+    /// Application full hash
+    pub hash: [u8; 32],
+}
+
+impl MockApp {
+    /// Create a new application with the provided name and version
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            flags: AppFlags::empty(),
+            list_flags: 0,
+            blocks: 1,
+            hash_code_data: [0u8; 32],
+            hash: [0u8; 32],
+        }
+    }
+}
+
+/// Device configuration for [MockDevice]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockConfig {
+    /// Device model (reported via [MockTransport])
+    pub model: Model,
+    /// Target ID
+    pub target_id: [u8; 4],
+    /// Secure element (OS) version
+    pub se_version: String,
+    /// MCU version
+    pub mcu_version: String,
+    /// Device flags
+    pub flags: Vec<u8>,
+    /// Installed applications
+    pub apps: Vec<MockApp>,
+    /// Maximum number of entries per app list response
+    pub app_list_page: usize,
+    /// Whether the device starts locked
+    pub locked: bool,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            model: Model::NanoX,
+            target_id: [0x33, 0x00, 0x00, 0x04],
+            se_version: "2.2.3".to_string(),
+            mcu_version: "2.30".to_string(),
+            flags: vec![0x00, 0x00, 0x00, 0x00],
+            apps: vec![
+                MockApp::new("Bitcoin", "2.1.3"),
+                MockApp::new("Ethereum", "1.10.3"),
+            ],
+            app_list_page: 2,
+            locked: false,
+        }
+    }
+}
+
+/// Faults injected into [MockDevice] exchanges
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Fault {
+    /// Respond with the provided status word
+    Status(u16),
+    /// Fail with [Error::Timeout](ledger_lib::Error::Timeout)
+    Timeout,
+    /// Fail with [Error::Closed](ledger_lib::Error::Closed)
+    Closed,
+    /// Fail with [Error::EmptyResponse](ledger_lib::Error::EmptyResponse)
+    EmptyResponse,
+}
@@ -0,0 +1,49 @@
+//! [MockTransport] implementation, listing and connecting to a shared [MockDevice]
+
+use tracing::debug;
+
+use ledger_lib::{info::LedgerInfo, transport::LoopbackInfo, Error, Filters, Transport};
+
+use crate::MockDevice;
+
+/// Mock [Transport], reporting a single (loopback) device backed by a shared [MockDevice]
+#[derive(Clone)]
+pub struct MockTransport {
+    device: MockDevice,
+}
+
+impl MockTransport {
+    /// Create a new transport for the provided device
+    pub fn new(device: MockDevice) -> Self {
+        Self { device }
+    }
+
+    /// Fetch the underlying device
+    pub fn device(&self) -> &MockDevice {
+        &self.device
+    }
+}
+
+impl Transport for MockTransport {
+    type Filters = Filters;
+    type Info = LedgerInfo;
+    type Device = MockDevice;
+
+    /// List the mock device where loopback devices are included by the filter
+    async fn list(&mut self, filters: Filters) -> Result<Vec<LedgerInfo>, Error> {
+        match filters {
+            Filters::Any | Filters::Loopback => Ok(vec![LedgerInfo {
+                model: self.device.model(),
+                conn: LoopbackInfo::default().into(),
+            }]),
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Connect to the mock device, returning a handle to the shared device state
+    async fn connect(&mut self, info: LedgerInfo) -> Result<MockDevice, Error> {
+        debug!("Connecting to mock device: {info}");
+
+        Ok(self.device.clone())
+    }
+}
@@ -0,0 +1,60 @@
+//! Benchmarks for shared APDU encoding and decoding
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use ledger_proto::{
+    apdus::{AppFlags, AppInfoResp, DeviceInfoResp, RunAppReq},
+    ApduHeader, Decode, DecodeOwned, Encode, GenericApdu,
+};
+
+fn encode(c: &mut Criterion) {
+    let mut buff = [0u8; 256];
+
+    c.bench_function("encode RunAppReq", |b| {
+        let req = RunAppReq::new("Ethereum");
+        b.iter(|| black_box(&req).encode(&mut buff).unwrap())
+    });
+
+    c.bench_function("encode GenericApdu", |b| {
+        let req = GenericApdu {
+            header: ApduHeader {
+                cla: 0xe0,
+                ins: 0x02,
+                p1: 0x00,
+                p2: 0x00,
+            },
+            data: vec![0xaa; 200],
+        };
+        b.iter(|| black_box(&req).encode(&mut buff).unwrap())
+    });
+}
+
+fn decode(c: &mut Criterion) {
+    let mut buff = [0u8; 256];
+
+    let n = AppInfoResp::new("Ethereum", "1.10.3", AppFlags::empty())
+        .encode(&mut buff)
+        .unwrap();
+    let app_info = buff[..n].to_vec();
+
+    c.bench_function("decode AppInfoResp", |b| {
+        b.iter(|| AppInfoResp::decode(black_box(&app_info)).unwrap())
+    });
+
+    let n = DeviceInfoResp::new([0x33, 0x10, 0x00, 0x04], "1.1.1", "4.03", &[0x00; 4])
+        .encode(&mut buff)
+        .unwrap();
+    let device_info = buff[..n].to_vec();
+
+    c.bench_function("decode DeviceInfoResp", |b| {
+        b.iter(|| DeviceInfoResp::decode(black_box(&device_info)).unwrap())
+    });
+
+    let generic = [0xaa; 200];
+    c.bench_function("decode GenericApdu", |b| {
+        b.iter(|| GenericApdu::decode_owned(black_box(&generic)).unwrap())
+    });
+}
+
+criterion_group!(benches, encode, decode);
+criterion_main!(benches);
@@ -1,11 +1,17 @@
 //! Application information request and response APDUs
+//!
+//! Responses are reported using format `1` (name, version, flags) or format `2`, which
+//! appends additional length-prefixed fields (see [AppInfoResp::extension_fields]).
 
 use encdec::{Decode, Encode};
 
-use crate::{ApduError, ApduStatic};
+use crate::{field_bytes, prefixed_field, ApduError, ApduStatic};
 
 /// Application information request APDU
 #[derive(Clone, Debug, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[encdec(error = "ApduError")]
 pub struct AppInfoReq {}
 
@@ -20,6 +26,9 @@ impl ApduStatic for AppInfoReq {
 
 /// Application information response APDU
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct AppInfoResp<'a> {
     /// Application name
     pub name: &'a str,
@@ -27,12 +36,17 @@ pub struct AppInfoResp<'a> {
     pub version: &'a str,
     /// Application flags
     pub flags: AppFlags,
+    /// Additional length-prefixed fields reported by format `2` responses
+    /// (empty for format `1`)
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub extensions: &'a [u8],
 }
 
 bitflags::bitflags! {
     /// Application info flags
     #[derive(Clone, Debug, PartialEq)]
     #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
     pub struct AppFlags: u8 {
         /// Recovery mode
         const RECOVERY = 1 << 0;
@@ -51,6 +65,14 @@ bitflags::bitflags! {
     }
 }
 
+/// [defmt::Format] implementation for [AppFlags], bitflags internals do not implement this
+#[cfg(feature = "defmt")]
+impl defmt::Format for AppFlags {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "AppFlags({=u8:#x})", self.bits())
+    }
+}
+
 impl<'a> AppInfoResp<'a> {
     /// Create a new application version APDU
     pub fn new(name: &'a str, version: &'a str, flags: AppFlags) -> Self {
@@ -58,22 +80,68 @@ impl<'a> AppInfoResp<'a> {
             name,
             version,
             flags,
+            extensions: &[],
+        }
+    }
+
+    /// Set additional length-prefixed fields, encoded using format `2`
+    pub fn with_extensions(mut self, extensions: &'a [u8]) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Fetch the response format, `2` where extension fields are present
+    pub fn format(&self) -> u8 {
+        match self.extensions.is_empty() {
+            true => APP_VERSION_FMT,
+            false => APP_VERSION_FMT_V2,
         }
     }
+
+    /// Iterate over additional (format `2`) fields
+    pub fn extension_fields(&self) -> impl Iterator<Item = &'a [u8]> {
+        let mut b = self.extensions;
+        core::iter::from_fn(move || {
+            // Fields are validated on encode / decode, so failures here only end iteration
+            let (f, n) = prefixed_field(b, "extensions", 0).ok()?;
+            b = &b[n..];
+            Some(f)
+        })
+    }
 }
 
 const APP_VERSION_FMT: u8 = 1;
 
+const APP_VERSION_FMT_V2: u8 = 2;
+
+/// Check extension data consists of complete length-prefixed fields
+fn check_extensions(b: &[u8]) -> Result<(), ApduError> {
+    let mut index = 0;
+    while index < b.len() {
+        let (_, n) = prefixed_field(b, "extensions", index)?;
+        index += n;
+    }
+    Ok(())
+}
+
 impl<'a> Encode for AppInfoResp<'a> {
     type Error = ApduError;
 
     fn encode_len(&self) -> Result<usize, Self::Error> {
+        // Fields are length-prefixed with a single byte
+        if self.name.len() > u8::MAX as usize || self.version.len() > u8::MAX as usize {
+            return Err(ApduError::InvalidLength);
+        }
+
+        check_extensions(self.extensions)?;
+
         let mut len = 0;
 
         len += 1;
         len += 1 + self.name.len();
         len += 1 + self.version.len();
         len += 2;
+        len += self.extensions.len();
 
         Ok(len)
     }
@@ -84,7 +152,7 @@ impl<'a> Encode for AppInfoResp<'a> {
         }
 
         let mut index = 0;
-        buff[0] = APP_VERSION_FMT;
+        buff[0] = self.format();
         index += 1;
 
         buff[index] = self.name.len() as u8;
@@ -99,6 +167,9 @@ impl<'a> Encode for AppInfoResp<'a> {
         buff[index + 1] = self.flags.bits();
         index += 2;
 
+        buff[index..][..self.extensions.len()].copy_from_slice(self.extensions);
+        index += self.extensions.len();
+
         Ok(index)
     }
 }
@@ -112,38 +183,48 @@ impl<'a> Decode<'a> for AppInfoResp<'a> {
         let mut index = 0;
 
         // Check app version format
-        if buff[index] != APP_VERSION_FMT {
-            return Err(ApduError::InvalidVersion(buff[index]));
+        let format = field_bytes(buff, "format", index, 1)?[0];
+        if format != APP_VERSION_FMT && format != APP_VERSION_FMT_V2 {
+            return Err(ApduError::InvalidVersion(format));
         }
         index += 1;
 
         // Fetch name string
-        let name_len = buff[index] as usize;
-        let name = core::str::from_utf8(&buff[index + 1..][..name_len])
-            .map_err(|_| ApduError::InvalidUtf8)?;
-        index += 1 + name_len;
+        let (name, n) = prefixed_field(buff, "name", index)?;
+        let name = core::str::from_utf8(name).map_err(|_| ApduError::InvalidUtf8)?;
+        index += n;
 
         // Fetch version string
-        let version_len = buff[index] as usize;
-        let version = core::str::from_utf8(&buff[index + 1..][..version_len])
-            .map_err(|_| ApduError::InvalidUtf8)?;
-        index += 1 + version_len;
+        let (version, n) = prefixed_field(buff, "version", index)?;
+        let version = core::str::from_utf8(version).map_err(|_| ApduError::InvalidUtf8)?;
+        index += n;
 
         // Fetch flags (if available)
         let flags = if buff.len() > index {
-            let flags_len = buff[index];
-            let flags = AppFlags::from_bits_truncate(buff[index + 1]);
-            index += 1 + flags_len as usize;
-            flags
+            let (flags, n) = prefixed_field(buff, "flags", index)?;
+            index += n;
+            AppFlags::from_bits_truncate(flags.first().copied().unwrap_or(0))
         } else {
             AppFlags::empty()
         };
 
+        // Fetch additional fields (format 2)
+        let extensions = match format {
+            APP_VERSION_FMT_V2 => {
+                let e = &buff[index..];
+                check_extensions(e)?;
+                index += e.len();
+                e
+            }
+            _ => &[],
+        };
+
         Ok((
             Self {
                 name,
                 version,
                 flags,
+                extensions,
             },
             index,
         ))
@@ -159,6 +240,78 @@ mod tests {
         let r = AppInfoResp::new("test name", "test version", AppFlags::ONBOARDED);
 
         let mut buff = [0u8; 256];
-        crate::tests::encode_decode(&mut buff, r);
+        crate::testing::encode_decode(&mut buff, r);
+    }
+
+    #[test]
+    fn app_info_resp_v2() {
+        let ext = [0x02, 0xaa, 0xbb, 0x00, 0x01, 0xcc];
+        let r = AppInfoResp::new("BTC", "1.0", AppFlags::SIGNED).with_extensions(&ext);
+        assert_eq!(r.format(), 2);
+
+        let mut buff = [0u8; 64];
+        let n = r.encode(&mut buff).unwrap();
+        assert_eq!(
+            &buff[..n],
+            &[
+                0x02, 0x03, 0x42, 0x54, 0x43, 0x03, 0x31, 0x2e, 0x30, 0x01, 0x02, 0x02, 0xaa, 0xbb,
+                0x00, 0x01, 0xcc
+            ]
+        );
+
+        let (d, m) = AppInfoResp::decode(&buff[..n]).unwrap();
+        assert_eq!((m, &d), (n, &r));
+
+        let f: Vec<&[u8]> = d.extension_fields().collect();
+        assert_eq!(f, [&[0xaa, 0xbb][..], &[], &[0xcc]]);
+
+        // Truncated extension fields are rejected
+        assert!(AppInfoResp::decode(&buff[..n - 1]).is_err());
+        assert!(r.with_extensions(&[0x02, 0xaa]).encode_len().is_err());
+
+        // Unknown formats are rejected
+        buff[0] = 0x03;
+        assert_eq!(
+            AppInfoResp::decode(&buff[..n]),
+            Err(ApduError::InvalidVersion(0x03))
+        );
+    }
+    /// Regression cases from `apdu_decode` fuzzing, short or corrupted responses
+    /// must return errors rather than panicking
+    #[test]
+    fn app_info_resp_malformed() {
+        let r = AppInfoResp::new("BTC", "1.0", AppFlags::SIGNED).with_extensions(&[0x01, 0xaa]);
+
+        let mut buff = [0u8; 64];
+        let n = r.encode(&mut buff).unwrap();
+
+        // Truncations within the name and version fields are rejected
+        for i in 0..9 {
+            assert!(AppInfoResp::decode(&buff[..i]).is_err(), "len {i}");
+        }
+        // Truncated flags or extensions are rejected
+        for i in [10, n - 1] {
+            assert!(AppInfoResp::decode(&buff[..i]).is_err(), "len {i}");
+        }
+
+        // Oversized length prefixes are rejected
+        for i in [1, 5, 9, 11] {
+            let mut b = buff;
+            b[i] = 0xff;
+            assert!(AppInfoResp::decode(&b[..n]).unwrap_err().is_length());
+        }
+
+        for b in [
+            &[0x01][..],
+            &[0x01, 0xff, 0x41],
+            &[0x01, 0x00, 0x02, 0x31],
+            &[0x02, 0x00, 0x00, 0x00, 0x05],
+        ] {
+            assert!(AppInfoResp::decode(b).unwrap_err().is_length());
+        }
+
+        // Empty flags are accepted
+        let (d, _) = AppInfoResp::decode(&[0x01, 0x00, 0x00, 0x00]).unwrap();
+        assert_eq!(d.flags, AppFlags::empty());
     }
 }
@@ -0,0 +1,527 @@
+//! Application list request and response APDUs, listing installed applications
+//! from the BOLOS dashboard
+
+use encdec::{Decode, Encode};
+
+use crate::{prefixed_field, ApduError, ApduHeader, ApduReq};
+
+/// Application list request APDU.
+///
+/// The first request starts the listing, following requests fetch further entries
+/// until a status-only (empty) response is returned.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct AppListReq {
+    /// Continue an existing listing (selects the instruction, not encoded in the APDU body)
+    pub next: bool,
+}
+
+impl AppListReq {
+    /// App list request APDU class
+    pub const CLA: u8 = 0xe0;
+
+    /// App list instruction for the first request
+    pub const INS_START: u8 = 0xde;
+
+    /// App list instruction for following requests
+    pub const INS_NEXT: u8 = 0xdf;
+
+    /// Create a new app list request APDU
+    pub fn new(next: bool) -> Self {
+        Self { next }
+    }
+}
+
+/// [ApduReq] implementation for [AppListReq], instruction varies with listing position
+impl<'a> ApduReq<'a> for AppListReq {
+    fn header(&self) -> ApduHeader {
+        ApduHeader {
+            cla: Self::CLA,
+            ins: match self.next {
+                true => Self::INS_NEXT,
+                false => Self::INS_START,
+            },
+            p1: 0x00,
+            p2: 0x00,
+        }
+    }
+}
+
+/// [Encode] implementation for [AppListReq], the request has no body
+impl Encode for AppListReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
+    fn encode(&self, _buff: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+}
+
+/// [Decode] implementation for [AppListReq], the listing position is carried in the header
+/// so decodes as [Default]
+impl<'a> Decode<'a> for AppListReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(_buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((Self::default(), 0))
+    }
+}
+
+/// Installed application entry, encoded with a length prefix within [AppListResp]
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct AppData<'a> {
+    /// Application name
+    pub name: &'a str,
+    /// Application flags
+    pub flags: u16,
+    /// Storage blocks used by the application
+    pub blocks: u16,
+    /// Application code hash
+    pub hash_code_data: [u8; 32],
+    /// Application full hash
+    pub hash: [u8; 32],
+}
+
+/// Fixed [AppData] field length: blocks (2), flags (2), code hash (32), full hash (32), name length (1)
+const APP_DATA_FIXED_LEN: usize = 69;
+
+impl<'a> Encode for AppData<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1 + APP_DATA_FIXED_LEN + self.name.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if n - 1 > u8::MAX as usize || buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = (n - 1) as u8;
+        buff[1..3].copy_from_slice(&self.blocks.to_be_bytes());
+        buff[3..5].copy_from_slice(&self.flags.to_be_bytes());
+        buff[5..37].copy_from_slice(&self.hash_code_data);
+        buff[37..69].copy_from_slice(&self.hash);
+        buff[69] = self.name.len() as u8;
+        buff[70..n].copy_from_slice(self.name.as_bytes());
+
+        Ok(n)
+    }
+}
+
+impl<'a> Decode<'a> for AppData<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        // Entry length prefix, followed by entry data
+        let (d, n) = prefixed_field(buff, "entry", 0)?;
+        let len = n - 1;
+
+        if d.len() < APP_DATA_FIXED_LEN {
+            return Err(ApduError::InvalidFieldLength {
+                field: "entry",
+                offset: 1,
+                needed: APP_DATA_FIXED_LEN,
+                available: d.len(),
+            });
+        }
+
+        let mut hash_code_data = [0u8; 32];
+        hash_code_data.copy_from_slice(&d[4..36]);
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&d[36..68]);
+
+        let (name, _) = prefixed_field(d, "name", APP_DATA_FIXED_LEN - 1)?;
+        let name = core::str::from_utf8(name).map_err(|_| ApduError::InvalidUtf8)?;
+
+        Ok((
+            Self {
+                name,
+                flags: u16::from_be_bytes([d[2], d[3]]),
+                blocks: u16::from_be_bytes([d[0], d[1]]),
+                hash_code_data,
+                hash,
+            },
+            1 + len,
+        ))
+    }
+}
+
+/// Application list response APDU, containing encoded [AppData] entries.
+///
+/// ```
+/// use ledger_proto::{apdus::{AppData, AppListResp}, Decode, Encode};
+///
+/// let app = AppData { name: "Bitcoin", flags: 0, blocks: 45, hash_code_data: [0; 32], hash: [0; 32] };
+///
+/// let mut entries = [0u8; 128];
+/// let n = app.encode(&mut entries).unwrap();
+///
+/// let mut buff = [0u8; 128];
+/// let n = AppListResp::new(&entries[..n]).encode(&mut buff).unwrap();
+///
+/// let (r, _) = AppListResp::decode(&buff[..n]).unwrap();
+/// assert_eq!(r.iter().collect::<Vec<_>>(), &[app]);
+/// ```
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AppListResp<'a> {
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    entries: &'a [u8],
+}
+
+impl<'a> AppListResp<'a> {
+    /// App list response format version
+    pub const FORMAT: u8 = 0x01;
+
+    /// Create an app list response from encoded [AppData] entries
+    pub fn new(entries: &'a [u8]) -> Self {
+        Self { entries }
+    }
+
+    /// Check whether the response is empty (indicating the end of the list)
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over application entries
+    pub fn iter(&self) -> AppListIter<'a> {
+        AppListIter {
+            entries: self.entries,
+        }
+    }
+}
+
+/// [arbitrary::Arbitrary] implementation for [AppListResp], truncating entries to
+/// the longest valid prefix so generated responses are structurally valid
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for AppListResp<'a> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let entries = <&'a [u8]>::arbitrary(u)?;
+
+        let mut index = 0;
+        while let Ok((_, n)) = AppData::decode(&entries[index..]) {
+            index += n;
+        }
+
+        Ok(Self::new(&entries[..index]))
+    }
+}
+
+impl<'a> IntoIterator for &AppListResp<'a> {
+    type Item = AppData<'a>;
+
+    type IntoIter = AppListIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over [AppListResp] entries
+#[derive(Clone, Debug)]
+pub struct AppListIter<'a> {
+    entries: &'a [u8],
+}
+
+impl<'a> Iterator for AppListIter<'a> {
+    type Item = AppData<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Entries are validated on decode, so failures here only end iteration
+        let (a, n) = AppData::decode(self.entries).ok()?;
+        self.entries = &self.entries[n..];
+
+        Some(a)
+    }
+}
+
+/// [Encode] implementation for [AppListResp], format byte followed by entries
+/// (or nothing for empty lists)
+impl<'a> Encode for AppListResp<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        match self.entries.is_empty() {
+            true => Ok(0),
+            false => Ok(1 + self.entries.len()),
+        }
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+        if n == 0 {
+            return Ok(0);
+        }
+
+        buff[0] = Self::FORMAT;
+        buff[1..n].copy_from_slice(self.entries);
+
+        Ok(n)
+    }
+}
+
+/// [Decode] implementation for [AppListResp], checks the format and that all entries are valid
+impl<'a> Decode<'a> for AppListResp<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let entries = match buff.split_first() {
+            None => return Ok((Self::default(), 0)),
+            Some((&Self::FORMAT, entries)) => entries,
+            Some((v, _)) => return Err(ApduError::InvalidVersion(*v)),
+        };
+
+        let mut index = 0;
+        while index < entries.len() {
+            let (_, n) = AppData::decode(&entries[index..])?;
+            index += n;
+        }
+
+        Ok((Self { entries }, buff.len()))
+    }
+}
+
+/// Application list session (enabled with `alloc` feature), tracking the listing
+/// position and accumulating entries across [AppListResp] APDUs.
+///
+/// ```
+/// use ledger_proto::apdus::{AppListReq, AppListResp, ListAppsSession};
+///
+/// let mut s = ListAppsSession::new();
+/// assert_eq!(s.next_req(), Some(AppListReq::new(false)));
+///
+/// // Exchange requests until an empty response completes the listing
+/// s.push(&AppListResp::default());
+/// assert_eq!(s.next_req(), None);
+/// assert!(s.finish().is_empty());
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ListAppsSession {
+    apps: crate::Vec<super::AppDataOwned>,
+    started: bool,
+    complete: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl ListAppsSession {
+    /// Create a new application list session
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fetch the next [AppListReq], or `None` once the listing is complete
+    pub fn next_req(&self) -> Option<AppListReq> {
+        match self.complete {
+            true => None,
+            false => Some(AppListReq::new(self.started)),
+        }
+    }
+
+    /// Accumulate entries from an [AppListResp], returning `true` once the
+    /// listing is complete (on receipt of an empty response)
+    pub fn push(&mut self, resp: &AppListResp) -> bool {
+        use super::ToOwnedApdu;
+
+        self.started = true;
+        if resp.is_empty() {
+            self.complete = true;
+        }
+        self.apps.extend(resp.iter().map(|a| a.to_owned_apdu()));
+
+        self.complete
+    }
+
+    /// Decode and accumulate entries from response data (excluding the status word),
+    /// see [ListAppsSession::push]
+    pub fn push_data(&mut self, data: &[u8]) -> Result<bool, ApduError> {
+        let (r, _) = AppListResp::decode(data)?;
+        Ok(self.push(&r))
+    }
+
+    /// Check whether the listing is complete
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Fetch entries accumulated so far
+    pub fn apps(&self) -> &[super::AppDataOwned] {
+        &self.apps
+    }
+
+    /// Complete the session, returning accumulated entries
+    pub fn finish(self) -> crate::Vec<super::AppDataOwned> {
+        self.apps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_list_req() {
+        assert_eq!(AppListReq::new(false).header().ins, 0xde);
+        assert_eq!(AppListReq::new(true).header().ins, 0xdf);
+    }
+
+    #[test]
+    fn encode_decode_app_list() {
+        let apps = [
+            AppData {
+                name: "Bitcoin",
+                flags: 0x0a50,
+                blocks: 45,
+                hash_code_data: [0x11; 32],
+                hash: [0x22; 32],
+            },
+            AppData {
+                name: "Ethereum",
+                flags: 0x0a50,
+                blocks: 120,
+                hash_code_data: [0x33; 32],
+                hash: [0x44; 32],
+            },
+        ];
+
+        let mut entries = [0u8; 256];
+        let mut n = 0;
+        for a in &apps {
+            n += a.encode(&mut entries[n..]).unwrap();
+        }
+
+        let mut buff = [0u8; 256];
+        crate::testing::encode_decode(&mut buff, AppListResp::new(&entries[..n]));
+
+        let (r, _) = AppListResp::decode(&buff[..1 + n]).unwrap();
+        assert!(r.iter().eq(apps));
+
+        // Truncated entries and unknown formats are rejected
+        assert!(AppListResp::decode(&buff[..n]).is_err());
+        assert!(matches!(
+            AppListResp::decode(&[0x02]),
+            Err(ApduError::InvalidVersion(0x02))
+        ));
+
+        // Empty responses contain no entries
+        let (r, _) = AppListResp::decode(&[]).unwrap();
+        assert!(r.is_empty());
+        assert_eq!(r.iter().count(), 0);
+    }
+
+    #[test]
+    fn app_data_malformed() {
+        let app = AppData {
+            name: "Bitcoin",
+            flags: 0x0a50,
+            blocks: 45,
+            hash_code_data: [0x11; 32],
+            hash: [0x22; 32],
+        };
+
+        let mut buff = [0u8; 128];
+        let n = app.encode(&mut buff).unwrap();
+
+        // Truncated entries are rejected at every length
+        for i in 0..n {
+            assert!(AppData::decode(&buff[..i]).unwrap_err().is_length());
+        }
+
+        // Entries too short for the fixed fields
+        assert_eq!(
+            AppData::decode(&[0x02, 0x00, 0x01]),
+            Err(ApduError::InvalidFieldLength {
+                field: "entry",
+                offset: 1,
+                needed: APP_DATA_FIXED_LEN,
+                available: 2,
+            })
+        );
+
+        // Name prefix exceeding the entry
+        let mut b = buff;
+        b[APP_DATA_FIXED_LEN] = 0x20;
+        assert!(AppData::decode(&b[..n]).unwrap_err().is_length());
+
+        // Non-UTF8 names
+        let mut b = buff;
+        b[APP_DATA_FIXED_LEN + 1] = 0xff;
+        assert_eq!(AppData::decode(&b[..n]), Err(ApduError::InvalidUtf8));
+
+        // Owned decoding surfaces the same errors
+        #[cfg(feature = "alloc")]
+        {
+            use crate::{apdus::AppDataOwned, DecodeOwned};
+
+            let (a, m) = AppDataOwned::decode_owned(&buff[..n]).unwrap();
+            assert_eq!((a.as_borrowed(), m), (app, n));
+
+            assert!(AppDataOwned::decode_owned(&buff[..n - 1])
+                .unwrap_err()
+                .is_length());
+            assert_eq!(
+                AppDataOwned::decode_owned(&b[..n]),
+                Err(ApduError::InvalidUtf8)
+            );
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn list_apps_session() {
+        let app = |name| AppData {
+            name,
+            flags: 0x0a50,
+            blocks: 45,
+            hash_code_data: [0x11; 32],
+            hash: [0x22; 32],
+        };
+
+        let mut s = ListAppsSession::new();
+        assert_eq!(s.next_req(), Some(AppListReq::new(false)));
+
+        // Entries are accumulated across responses
+        for name in ["Bitcoin", "Ethereum"] {
+            let mut buff = [0u8; 128];
+            buff[0] = AppListResp::FORMAT;
+            let n = app(name).encode(&mut buff[1..]).unwrap();
+
+            assert_eq!(s.push_data(&buff[..1 + n]), Ok(false));
+            assert_eq!(s.next_req(), Some(AppListReq::new(true)));
+        }
+        assert_eq!(s.apps().len(), 2);
+
+        // Invalid responses are rejected without completing the session
+        assert!(s.push_data(&[0x02]).is_err());
+        assert!(!s.is_complete());
+
+        // Empty responses complete the session
+        assert_eq!(s.push_data(&[]), Ok(true));
+        assert_eq!(s.next_req(), None);
+
+        let apps = s.finish();
+        assert_eq!(apps[0].as_borrowed(), app("Bitcoin"));
+        assert_eq!(apps[1].name, "Ethereum");
+    }
+}
@@ -0,0 +1,254 @@
+//! Battery status request and response APDUs (Nano X and Stax)
+
+use encdec::{Decode, Encode};
+
+use crate::{ApduError, ApduStatic};
+
+/// Battery status request APDU, fetching the selected [BatteryStatusKind]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GetBatteryStatusReq {
+    /// Requested status kind (sent as P2, not encoded in the APDU body)
+    pub kind: BatteryStatusKind,
+}
+
+/// Battery status kinds, selecting the value returned by [GetBatteryStatusReq]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum BatteryStatusKind {
+    /// Charge percentage
+    #[default]
+    Percentage = 0x00,
+    /// Voltage in millivolts
+    Voltage = 0x01,
+    /// Temperature in degrees celsius
+    Temperature = 0x02,
+    /// Current in milliamps
+    Current = 0x03,
+    /// Battery and charging flags
+    Flags = 0x0f,
+}
+
+impl GetBatteryStatusReq {
+    /// Create a new battery status request APDU
+    pub fn new(kind: BatteryStatusKind) -> Self {
+        Self { kind }
+    }
+}
+
+/// Set CLA and INS values for [GetBatteryStatusReq]
+impl ApduStatic for GetBatteryStatusReq {
+    /// Battery status request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Battery status request APDU is instruction `0x10`
+    const INS: u8 = 0x10;
+
+    /// Status kind is selected via P2
+    fn p2(&self) -> u8 {
+        self.kind as u8
+    }
+}
+
+/// [Encode] implementation for [GetBatteryStatusReq], the request has no body
+impl Encode for GetBatteryStatusReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
+    fn encode(&self, _buff: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+}
+
+/// [Decode] implementation for [GetBatteryStatusReq], the status kind is carried
+/// in the header so decodes as [Default]
+impl<'a> Decode<'a> for GetBatteryStatusReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(_buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((Self::default(), 0))
+    }
+}
+
+/// Battery status response APDU, containing the raw value for the requested
+/// kind (see [GetBatteryStatusResp::status] for typed access)
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GetBatteryStatusResp<'a> {
+    /// Raw status value
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub data: &'a [u8],
+}
+
+bitflags::bitflags! {
+    /// Battery status flags
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+    pub struct BatteryFlags: u32 {
+        /// Battery charging
+        const CHARGING = 1 << 0;
+        /// USB connected
+        const USB = 1 << 1;
+        /// Powered via USB
+        const USB_POWERED = 1 << 2;
+        /// Battery issue
+        const ISSUE_BATTERY = 1 << 3;
+        /// Charging issue
+        const ISSUE_CHARGING = 1 << 4;
+        /// Temperature issue
+        const ISSUE_TEMPERATURE = 1 << 5;
+    }
+}
+
+/// [defmt::Format] implementation for [BatteryFlags], bitflags internals do not implement this
+#[cfg(feature = "defmt")]
+impl defmt::Format for BatteryFlags {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "BatteryFlags({=u32:#x})", self.bits())
+    }
+}
+
+/// Typed battery status values
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum BatteryStatus {
+    /// Charge percentage (`None` where unavailable)
+    Percentage(Option<u8>),
+    /// Voltage in millivolts
+    Voltage(u16),
+    /// Temperature in degrees celsius
+    Temperature(i8),
+    /// Current in milliamps
+    Current(i8),
+    /// Battery and charging flags
+    Flags(BatteryFlags),
+}
+
+impl<'a> GetBatteryStatusResp<'a> {
+    /// Create a new battery status response APDU
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Parse the typed status value for the requested kind
+    pub fn status(&self, kind: BatteryStatusKind) -> Result<BatteryStatus, ApduError> {
+        let s = match (kind, self.data) {
+            (BatteryStatusKind::Percentage, [v, ..]) => {
+                BatteryStatus::Percentage((*v <= 100).then_some(*v))
+            }
+            (BatteryStatusKind::Voltage, [a, b, ..]) => {
+                BatteryStatus::Voltage(u16::from_be_bytes([*a, *b]))
+            }
+            (BatteryStatusKind::Temperature, [v, ..]) => BatteryStatus::Temperature(*v as i8),
+            (BatteryStatusKind::Current, [v, ..]) => BatteryStatus::Current(*v as i8),
+            (BatteryStatusKind::Flags, [a, b, c, d, ..]) => {
+                BatteryStatus::Flags(BatteryFlags::from_bits_retain(u32::from_be_bytes([
+                    *a, *b, *c, *d,
+                ])))
+            }
+            _ => return Err(ApduError::InvalidLength),
+        };
+
+        Ok(s)
+    }
+}
+
+impl<'a> Encode for GetBatteryStatusResp<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.data.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.data.len() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[..self.data.len()].copy_from_slice(self.data);
+
+        Ok(self.data.len())
+    }
+}
+
+impl<'a> Decode<'a> for GetBatteryStatusResp<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        if buff.is_empty() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        Ok((Self { data: buff }, buff.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApduReq;
+
+    #[test]
+    fn battery_status_req() {
+        let r = GetBatteryStatusReq::new(BatteryStatusKind::Flags);
+        let h = r.header();
+
+        assert_eq!((h.cla, h.ins, h.p1, h.p2), (0xe0, 0x10, 0x00, 0x0f));
+        assert_eq!(r.encode_len().unwrap(), 0);
+    }
+
+    #[test]
+    fn battery_status_resp() {
+        let r = GetBatteryStatusResp::new(&[0x0f, 0xa0]);
+
+        let mut buff = [0u8; 16];
+        crate::testing::encode_decode(&mut buff, r);
+
+        assert_eq!(
+            r.status(BatteryStatusKind::Voltage).unwrap(),
+            BatteryStatus::Voltage(4000)
+        );
+        assert_eq!(
+            GetBatteryStatusResp::new(&[0x55])
+                .status(BatteryStatusKind::Percentage)
+                .unwrap(),
+            BatteryStatus::Percentage(Some(85))
+        );
+        assert_eq!(
+            GetBatteryStatusResp::new(&[0xff])
+                .status(BatteryStatusKind::Percentage)
+                .unwrap(),
+            BatteryStatus::Percentage(None)
+        );
+        assert_eq!(
+            GetBatteryStatusResp::new(&[0xf6])
+                .status(BatteryStatusKind::Temperature)
+                .unwrap(),
+            BatteryStatus::Temperature(-10)
+        );
+        assert_eq!(
+            GetBatteryStatusResp::new(&[0x00, 0x00, 0x00, 0x03])
+                .status(BatteryStatusKind::Flags)
+                .unwrap(),
+            BatteryStatus::Flags(BatteryFlags::CHARGING | BatteryFlags::USB)
+        );
+        assert!(r.status(BatteryStatusKind::Flags).is_err());
+    }
+}
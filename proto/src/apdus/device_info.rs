@@ -2,10 +2,13 @@
 
 use encdec::{Decode, Encode};
 
-use crate::{ApduError, ApduStatic};
+use crate::{field_bytes, prefixed_field, ApduError, ApduStatic, Lv, LvStr};
 
 /// Device info APDU command
 #[derive(Copy, Clone, PartialEq, Debug, Default, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[encdec(error = "ApduError")]
 pub struct DeviceInfoReq {}
 
@@ -19,6 +22,9 @@ impl ApduStatic for DeviceInfoReq {
 
 /// Device info APDU response
 #[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct DeviceInfoResp<'a> {
     /// Target ID
     pub target_id: [u8; 4],
@@ -27,10 +33,32 @@ pub struct DeviceInfoResp<'a> {
     pub se_version: &'a str,
 
     /// Device Flag(s)
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
     pub flags: &'a [u8],
 
     /// MCU Version
     pub mcu_version: &'a str,
+
+    /// MCU bootloader version, reported by newer firmwares (empty where not provided)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "str::is_empty")
+    )]
+    pub bootloader_version: &'a str,
+
+    /// Hardware revision, reported by newer firmwares
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub hardware_revision: Option<u8>,
+
+    /// Language ID, reported by newer firmwares
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub language_id: Option<u8>,
 }
 
 impl<'a> DeviceInfoResp<'a> {
@@ -46,6 +74,42 @@ impl<'a> DeviceInfoResp<'a> {
             se_version,
             mcu_version,
             flags,
+            bootloader_version: "",
+            hardware_revision: None,
+            language_id: None,
+        }
+    }
+
+    /// Set the MCU bootloader version
+    pub fn with_bootloader_version(mut self, bootloader_version: &'a str) -> Self {
+        self.bootloader_version = bootloader_version;
+        self
+    }
+
+    /// Set the hardware revision
+    pub fn with_hardware_revision(mut self, hardware_revision: u8) -> Self {
+        self.hardware_revision = Some(hardware_revision);
+        self
+    }
+
+    /// Set the language ID
+    pub fn with_language_id(mut self, language_id: u8) -> Self {
+        self.language_id = Some(language_id);
+        self
+    }
+
+    /// Fetch the number of optional trailing fields to encode, omitting unset
+    /// fields following the last set field
+    fn trailing_fields(&self) -> usize {
+        match (
+            !self.bootloader_version.is_empty(),
+            self.hardware_revision.is_some(),
+            self.language_id.is_some(),
+        ) {
+            (_, _, true) => 3,
+            (_, true, false) => 2,
+            (true, false, false) => 1,
+            (false, false, false) => 0,
         }
     }
 }
@@ -66,20 +130,25 @@ impl<'a> Encode for DeviceInfoResp<'a> {
         buff[index..][..4].copy_from_slice(&self.target_id);
         index += 4;
 
-        // Write SE version
-        buff[index] = self.se_version.len() as u8;
-        buff[index + 1..][..self.se_version.len()].copy_from_slice(self.se_version.as_bytes());
-        index += 1 + self.se_version.len();
-
-        // Write flags
-        buff[index] = self.flags.len() as u8;
-        buff[index + 1..][..self.flags.len()].copy_from_slice(self.flags);
-        index += 1 + self.flags.len();
+        // Write SE version, flags and MCU version
+        index += LvStr(self.se_version).encode(&mut buff[index..])?;
+        index += Lv(self.flags).encode(&mut buff[index..])?;
+        index += LvStr(self.mcu_version).encode(&mut buff[index..])?;
 
-        // Write MCU version
-        buff[index] = self.mcu_version.len() as u8;
-        buff[index + 1..][..self.mcu_version.len()].copy_from_slice(self.mcu_version.as_bytes());
-        index += 1 + self.mcu_version.len();
+        // Write optional trailing fields
+        let hardware_revision = self.hardware_revision.map(|v| [v]);
+        let language_id = self.language_id.map(|v| [v]);
+        let trailing = [
+            self.bootloader_version.as_bytes(),
+            hardware_revision
+                .as_ref()
+                .map(|v| &v[..])
+                .unwrap_or_default(),
+            language_id.as_ref().map(|v| &v[..]).unwrap_or_default(),
+        ];
+        for f in &trailing[..self.trailing_fields()] {
+            index += Lv(f).encode(&mut buff[index..])?;
+        }
 
         Ok(index)
     }
@@ -88,9 +157,16 @@ impl<'a> Encode for DeviceInfoResp<'a> {
     fn encode_len(&self) -> Result<usize, ApduError> {
         let mut len = 4;
 
-        len += 1 + self.se_version.len();
-        len += 1 + self.flags.len();
-        len += 1 + self.mcu_version.len();
+        // Fields are length-prefixed with a single byte
+        len += LvStr(self.se_version).encode_len()?;
+        len += Lv(self.flags).encode_len()?;
+        len += LvStr(self.mcu_version).encode_len()?;
+
+        // Optional trailing fields, single byte values are encoded with a length prefix
+        len += match self.trailing_fields() {
+            0 => 0,
+            n => LvStr(self.bootloader_version).encode_len()? + 2 * (n - 1),
+        };
 
         Ok(len)
     }
@@ -101,40 +177,54 @@ impl<'a> Decode<'a> for DeviceInfoResp<'a> {
     type Error = ApduError;
 
     /// Decode an device info APDU from the provided buffer
+    ///
+    /// Optional trailing fields are decoded where present, with empty values treated as unset.
     fn decode(buff: &'a [u8]) -> Result<(Self, usize), ApduError> {
         let mut index = 0;
 
         // Fetch target id
         let mut target_id = [0u8; 4];
-        target_id.copy_from_slice(&buff[..4]);
+        target_id.copy_from_slice(field_bytes(buff, "target_id", index, 4)?);
         index += 4;
 
         // Fetch secure element version
-        let se_version_len = buff[index] as usize;
-        let se_version = core::str::from_utf8(&buff[index + 1..][..se_version_len])
-            .map_err(|_| ApduError::InvalidUtf8)?;
-        index += 1 + se_version_len;
+        let (se_version, n) = prefixed_field(buff, "se_version", index)?;
+        let se_version = core::str::from_utf8(se_version).map_err(|_| ApduError::InvalidUtf8)?;
+        index += n;
 
         // Fetch flags
-        let flags_len = buff[index] as usize;
-        let flags = &buff[index + 1..][..flags_len];
-        index += 1 + flags_len;
+        let (flags, n) = prefixed_field(buff, "flags", index)?;
+        index += n;
 
         // Fetch mcu version
-        let mcu_version_len = buff[index] as usize;
-        let mcu_version = core::str::from_utf8(&buff[index + 1..][..mcu_version_len])
-            .map_err(|_| ApduError::InvalidUtf8)?;
-        index += 1 + mcu_version_len;
-
-        Ok((
-            Self {
-                target_id,
-                se_version,
-                flags,
-                mcu_version,
-            },
-            index,
-        ))
+        let (mcu_version, n) = prefixed_field(buff, "mcu_version", index)?;
+        let mcu_version = core::str::from_utf8(mcu_version).map_err(|_| ApduError::InvalidUtf8)?;
+        index += n;
+
+        let mut r = Self::new(target_id, se_version, mcu_version, flags);
+
+        // Fetch optional bootloader version
+        if index < buff.len() {
+            let (v, n) = prefixed_field(buff, "bootloader_version", index)?;
+            r.bootloader_version = core::str::from_utf8(v).map_err(|_| ApduError::InvalidUtf8)?;
+            index += n;
+        }
+
+        // Fetch optional hardware revision
+        if index < buff.len() {
+            let (v, n) = prefixed_field(buff, "hardware_revision", index)?;
+            r.hardware_revision = v.first().copied();
+            index += n;
+        }
+
+        // Fetch optional language ID
+        if index < buff.len() {
+            let (v, n) = prefixed_field(buff, "language_id", index)?;
+            r.language_id = v.first().copied();
+            index += n;
+        }
+
+        Ok((r, index))
     }
 }
 
@@ -147,6 +237,100 @@ mod tests {
         let r = DeviceInfoResp::new([0x01, 0x02, 0x03, 0x04], "SOME SE", "SOME MCU", &[0xaa]);
 
         let mut buff = [0u8; 256];
-        crate::tests::encode_decode(&mut buff, r);
+        crate::testing::encode_decode(&mut buff, r);
+    }
+
+    #[test]
+    fn device_info_resp_trailing() {
+        let base = DeviceInfoResp::new([0x33, 0x10, 0x00, 0x04], "1.1.1", "5.24", &[0xa6]);
+        let mut buff = [0u8; 64];
+
+        // Short responses leave trailing fields unset
+        let n = base.encode(&mut buff).unwrap();
+        let (r, _) = DeviceInfoResp::decode(&buff[..n]).unwrap();
+        assert_eq!((r.bootloader_version, r.hardware_revision), ("", None));
+
+        // Unset fields preceding set fields are encoded as empty values
+        for r in [
+            base.with_bootloader_version("0.11"),
+            base.with_bootloader_version("0.11")
+                .with_hardware_revision(0x02),
+            base.with_language_id(0x01),
+            base.with_bootloader_version("0.11")
+                .with_hardware_revision(0x02)
+                .with_language_id(0x01),
+        ] {
+            crate::testing::encode_decode(&mut buff, r);
+        }
+
+        let r = base.with_language_id(0x01);
+        let n = r.encode(&mut buff).unwrap();
+        assert_eq!(&buff[n - 4..n], &[0x00, 0x00, 0x01, 0x01]);
+
+        // Truncated trailing fields are rejected
+        let r = base
+            .with_bootloader_version("0.11")
+            .with_hardware_revision(0x02);
+        let n = r.encode(&mut buff).unwrap();
+        assert_eq!(
+            DeviceInfoResp::decode(&buff[..n - 1]),
+            Err(ApduError::InvalidFieldLength {
+                field: "hardware_revision",
+                offset: n - 1,
+                needed: 1,
+                available: 0
+            })
+        );
+    }
+
+    #[test]
+    fn device_info_resp_truncated() {
+        let r = DeviceInfoResp::new([0x01, 0x02, 0x03, 0x04], "SOME SE", "SOME MCU", &[0xaa]);
+
+        let mut buff = [0u8; 256];
+        let n = r.encode(&mut buff).unwrap();
+
+        let e = DeviceInfoResp::decode(&buff[..n - 3]).unwrap_err();
+        assert!(matches!(
+            e,
+            ApduError::InvalidFieldLength {
+                field: "mcu_version",
+                offset: 15,
+                needed: 8,
+                available: 5
+            }
+        ));
+
+        assert!(DeviceInfoResp::decode(&buff[..2]).unwrap_err().is_length());
+    }
+
+    /// Regression cases from `apdu_decode` fuzzing, short or corrupted responses
+    /// must return errors rather than panicking
+    #[test]
+    fn device_info_resp_malformed() {
+        let r = DeviceInfoResp::new([0x01, 0x02, 0x03, 0x04], "SE", "MCU", &[0xaa]);
+
+        let mut buff = [0u8; 64];
+        let n = r.encode(&mut buff).unwrap();
+
+        // Every truncation is rejected
+        for i in 0..n {
+            assert!(DeviceInfoResp::decode(&buff[..i]).is_err(), "len {i}");
+        }
+
+        // Oversized length prefixes are rejected
+        for i in [4, 7, 9] {
+            let mut b = buff;
+            b[i] = 0xff;
+            assert!(DeviceInfoResp::decode(&b[..n]).unwrap_err().is_length());
+        }
+
+        for b in [
+            &[0x00, 0x00, 0x00, 0x00][..],
+            &[0x33, 0x00, 0x00, 0x04, 0xff],
+            &[0x33, 0x00, 0x00, 0x04, 0x00, 0x00, 0x80],
+        ] {
+            assert!(DeviceInfoResp::decode(b).unwrap_err().is_length());
+        }
     }
 }
@@ -8,6 +8,9 @@ use crate::{ApduError, ApduStatic};
 ///
 /// Note this is not supported by _all_ applications
 #[derive(Clone, Debug, PartialEq, Default, Encode, DecodeOwned)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[encdec(error = "ApduError")]
 pub struct ExitAppReq {}
 
@@ -0,0 +1,604 @@
+//! Genuine check and endorsement APDUs, implementing the device side of the
+//! secure channel used to attest a device was issued by Ledger.
+//!
+//! The genuine check flow is:
+//! 1. identify the device with [ValidateTargetIdReq]
+//! 2. exchange nonces with [InitAuthReq] / [InitAuthResp]
+//! 3. submit the host signer certificate chain with [ValidateCertReq]
+//! 4. fetch the device certificate chain with [GetCertReq] / [DeviceCert]
+//! 5. commit the secure channel with [MutualAuthReq]
+//!
+//! Verification of the device certificates against the Ledger issuer key is
+//! performed on the host (typically via the Ledger HSM).
+
+use encdec::{Decode, Encode};
+
+use crate::{field_bytes, prefixed_field, ApduError, ApduStatic, Lv};
+
+/// Validate target ID request APDU, identifying the device prior to authentication
+#[derive(Copy, Clone, PartialEq, Debug, Default, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[encdec(error = "ApduError")]
+pub struct ValidateTargetIdReq {
+    /// Expected target ID (see [DeviceInfoResp](super::DeviceInfoResp))
+    pub target_id: [u8; 4],
+}
+
+impl ValidateTargetIdReq {
+    /// Create a new validate target ID request APDU
+    pub fn new(target_id: [u8; 4]) -> Self {
+        Self { target_id }
+    }
+}
+
+/// Set CLA and INS values for [ValidateTargetIdReq]
+impl ApduStatic for ValidateTargetIdReq {
+    /// Validate target ID request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Validate target ID request APDU is instruction `0x04`
+    const INS: u8 = 0x04;
+}
+
+/// Initialise authentication request APDU, providing the host nonce
+#[derive(Copy, Clone, PartialEq, Debug, Default, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[encdec(error = "ApduError")]
+pub struct InitAuthReq {
+    /// Host nonce
+    pub nonce: [u8; 8],
+}
+
+impl InitAuthReq {
+    /// Create a new initialise authentication request APDU
+    pub fn new(nonce: [u8; 8]) -> Self {
+        Self { nonce }
+    }
+}
+
+/// Set CLA and INS values for [InitAuthReq]
+impl ApduStatic for InitAuthReq {
+    /// Initialise authentication request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Initialise authentication request APDU is instruction `0x50`
+    const INS: u8 = 0x50;
+}
+
+/// Initialise authentication response APDU
+#[derive(Copy, Clone, PartialEq, Debug, Default, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[encdec(error = "ApduError")]
+pub struct InitAuthResp {
+    /// Serial of the batch signer key used to issue the device certificate
+    pub batch_serial: [u8; 4],
+    /// Device nonce
+    pub device_nonce: [u8; 8],
+}
+
+/// Validate certificate request APDU, submitting a host signer certificate.
+///
+/// Certificates are submitted root first, with the final (ephemeral) certificate
+/// flagged via P1 `0x80`.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ValidateCertReq<'a> {
+    /// Final certificate in the chain (sent as P1, not encoded in the APDU body)
+    pub last: bool,
+    /// Encoded certificate
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub cert: &'a [u8],
+}
+
+impl<'a> ValidateCertReq<'a> {
+    /// Create a new validate certificate request APDU
+    pub fn new(cert: &'a [u8], last: bool) -> Self {
+        Self { last, cert }
+    }
+}
+
+/// Set CLA and INS values for [ValidateCertReq]
+impl<'a> ApduStatic for ValidateCertReq<'a> {
+    /// Validate certificate request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Validate certificate request APDU is instruction `0x51`
+    const INS: u8 = 0x51;
+
+    /// Final certificate is flagged via P1
+    fn p1(&self) -> u8 {
+        match self.last {
+            true => 0x80,
+            false => 0x00,
+        }
+    }
+}
+
+impl<'a> Encode for ValidateCertReq<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.cert.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.cert.len() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[..self.cert.len()].copy_from_slice(self.cert);
+
+        Ok(self.cert.len())
+    }
+}
+
+/// [Decode] implementation for [ValidateCertReq], the final flag is carried in the header
+/// so is not decoded
+impl<'a> Decode<'a> for ValidateCertReq<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((
+            Self {
+                last: false,
+                cert: buff,
+            },
+            buff.len(),
+        ))
+    }
+}
+
+/// Get certificate request APDU, fetching device certificates following authentication.
+///
+/// The first request returns the device certificate issued by the batch signer, the
+/// next returns the ephemeral certificate issued by the device.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GetCertReq {
+    /// Fetch the next certificate in the chain (sent as P1, not encoded in the APDU body)
+    pub next: bool,
+}
+
+impl GetCertReq {
+    /// Create a new get certificate request APDU
+    pub fn new(next: bool) -> Self {
+        Self { next }
+    }
+}
+
+/// Set CLA and INS values for [GetCertReq]
+impl ApduStatic for GetCertReq {
+    /// Get certificate request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Get certificate request APDU is instruction `0x52`
+    const INS: u8 = 0x52;
+
+    /// Chain position is selected via P1
+    fn p1(&self) -> u8 {
+        match self.next {
+            true => 0x80,
+            false => 0x00,
+        }
+    }
+}
+
+/// [Encode] implementation for [GetCertReq], the request has no body
+impl Encode for GetCertReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
+    fn encode(&self, _buff: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+}
+
+/// [Decode] implementation for [GetCertReq], the chain position is carried in the header
+/// so decodes as [Default]
+impl<'a> Decode<'a> for GetCertReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(_buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((Self::default(), 0))
+    }
+}
+
+/// Device certificate, returned in response to [GetCertReq].
+///
+/// Encoded as length-prefixed header, public key, and signature fields, with an
+/// empty response indicating the end of the chain.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct DeviceCert<'a> {
+    /// Certificate header (role and serial)
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub header: &'a [u8],
+    /// Certified public key
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub public_key: &'a [u8],
+    /// Issuer signature over the header and public key
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub signature: &'a [u8],
+}
+
+impl<'a> DeviceCert<'a> {
+    /// Create a new device certificate
+    pub fn new(header: &'a [u8], public_key: &'a [u8], signature: &'a [u8]) -> Self {
+        Self {
+            header,
+            public_key,
+            signature,
+        }
+    }
+
+    /// Check whether the certificate is empty (indicating the end of the chain)
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty() && self.public_key.is_empty() && self.signature.is_empty()
+    }
+}
+
+impl<'a> Encode for DeviceCert<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+
+        Ok(3 + self.header.len() + self.public_key.len() + self.signature.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.encode_len()? {
+            return Err(ApduError::InvalidLength);
+        }
+        if self.is_empty() {
+            return Ok(0);
+        }
+
+        let mut index = 0;
+        for f in [self.header, self.public_key, self.signature] {
+            index += Lv(f).encode(&mut buff[index..])?;
+        }
+
+        Ok(index)
+    }
+}
+
+impl<'a> Decode<'a> for DeviceCert<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        if buff.is_empty() {
+            return Ok((Self::default(), 0));
+        }
+
+        let (header, n1) = prefixed_field(buff, "header", 0)?;
+        let (public_key, n2) = prefixed_field(buff, "public_key", n1)?;
+        let (signature, n3) = prefixed_field(buff, "signature", n1 + n2)?;
+
+        Ok((
+            Self {
+                header,
+                public_key,
+                signature,
+            },
+            n1 + n2 + n3,
+        ))
+    }
+}
+
+/// Mutual authentication request APDU, committing the secure channel once certificates
+/// have been exchanged.
+///
+/// Following a successful response, subsequent APDU bodies are wrapped using the session
+/// keys derived from the exchanged ephemeral keys.
+#[derive(Copy, Clone, PartialEq, Debug, Default, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[encdec(error = "ApduError")]
+pub struct MutualAuthReq {}
+
+impl MutualAuthReq {
+    /// Create a new mutual authentication request APDU
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Set CLA and INS values for [MutualAuthReq]
+impl ApduStatic for MutualAuthReq {
+    /// Mutual authentication request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Mutual authentication request APDU is instruction `0x53`
+    const INS: u8 = 0x53;
+}
+
+/// Endorsement key slots
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum EndorsementSlot {
+    /// Endorsement key 1, used for application attestation
+    #[default]
+    Key1 = 0x01,
+    /// Endorsement key 2, used for code attestation
+    Key2 = 0x02,
+}
+
+/// Create endorsement key request APDU, generating a new endorsement key in the
+/// selected slot.
+///
+/// This replaces any existing key (and certificate) in the slot, the returned
+/// public key must then be certified and committed with [CommitEndorsementReq].
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CreateEndorsementKeyReq {
+    /// Endorsement key slot (sent as P1, not encoded in the APDU body)
+    pub slot: EndorsementSlot,
+}
+
+impl CreateEndorsementKeyReq {
+    /// Create a new create endorsement key request APDU
+    pub fn new(slot: EndorsementSlot) -> Self {
+        Self { slot }
+    }
+}
+
+/// Set CLA and INS values for [CreateEndorsementKeyReq]
+impl ApduStatic for CreateEndorsementKeyReq {
+    /// Create endorsement key request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Create endorsement key request APDU is instruction `0xc0`
+    const INS: u8 = 0xc0;
+
+    /// Key slot is selected via P1
+    fn p1(&self) -> u8 {
+        self.slot as u8
+    }
+}
+
+/// [Encode] implementation for [CreateEndorsementKeyReq], the request has no body
+impl Encode for CreateEndorsementKeyReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
+    fn encode(&self, _buff: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+}
+
+/// [Decode] implementation for [CreateEndorsementKeyReq], the slot is carried in the header
+/// so decodes as [Default]
+impl<'a> Decode<'a> for CreateEndorsementKeyReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(_buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((Self::default(), 0))
+    }
+}
+
+/// Endorsement key response APDU
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct EndorsementKeyResp<'a> {
+    /// Uncompressed endorsement public key
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub public_key: &'a [u8],
+    /// Signature over the public key by the device key
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub signature: &'a [u8],
+}
+
+/// Uncompressed secp256k1 public key length
+pub const ENDORSEMENT_KEY_LEN: usize = 65;
+
+impl<'a> EndorsementKeyResp<'a> {
+    /// Create a new endorsement key response APDU
+    pub fn new(public_key: &'a [u8], signature: &'a [u8]) -> Self {
+        Self {
+            public_key,
+            signature,
+        }
+    }
+}
+
+impl<'a> Encode for EndorsementKeyResp<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.public_key.len() + self.signature.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if self.public_key.len() != ENDORSEMENT_KEY_LEN {
+            return Err(ApduError::InvalidEncoding);
+        }
+        if buff.len() < self.encode_len()? {
+            return Err(ApduError::InvalidLength);
+        }
+
+        let (k, s) = buff.split_at_mut(ENDORSEMENT_KEY_LEN);
+        k.copy_from_slice(self.public_key);
+        s[..self.signature.len()].copy_from_slice(self.signature);
+
+        self.encode_len()
+    }
+}
+
+impl<'a> Decode<'a> for EndorsementKeyResp<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        field_bytes(buff, "public_key", 0, ENDORSEMENT_KEY_LEN)?;
+
+        let (public_key, signature) = buff.split_at(ENDORSEMENT_KEY_LEN);
+
+        Ok((
+            Self {
+                public_key,
+                signature,
+            },
+            buff.len(),
+        ))
+    }
+}
+
+/// Commit endorsement request APDU, storing the issuer certificate for the most
+/// recently created endorsement key
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CommitEndorsementReq<'a> {
+    /// Issuer certificate (signature over the endorsement public key)
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub cert: &'a [u8],
+}
+
+impl<'a> CommitEndorsementReq<'a> {
+    /// Create a new commit endorsement request APDU
+    pub fn new(cert: &'a [u8]) -> Self {
+        Self { cert }
+    }
+}
+
+/// Set CLA and INS values for [CommitEndorsementReq]
+impl<'a> ApduStatic for CommitEndorsementReq<'a> {
+    /// Commit endorsement request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Commit endorsement request APDU is instruction `0xc2`
+    const INS: u8 = 0xc2;
+}
+
+impl<'a> Encode for CommitEndorsementReq<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.cert.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.cert.len() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[..self.cert.len()].copy_from_slice(self.cert);
+
+        Ok(self.cert.len())
+    }
+}
+
+impl<'a> Decode<'a> for CommitEndorsementReq<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((Self { cert: buff }, buff.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApduReq;
+
+    #[test]
+    fn genuine_reqs() {
+        let mut buff = [0u8; 32];
+        crate::testing::encode_decode(
+            &mut buff,
+            ValidateTargetIdReq::new([0x33, 0x10, 0x00, 0x04]),
+        );
+        crate::testing::encode_decode(&mut buff, InitAuthReq::new([0xaa; 8]));
+
+        let r = ValidateCertReq::new(&[0x01, 0x02], true);
+        assert_eq!(r.header().p1, 0x80);
+        assert_eq!(r.encode_len().unwrap(), 2);
+
+        let h = GetCertReq::new(true).header();
+        assert_eq!((h.cla, h.ins, h.p1), (0xe0, 0x52, 0x80));
+
+        crate::assert_golden!(req: MutualAuthReq::new(), &[0xe0, 0x53, 0x00, 0x00, 0x00]);
+
+        let h = CreateEndorsementKeyReq::new(EndorsementSlot::Key2).header();
+        assert_eq!((h.cla, h.ins, h.p1), (0xe0, 0xc0, 0x02));
+    }
+
+    #[test]
+    fn init_auth_resp() {
+        let r = InitAuthResp {
+            batch_serial: [0x00, 0x00, 0x00, 0x01],
+            device_nonce: [0xbb; 8],
+        };
+
+        let mut buff = [0u8; 32];
+        crate::testing::encode_decode(&mut buff, r);
+        assert_eq!(r.encode_len().unwrap(), 12);
+    }
+
+    #[test]
+    fn device_cert() {
+        let c = DeviceCert::new(&[0x02, 0x00], &[0x04; 65], &[0x30; 70]);
+
+        let mut buff = [0u8; 256];
+        crate::testing::encode_decode(&mut buff, c);
+
+        let n = c.encode(&mut buff).unwrap();
+        assert_eq!(&buff[..3], &[0x02, 0x02, 0x00]);
+        assert!(DeviceCert::decode(&buff[..n - 1]).is_err());
+
+        // Empty responses mark the end of the chain
+        let (d, _) = DeviceCert::decode(&[]).unwrap();
+        assert!(d.is_empty());
+    }
+
+    #[test]
+    fn endorsement_key_resp() {
+        let r = EndorsementKeyResp::new(&[0x04; 65], &[0x30; 70]);
+
+        let mut buff = [0u8; 256];
+        crate::testing::encode_decode(&mut buff, r);
+
+        assert!(EndorsementKeyResp::decode(&[0x04; 64]).is_err());
+    }
+}
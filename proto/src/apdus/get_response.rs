@@ -0,0 +1,147 @@
+//! GET RESPONSE APDU and follow-up handling for `0x61XX` (more data available)
+//! and `0x6CXX` (wrong Le) status words, as used by T=0 style applications
+
+use encdec::{Decode, Encode};
+
+use crate::{ApduError, ApduStatic};
+
+/// GET RESPONSE request APDU, fetching remaining response data following a
+/// `0x61XX` status
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GetResponseReq {
+    /// Expected response length (sent as Le, not encoded in the APDU body)
+    pub le: usize,
+}
+
+impl GetResponseReq {
+    /// Create a new GET RESPONSE request APDU for the provided length
+    pub fn new(le: usize) -> Self {
+        Self { le }
+    }
+}
+
+/// Default [GetResponseReq], requesting the maximum short response length
+impl Default for GetResponseReq {
+    fn default() -> Self {
+        Self { le: 256 }
+    }
+}
+
+/// Set CLA and INS values for [GetResponseReq]
+impl ApduStatic for GetResponseReq {
+    /// GET RESPONSE APDU is class `0x00`
+    const CLA: u8 = 0x00;
+
+    /// GET RESPONSE APDU is instruction `0xc0`
+    const INS: u8 = 0xc0;
+
+    /// Expected response length is passed as Le
+    fn le(&self) -> Option<usize> {
+        Some(self.le)
+    }
+}
+
+/// [Encode] implementation for [GetResponseReq], the body is empty
+impl Encode for GetResponseReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
+    fn encode(&self, _buff: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+}
+
+/// [Decode] implementation for [GetResponseReq], Le is not available from the body
+/// so this returns the default
+impl<'a> Decode<'a> for GetResponseReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(_buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((Self::default(), 0))
+    }
+}
+
+/// Follow-up actions for `0x61XX` and `0x6CXX` status words, see [FollowUp::from_status]
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum FollowUp {
+    /// More data available (`0x61XX`), fetch with the provided [GetResponseReq]
+    GetResponse(GetResponseReq),
+    /// Wrong Le (`0x6CXX`), re-send the previous request with the provided Le
+    Resend {
+        /// Expected response length for the re-sent request
+        le: usize,
+    },
+}
+
+impl FollowUp {
+    /// Compute the follow-up action for a status word, returning `None` where
+    /// no follow-up is required.
+    ///
+    /// Zero lengths (`0x6100` / `0x6c00`) indicate 256 bytes.
+    pub fn from_status(sw: u16) -> Option<Self> {
+        let le = match sw & 0xff {
+            0 => 256,
+            v => v as usize,
+        };
+
+        match sw >> 8 {
+            0x61 => Some(Self::GetResponse(GetResponseReq::new(le))),
+            0x6c => Some(Self::Resend { le }),
+            _ => None,
+        }
+    }
+
+    /// Fetch the expected response length for the follow-up request
+    pub fn le(&self) -> usize {
+        match self {
+            Self::GetResponse(r) => r.le,
+            Self::Resend { le } => *le,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApduReq;
+
+    #[test]
+    fn get_response_req() {
+        let r = GetResponseReq::new(0x20);
+        let h = r.header();
+        assert_eq!((h.cla, h.ins, h.p1, h.p2), (0x00, 0xc0, 0x00, 0x00));
+        assert_eq!(ApduReq::le(&r), Some(0x20));
+        assert_eq!(r.encode_len().unwrap(), 0);
+    }
+
+    #[test]
+    fn follow_up() {
+        assert_eq!(
+            FollowUp::from_status(0x6110),
+            Some(FollowUp::GetResponse(GetResponseReq::new(0x10)))
+        );
+        assert_eq!(
+            FollowUp::from_status(0x6100),
+            Some(FollowUp::GetResponse(GetResponseReq::new(256)))
+        );
+        assert_eq!(
+            FollowUp::from_status(0x6c08),
+            Some(FollowUp::Resend { le: 8 })
+        );
+        assert_eq!(FollowUp::from_status(0x6c00).map(|f| f.le()), Some(256));
+
+        assert_eq!(FollowUp::from_status(0x9000), None);
+        assert_eq!(FollowUp::from_status(0x6985), None);
+    }
+}
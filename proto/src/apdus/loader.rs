@@ -0,0 +1,597 @@
+//! Dashboard application loader APDUs, for installing and deleting applications.
+//!
+//! These are BOLOS loader commands (class `0xe0`, instruction `0x00`) identified by the
+//! leading command byte. Loader commands must be wrapped using a secure channel,
+//! established with the [genuine](super::InitAuthReq) APDUs (see the `scp` feature).
+//! A typical application install flow is:
+//!
+//! 1. open a secure channel with the device
+//! 2. allocate the application with [CreateAppReq]
+//! 3. for each segment, select the segment with [SelectSegmentReq] then write
+//!    application data with [LoadSegmentReq] chunks followed by [FlushReq]
+//! 4. commit the application with [CommitAppReq]
+//!
+//! Applications are removed with [DeleteAppReq] (by name) or [DeleteAppByHashReq].
+
+use encdec::{Decode, Encode};
+
+use super::mcu::decode_cmd;
+use crate::{field_bytes, prefixed_field, ApduError, ApduStatic, LvStr};
+
+/// Implement [ApduStatic] for loader commands (class `0xe0`, instruction `0x00`)
+macro_rules! loader_cmd {
+    ($t:ty, $name:literal) => {
+        #[doc = concat!("Set CLA and INS values for [", $name, "]")]
+        impl ApduStatic for $t {
+            /// Loader commands are class `0xe0`
+            const CLA: u8 = 0xe0;
+
+            /// Loader commands are instruction `0x00`
+            const INS: u8 = 0x00;
+        }
+    };
+}
+
+/// Create application request APDU (loader command byte `0x0b`), allocating
+/// space for a new application prior to loading
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CreateAppReq {
+    /// Target API level (omitted for legacy firmwares)
+    pub api_level: Option<u8>,
+    /// Application code length
+    pub code_len: u32,
+    /// Application data length
+    pub data_len: u32,
+    /// Install parameters length
+    pub params_len: u32,
+    /// Application flags
+    pub flags: u32,
+    /// Boot offset, relative to the application base
+    pub boot_offset: u32,
+}
+
+/// Encoded [CreateAppReq] length parameters, five big-endian `u32` values
+const CREATE_APP_PARAMS_LEN: usize = 20;
+
+impl CreateAppReq {
+    /// Loader command byte for create application requests
+    pub const CMD: u8 = 0x0b;
+
+    /// Create a new create application request APDU
+    pub fn new(
+        code_len: u32,
+        data_len: u32,
+        params_len: u32,
+        flags: u32,
+        boot_offset: u32,
+    ) -> Self {
+        Self {
+            api_level: None,
+            code_len,
+            data_len,
+            params_len,
+            flags,
+            boot_offset,
+        }
+    }
+
+    /// Set the target API level
+    pub fn with_api_level(mut self, api_level: u8) -> Self {
+        self.api_level = Some(api_level);
+        self
+    }
+}
+
+loader_cmd!(CreateAppReq, "CreateAppReq");
+
+impl Encode for CreateAppReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1 + self.api_level.map_or(0, |_| 1) + CREATE_APP_PARAMS_LEN)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        let mut index = 0;
+        buff[index] = Self::CMD;
+        index += 1;
+
+        if let Some(l) = self.api_level {
+            buff[index] = l;
+            index += 1;
+        }
+
+        for v in [
+            self.code_len,
+            self.data_len,
+            self.params_len,
+            self.flags,
+            self.boot_offset,
+        ] {
+            buff[index..][..4].copy_from_slice(&v.to_be_bytes());
+            index += 4;
+        }
+
+        Ok(index)
+    }
+}
+
+/// [Decode] implementation for [CreateAppReq], consuming the full buffer
+/// (as the API level is detected by length)
+impl<'a> Decode<'a> for CreateAppReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+
+        let (api_level, mut index) = match buff.len() {
+            n if n > 1 + CREATE_APP_PARAMS_LEN => (Some(buff[1]), 2),
+            _ => (None, 1),
+        };
+
+        let mut v = [0u32; 5];
+        for (i, f) in ["code_len", "data_len", "params_len", "flags", "boot_offset"]
+            .iter()
+            .enumerate()
+        {
+            let b = field_bytes(buff, f, index, 4)?;
+            v[i] = u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+            index += 4;
+        }
+
+        Ok((
+            Self {
+                api_level,
+                code_len: v[0],
+                data_len: v[1],
+                params_len: v[2],
+                flags: v[3],
+                boot_offset: v[4],
+            },
+            index,
+        ))
+    }
+}
+
+/// Select segment request APDU (loader command byte `0x05`), setting the base address
+/// for subsequent [LoadSegmentReq] writes
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct SelectSegmentReq {
+    /// Segment base address
+    pub addr: u32,
+}
+
+impl SelectSegmentReq {
+    /// Loader command byte for select segment requests
+    pub const CMD: u8 = 0x05;
+
+    /// Create a new select segment request APDU
+    pub fn new(addr: u32) -> Self {
+        Self { addr }
+    }
+}
+
+loader_cmd!(SelectSegmentReq, "SelectSegmentReq");
+
+impl Encode for SelectSegmentReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(5)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < 5 {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+        buff[1..5].copy_from_slice(&self.addr.to_be_bytes());
+
+        Ok(5)
+    }
+}
+
+impl<'a> Decode<'a> for SelectSegmentReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+
+        let a = field_bytes(buff, "addr", 1, 4)?;
+
+        Ok((Self::new(u32::from_be_bytes([a[0], a[1], a[2], a[3]])), 5))
+    }
+}
+
+/// Load segment request APDU (loader command byte `0x06`), writing a chunk of
+/// application data at an offset from the selected segment base
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct LoadSegmentReq<'a> {
+    /// Offset from the segment base
+    pub offset: u16,
+    /// Chunk data
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub data: &'a [u8],
+}
+
+impl<'a> LoadSegmentReq<'a> {
+    /// Loader command byte for load segment requests
+    pub const CMD: u8 = 0x06;
+
+    /// Create a new load segment request APDU
+    pub fn new(offset: u16, data: &'a [u8]) -> Self {
+        Self { offset, data }
+    }
+}
+
+loader_cmd!(LoadSegmentReq<'_>, "LoadSegmentReq");
+
+impl<'a> Encode for LoadSegmentReq<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(3 + self.data.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+        buff[1..3].copy_from_slice(&self.offset.to_be_bytes());
+        buff[3..n].copy_from_slice(self.data);
+
+        Ok(n)
+    }
+}
+
+/// [Decode] implementation for [LoadSegmentReq], consuming the full buffer
+impl<'a> Decode<'a> for LoadSegmentReq<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+
+        let o = field_bytes(buff, "offset", 1, 2)?;
+
+        Ok((
+            Self::new(u16::from_be_bytes([o[0], o[1]]), &buff[3..]),
+            buff.len(),
+        ))
+    }
+}
+
+/// Flush request APDU (loader command byte `0x07`), writing buffered segment
+/// data to flash
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct FlushReq {}
+
+impl FlushReq {
+    /// Loader command byte for flush requests
+    pub const CMD: u8 = 0x07;
+
+    /// Create a new flush request APDU
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+loader_cmd!(FlushReq, "FlushReq");
+
+impl Encode for FlushReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.is_empty() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+
+        Ok(1)
+    }
+}
+
+impl<'a> Decode<'a> for FlushReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+        Ok((Self {}, 1))
+    }
+}
+
+/// Commit application request APDU (loader command byte `0x09`), completing an
+/// install with an optional signature over the application hash
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct CommitAppReq<'a> {
+    /// Application signature (empty for unsigned applications)
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub signature: &'a [u8],
+}
+
+impl<'a> CommitAppReq<'a> {
+    /// Loader command byte for commit requests
+    pub const CMD: u8 = 0x09;
+
+    /// Create a new commit application request APDU
+    pub fn new(signature: &'a [u8]) -> Self {
+        Self { signature }
+    }
+}
+
+loader_cmd!(CommitAppReq<'_>, "CommitAppReq");
+
+impl<'a> Encode for CommitAppReq<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        // Signature is length-prefixed with a single byte
+        match self.signature.len() {
+            0 => Ok(1),
+            n if n <= u8::MAX as usize => Ok(2 + n),
+            _ => Err(ApduError::InvalidLength),
+        }
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+        if !self.signature.is_empty() {
+            buff[1] = self.signature.len() as u8;
+            buff[2..n].copy_from_slice(self.signature);
+        }
+
+        Ok(n)
+    }
+}
+
+impl<'a> Decode<'a> for CommitAppReq<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+
+        if buff.len() == 1 {
+            return Ok((Self::default(), 1));
+        }
+
+        let (signature, n) = prefixed_field(buff, "signature", 1)?;
+
+        Ok((Self { signature }, 1 + n))
+    }
+}
+
+/// Delete application request APDU (loader command byte `0x0c`), removing an
+/// installed application by name
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct DeleteAppReq<'a> {
+    /// Application name
+    pub name: &'a str,
+}
+
+impl<'a> DeleteAppReq<'a> {
+    /// Loader command byte for delete by name requests
+    pub const CMD: u8 = 0x0c;
+
+    /// Create a new delete application request APDU
+    pub fn new(name: &'a str) -> Self {
+        Self { name }
+    }
+}
+
+loader_cmd!(DeleteAppReq<'_>, "DeleteAppReq");
+
+impl<'a> Encode for DeleteAppReq<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1 + LvStr(self.name).encode_len()?)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+        LvStr(self.name).encode(&mut buff[1..])?;
+
+        Ok(n)
+    }
+}
+
+impl<'a> Decode<'a> for DeleteAppReq<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+
+        let (name, n) = prefixed_field(buff, "name", 1)?;
+        let name = core::str::from_utf8(name).map_err(|_| ApduError::InvalidUtf8)?;
+
+        Ok((Self { name }, 1 + n))
+    }
+}
+
+/// Delete application by hash request APDU (loader command byte `0x15`), removing an
+/// installed application by its (SHA-256) code hash
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct DeleteAppByHashReq {
+    /// Application hash
+    pub hash: [u8; 32],
+}
+
+impl DeleteAppByHashReq {
+    /// Loader command byte for delete by hash requests
+    pub const CMD: u8 = 0x15;
+
+    /// Create a new delete application by hash request APDU
+    pub fn new(hash: [u8; 32]) -> Self {
+        Self { hash }
+    }
+}
+
+loader_cmd!(DeleteAppByHashReq, "DeleteAppByHashReq");
+
+impl Encode for DeleteAppByHashReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1 + self.hash.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < 33 {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+        buff[1..33].copy_from_slice(&self.hash);
+
+        Ok(33)
+    }
+}
+
+impl<'a> Decode<'a> for DeleteAppByHashReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(field_bytes(buff, "hash", 1, 32)?);
+
+        Ok((Self { hash }, 33))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_golden, testing::encode_decode};
+
+    #[test]
+    fn create_app() {
+        let r = CreateAppReq::new(0x1000, 0x40, 0x20, 0x0a, 0x01);
+        assert_golden!(
+            req: r,
+            &[
+                0xe0, 0x00, 0x00, 0x00, 0x15, 0x0b, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x40,
+                0x00, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x01
+            ]
+        );
+
+        let mut buff = [0u8; 32];
+        encode_decode(&mut buff, r);
+        encode_decode(&mut buff, r.with_api_level(0x0f));
+        assert!(CreateAppReq::decode(&[0x0b, 0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn load_segment() {
+        assert_golden!(
+            req: SelectSegmentReq::new(0xc0d0_0000),
+            &[0xe0, 0x00, 0x00, 0x00, 0x05, 0x05, 0xc0, 0xd0, 0x00, 0x00]
+        );
+        assert_golden!(
+            req: LoadSegmentReq::new(0x0100, &[0xaa, 0xbb]),
+            &[0xe0, 0x00, 0x00, 0x00, 0x05, 0x06, 0x01, 0x00, 0xaa, 0xbb]
+        );
+        assert_golden!(req: FlushReq::new(), &[0xe0, 0x00, 0x00, 0x00, 0x01, 0x07]);
+
+        let mut buff = [0u8; 16];
+        encode_decode(&mut buff, SelectSegmentReq::new(0x1234_5678));
+        encode_decode(&mut buff, LoadSegmentReq::new(0x10, &[0x01, 0x02, 0x03]));
+        encode_decode(&mut buff, FlushReq::new());
+
+        assert_eq!(
+            LoadSegmentReq::decode(&[0x07]),
+            Err(ApduError::InvalidEncoding)
+        );
+        assert!(LoadSegmentReq::decode(&[0x06, 0x00]).is_err());
+    }
+
+    #[test]
+    fn commit_app() {
+        assert_golden!(req: CommitAppReq::default(), &[0xe0, 0x00, 0x00, 0x00, 0x01, 0x09]);
+        assert_golden!(
+            req: CommitAppReq::new(&[0x30, 0x01]),
+            &[0xe0, 0x00, 0x00, 0x00, 0x04, 0x09, 0x02, 0x30, 0x01]
+        );
+
+        let mut buff = [0u8; 16];
+        encode_decode(&mut buff, CommitAppReq::new(&[0x30, 0x44, 0x02]));
+        assert!(CommitAppReq::new(&[0u8; 256]).encode_len().is_err());
+    }
+
+    #[test]
+    fn delete_app() {
+        assert_golden!(
+            req: DeleteAppReq::new("BTC"),
+            &[0xe0, 0x00, 0x00, 0x00, 0x05, 0x0c, 0x03, 0x42, 0x54, 0x43]
+        );
+
+        let mut buff = [0u8; 64];
+        encode_decode(&mut buff, DeleteAppReq::new("Ethereum"));
+        encode_decode(&mut buff, DeleteAppByHashReq::new([0xab; 32]));
+
+        assert_eq!(
+            DeleteAppReq::decode(&[0x0c, 0x01, 0xff]),
+            Err(ApduError::InvalidUtf8)
+        );
+        assert!(DeleteAppByHashReq::decode(&[0x15; 32]).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! MCU / bootloader APDUs, for recovery and firmware tooling workflows.
+//!
+//! These are BOLOS loader commands (class `0xe0`, instruction `0x00`) identified by the
+//! leading command byte, and are available when the device is in bootloader mode.
+//! A typical MCU recovery flow is:
+//!
+//! 1. fetch the bootloader version with [McuVersionReq] / [McuVersionResp]
+//! 2. identify the device with [ValidateTargetIdReq](super::ValidateTargetIdReq)
+//! 3. load the firmware, then check the loaded segment with [McuValidateReq]
+//! 4. boot into the loaded firmware with [McuResetReq]
+
+use encdec::{Decode, Encode};
+
+use crate::{field_bytes, prefixed_field, ApduError, ApduStatic, LvStr};
+
+/// Check the loader command byte when decoding loader requests
+pub(super) fn decode_cmd(buff: &[u8], cmd: u8) -> Result<(), ApduError> {
+    match buff.first() {
+        Some(c) if *c == cmd => Ok(()),
+        Some(_) => Err(ApduError::InvalidEncoding),
+        None => Err(ApduError::InvalidLength),
+    }
+}
+
+/// MCU bootloader version request APDU (loader command byte `0x10`)
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct McuVersionReq {}
+
+impl McuVersionReq {
+    /// Loader command byte for version requests
+    pub const CMD: u8 = 0x10;
+
+    /// Create a new MCU version request APDU
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Set CLA and INS values for [McuVersionReq]
+impl ApduStatic for McuVersionReq {
+    /// MCU version request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// MCU version request APDU is instruction `0x00`
+    const INS: u8 = 0x00;
+}
+
+/// [Encode] implementation for [McuVersionReq], the body contains the loader command byte
+impl Encode for McuVersionReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.is_empty() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+
+        Ok(1)
+    }
+}
+
+impl<'a> Decode<'a> for McuVersionReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+        Ok((Self {}, 1))
+    }
+}
+
+/// MCU bootloader version response APDU
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct McuVersionResp<'a> {
+    /// Bootloader target ID
+    pub target_id: [u8; 4],
+    /// Bootloader version
+    pub version: &'a str,
+}
+
+impl<'a> McuVersionResp<'a> {
+    /// Create a new MCU version response APDU
+    pub fn new(target_id: [u8; 4], version: &'a str) -> Self {
+        Self { target_id, version }
+    }
+}
+
+impl<'a> Encode for McuVersionResp<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(4 + LvStr(self.version).encode_len()?)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        // Write target ID
+        buff[..4].copy_from_slice(&self.target_id);
+
+        // Write version
+        LvStr(self.version).encode(&mut buff[4..])?;
+
+        Ok(n)
+    }
+}
+
+impl<'a> Decode<'a> for McuVersionResp<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        // Fetch target ID
+        let mut target_id = [0u8; 4];
+        target_id.copy_from_slice(field_bytes(buff, "target_id", 0, 4)?);
+
+        // Fetch version
+        let (version, n) = prefixed_field(buff, "version", 4)?;
+        let version = core::str::from_utf8(version).map_err(|_| ApduError::InvalidUtf8)?;
+
+        Ok((Self { target_id, version }, 4 + n))
+    }
+}
+
+/// MCU validate request APDU (loader command byte `0x08`), checking the CRC of
+/// a loaded firmware segment
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct McuValidateReq {
+    /// Offset of the data to be validated within the segment
+    pub offset: u16,
+    /// Length of the data to be validated
+    pub len: u32,
+    /// Expected CRC16 of the data
+    pub crc: u16,
+}
+
+/// Encoded [McuValidateReq] length, command byte and big-endian fields
+const MCU_VALIDATE_LEN: usize = 9;
+
+impl McuValidateReq {
+    /// Loader command byte for validate requests
+    pub const CMD: u8 = 0x08;
+
+    /// Create a new MCU validate request APDU
+    pub fn new(offset: u16, len: u32, crc: u16) -> Self {
+        Self { offset, len, crc }
+    }
+}
+
+/// Set CLA and INS values for [McuValidateReq]
+impl ApduStatic for McuValidateReq {
+    /// MCU validate request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// MCU validate request APDU is instruction `0x00`
+    const INS: u8 = 0x00;
+}
+
+impl Encode for McuValidateReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(MCU_VALIDATE_LEN)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < MCU_VALIDATE_LEN {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+        buff[1..3].copy_from_slice(&self.offset.to_be_bytes());
+        buff[3..7].copy_from_slice(&self.len.to_be_bytes());
+        buff[7..9].copy_from_slice(&self.crc.to_be_bytes());
+
+        Ok(MCU_VALIDATE_LEN)
+    }
+}
+
+impl<'a> Decode<'a> for McuValidateReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+
+        let offset = field_bytes(buff, "offset", 1, 2)?;
+        let len = field_bytes(buff, "len", 3, 4)?;
+        let crc = field_bytes(buff, "crc", 7, 2)?;
+
+        Ok((
+            Self {
+                offset: u16::from_be_bytes([offset[0], offset[1]]),
+                len: u32::from_be_bytes([len[0], len[1], len[2], len[3]]),
+                crc: u16::from_be_bytes([crc[0], crc[1]]),
+            },
+            MCU_VALIDATE_LEN,
+        ))
+    }
+}
+
+/// MCU reset request APDU (loader command byte `0x09`), leaving the bootloader
+/// and booting into the loaded firmware
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct McuResetReq {
+    /// Firmware boot address (the low bit is set for Thumb mode entry)
+    pub boot_addr: u32,
+}
+
+/// Encoded [McuResetReq] length, command byte and big-endian boot address
+const MCU_RESET_LEN: usize = 5;
+
+impl McuResetReq {
+    /// Loader command byte for reset (boot) requests
+    pub const CMD: u8 = 0x09;
+
+    /// Create a new MCU reset request APDU, booting into the provided address
+    pub fn new(boot_addr: u32) -> Self {
+        Self { boot_addr }
+    }
+}
+
+/// Set CLA and INS values for [McuResetReq]
+impl ApduStatic for McuResetReq {
+    /// MCU reset request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// MCU reset request APDU is instruction `0x00`
+    const INS: u8 = 0x00;
+}
+
+impl Encode for McuResetReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(MCU_RESET_LEN)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < MCU_RESET_LEN {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+        buff[1..5].copy_from_slice(&self.boot_addr.to_be_bytes());
+
+        Ok(MCU_RESET_LEN)
+    }
+}
+
+impl<'a> Decode<'a> for McuResetReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        decode_cmd(buff, Self::CMD)?;
+
+        let a = field_bytes(buff, "boot_addr", 1, 4)?;
+
+        Ok((
+            Self {
+                boot_addr: u32::from_be_bytes([a[0], a[1], a[2], a[3]]),
+            },
+            MCU_RESET_LEN,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_golden;
+
+    #[test]
+    fn mcu_version() {
+        assert_golden!(req: McuVersionReq::new(), &[0xe0, 0x00, 0x00, 0x00, 0x01, 0x10]);
+
+        let r = McuVersionResp::new([0x01, 0x00, 0x00, 0x01], "0.11");
+        assert_golden!(
+            resp: r,
+            &[0x01, 0x00, 0x00, 0x01, 0x04, 0x30, 0x2e, 0x31, 0x31, 0x90, 0x00]
+        );
+
+        let mut buff = [0u8; 16];
+        crate::testing::encode_decode(&mut buff, r);
+        assert!(McuVersionResp::decode(&[0x01, 0x00, 0x00, 0x01, 0x04, 0x30]).is_err());
+    }
+
+    #[test]
+    fn mcu_validate() {
+        assert_golden!(
+            req: McuValidateReq::new(0x0000, 0x0001_2000, 0xbeef),
+            &[0xe0, 0x00, 0x00, 0x00, 0x09, 0x08, 0x00, 0x00, 0x00, 0x01, 0x20, 0x00, 0xbe, 0xef]
+        );
+
+        let mut buff = [0u8; 16];
+        crate::testing::encode_decode(&mut buff, McuValidateReq::new(0x10, 0x400, 0x1234));
+
+        assert_eq!(
+            McuValidateReq::decode(&[0x09, 0x00]),
+            Err(ApduError::InvalidEncoding)
+        );
+        assert!(McuValidateReq::decode(&[0x08, 0x00]).is_err());
+    }
+
+    #[test]
+    fn mcu_reset() {
+        assert_golden!(
+            req: McuResetReq::new(0x0800_0001),
+            &[0xe0, 0x00, 0x00, 0x00, 0x05, 0x09, 0x08, 0x00, 0x00, 0x01]
+        );
+
+        let mut buff = [0u8; 8];
+        crate::testing::encode_decode(&mut buff, McuResetReq::new(0xc0d0_0001));
+        assert_eq!(McuResetReq::decode(&[]), Err(ApduError::InvalidLength));
+    }
+}
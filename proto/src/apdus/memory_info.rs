@@ -0,0 +1,183 @@
+//! Memory information request and response APDUs, reporting flash usage and
+//! application slots for app install management
+
+use encdec::{Decode, Encode};
+
+use crate::{field_bytes, ApduError, ApduStatic};
+
+/// Memory information request APDU.
+///
+/// This is a BOLOS loader command (instruction `0x00`, command byte `0x11`) and is
+/// typically issued via the manager secure channel.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GetMemoryInfoReq {}
+
+impl GetMemoryInfoReq {
+    /// Loader command byte for memory information requests
+    pub const CMD: u8 = 0x11;
+
+    /// Create a new memory information request APDU
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Set CLA and INS values for [GetMemoryInfoReq]
+impl ApduStatic for GetMemoryInfoReq {
+    /// Memory information request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Memory information request APDU is instruction `0x00`
+    const INS: u8 = 0x00;
+}
+
+/// [Encode] implementation for [GetMemoryInfoReq], the body contains the loader command byte
+impl Encode for GetMemoryInfoReq {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.is_empty() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = Self::CMD;
+
+        Ok(1)
+    }
+}
+
+impl<'a> Decode<'a> for GetMemoryInfoReq {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        match buff.first() {
+            Some(&Self::CMD) => Ok((Self {}, 1)),
+            Some(_) => Err(ApduError::InvalidEncoding),
+            None => Err(ApduError::InvalidLength),
+        }
+    }
+}
+
+/// Memory information response APDU, sizes are in bytes
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct MemoryInfoResp {
+    /// Flash used by the system (OS and reserved areas)
+    pub system_size: u32,
+    /// Flash used by installed applications
+    pub apps_size: u32,
+    /// Free flash available for applications
+    pub free_size: u32,
+    /// Number of application slots in use
+    pub used_app_slots: u32,
+    /// Total number of application slots
+    pub total_app_slots: u32,
+}
+
+/// Encoded [MemoryInfoResp] length, five big-endian `u32` fields
+const MEMORY_INFO_LEN: usize = 20;
+
+impl MemoryInfoResp {
+    /// Check whether an application of the provided size can be installed
+    pub fn can_install(&self, size: u32) -> bool {
+        self.free_size >= size && self.used_app_slots < self.total_app_slots
+    }
+}
+
+impl Encode for MemoryInfoResp {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(MEMORY_INFO_LEN)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < MEMORY_INFO_LEN {
+            return Err(ApduError::InvalidLength);
+        }
+
+        let fields = [
+            self.system_size,
+            self.apps_size,
+            self.free_size,
+            self.used_app_slots,
+            self.total_app_slots,
+        ];
+        for (i, v) in fields.iter().enumerate() {
+            buff[i * 4..][..4].copy_from_slice(&v.to_be_bytes());
+        }
+
+        Ok(MEMORY_INFO_LEN)
+    }
+}
+
+impl<'a> Decode<'a> for MemoryInfoResp {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let f = |i: usize, field| {
+            let b = field_bytes(buff, field, i * 4, 4)?;
+            Ok::<_, ApduError>(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        Ok((
+            Self {
+                system_size: f(0, "system_size")?,
+                apps_size: f(1, "apps_size")?,
+                free_size: f(2, "free_size")?,
+                used_app_slots: f(3, "used_app_slots")?,
+                total_app_slots: f(4, "total_app_slots")?,
+            },
+            MEMORY_INFO_LEN,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApduReq;
+
+    #[test]
+    fn memory_info_req() {
+        let r = GetMemoryInfoReq::new();
+        let h = r.header();
+        assert_eq!((h.cla, h.ins, h.p1, h.p2), (0xe0, 0x00, 0x00, 0x00));
+
+        let mut buff = [0u8; 4];
+        crate::testing::encode_decode(&mut buff, r);
+        assert_eq!(buff[0], 0x11);
+    }
+
+    #[test]
+    fn memory_info_resp() {
+        let r = MemoryInfoResp {
+            system_size: 0x0004_0000,
+            apps_size: 0x0001_2000,
+            free_size: 0x0002_e000,
+            used_app_slots: 3,
+            total_app_slots: 30,
+        };
+
+        let mut buff = [0u8; 32];
+        crate::testing::encode_decode(&mut buff, r);
+        assert_eq!(&buff[..4], &[0x00, 0x04, 0x00, 0x00]);
+
+        assert!(r.can_install(0x1000));
+        assert!(!r.can_install(0x0003_0000));
+        assert!(MemoryInfoResp::decode(&buff[..19]).is_err());
+    }
+}
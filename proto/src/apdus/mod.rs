@@ -6,8 +6,59 @@ pub use app_info::{AppFlags, AppInfoReq, AppInfoResp};
 mod device_info;
 pub use device_info::{DeviceInfoReq, DeviceInfoResp};
 
+mod app_list;
+#[cfg(feature = "alloc")]
+pub use app_list::ListAppsSession;
+pub use app_list::{AppData, AppListIter, AppListReq, AppListResp};
+
 mod run_app;
-pub use run_app::RunAppReq;
+pub use run_app::{RunAppError, RunAppReq};
 
 mod exit_app;
 pub use exit_app::ExitAppReq;
+
+mod wallet_id;
+pub use wallet_id::{WalletIdReq, WalletIdResp};
+
+mod battery;
+pub use battery::{
+    BatteryFlags, BatteryStatus, BatteryStatusKind, GetBatteryStatusReq, GetBatteryStatusResp,
+};
+
+mod genuine;
+pub use genuine::{
+    CommitEndorsementReq, CreateEndorsementKeyReq, DeviceCert, EndorsementKeyResp, EndorsementSlot,
+    GetCertReq, InitAuthReq, InitAuthResp, MutualAuthReq, ValidateCertReq, ValidateTargetIdReq,
+    ENDORSEMENT_KEY_LEN,
+};
+
+mod memory_info;
+pub use memory_info::{GetMemoryInfoReq, MemoryInfoResp};
+
+mod get_response;
+pub use get_response::{FollowUp, GetResponseReq};
+
+mod mcu;
+pub use mcu::{McuResetReq, McuValidateReq, McuVersionReq, McuVersionResp};
+
+mod loader;
+pub use loader::{
+    CommitAppReq, CreateAppReq, DeleteAppByHashReq, DeleteAppReq, FlushReq, LoadSegmentReq,
+    SelectSegmentReq,
+};
+
+mod pki;
+#[cfg(feature = "alloc")]
+pub use pki::TlvPayload;
+pub use pki::{KeyUsage, LoadCertificateReq, Tlv, TlvIter, TrustedNameTag};
+
+#[cfg(feature = "signer")]
+pub mod signer;
+
+#[cfg(feature = "alloc")]
+mod owned;
+#[cfg(feature = "alloc")]
+pub use owned::{
+    AppDataOwned, AppInfoRespOwned, DeviceCertOwned, DeviceInfoRespOwned, EndorsementKeyRespOwned,
+    McuVersionRespOwned, ToOwnedApdu, WalletIdRespOwned,
+};
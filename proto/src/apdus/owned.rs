@@ -0,0 +1,435 @@
+//! Owned variants of borrowed response APDUs (enabled with `alloc` feature), allowing
+//! responses to outlive the decode buffer.
+//!
+//! Owned types implement [Encode] and [DecodeOwned] using the borrowed encodings, so
+//! may be requested directly or converted from borrowed responses via [ToOwnedApdu].
+//!
+//! ```
+//! use ledger_proto::{apdus::{AppInfoResp, AppInfoRespOwned, ToOwnedApdu}, Decode};
+//!
+//! let buff = [0x01, 0x03, 0x42, 0x54, 0x43, 0x03, 0x31, 0x2e, 0x30, 0x01, 0x00];
+//!
+//! let owned: AppInfoRespOwned = {
+//!     let (r, _) = AppInfoResp::decode(&buff).unwrap();
+//!     r.to_owned_apdu()
+//! };
+//! assert_eq!(owned.name, "BTC");
+//!
+//! // Owned types may also be decoded directly
+//! assert_eq!(AppInfoRespOwned::decode(&buff).unwrap().0, owned);
+//! ```
+
+use alloc::string::{String, ToString};
+
+use encdec::{Decode, DecodeOwned, Encode};
+
+use super::{
+    AppData, AppFlags, AppInfoResp, AppListResp, DeviceCert, DeviceInfoResp, EndorsementKeyResp,
+    McuVersionResp, WalletIdResp,
+};
+use crate::{ApduError, Vec};
+
+/// Conversion from borrowed response APDUs to their owned equivalents
+pub trait ToOwnedApdu {
+    /// Owned APDU type
+    type Owned;
+
+    /// Copy borrowed fields into an owned APDU
+    fn to_owned_apdu(&self) -> Self::Owned;
+}
+
+/// Implement [ToOwnedApdu] for a borrowed APDU, and [Encode] / [DecodeOwned] for
+/// the owned APDU via the borrowed encoding (using `From<&Borrowed>` and `as_borrowed`)
+macro_rules! owned_apdu {
+    ($borrowed:ident, $owned:ident) => {
+        impl<'a> ToOwnedApdu for $borrowed<'a> {
+            type Owned = $owned;
+
+            fn to_owned_apdu(&self) -> Self::Owned {
+                $owned::from(self)
+            }
+        }
+
+        impl Encode for $owned {
+            type Error = ApduError;
+
+            fn encode_len(&self) -> Result<usize, Self::Error> {
+                self.as_borrowed().encode_len()
+            }
+
+            fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+                self.as_borrowed().encode(buff)
+            }
+        }
+
+        impl DecodeOwned for $owned {
+            type Output = Self;
+
+            type Error = ApduError;
+
+            fn decode_owned(buff: &[u8]) -> Result<(Self::Output, usize), Self::Error> {
+                let (r, n) = $borrowed::decode(buff)?;
+                Ok((r.to_owned_apdu(), n))
+            }
+        }
+    };
+}
+
+/// Owned [AppInfoResp]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct AppInfoRespOwned {
+    /// Application name
+    pub name: String,
+    /// Application version
+    pub version: String,
+    /// Application flags
+    pub flags: AppFlags,
+    /// Additional length-prefixed fields reported by format `2` responses
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub extensions: Vec<u8>,
+}
+
+impl AppInfoRespOwned {
+    /// Borrow as an [AppInfoResp]
+    pub fn as_borrowed(&self) -> AppInfoResp<'_> {
+        AppInfoResp::new(&self.name, &self.version, self.flags.clone())
+            .with_extensions(&self.extensions)
+    }
+}
+
+impl<'a> From<&AppInfoResp<'a>> for AppInfoRespOwned {
+    fn from(r: &AppInfoResp<'a>) -> Self {
+        Self {
+            name: r.name.to_string(),
+            version: r.version.to_string(),
+            flags: r.flags.clone(),
+            extensions: r.extensions.to_vec(),
+        }
+    }
+}
+
+owned_apdu!(AppInfoResp, AppInfoRespOwned);
+
+/// Owned [DeviceInfoResp]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct DeviceInfoRespOwned {
+    /// Target ID
+    pub target_id: [u8; 4],
+    /// Secure Element Version
+    pub se_version: String,
+    /// Device Flag(s)
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub flags: Vec<u8>,
+    /// MCU Version
+    pub mcu_version: String,
+    /// MCU bootloader version (empty where not provided)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "String::is_empty")
+    )]
+    pub bootloader_version: String,
+    /// Hardware revision
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub hardware_revision: Option<u8>,
+    /// Language ID
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub language_id: Option<u8>,
+}
+
+impl DeviceInfoRespOwned {
+    /// Borrow as a [DeviceInfoResp]
+    pub fn as_borrowed(&self) -> DeviceInfoResp<'_> {
+        DeviceInfoResp {
+            bootloader_version: &self.bootloader_version,
+            hardware_revision: self.hardware_revision,
+            language_id: self.language_id,
+            ..DeviceInfoResp::new(
+                self.target_id,
+                &self.se_version,
+                &self.mcu_version,
+                &self.flags,
+            )
+        }
+    }
+}
+
+impl<'a> From<&DeviceInfoResp<'a>> for DeviceInfoRespOwned {
+    fn from(r: &DeviceInfoResp<'a>) -> Self {
+        Self {
+            target_id: r.target_id,
+            se_version: r.se_version.to_string(),
+            flags: r.flags.to_vec(),
+            mcu_version: r.mcu_version.to_string(),
+            bootloader_version: r.bootloader_version.to_string(),
+            hardware_revision: r.hardware_revision,
+            language_id: r.language_id,
+        }
+    }
+}
+
+owned_apdu!(DeviceInfoResp, DeviceInfoRespOwned);
+
+/// Owned [AppData]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct AppDataOwned {
+    /// Application name
+    pub name: String,
+    /// Application flags
+    pub flags: u16,
+    /// Storage blocks used by the application
+    pub blocks: u16,
+    /// Application code hash
+    pub hash_code_data: [u8; 32],
+    /// Application full hash
+    pub hash: [u8; 32],
+}
+
+impl AppDataOwned {
+    /// Borrow as an [AppData]
+    pub fn as_borrowed(&self) -> AppData<'_> {
+        AppData {
+            name: &self.name,
+            flags: self.flags,
+            blocks: self.blocks,
+            hash_code_data: self.hash_code_data,
+            hash: self.hash,
+        }
+    }
+}
+
+impl<'a> From<&AppData<'a>> for AppDataOwned {
+    fn from(a: &AppData<'a>) -> Self {
+        Self {
+            name: a.name.to_string(),
+            flags: a.flags,
+            blocks: a.blocks,
+            hash_code_data: a.hash_code_data,
+            hash: a.hash,
+        }
+    }
+}
+
+owned_apdu!(AppData, AppDataOwned);
+
+/// [ToOwnedApdu] for [AppListResp], collecting entries as [AppDataOwned]
+impl<'a> ToOwnedApdu for AppListResp<'a> {
+    type Owned = Vec<AppDataOwned>;
+
+    fn to_owned_apdu(&self) -> Self::Owned {
+        self.iter().map(|a| a.to_owned_apdu()).collect()
+    }
+}
+
+/// Owned [WalletIdResp]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WalletIdRespOwned {
+    /// Wallet identifier
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub id: Vec<u8>,
+}
+
+impl WalletIdRespOwned {
+    /// Borrow as a [WalletIdResp]
+    pub fn as_borrowed(&self) -> WalletIdResp<'_> {
+        WalletIdResp::new(&self.id)
+    }
+}
+
+impl<'a> From<&WalletIdResp<'a>> for WalletIdRespOwned {
+    fn from(r: &WalletIdResp<'a>) -> Self {
+        Self { id: r.id.to_vec() }
+    }
+}
+
+owned_apdu!(WalletIdResp, WalletIdRespOwned);
+
+/// Owned [DeviceCert]
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct DeviceCertOwned {
+    /// Certificate header (role and serial)
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub header: Vec<u8>,
+    /// Certified public key
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub public_key: Vec<u8>,
+    /// Issuer signature over the header and public key
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub signature: Vec<u8>,
+}
+
+impl DeviceCertOwned {
+    /// Borrow as a [DeviceCert]
+    pub fn as_borrowed(&self) -> DeviceCert<'_> {
+        DeviceCert::new(&self.header, &self.public_key, &self.signature)
+    }
+}
+
+impl<'a> From<&DeviceCert<'a>> for DeviceCertOwned {
+    fn from(c: &DeviceCert<'a>) -> Self {
+        Self {
+            header: c.header.to_vec(),
+            public_key: c.public_key.to_vec(),
+            signature: c.signature.to_vec(),
+        }
+    }
+}
+
+owned_apdu!(DeviceCert, DeviceCertOwned);
+
+/// Owned [EndorsementKeyResp]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct EndorsementKeyRespOwned {
+    /// Uncompressed endorsement public key
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub public_key: Vec<u8>,
+    /// Signature over the public key by the device key
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub signature: Vec<u8>,
+}
+
+impl EndorsementKeyRespOwned {
+    /// Borrow as an [EndorsementKeyResp]
+    pub fn as_borrowed(&self) -> EndorsementKeyResp<'_> {
+        EndorsementKeyResp::new(&self.public_key, &self.signature)
+    }
+}
+
+impl<'a> From<&EndorsementKeyResp<'a>> for EndorsementKeyRespOwned {
+    fn from(r: &EndorsementKeyResp<'a>) -> Self {
+        Self {
+            public_key: r.public_key.to_vec(),
+            signature: r.signature.to_vec(),
+        }
+    }
+}
+
+owned_apdu!(EndorsementKeyResp, EndorsementKeyRespOwned);
+
+/// Owned [McuVersionResp]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct McuVersionRespOwned {
+    /// Bootloader target ID
+    pub target_id: [u8; 4],
+    /// Bootloader version
+    pub version: String,
+}
+
+impl McuVersionRespOwned {
+    /// Borrow as an [McuVersionResp]
+    pub fn as_borrowed(&self) -> McuVersionResp<'_> {
+        McuVersionResp::new(self.target_id, &self.version)
+    }
+}
+
+impl<'a> From<&McuVersionResp<'a>> for McuVersionRespOwned {
+    fn from(r: &McuVersionResp<'a>) -> Self {
+        Self {
+            target_id: r.target_id,
+            version: r.version.to_string(),
+        }
+    }
+}
+
+owned_apdu!(McuVersionResp, McuVersionRespOwned);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        apdus::AppListResp,
+        fixtures::{APP_INFO_BITCOIN, DEVICE_INFO_NANOS},
+        testing::encode_decode,
+    };
+
+    #[test]
+    fn owned_app_info() {
+        let owned = {
+            let (r, _) = AppInfoResp::decode(APP_INFO_BITCOIN.data()).unwrap();
+            r.to_owned_apdu()
+        };
+        assert_eq!(
+            (owned.name.as_str(), owned.version.as_str()),
+            ("Bitcoin", "2.1.3")
+        );
+
+        let mut buff = [0u8; 64];
+        let n = owned.encode(&mut buff).unwrap();
+        assert_eq!(&buff[..n], APP_INFO_BITCOIN.data());
+
+        encode_decode(&mut buff, owned);
+    }
+
+    #[test]
+    fn owned_device_info() {
+        let (owned, n) = DeviceInfoRespOwned::decode(DEVICE_INFO_NANOS.data()).unwrap();
+        assert_eq!(n, DEVICE_INFO_NANOS.data().len());
+        assert_eq!(owned.as_borrowed().se_version, "2.1.0");
+
+        let mut buff = [0u8; 64];
+        encode_decode(&mut buff, owned);
+    }
+
+    #[test]
+    fn owned_responses() {
+        let mut buff = [0u8; 256];
+
+        encode_decode(&mut buff, WalletIdResp::new(&[0xaa, 0xbb]).to_owned_apdu());
+        encode_decode(
+            &mut buff,
+            DeviceCert::new(&[0x01], &[0x02; 65], &[0x03; 70]).to_owned_apdu(),
+        );
+        encode_decode(
+            &mut buff,
+            EndorsementKeyResp::new(&[0x04; 65], &[0x05; 70]).to_owned_apdu(),
+        );
+        encode_decode(
+            &mut buff,
+            McuVersionResp::new([0x01, 0x00, 0x00, 0x01], "0.11").to_owned_apdu(),
+        );
+    }
+
+    #[test]
+    fn owned_app_list() {
+        let a = AppData {
+            name: "Bitcoin",
+            flags: 0x0a,
+            blocks: 0x20,
+            hash_code_data: [0x11; 32],
+            hash: [0x22; 32],
+        };
+
+        let mut buff = [0u8; 128];
+        let n = a.encode(&mut buff).unwrap();
+
+        let entries = AppListResp::new(&buff[..n]).to_owned_apdu();
+        assert_eq!(entries, &[a.to_owned_apdu()]);
+        assert_eq!(entries[0].as_borrowed(), a);
+    }
+}
@@ -0,0 +1,487 @@
+//! PKI certificate and TLV metadata APDUs, used by recent applications to accept
+//! signed metadata (eg. trusted names for address resolution) from the host.
+//!
+//! Metadata signing keys are provided to the device with [LoadCertificateReq], after
+//! which applications accept [Tlv] encoded payloads signed by those keys. Payloads are
+//! prefixed with a big-endian `u16` length and split across application-specific
+//! commands using [ChunkMarkers::FIRST_FOLLOWING](crate::ChunkMarkers::FIRST_FOLLOWING).
+//!
+//! ```
+//! use ledger_proto::{apdus::{Tlv, TlvIter, TrustedNameTag}, Decode};
+//!
+//! let b = [0x02, 0x01, 0x02, 0x20, 0x03, 0x61, 0x62, 0x63];
+//! let (t, n) = Tlv::decode(&b).unwrap();
+//! assert_eq!((t.tag, t.value, n), (TrustedNameTag::Version.into(), &[0x02][..], 3));
+//!
+//! let names: Vec<_> = TlvIter::new(&b).map(|t| t.unwrap().tag).collect();
+//! assert_eq!(names, [0x02, 0x20]);
+//! ```
+
+use encdec::{Decode, Encode};
+
+use crate::{field_bytes, ApduError, ApduStatic};
+
+/// Certificate key usage, identifying the purpose of a certificate loaded with
+/// [LoadCertificateReq]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, num_enum::FromPrimitive, num_enum::IntoPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum KeyUsage {
+    /// Genuine check
+    GenuineCheck = 0x01,
+    /// Exchange (swap) payload signing
+    ExchangePayload = 0x02,
+    /// NFT metadata
+    NftMetadata = 0x03,
+    /// Trusted name (address resolution) metadata
+    TrustedName = 0x04,
+    /// Backup provider
+    BackupProvider = 0x05,
+    /// Recover orchestrator
+    RecoverOrchestrator = 0x06,
+    /// Plugin metadata
+    PluginMetadata = 0x07,
+    /// Coin metadata
+    CoinMeta = 0x08,
+    /// Seed ID authentication
+    SeedIdAuth = 0x09,
+    /// Unrecognised key usage
+    #[num_enum(catch_all)]
+    Unknown(u8),
+}
+
+// `#[default]` conflicts with the `num_enum` catch-all, so this is implemented manually
+#[allow(clippy::derivable_impls)]
+impl Default for KeyUsage {
+    fn default() -> Self {
+        Self::TrustedName
+    }
+}
+
+/// Load certificate request APDU, providing a PKI certificate for validating
+/// signed metadata.
+///
+/// Certificates are issued by the Ledger PKI and are checked by the device, with
+/// the loaded key then used to verify metadata for the selected [KeyUsage].
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct LoadCertificateReq<'a> {
+    /// Certificate key usage (sent as P1, not encoded in the APDU body)
+    pub key_usage: KeyUsage,
+    /// Encoded certificate
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub cert: &'a [u8],
+}
+
+impl<'a> LoadCertificateReq<'a> {
+    /// Create a new load certificate request APDU
+    pub fn new(key_usage: KeyUsage, cert: &'a [u8]) -> Self {
+        Self { key_usage, cert }
+    }
+}
+
+/// Set CLA and INS values for [LoadCertificateReq]
+impl<'a> ApduStatic for LoadCertificateReq<'a> {
+    /// Load certificate request APDU is class `0xb0`
+    const CLA: u8 = 0xb0;
+
+    /// Load certificate request APDU is instruction `0x06`
+    const INS: u8 = 0x06;
+
+    /// Key usage is selected via P1
+    fn p1(&self) -> u8 {
+        self.key_usage.into()
+    }
+}
+
+impl<'a> Encode for LoadCertificateReq<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.cert.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.cert.len() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[..self.cert.len()].copy_from_slice(self.cert);
+
+        Ok(self.cert.len())
+    }
+}
+
+/// [Decode] implementation for [LoadCertificateReq], the key usage is carried in the
+/// header so is not decoded
+impl<'a> Decode<'a> for LoadCertificateReq<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((
+            Self {
+                key_usage: KeyUsage::default(),
+                cert: buff,
+            },
+            buff.len(),
+        ))
+    }
+}
+
+/// Trusted name payload tags, for [Tlv] fields provided via `PROVIDE_TRUSTED_NAME`
+/// commands (eg. Ethereum app class `0xe0`, instruction `0x22`)
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum TrustedNameTag {
+    /// Structure type
+    StructureType = 0x01,
+    /// Structure version
+    Version = 0x02,
+    /// Expiry timestamp
+    NotValidAfter = 0x10,
+    /// Device challenge
+    Challenge = 0x12,
+    /// Signer key ID
+    SignerKeyId = 0x13,
+    /// Signer algorithm
+    SignerAlgorithm = 0x14,
+    /// DER encoded signature
+    Signature = 0x15,
+    /// Trusted name
+    TrustedName = 0x20,
+    /// Address
+    Address = 0x22,
+    /// Chain ID
+    ChainId = 0x23,
+    /// Trusted name type (eg. account, contract)
+    TrustedNameType = 0x70,
+    /// Trusted name source (eg. ENS, CAL)
+    TrustedNameSource = 0x71,
+    /// NFT ID
+    NftId = 0x72,
+    /// Source contract address
+    SourceContract = 0x73,
+}
+
+impl From<TrustedNameTag> for u32 {
+    fn from(t: TrustedNameTag) -> Self {
+        t as u32
+    }
+}
+
+/// Compute the length of a DER-style encoded value (used for [Tlv] tags and lengths)
+fn der_len(v: u32) -> usize {
+    match v {
+        0..=0x7f => 1,
+        _ => 1 + (4 - v.leading_zeros() as usize / 8),
+    }
+}
+
+/// Encode a DER-style value, single byte for values below `0x80`, otherwise
+/// `0x80 | n` followed by `n` big-endian bytes
+fn der_encode(v: u32, buff: &mut [u8]) -> Result<usize, ApduError> {
+    let n = der_len(v);
+    if buff.len() < n {
+        return Err(ApduError::InvalidLength);
+    }
+
+    match n {
+        1 => buff[0] = v as u8,
+        _ => {
+            buff[0] = 0x80 | (n - 1) as u8;
+            buff[1..n].copy_from_slice(&v.to_be_bytes()[5 - n..]);
+        }
+    }
+
+    Ok(n)
+}
+
+/// Decode a DER-style value for `field` at `offset`, rejecting non-minimal encodings
+fn der_decode(buff: &[u8], field: &'static str, offset: usize) -> Result<(u32, usize), ApduError> {
+    let b = field_bytes(buff, field, offset, 1)?[0];
+    if b < 0x80 {
+        return Ok((b as u32, 1));
+    }
+
+    let n = (b & 0x7f) as usize;
+    if !(1..=4).contains(&n) {
+        return Err(ApduError::InvalidEncoding);
+    }
+
+    let v = field_bytes(buff, field, offset + 1, n)?
+        .iter()
+        .fold(0u32, |a, b| a << 8 | *b as u32);
+    if der_len(v) != 1 + n {
+        return Err(ApduError::InvalidEncoding);
+    }
+
+    Ok((v, 1 + n))
+}
+
+/// Tag-length-value field, with DER-style encoded tag and length, as used for
+/// signed metadata payloads
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Tlv<'a> {
+    /// Field tag
+    pub tag: u32,
+    /// Field value
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub value: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// Create a new TLV field
+    pub fn new(tag: impl Into<u32>, value: &'a [u8]) -> Self {
+        Self {
+            tag: tag.into(),
+            value,
+        }
+    }
+}
+
+impl<'a> Encode for Tlv<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        if self.value.len() > u32::MAX as usize {
+            return Err(ApduError::InvalidLength);
+        }
+
+        Ok(der_len(self.tag) + der_len(self.value.len() as u32) + self.value.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        let mut index = der_encode(self.tag, buff)?;
+        index += der_encode(self.value.len() as u32, &mut buff[index..])?;
+
+        buff[index..][..self.value.len()].copy_from_slice(self.value);
+
+        Ok(n)
+    }
+}
+
+impl<'a> Decode<'a> for Tlv<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let (tag, mut index) = der_decode(buff, "tag", 0)?;
+
+        let (len, n) = der_decode(buff, "length", index)?;
+        index += n;
+
+        let value = field_bytes(buff, "value", index, len as usize)?;
+        index += value.len();
+
+        Ok((Self { tag, value }, index))
+    }
+}
+
+/// Iterator over [Tlv] fields in a payload, stopping after the first decode error
+#[derive(Clone, Debug)]
+pub struct TlvIter<'a> {
+    buff: &'a [u8],
+    index: usize,
+}
+
+impl<'a> TlvIter<'a> {
+    /// Create an iterator over TLV fields in the provided buffer
+    pub fn new(buff: &'a [u8]) -> Self {
+        Self { buff, index: 0 }
+    }
+}
+
+impl<'a> Iterator for TlvIter<'a> {
+    type Item = Result<Tlv<'a>, ApduError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.buff.len() {
+            return None;
+        }
+
+        match Tlv::decode(&self.buff[self.index..]) {
+            Ok((t, n)) => {
+                self.index += n;
+                Some(Ok(t))
+            }
+            Err(e) => {
+                self.index = self.buff.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// TLV payload builder, producing length-prefixed payloads for chunked metadata
+/// commands such as `PROVIDE_TRUSTED_NAME`.
+///
+/// ```
+/// use ledger_proto::{apdus::{TlvPayload, TrustedNameTag}, ChunkedReq, ChunkMarkers};
+///
+/// let p = TlvPayload::new()
+///     .with_field(TrustedNameTag::Version, &[0x02])
+///     .with_field(TrustedNameTag::TrustedName, b"vitalik.eth")
+///     .finish()
+///     .unwrap();
+/// assert_eq!(&p[..5], &[0x00, 0x10, 0x02, 0x01, 0x02]);
+///
+/// // Ethereum app `PROVIDE_TRUSTED_NAME`
+/// let chunks: Vec<_> = ChunkedReq::new(0xe0, 0x22, &p)
+///     .with_markers(ChunkMarkers::FIRST_FOLLOWING)
+///     .collect();
+/// assert_eq!((chunks[0].header.p1, chunks[0].data), (0x01, &p[..]));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TlvPayload {
+    data: crate::Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl TlvPayload {
+    /// Create a new empty payload
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a field to the payload
+    pub fn with_field(mut self, tag: impl Into<u32>, value: &[u8]) -> Self {
+        let t = Tlv::new(tag, value);
+
+        // Encoding is infallible with a correctly sized buffer
+        let n = self.data.len();
+        self.data.resize(n + t.encode_len().unwrap_or(0), 0);
+        let _ = t.encode(&mut self.data[n..]);
+
+        self
+    }
+
+    /// Fetch encoded fields, without the length prefix
+    pub fn fields(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Finalise the payload, prefixing encoded fields with a big-endian `u16` length
+    pub fn finish(self) -> Result<crate::Vec<u8>, ApduError> {
+        let n = u16::try_from(self.data.len()).map_err(|_| ApduError::InvalidLength)?;
+
+        let mut b = crate::Vec::with_capacity(2 + self.data.len());
+        b.extend_from_slice(&n.to_be_bytes());
+        b.extend_from_slice(&self.data);
+
+        Ok(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_golden, testing::encode_decode, ApduReq};
+
+    #[test]
+    fn load_certificate_req() {
+        let r = LoadCertificateReq::new(KeyUsage::TrustedName, &[0xaa, 0xbb]);
+        assert_golden!(req: r, &[0xb0, 0x06, 0x04, 0x00, 0x02, 0xaa, 0xbb]);
+
+        let h = LoadCertificateReq::new(KeyUsage::Unknown(0x42), &[]).header();
+        assert_eq!(h.p1, 0x42);
+        assert_eq!(KeyUsage::from(0x09), KeyUsage::SeedIdAuth);
+        assert_eq!(KeyUsage::from(0x42), KeyUsage::Unknown(0x42));
+    }
+
+    #[test]
+    fn tlv() {
+        let mut buff = [0u8; 512];
+
+        // Short, one and two byte lengths
+        let v = [0xaa; 300];
+        for (t, h) in [
+            (Tlv::new(0x02u32, &v[..1]), &[0x02, 0x01][..]),
+            (Tlv::new(0x20u32, &v[..0x80]), &[0x20, 0x81, 0x80]),
+            (Tlv::new(0x81u32, &v[..]), &[0x81, 0x81, 0x82, 0x01, 0x2c]),
+        ] {
+            let n = t.encode(&mut buff).unwrap();
+            assert_eq!(&buff[..h.len()], h);
+            assert_eq!(n, h.len() + t.value.len());
+            encode_decode(&mut buff, t);
+        }
+
+        assert_eq!(
+            Tlv::new(0x01u32, &[0x01]).encode(&mut buff[..2]),
+            Err(ApduError::InvalidLength)
+        );
+
+        // Truncated and non-minimal encodings are rejected
+        assert!(Tlv::decode(&[]).unwrap_err().is_length());
+        assert!(Tlv::decode(&[0x01, 0x02, 0xaa]).unwrap_err().is_length());
+        assert!(Tlv::decode(&[0x01, 0x82, 0x01]).unwrap_err().is_length());
+        assert_eq!(
+            Tlv::decode(&[0x01, 0x81, 0x01, 0xaa]),
+            Err(ApduError::InvalidEncoding)
+        );
+        assert_eq!(Tlv::decode(&[0x01, 0x80]), Err(ApduError::InvalidEncoding));
+        assert_eq!(
+            Tlv::decode(&[0x01, 0x85, 0, 0, 0, 0, 1]),
+            Err(ApduError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn tlv_iter() {
+        let b = [0x01, 0x01, 0x03, 0x70, 0x00, 0x20, 0x05, 0xaa];
+
+        let mut i = TlvIter::new(&b);
+        assert_eq!(
+            i.next(),
+            Some(Ok(Tlv::new(TrustedNameTag::StructureType, &[0x03])))
+        );
+        assert_eq!(
+            i.next(),
+            Some(Ok(Tlv::new(TrustedNameTag::TrustedNameType, &[])))
+        );
+        assert!(i.next().unwrap().unwrap_err().is_length());
+        assert_eq!(i.next(), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn tlv_payload() {
+        let p = TlvPayload::new()
+            .with_field(TrustedNameTag::StructureType, &[0x03])
+            .with_field(TrustedNameTag::ChainId, &[0x01]);
+        assert_eq!(p.fields(), &[0x01, 0x01, 0x03, 0x23, 0x01, 0x01]);
+
+        let f = TlvIter::new(p.fields())
+            .collect::<Result<crate::Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(f.len(), 2);
+
+        assert_eq!(
+            p.finish().unwrap(),
+            [0x00, 0x06, 0x01, 0x01, 0x03, 0x23, 0x01, 0x01]
+        );
+
+        // Payloads are limited by the u16 length prefix
+        let v = alloc::vec![0u8; 0xffff];
+        let p = TlvPayload::new().with_field(0x01u32, &v);
+        assert_eq!(p.finish(), Err(ApduError::InvalidLength));
+    }
+}
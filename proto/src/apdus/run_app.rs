@@ -2,10 +2,13 @@
 
 use encdec::{Decode, Encode};
 
-use crate::{ApduError, ApduStatic};
+use crate::{ApduError, ApduStatic, StatusCode};
 
 /// Run application request APDU, request to BOLOS to launch an application on the Ledger Device
 #[derive(Clone, Debug, PartialEq, Encode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[encdec(error = "ApduError")]
 pub struct RunAppReq<'a> {
     /// Application name to launch (note this is case sensitive)
@@ -40,15 +43,87 @@ impl<'a> Decode<'a> for RunAppReq<'a> {
     }
 }
 
+/// Run application errors, decoded from [RunAppReq] response status words
+#[derive(Copy, Clone, PartialEq, Debug, displaydoc::Display)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RunAppError {
+    /// Application not installed
+    AppNotFound,
+
+    /// User declined application launch
+    UserRefused,
+
+    /// Device locked
+    Locked,
+
+    /// Application already running (run requests are only supported by the dashboard)
+    AppRunning,
+
+    /// Run application failed with status: {0}
+    Status(StatusCode),
+}
+
+/// [core::error::Error] implementation for [RunAppError] without the `std` feature
+#[cfg(not(feature = "std"))]
+impl core::error::Error for RunAppError {}
+
+impl RunAppError {
+    /// Decode a [RunAppReq] response status word, returning `None` on success (`0x9000`).
+    ///
+    /// Statuses are mapped independently of any response payload.
+    pub fn from_status(sw: u16) -> Option<Self> {
+        let e = match StatusCode::from(sw) {
+            StatusCode::Ok => return None,
+            StatusCode::AppNotFound
+            | StatusCode::DataInvalid
+            | StatusCode::AppNotFoundOrInvalidContext => Self::AppNotFound,
+            StatusCode::ConditionsOfUseNotSatisfied | StatusCode::UserRefusedOnDevice => {
+                Self::UserRefused
+            }
+            StatusCode::LockedDevice => Self::Locked,
+            StatusCode::ClaNotSupported | StatusCode::InsNotSupported => Self::AppRunning,
+            s => Self::Status(s),
+        };
+
+        Some(e)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::RunAppReq;
+    use super::*;
 
     #[test]
     fn encode_decode_run_app_req() {
         let r = RunAppReq::new("test app");
 
         let mut buff = [0u8; 256];
-        crate::tests::encode_decode(&mut buff, r);
+        crate::testing::encode_decode(&mut buff, r);
+    }
+
+    #[test]
+    fn run_app_errors() {
+        assert_eq!(RunAppError::from_status(0x9000), None);
+        assert_eq!(
+            RunAppError::from_status(0x6807),
+            Some(RunAppError::AppNotFound)
+        );
+        assert_eq!(
+            RunAppError::from_status(0x6985),
+            Some(RunAppError::UserRefused)
+        );
+        assert_eq!(
+            RunAppError::from_status(0x5501),
+            Some(RunAppError::UserRefused)
+        );
+        assert_eq!(
+            RunAppError::from_status(0x6e00),
+            Some(RunAppError::AppRunning)
+        );
+        assert_eq!(
+            RunAppError::from_status(0x6a84),
+            Some(RunAppError::Status(StatusCode::NotEnoughMemorySpace))
+        );
     }
 }
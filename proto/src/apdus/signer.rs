@@ -0,0 +1,395 @@
+//! Generic signer application APDUs (enabled with `signer` feature), providing the
+//! request shapes shared by most Ledger applications.
+//!
+//! Class and instruction values are set via const generics, so application client
+//! crates can define typed requests with aliases rather than re-implementing
+//! common encodings. Responses are application-specific so are not included.
+//!
+//! ```
+//! use ledger_proto::{
+//!     apdus::signer::{GetPublicKeyReq, GetVersionReq, SignChunks},
+//!     ApduReq, Bip32Path,
+//! };
+//!
+//! // Ethereum application requests
+//! type EthGetVersion = GetVersionReq<0xe0, 0x06>;
+//! type EthGetPublicKey = GetPublicKeyReq<0xe0, 0x02>;
+//! type EthSign<'a> = SignChunks<'a, 0xe0, 0x04>;
+//!
+//! let path: Bip32Path = "m/44'/60'/0'/0/0".parse().unwrap();
+//!
+//! let h = EthGetPublicKey::new(path, true).header();
+//! assert_eq!((h.cla, h.ins, h.p1), (0xe0, 0x02, 0x01));
+//!
+//! // Path and transaction data are split across chunks, with `0x00` / `0x80` markers
+//! let tx = [0xaa; 300];
+//! let chunks: Vec<_> = EthSign::new(path, &tx).collect();
+//! assert_eq!(chunks.len(), 2);
+//! assert_eq!(chunks[0].header().p1, 0x00);
+//! assert_eq!(chunks[1].header().p1, 0x80);
+//! ```
+
+use encdec::{Decode, DecodeOwned, Encode};
+
+use crate::{length::SHORT_MAX_LEN, ApduError, ApduStatic, Bip32Path, ChunkMarkers};
+
+/// Get application version request APDU, the request has no body
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GetVersionReq<const CLA: u8, const INS: u8> {}
+
+impl<const CLA: u8, const INS: u8> GetVersionReq<CLA, INS> {
+    /// Create a new get version request APDU
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Set CLA and INS values for [GetVersionReq]
+impl<const C: u8, const I: u8> ApduStatic for GetVersionReq<C, I> {
+    const CLA: u8 = C;
+
+    const INS: u8 = I;
+}
+
+impl<const CLA: u8, const INS: u8> Encode for GetVersionReq<CLA, INS> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+
+    fn encode(&self, _buff: &mut [u8]) -> Result<usize, Self::Error> {
+        Ok(0)
+    }
+}
+
+impl<'a, const CLA: u8, const INS: u8> Decode<'a> for GetVersionReq<CLA, INS> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(_buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((Self {}, 0))
+    }
+}
+
+/// Get public key request APDU, deriving the key for a [Bip32Path] with optional
+/// on-device confirmation
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GetPublicKeyReq<const CLA: u8, const INS: u8> {
+    /// Derivation path
+    pub path: Bip32Path,
+    /// Display the key / address for user confirmation (sent as P1, not encoded in the APDU body)
+    pub confirm: bool,
+}
+
+impl<const CLA: u8, const INS: u8> GetPublicKeyReq<CLA, INS> {
+    /// Create a new get public key request APDU
+    pub fn new(path: Bip32Path, confirm: bool) -> Self {
+        Self { path, confirm }
+    }
+}
+
+/// Set CLA and INS values for [GetPublicKeyReq]
+impl<const C: u8, const I: u8> ApduStatic for GetPublicKeyReq<C, I> {
+    const CLA: u8 = C;
+
+    const INS: u8 = I;
+
+    /// Confirmation is requested via P1
+    fn p1(&self) -> u8 {
+        match self.confirm {
+            true => 0x01,
+            false => 0x00,
+        }
+    }
+}
+
+impl<const CLA: u8, const INS: u8> Encode for GetPublicKeyReq<CLA, INS> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        self.path.encode_len()
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        self.path.encode(buff)
+    }
+}
+
+/// [DecodeOwned] implementation for [GetPublicKeyReq], the confirmation flag is
+/// carried in the header so is not decoded
+impl<const CLA: u8, const INS: u8> DecodeOwned for GetPublicKeyReq<CLA, INS> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode_owned(buff: &[u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let (path, n) = Bip32Path::decode_owned(buff)?;
+        Ok((Self::new(path, false), n))
+    }
+}
+
+/// Sign chunk request APDU, a single command of a chunked signing flow (see [SignChunks]).
+///
+/// The first chunk contains the derivation path, optionally followed by the start of
+/// the data to be signed, with following chunks containing the remaining data.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SignChunkReq<'a, const CLA: u8, const INS: u8> {
+    /// Chunk marker (sent as P1, not encoded in the APDU body)
+    pub marker: u8,
+    /// Derivation path, for the first chunk
+    pub path: Option<Bip32Path>,
+    /// Chunk data
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub data: &'a [u8],
+}
+
+/// Set CLA and INS values for [SignChunkReq]
+impl<'a, const C: u8, const I: u8> ApduStatic for SignChunkReq<'a, C, I> {
+    const CLA: u8 = C;
+
+    const INS: u8 = I;
+
+    /// Chunk position is marked via P1
+    fn p1(&self) -> u8 {
+        self.marker
+    }
+}
+
+impl<'a, const CLA: u8, const INS: u8> Encode for SignChunkReq<'a, CLA, INS> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        let n = match &self.path {
+            Some(p) => p.encode_len()?,
+            None => 0,
+        };
+
+        Ok(n + self.data.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.encode_len()? {
+            return Err(ApduError::InvalidLength);
+        }
+
+        let mut index = 0;
+        if let Some(p) = &self.path {
+            index += p.encode(buff)?;
+        }
+
+        buff[index..][..self.data.len()].copy_from_slice(self.data);
+        index += self.data.len();
+
+        Ok(index)
+    }
+}
+
+/// [Decode] implementation for [SignChunkReq], the chunk position is carried in the
+/// header so the body is decoded as data
+impl<'a, const CLA: u8, const INS: u8> Decode<'a> for SignChunkReq<'a, CLA, INS> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((
+            Self {
+                marker: 0,
+                path: None,
+                data: buff,
+            },
+            buff.len(),
+        ))
+    }
+}
+
+/// Chunked signing request builder, iterating over [SignChunkReq] commands for a
+/// derivation path and payload.
+///
+/// By default the path is followed by the start of the payload in the first chunk,
+/// using [ChunkMarkers::FIRST_NEXT] and chunks of up to 255 bytes.
+#[derive(Clone, Debug)]
+pub struct SignChunks<'a, const CLA: u8, const INS: u8> {
+    path: Bip32Path,
+    data: &'a [u8],
+    chunk_size: usize,
+    markers: ChunkMarkers,
+    separate_path: bool,
+    index: usize,
+}
+
+impl<'a, const CLA: u8, const INS: u8> SignChunks<'a, CLA, INS> {
+    /// Create a chunked signing request for the provided path and payload
+    pub fn new(path: Bip32Path, data: &'a [u8]) -> Self {
+        Self {
+            path,
+            data,
+            chunk_size: SHORT_MAX_LEN,
+            markers: ChunkMarkers::default(),
+            separate_path: false,
+            index: 0,
+        }
+    }
+
+    /// Set the maximum chunk size (minimum 1), including the path in the first chunk
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set P1 chunk markers
+    pub fn with_markers(mut self, markers: ChunkMarkers) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// Send the path in a separate first chunk, as used by Zondax apps
+    /// (typically with [ChunkMarkers::INIT_ADD_LAST])
+    pub fn with_separate_path(mut self) -> Self {
+        self.separate_path = true;
+        self
+    }
+
+    /// Fetch the payload length carried by the first chunk
+    fn first_len(&self) -> usize {
+        match self.separate_path {
+            true => 0,
+            false => self
+                .chunk_size
+                .saturating_sub(self.path.encode_len().unwrap_or(0))
+                .min(self.data.len()),
+        }
+    }
+
+    /// Fetch the total number of chunks (at least one, for the path)
+    pub fn chunks(&self) -> usize {
+        let rest = self.data.len() - self.first_len();
+        1 + rest.div_ceil(self.chunk_size)
+    }
+}
+
+impl<'a, const CLA: u8, const INS: u8> Iterator for SignChunks<'a, CLA, INS> {
+    type Item = SignChunkReq<'a, CLA, INS>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let count = self.chunks();
+        if self.index >= count {
+            return None;
+        }
+
+        let marker = match self.index {
+            0 => self.markers.first,
+            i if i == count - 1 => self.markers.last.unwrap_or(self.markers.next),
+            _ => self.markers.next,
+        };
+
+        let first = self.first_len();
+        let (path, start, end) = match self.index {
+            0 => (Some(self.path), 0, first),
+            i => {
+                let start = first + (i - 1) * self.chunk_size;
+                (None, start, (start + self.chunk_size).min(self.data.len()))
+            }
+        };
+
+        self.index += 1;
+
+        Some(SignChunkReq {
+            marker,
+            path,
+            data: &self.data[start..end],
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.chunks() - self.index;
+        (n, Some(n))
+    }
+}
+
+impl<'a, const CLA: u8, const INS: u8> ExactSizeIterator for SignChunks<'a, CLA, INS> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_golden, ApduEncoder, ApduReq};
+
+    type GetVersion = GetVersionReq<0x55, 0x00>;
+    type GetPublicKey = GetPublicKeyReq<0x55, 0x01>;
+    type Sign<'a> = SignChunks<'a, 0x55, 0x02>;
+
+    fn path() -> Bip32Path {
+        "m/44'/118'/0'/0/0".parse().unwrap()
+    }
+
+    #[test]
+    fn get_version_req() {
+        assert_golden!(req: GetVersion::new(), &[0x55, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(GetVersion::decode(&[]), Ok((GetVersion::new(), 0)));
+    }
+
+    #[test]
+    fn get_public_key_req() {
+        let r = GetPublicKey::new(path(), true);
+        assert_eq!(r.header().p1, 0x01);
+        assert_eq!(GetPublicKey::new(path(), false).header().p1, 0x00);
+
+        let mut buff = [0u8; 32];
+        let n = r.encode(&mut buff).unwrap();
+        assert_eq!(n, 21);
+        assert_eq!(&buff[..5], &[0x05, 0x80, 0x00, 0x00, 0x2c]);
+
+        // Confirmation is header-carried so decodes as false
+        assert_eq!(
+            GetPublicKey::decode_owned(&buff[..n]),
+            Ok((GetPublicKey::new(path(), false), n))
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn sign_chunks() {
+        let data = [0xaa; 500];
+
+        // Path shares the first chunk with the start of the payload
+        let c: crate::Vec<_> = Sign::new(path(), &data).collect();
+        assert_eq!(c.len(), 3);
+        assert_eq!(c[0].path, Some(path()));
+        assert_eq!(c[0].data.len(), 255 - 21);
+        assert_eq!(c[0].encode_len(), Ok(255));
+        assert_eq!(c[1].data.len(), 255);
+        assert_eq!(c[2].data.len(), 500 - 255 - 234);
+        assert_eq!(
+            c.iter().map(|c| c.marker).collect::<crate::Vec<_>>(),
+            [0x00, 0x80, 0x80]
+        );
+
+        // Separate path chunk with init / add / last markers
+        let s = Sign::new(path(), &data[..300])
+            .with_separate_path()
+            .with_markers(ChunkMarkers::INIT_ADD_LAST);
+        assert_eq!(s.len(), 3);
+
+        let c: crate::Vec<_> = s.collect();
+        assert_eq!((c[0].marker, c[0].data.len()), (0x00, 0));
+        assert_eq!((c[1].marker, c[1].path, c[1].data.len()), (0x01, None, 255));
+        assert_eq!((c[2].marker, c[2].data.len()), (0x02, 45));
+
+        let mut buff = [0u8; 300];
+        let n = ApduEncoder::encode(&c[0], &mut buff).unwrap();
+        assert_eq!(&buff[..6], &[0x55, 0x02, 0x00, 0x00, 21, 0x05]);
+        assert_eq!(n, 5 + 21);
+
+        // Empty payloads produce a single path chunk
+        let c: crate::Vec<_> = Sign::new(path(), &[]).collect();
+        assert_eq!(c.len(), 1);
+        assert_eq!(c[0].encode_len(), Ok(21));
+    }
+}
@@ -0,0 +1,87 @@
+//! Wallet identifier request and response APDUs
+
+use encdec::{Decode, Encode};
+
+use crate::{ApduError, ApduStatic};
+
+/// Wallet identifier request APDU, fetches the seed-derived wallet identifier
+/// (used to check whether devices share the same seed without exposing keys)
+#[derive(Copy, Clone, PartialEq, Debug, Default, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[encdec(error = "ApduError")]
+pub struct WalletIdReq {}
+
+/// Set CLA and INS values for [WalletIdReq]
+impl ApduStatic for WalletIdReq {
+    /// Wallet ID request APDU is class `0xe0`
+    const CLA: u8 = 0xe0;
+
+    /// Wallet ID request APDU is instruction `0x04`
+    const INS: u8 = 0x04;
+}
+
+/// Wallet identifier response APDU
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WalletIdResp<'a> {
+    /// Wallet identifier
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub id: &'a [u8],
+}
+
+impl<'a> WalletIdResp<'a> {
+    /// Create a new wallet identifier response APDU
+    pub fn new(id: &'a [u8]) -> Self {
+        Self { id }
+    }
+}
+
+impl<'a> Encode for WalletIdResp<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.id.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.id.len() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[..self.id.len()].copy_from_slice(self.id);
+
+        Ok(self.id.len())
+    }
+}
+
+impl<'a> Decode<'a> for WalletIdResp<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        // Wallet identifiers are opaque, reject only empty responses
+        if buff.is_empty() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        Ok((Self { id: buff }, buff.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wallet_id_resp() {
+        let r = WalletIdResp::new(&[0xab; 32]);
+
+        let mut buff = [0u8; 256];
+        crate::testing::encode_decode(&mut buff, r);
+    }
+}
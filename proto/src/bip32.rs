@@ -0,0 +1,303 @@
+//! BIP32 derivation paths, encoded in the standard Ledger format
+//! (component count followed by big-endian `u32` components)
+
+use core::{fmt::Display, str::FromStr};
+
+use encdec::{DecodeOwned, Encode};
+
+use crate::ApduError;
+
+/// Hardened derivation index offset
+pub const HARDENED: u32 = 0x8000_0000;
+
+/// Maximum supported path depth (matches the BOLOS limit)
+pub const MAX_BIP32_DEPTH: usize = 10;
+
+/// BIP32 derivation path, for example `m/44'/60'/0'/0/0`
+///
+/// ```
+/// use ledger_proto::{Bip32Path, Encode};
+///
+/// let p: Bip32Path = "m/44'/60'/0'/0/0".parse().unwrap();
+/// assert_eq!(p.components()[0], Bip32Path::hardened(44));
+///
+/// let mut buff = [0u8; 32];
+/// let n = p.encode(&mut buff).unwrap();
+/// assert_eq!(&buff[..5], &[0x05, 0x80, 0x00, 0x00, 0x2c]);
+/// assert_eq!(n, 21);
+/// ```
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Bip32Path {
+    components: [u32; MAX_BIP32_DEPTH],
+    len: usize,
+}
+
+/// BIP32 path parsing errors
+#[derive(Copy, Clone, PartialEq, Debug, displaydoc::Display)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum Bip32Error {
+    /// Path exceeds maximum depth ({0})
+    TooDeep(usize),
+
+    /// Invalid path component at index {0}
+    InvalidComponent(usize),
+
+    /// Path component at index {0} exceeds the hardened index range
+    IndexOutOfRange(usize),
+}
+
+/// [core::error::Error] implementation for [Bip32Error] without the `std` feature
+#[cfg(not(feature = "std"))]
+impl core::error::Error for Bip32Error {}
+
+impl Bip32Path {
+    /// Create a path from raw components (hardened components include the [HARDENED] offset)
+    pub fn new(components: &[u32]) -> Result<Self, Bip32Error> {
+        if components.len() > MAX_BIP32_DEPTH {
+            return Err(Bip32Error::TooDeep(components.len()));
+        }
+
+        let mut p = Self {
+            components: [0u32; MAX_BIP32_DEPTH],
+            len: components.len(),
+        };
+        p.components[..components.len()].copy_from_slice(components);
+
+        Ok(p)
+    }
+
+    /// Fetch path components
+    pub fn components(&self) -> &[u32] {
+        &self.components[..self.len]
+    }
+
+    /// Fetch path depth
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the path is empty (the master key, `m`)
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append a component to the path, returning the extended path
+    pub fn child(mut self, index: u32) -> Result<Self, Bip32Error> {
+        if self.len >= MAX_BIP32_DEPTH {
+            return Err(Bip32Error::TooDeep(self.len + 1));
+        }
+
+        self.components[self.len] = index;
+        self.len += 1;
+
+        Ok(self)
+    }
+
+    /// Build a hardened index
+    pub const fn hardened(index: u32) -> u32 {
+        index | HARDENED
+    }
+
+    /// Check whether an index is hardened
+    pub const fn is_hardened(index: u32) -> bool {
+        index & HARDENED != 0
+    }
+}
+
+impl core::fmt::Debug for Bip32Path {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Bip32Path({self})")
+    }
+}
+
+/// Format paths as `m/44'/60'/0'/0/0`
+impl Display for Bip32Path {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "m")?;
+
+        for c in self.components() {
+            match Self::is_hardened(*c) {
+                true => write!(f, "/{}'", c & !HARDENED)?,
+                false => write!(f, "/{c}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse paths from strings, with or without the `m/` prefix, using `'`, `h` or `H`
+/// to indicate hardened components
+impl FromStr for Bip32Path {
+    type Err = Bip32Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("m").unwrap_or(s);
+        let s = s.strip_prefix('/').unwrap_or(s);
+
+        let mut p = Self::default();
+        if s.is_empty() {
+            return Ok(p);
+        }
+
+        for (i, c) in s.split('/').enumerate() {
+            let (c, hardened) = match c.strip_suffix(['\'', 'h', 'H']) {
+                Some(v) => (v, true),
+                None => (c, false),
+            };
+
+            let index = c
+                .parse::<u32>()
+                .map_err(|_| Bip32Error::InvalidComponent(i))?;
+            if Self::is_hardened(index) {
+                return Err(Bip32Error::IndexOutOfRange(i));
+            }
+
+            p = p.child(match hardened {
+                true => Self::hardened(index),
+                false => index,
+            })?;
+        }
+
+        Ok(p)
+    }
+}
+
+impl TryFrom<&[u32]> for Bip32Path {
+    type Error = Bip32Error;
+
+    fn try_from(value: &[u32]) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+/// [Encode] implementation for [Bip32Path], count followed by big-endian components
+impl Encode for Bip32Path {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1 + self.len * 4)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = self.len as u8;
+        for (i, c) in self.components().iter().enumerate() {
+            buff[1 + i * 4..][..4].copy_from_slice(&c.to_be_bytes());
+        }
+
+        Ok(n)
+    }
+}
+
+/// [DecodeOwned] implementation for [Bip32Path]
+impl DecodeOwned for Bip32Path {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode_owned(buff: &[u8]) -> Result<(Self::Output, usize), Self::Error> {
+        if buff.is_empty() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        let len = buff[0] as usize;
+        if len > MAX_BIP32_DEPTH {
+            return Err(ApduError::InvalidEncoding);
+        }
+        if buff.len() < 1 + len * 4 {
+            return Err(ApduError::InvalidLength);
+        }
+
+        let mut p = Self {
+            components: [0u32; MAX_BIP32_DEPTH],
+            len,
+        };
+        for (i, c) in p.components[..len].iter_mut().enumerate() {
+            let b = &buff[1 + i * 4..][..4];
+            *c = u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+        }
+
+        Ok((p, 1 + len * 4))
+    }
+}
+
+/// Serialise paths as strings
+#[cfg(feature = "serde")]
+impl serde::Serialize for Bip32Path {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Deserialise paths from strings
+#[cfg(all(feature = "serde", feature = "alloc"))]
+impl<'de> serde::Deserialize<'de> for Bip32Path {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = alloc::string::String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_paths() {
+        let p: Bip32Path = "m/44'/60'/0'/0/0".parse().unwrap();
+        assert_eq!(
+            p.components(),
+            &[HARDENED | 44, HARDENED | 60, HARDENED, 0, 0]
+        );
+
+        assert_eq!("44h/60H/0'/0/0".parse::<Bip32Path>().unwrap(), p);
+        assert_eq!("m".parse::<Bip32Path>().unwrap(), Bip32Path::default());
+
+        assert_eq!(
+            "m/44'/a".parse::<Bip32Path>(),
+            Err(Bip32Error::InvalidComponent(1))
+        );
+        assert_eq!(
+            "m/2147483648".parse::<Bip32Path>(),
+            Err(Bip32Error::IndexOutOfRange(0))
+        );
+        assert_eq!(
+            "m/0/1/2/3/4/5/6/7/8/9/10".parse::<Bip32Path>(),
+            Err(Bip32Error::TooDeep(11))
+        );
+        assert!("m//0".parse::<Bip32Path>().is_err());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn display_paths() {
+        use alloc::string::ToString;
+
+        let s = "m/44'/60'/0'/0/0";
+        assert_eq!(s.parse::<Bip32Path>().unwrap().to_string(), s);
+    }
+
+    #[test]
+    fn encode_decode_paths() {
+        let p: Bip32Path = "m/44'/0'/1".parse().unwrap();
+
+        let mut buff = [0u8; 64];
+        crate::testing::encode_decode(&mut buff, p);
+
+        let n = p.encode(&mut buff).unwrap();
+        assert_eq!(
+            &buff[..n],
+            &[0x03, 0x80, 0x00, 0x00, 0x2c, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01]
+        );
+
+        // Truncated and over-long paths are rejected
+        assert!(Bip32Path::decode_owned(&buff[..n - 1]).is_err());
+        assert!(Bip32Path::decode_owned(&[11u8; 45]).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Borrowed APDU objects, supporting raw exchanges without copying or allocating
+//! request and response data.
+//!
+//! ```
+//! use ledger_proto::{ApduEncoder, ApduHeader, Decode, GenericApduRef};
+//!
+//! // Encode a request over borrowed data
+//! let h = ApduHeader { cla: 0xe0, ins: 0x02, p1: 0x00, p2: 0x00 };
+//! let req = GenericApduRef::new(h, &[0xaa, 0xbb]);
+//!
+//! let mut buff = [0u8; 16];
+//! let n = ApduEncoder::encode(&req, &mut buff).unwrap();
+//! assert_eq!(&buff[..n], &[0xe0, 0x02, 0x00, 0x00, 0x02, 0xaa, 0xbb]);
+//!
+//! // Decode a response borrowing from the receive buffer
+//! let (r, _) = GenericApduRef::decode(&[0x01, 0x02]).unwrap();
+//! assert_eq!(r.data, &[0x01, 0x02]);
+//! ```
+
+use encdec::{Decode, Encode};
+
+use crate::{ApduError, ApduHeader, ApduReq};
+
+/// Borrowed generic APDU object, referencing data rather than owning it
+/// (see [GenericApdu](crate::GenericApdu) for the `alloc` equivalent)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct GenericApduRef<'a> {
+    /// Request APDU Header (uses [Default] for incoming / response APDUs)
+    pub header: ApduHeader,
+    /// APDU data
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))]
+    pub data: &'a [u8],
+}
+
+impl<'a> GenericApduRef<'a> {
+    /// Create a new borrowed APDU
+    pub fn new(header: ApduHeader, data: &'a [u8]) -> Self {
+        Self { header, data }
+    }
+}
+
+/// [ApduReq] implementation for [GenericApduRef], exposes internal header
+impl<'a> ApduReq<'a> for GenericApduRef<'a> {
+    fn header(&self) -> ApduHeader {
+        self.header
+    }
+}
+
+/// [Encode] implementation for [GenericApduRef]
+impl<'a> Encode for GenericApduRef<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.data.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        // Check buffer length
+        if buff.len() < self.data.len() {
+            return Err(ApduError::InvalidLength);
+        }
+        // Copy data
+        buff[..self.data.len()].copy_from_slice(self.data);
+        // Return write length
+        Ok(self.data.len())
+    }
+}
+
+/// [Decode] implementation for [GenericApduRef], borrowing the full buffer
+impl<'a> Decode<'a> for GenericApduRef<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((Self::new(Default::default(), buff), buff.len()))
+    }
+}
+
+/// Borrow a [GenericApdu](crate::GenericApdu) as a [GenericApduRef]
+#[cfg(feature = "alloc")]
+impl<'a> From<&'a crate::GenericApdu> for GenericApduRef<'a> {
+    fn from(a: &'a crate::GenericApdu) -> Self {
+        Self::new(a.header, &a.data)
+    }
+}
+
+/// Convert a [GenericApduRef] to a [GenericApdu](crate::GenericApdu), copying the data
+#[cfg(feature = "alloc")]
+impl<'a> From<GenericApduRef<'a>> for crate::GenericApdu {
+    fn from(a: GenericApduRef<'a>) -> Self {
+        Self {
+            header: a.header,
+            data: a.data.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_golden, testing::encode_decode};
+
+    #[test]
+    fn generic_apdu_ref() {
+        let h = ApduHeader {
+            cla: 0xe0,
+            ins: 0x01,
+            p1: 0x02,
+            p2: 0x03,
+        };
+        let a = GenericApduRef::new(h, &[0xaa, 0xbb]);
+        assert_eq!(a.header(), h);
+
+        assert_golden!(req: a, &[0xe0, 0x01, 0x02, 0x03, 0x02, 0xaa, 0xbb]);
+
+        let mut buff = [0u8; 8];
+        assert_eq!(a.encode(&mut buff[..1]), Err(ApduError::InvalidLength));
+
+        // Decoded objects borrow the full buffer with a default header
+        encode_decode(
+            &mut buff,
+            GenericApduRef::new(Default::default(), &[0x01, 0x02]),
+        );
+        assert_eq!(
+            GenericApduRef::decode(&[]),
+            Ok((GenericApduRef::default(), 0))
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn generic_apdu_ref_convert() {
+        let a = crate::GenericApdu::build()
+            .cla(0xb0)
+            .ins(0x01)
+            .data([0x01, 0x02])
+            .finish();
+
+        let r = GenericApduRef::from(&a);
+        assert_eq!((r.header, r.data), (a.header, &[0x01, 0x02][..]));
+        assert_eq!(crate::GenericApdu::from(r), a);
+    }
+}
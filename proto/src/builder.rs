@@ -0,0 +1,254 @@
+//! Fluent builder for [GenericApdu] requests, see [GenericApdu::build], and
+//! conversions to / from framed hex strings
+
+use alloc::{string::String, vec::Vec};
+use core::str::FromStr;
+
+use crate::{length::ApduCase, ApduEncoder, ApduError, ApduHeader, GenericApdu};
+
+/// [GenericApdu] builder, simplifying construction of ad-hoc requests.
+///
+/// ```
+/// use ledger_proto::GenericApdu;
+///
+/// let apdu = GenericApdu::build()
+///     .cla(0xe0)
+///     .ins(0x03)
+///     .p1(0x01)
+///     .hex("0x00aabb")
+///     .unwrap()
+///     .finish();
+///
+/// assert_eq!(apdu.header.ins, 0x03);
+/// assert_eq!(apdu.data, &[0x00, 0xaa, 0xbb]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GenericApduBuilder {
+    header: ApduHeader,
+    data: Vec<u8>,
+}
+
+impl GenericApdu {
+    /// Create a new [GenericApduBuilder] with an empty header and data
+    pub fn build() -> GenericApduBuilder {
+        GenericApduBuilder::default()
+    }
+}
+
+impl GenericApduBuilder {
+    /// Set the APDU class
+    pub fn cla(mut self, cla: u8) -> Self {
+        self.header.cla = cla;
+        self
+    }
+
+    /// Set the APDU instruction
+    pub fn ins(mut self, ins: u8) -> Self {
+        self.header.ins = ins;
+        self
+    }
+
+    /// Set the first APDU parameter
+    pub fn p1(mut self, p1: u8) -> Self {
+        self.header.p1 = p1;
+        self
+    }
+
+    /// Set the second APDU parameter
+    pub fn p2(mut self, p2: u8) -> Self {
+        self.header.p2 = p2;
+        self
+    }
+
+    /// Set the APDU data
+    pub fn data(mut self, data: impl AsRef<[u8]>) -> Self {
+        self.data = data.as_ref().to_vec();
+        self
+    }
+
+    /// Set the APDU data from a hex string (with optional `0x` prefix)
+    pub fn hex(mut self, s: &str) -> Result<Self, ApduError> {
+        let s = s.trim();
+        self.data = parse_hex(s.strip_prefix("0x").unwrap_or(s))?;
+
+        Ok(self)
+    }
+
+    /// Build the [GenericApdu]
+    pub fn finish(self) -> GenericApdu {
+        GenericApdu {
+            header: self.header,
+            data: self.data,
+        }
+    }
+}
+
+/// Parse a hex string (without prefix) to bytes
+pub(crate) fn parse_hex(s: &str) -> Result<Vec<u8>, ApduError> {
+    if !s.len().is_multiple_of(2) {
+        return Err(ApduError::InvalidLength);
+    }
+
+    s.as_bytes()
+        .chunks(2)
+        .map(|c| {
+            let n = |v: u8| (v as char).to_digit(16).ok_or(ApduError::InvalidEncoding);
+            Ok((n(c[0])? << 4 | n(c[1])?) as u8)
+        })
+        .collect()
+}
+
+impl From<GenericApduBuilder> for GenericApdu {
+    fn from(b: GenericApduBuilder) -> Self {
+        b.finish()
+    }
+}
+
+impl GenericApdu {
+    /// Decode a framed command APDU (header, Lc, data, optional Le).
+    ///
+    /// Any Le field is validated but discarded, as this is not stored in [GenericApdu].
+    pub fn from_frame(frame: &[u8]) -> Result<Self, ApduError> {
+        let c = ApduCase::classify(frame)?;
+
+        Ok(Self {
+            header: ApduHeader {
+                cla: frame[0],
+                ins: frame[1],
+                p1: frame[2],
+                p2: frame[3],
+            },
+            data: c.data(frame).to_vec(),
+        })
+    }
+
+    /// Encode the framed command APDU (header, Lc, data) as a lowercase hex string,
+    /// as used in logs and transcripts
+    ///
+    /// ```
+    /// use ledger_proto::GenericApdu;
+    ///
+    /// let a: GenericApdu = "e0d8000003425443".parse().unwrap();
+    /// assert_eq!((a.header.ins, &a.data[..]), (0xd8, &b"BTC"[..]));
+    ///
+    /// assert_eq!(a.to_hex_string().unwrap(), "e0d8000003425443");
+    /// ```
+    pub fn to_hex_string(&self) -> Result<String, ApduError> {
+        let b = ApduEncoder::encode_vec(self)?;
+
+        let mut s = String::with_capacity(b.len() * 2);
+        for v in b {
+            let _ = core::fmt::Write::write_fmt(&mut s, format_args!("{v:02x}"));
+        }
+
+        Ok(s)
+    }
+}
+
+/// Parse a [GenericApdu] from a framed hex string (with optional `0x` prefix,
+/// ignoring whitespace), see [GenericApdu::from_frame]
+impl FromStr for GenericApdu {
+    type Err = ApduError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s: String = s
+            .strip_prefix("0x")
+            .unwrap_or(s)
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+
+        Self::from_frame(&parse_hex(&s)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_apdu() {
+        let a = GenericApdu::build()
+            .cla(0xe0)
+            .ins(0x02)
+            .p1(0x01)
+            .p2(0x02)
+            .data([0xaa, 0xbb])
+            .finish();
+
+        assert_eq!(
+            a,
+            GenericApdu {
+                header: ApduHeader {
+                    cla: 0xe0,
+                    ins: 0x02,
+                    p1: 0x01,
+                    p2: 0x02
+                },
+                data: alloc::vec![0xaa, 0xbb],
+            }
+        );
+    }
+
+    #[test]
+    fn build_apdu_hex() {
+        let b = GenericApdu::build();
+
+        assert_eq!(b.clone().hex("AaBb").unwrap().finish().data, &[0xaa, 0xbb]);
+        assert_eq!(b.clone().hex("").unwrap().finish().data, &[] as &[u8]);
+
+        assert!(b.clone().hex("0xabc").is_err());
+        assert!(b.clone().hex("zz").is_err());
+        assert!(b.hex("+1").is_err());
+    }
+
+    #[test]
+    fn hex_string() {
+        let a = GenericApdu::build()
+            .cla(0xe0)
+            .ins(0x02)
+            .p1(0x01)
+            .data([0xaa, 0xbb])
+            .finish();
+
+        assert_eq!(a.to_hex_string().unwrap(), "e002010002aabb");
+        assert_eq!("e002010002aabb".parse(), Ok(a.clone()));
+        assert_eq!("0xE0 02 01 00 02 AA BB\n".parse(), Ok(a));
+
+        // Header-only requests are encoded with a zero Lc
+        let a = GenericApdu::build().cla(0xb0).ins(0x01).finish();
+        assert_eq!(a.to_hex_string().unwrap(), "b001000000");
+        assert_eq!("b001000000".parse(), Ok(a.clone()));
+        assert_eq!("b0010000".parse(), Ok(a));
+
+        // Extended lengths, with Le discarded
+        let a = GenericApdu::build()
+            .cla(0xe0)
+            .ins(0x04)
+            .data([0x11; 300])
+            .finish();
+        let s = a.to_hex_string().unwrap();
+        assert!(s.starts_with("e004000000012c1111"));
+        assert_eq!(s.parse(), Ok(a.clone()));
+        assert_eq!((s + "0000").parse(), Ok(a));
+
+        // Invalid hex and inconsistent lengths are rejected
+        assert_eq!(
+            "e00201".parse::<GenericApdu>(),
+            Err(ApduError::InvalidLength)
+        );
+        assert_eq!(
+            "e0020100zz".parse::<GenericApdu>(),
+            Err(ApduError::InvalidEncoding)
+        );
+        assert!("e002010003aabb"
+            .parse::<GenericApdu>()
+            .unwrap_err()
+            .is_length());
+        assert_eq!(
+            "e002010001aabbcc".parse::<GenericApdu>(),
+            Err(ApduError::InvalidEncoding)
+        );
+    }
+}
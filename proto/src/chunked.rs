@@ -0,0 +1,262 @@
+//! Chunked command helper, splitting payloads larger than a single APDU across multiple
+//! commands using P1 to mark the first / next / last chunks
+
+use encdec::{Decode, Encode};
+
+use crate::{length::SHORT_MAX_LEN, ApduError, ApduHeader, ApduReq};
+
+/// P1 markers identifying chunk positions
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ChunkMarkers {
+    /// P1 for the first chunk (including single-chunk payloads)
+    pub first: u8,
+    /// P1 for following chunks
+    pub next: u8,
+    /// P1 for the final chunk, where distinct from `next`
+    pub last: Option<u8>,
+}
+
+impl ChunkMarkers {
+    /// First / next markers (`0x00`, `0x80`), as used by the Ethereum and Bitcoin apps
+    pub const FIRST_NEXT: Self = Self {
+        first: 0x00,
+        next: 0x80,
+        last: None,
+    };
+
+    /// First / following markers (`0x01`, `0x00`), as used for TLV payloads
+    /// (see [TlvPayload](crate::apdus::TlvPayload))
+    pub const FIRST_FOLLOWING: Self = Self {
+        first: 0x01,
+        next: 0x00,
+        last: None,
+    };
+
+    /// Init / add / last markers (`0x00`, `0x01`, `0x02`), as used by Zondax apps
+    pub const INIT_ADD_LAST: Self = Self {
+        first: 0x00,
+        next: 0x01,
+        last: Some(0x02),
+    };
+}
+
+impl Default for ChunkMarkers {
+    fn default() -> Self {
+        Self::FIRST_NEXT
+    }
+}
+
+/// Chunked request builder, iterating over [ReqChunk] commands for a payload.
+///
+/// ```
+/// use ledger_proto::{ChunkedReq, ChunkMarkers};
+///
+/// let payload = [0xaa; 600];
+/// let chunks: Vec<_> = ChunkedReq::new(0xe0, 0x04, &payload)
+///     .with_markers(ChunkMarkers::INIT_ADD_LAST)
+///     .collect();
+///
+/// assert_eq!(chunks.len(), 3);
+/// assert_eq!(chunks[0].header.p1, 0x00);
+/// assert_eq!(chunks[1].header.p1, 0x01);
+/// assert_eq!(chunks[2].header.p1, 0x02);
+/// assert_eq!(chunks[2].data.len(), 600 - 2 * 255);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ChunkedReq<'a> {
+    cla: u8,
+    ins: u8,
+    p2: u8,
+    data: &'a [u8],
+    chunk_size: usize,
+    markers: ChunkMarkers,
+    index: usize,
+}
+
+impl<'a> ChunkedReq<'a> {
+    /// Create a chunked request for the provided payload, using [ChunkMarkers::FIRST_NEXT]
+    /// and chunks of up to 255 bytes
+    pub fn new(cla: u8, ins: u8, data: &'a [u8]) -> Self {
+        Self {
+            cla,
+            ins,
+            p2: 0,
+            data,
+            chunk_size: SHORT_MAX_LEN,
+            markers: ChunkMarkers::default(),
+            index: 0,
+        }
+    }
+
+    /// Set P2 for all chunks
+    pub fn with_p2(mut self, p2: u8) -> Self {
+        self.p2 = p2;
+        self
+    }
+
+    /// Set the maximum chunk size (minimum 1)
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Set P1 chunk markers
+    pub fn with_markers(mut self, markers: ChunkMarkers) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// Fetch the total number of chunks (at least one, for empty payloads)
+    pub fn chunks(&self) -> usize {
+        self.data.len().div_ceil(self.chunk_size).max(1)
+    }
+}
+
+impl<'a> Iterator for ChunkedReq<'a> {
+    type Item = ReqChunk<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let count = self.chunks();
+        if self.index >= count {
+            return None;
+        }
+
+        let p1 = match self.index {
+            0 => self.markers.first,
+            i if i == count - 1 => self.markers.last.unwrap_or(self.markers.next),
+            _ => self.markers.next,
+        };
+
+        let start = self.index * self.chunk_size;
+        let end = (start + self.chunk_size).min(self.data.len());
+
+        self.index += 1;
+
+        Some(ReqChunk {
+            header: ApduHeader {
+                cla: self.cla,
+                ins: self.ins,
+                p1,
+                p2: self.p2,
+            },
+            data: &self.data[start..end],
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.chunks() - self.index;
+        (n, Some(n))
+    }
+}
+
+impl<'a> ExactSizeIterator for ChunkedReq<'a> {}
+
+/// Single chunk of a [ChunkedReq], borrowing from the payload
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ReqChunk<'a> {
+    /// Chunk command header
+    pub header: ApduHeader,
+    /// Chunk data
+    pub data: &'a [u8],
+}
+
+/// [ApduReq] implementation for [ReqChunk], exposes the chunk header
+impl<'a> ApduReq<'a> for ReqChunk<'a> {
+    fn header(&self) -> ApduHeader {
+        self.header
+    }
+}
+
+impl<'a> Encode for ReqChunk<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.data.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.data.len() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[..self.data.len()].copy_from_slice(self.data);
+
+        Ok(self.data.len())
+    }
+}
+
+/// [Decode] implementation for [ReqChunk], headers are not encoded so use [Default]
+impl<'a> Decode<'a> for ReqChunk<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((
+            Self {
+                header: ApduHeader::default(),
+                data: buff,
+            },
+            buff.len(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_markers() {
+        let data = [0u8; 600];
+
+        let p1: [u8; 3] = core::array::from_fn({
+            let mut c = ChunkedReq::new(0xe0, 0x04, &data);
+            move |_| c.next().unwrap().header.p1
+        });
+        assert_eq!(p1, [0x00, 0x80, 0x80]);
+
+        // Single chunks use the first marker
+        let mut c =
+            ChunkedReq::new(0xe0, 0x04, &data[..10]).with_markers(ChunkMarkers::INIT_ADD_LAST);
+        assert_eq!(c.len(), 1);
+        assert_eq!(c.next().unwrap().header.p1, 0x00);
+        assert!(c.next().is_none());
+
+        // Empty payloads yield a single empty chunk
+        let mut c = ChunkedReq::new(0xe0, 0x04, &[]);
+        assert_eq!(c.next().unwrap().data, &[] as &[u8]);
+        assert!(c.next().is_none());
+    }
+
+    #[test]
+    fn chunk_sizes() {
+        let data: [u8; 100] = core::array::from_fn(|i| i as u8);
+
+        let c = ChunkedReq::new(0xe0, 0x04, &data)
+            .with_p2(0x01)
+            .with_chunk_size(30);
+        assert_eq!(c.len(), 4);
+
+        let mut index = 0;
+        for (i, chunk) in c.enumerate() {
+            assert_eq!(chunk.header.p2, 0x01);
+            assert_eq!(chunk.data, &data[index..][..chunk.data.len()]);
+            assert_eq!(chunk.data.len(), if i < 3 { 30 } else { 10 });
+            index += chunk.data.len();
+        }
+        assert_eq!(index, data.len());
+    }
+
+    #[test]
+    fn encode_decode_chunk() {
+        let data = [0xaa; 10];
+        let chunk = ChunkedReq::new(0xe0, 0x04, &data).next().unwrap();
+
+        let mut buff = [0u8; 16];
+        let n = chunk.encode(&mut buff).unwrap();
+        let (d, _) = ReqChunk::decode(&buff[..n]).unwrap();
+
+        assert_eq!(d.data, chunk.data);
+    }
+}
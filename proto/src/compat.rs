@@ -0,0 +1,151 @@
+//! Conversions between ledger-proto types and the [ledger_apdu] command / answer types
+//! (enabled with the `ledger_apdu` feature), easing migration of existing code.
+//!
+//! [APDUAnswer] status words are checked on conversion, as [GenericApdu] carries
+//! response data only.
+
+#[cfg(feature = "alloc")]
+use core::ops::Deref;
+
+#[cfg(feature = "alloc")]
+use ledger_apdu::APDUAnswer;
+use ledger_apdu::APDUCommand;
+
+use crate::ApduHeader;
+#[cfg(feature = "alloc")]
+use crate::{ApduError, GenericApdu, Vec};
+
+/// Successful APDU status word
+#[cfg(feature = "alloc")]
+const STATUS_OK: u16 = 0x9000;
+
+/// Fetch an [ApduHeader] from an [APDUCommand]
+impl<B> From<&APDUCommand<B>> for ApduHeader {
+    fn from(c: &APDUCommand<B>) -> Self {
+        Self {
+            cla: c.cla,
+            ins: c.ins,
+            p1: c.p1,
+            p2: c.p2,
+        }
+    }
+}
+
+/// Convert an [APDUCommand] to a [GenericApdu] request
+#[cfg(feature = "alloc")]
+impl<B: Deref<Target = [u8]>> From<APDUCommand<B>> for GenericApdu {
+    fn from(c: APDUCommand<B>) -> Self {
+        Self {
+            header: ApduHeader::from(&c),
+            data: c.data.to_vec(),
+        }
+    }
+}
+
+/// Convert a [GenericApdu] request to an [APDUCommand], failing where data exceeds
+/// the maximum (short) APDU length
+#[cfg(feature = "alloc")]
+impl TryFrom<GenericApdu> for APDUCommand<Vec<u8>> {
+    type Error = ApduError;
+
+    fn try_from(a: GenericApdu) -> Result<Self, Self::Error> {
+        if a.data.len() > u8::MAX as usize {
+            return Err(ApduError::InvalidLength);
+        }
+
+        Ok(Self {
+            cla: a.header.cla,
+            ins: a.header.ins,
+            p1: a.header.p1,
+            p2: a.header.p2,
+            data: a.data,
+        })
+    }
+}
+
+/// Convert an [APDUAnswer] to a [GenericApdu] response,
+/// returning the status word on error where this is not `0x9000`
+#[cfg(feature = "alloc")]
+impl<B: Deref<Target = [u8]>> TryFrom<APDUAnswer<B>> for GenericApdu {
+    type Error = u16;
+
+    fn try_from(a: APDUAnswer<B>) -> Result<Self, Self::Error> {
+        if a.retcode() != STATUS_OK {
+            return Err(a.retcode());
+        }
+
+        Ok(Self {
+            header: Default::default(),
+            data: a.data().to_vec(),
+        })
+    }
+}
+
+/// Convert a [GenericApdu] response to an [APDUAnswer] with a successful status
+#[cfg(feature = "alloc")]
+impl From<GenericApdu> for APDUAnswer<Vec<u8>> {
+    fn from(a: GenericApdu) -> Self {
+        let mut data = a.data;
+        data.extend_from_slice(&STATUS_OK.to_be_bytes());
+
+        match APDUAnswer::from_answer(data) {
+            Ok(v) => v,
+            // Answers are always at least two bytes with the appended status
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_conversions() {
+        let c = APDUCommand {
+            cla: 0xe0,
+            ins: 0x01,
+            p1: 0x02,
+            p2: 0x03,
+            data: vec![0xaa, 0xbb],
+        };
+
+        let a = GenericApdu::from(c.clone());
+        assert_eq!(
+            a.header,
+            ApduHeader {
+                cla: 0xe0,
+                ins: 0x01,
+                p1: 0x02,
+                p2: 0x03
+            }
+        );
+        assert_eq!(&a.data, &[0xaa, 0xbb]);
+
+        let c1 = APDUCommand::try_from(a).unwrap();
+        assert_eq!(
+            (c1.cla, c1.ins, c1.p1, c1.p2, &c1.data),
+            (c.cla, c.ins, c.p1, c.p2, &c.data)
+        );
+
+        let a = GenericApdu {
+            header: Default::default(),
+            data: vec![0u8; 256],
+        };
+        assert!(APDUCommand::try_from(a).is_err());
+    }
+
+    #[test]
+    fn answer_conversions() {
+        let a = APDUAnswer::from_answer(vec![0xaa, 0x90, 0x00]).unwrap();
+        let g = GenericApdu::try_from(a).unwrap();
+        assert_eq!(&g.data, &[0xaa]);
+
+        let a = APDUAnswer::<Vec<u8>>::from(g);
+        assert_eq!(a.data(), &[0xaa]);
+        assert_eq!(a.retcode(), STATUS_OK);
+
+        let a = APDUAnswer::from_answer(vec![0x69, 0x85]).unwrap();
+        assert_eq!(GenericApdu::try_from(a), Err(0x6985));
+    }
+}
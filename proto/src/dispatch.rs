@@ -0,0 +1,188 @@
+//! Device-side APDU dispatch (enabled with `alloc` feature), routing incoming commands
+//! to handlers by CLA and INS for building in-process device emulators and app-side handlers.
+//!
+//! ```
+//! use ledger_proto::{apdus::AppInfoReq, ApduDispatcher};
+//!
+//! // Dispatcher with a call counter as context
+//! let mut d = ApduDispatcher::<u32>::new();
+//! d.on::<AppInfoReq>(|calls, _h, _data| {
+//!     *calls += 1;
+//!     Ok(vec![0x01, 0x00, 0x00])
+//! });
+//!
+//! let mut calls = 0;
+//! let resp = d.dispatch(&mut calls, &[0xb0, 0x01, 0x00, 0x00, 0x00]);
+//! assert_eq!(resp, &[0x01, 0x00, 0x00, 0x90, 0x00]);
+//! assert_eq!(calls, 1);
+//!
+//! // Unregistered instructions return a status
+//! let resp = d.dispatch(&mut calls, &[0xb0, 0x02, 0x00, 0x00, 0x00]);
+//! assert_eq!(resp, &[0x6d, 0x00]);
+//! ```
+
+use alloc::boxed::Box;
+
+use crate::{length::ApduCase, ApduHeader, ApduStatic, StatusCode, Vec};
+
+/// Dispatcher handler result, response data (excluding status) or an error status
+pub type HandlerResult = Result<Vec<u8>, StatusCode>;
+
+/// Boxed dispatcher handler, called with the context, command header and command data
+pub type Handler<C> = Box<dyn FnMut(&mut C, &ApduHeader, &[u8]) -> HandlerResult + Send>;
+
+/// APDU dispatcher, routing commands to registered handlers by (CLA, INS) and
+/// framing responses with the trailing status word
+pub struct ApduDispatcher<C = ()> {
+    handlers: Vec<(u8, u8, Handler<C>)>,
+}
+
+impl<C> Default for ApduDispatcher<C> {
+    fn default() -> Self {
+        Self {
+            handlers: Vec::new(),
+        }
+    }
+}
+
+impl<C> core::fmt::Debug for ApduDispatcher<C> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list()
+            .entries(self.handlers.iter().map(|(cla, ins, _)| (cla, ins)))
+            .finish()
+    }
+}
+
+impl<C> ApduDispatcher<C> {
+    /// Create a new dispatcher with no registered handlers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler for the provided CLA and INS, replacing any existing handler
+    pub fn register(
+        &mut self,
+        cla: u8,
+        ins: u8,
+        handler: impl FnMut(&mut C, &ApduHeader, &[u8]) -> HandlerResult + Send + 'static,
+    ) -> &mut Self {
+        self.handlers.retain(|(c, i, _)| (*c, *i) != (cla, ins));
+        self.handlers.push((cla, ins, Box::new(handler)));
+        self
+    }
+
+    /// Register a handler for an [ApduStatic] request type, using the type's CLA and INS
+    pub fn on<T: ApduStatic>(
+        &mut self,
+        handler: impl FnMut(&mut C, &ApduHeader, &[u8]) -> HandlerResult + Send + 'static,
+    ) -> &mut Self {
+        self.register(T::CLA, T::INS, handler)
+    }
+
+    /// Dispatch an encoded command APDU (header, Lc, data, optional Le), returning
+    /// the encoded response (data followed by status word).
+    ///
+    /// Malformed commands return [StatusCode::IncorrectLength], unregistered commands
+    /// [StatusCode::InsNotSupported] (or [StatusCode::ClaNotSupported] where no
+    /// handlers exist for the class).
+    pub fn dispatch(&mut self, ctx: &mut C, command: &[u8]) -> Vec<u8> {
+        // Parse header and data
+        let (header, data) = match ApduCase::classify(command) {
+            Ok(c) => (
+                ApduHeader {
+                    cla: command[0],
+                    ins: command[1],
+                    p1: command[2],
+                    p2: command[3],
+                },
+                c.data(command),
+            ),
+            Err(_) => return status(StatusCode::IncorrectLength),
+        };
+
+        // Locate handler
+        let has_cla = self.handlers.iter().any(|(c, _, _)| *c == header.cla);
+        let handler = self
+            .handlers
+            .iter_mut()
+            .find(|(c, i, _)| (*c, *i) == (header.cla, header.ins));
+
+        let r = match handler {
+            Some((_, _, h)) => h(ctx, &header, data),
+            None if has_cla => Err(StatusCode::InsNotSupported),
+            None => Err(StatusCode::ClaNotSupported),
+        };
+
+        // Frame response with status word
+        match r {
+            Ok(mut d) => {
+                d.extend_from_slice(&u16::from(StatusCode::Ok).to_be_bytes());
+                d
+            }
+            Err(s) => status(s),
+        }
+    }
+}
+
+/// Encode a status-only response
+fn status(s: StatusCode) -> Vec<u8> {
+    u16::from(s).to_be_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apdus::{AppInfoReq, ExitAppReq};
+
+    #[test]
+    fn dispatch_routes() {
+        let mut d = ApduDispatcher::<Vec<ApduHeader>>::new();
+        d.on::<AppInfoReq>(|log, h, data| {
+            log.push(*h);
+            Ok(data.to_vec())
+        })
+        .on::<ExitAppReq>(|_, _, _| Err(StatusCode::ConditionsOfUseNotSatisfied));
+
+        let mut log = Vec::new();
+
+        // Data is passed to handlers and framed with the status
+        let r = d.dispatch(&mut log, &[0xb0, 0x01, 0x02, 0x03, 0x01, 0xaa]);
+        assert_eq!(r, &[0xaa, 0x90, 0x00]);
+        assert_eq!((log[0].p1, log[0].p2), (0x02, 0x03));
+
+        // Handler errors return status only
+        let r = d.dispatch(&mut log, &[0xb0, 0xa7, 0x00, 0x00, 0x00]);
+        assert_eq!(r, &[0x69, 0x85]);
+
+        // Unknown class / instruction
+        assert_eq!(
+            d.dispatch(&mut log, &[0xb0, 0xff, 0x00, 0x00, 0x00]),
+            &[0x6d, 0x00]
+        );
+        assert_eq!(
+            d.dispatch(&mut log, &[0xe0, 0x01, 0x00, 0x00, 0x00]),
+            &[0x6e, 0x00]
+        );
+
+        // Malformed commands
+        assert_eq!(d.dispatch(&mut log, &[0xb0, 0x01, 0x00]), &[0x67, 0x00]);
+        assert_eq!(
+            d.dispatch(&mut log, &[0xb0, 0x01, 0x00, 0x00, 0x02, 0xaa]),
+            &[0x67, 0x00]
+        );
+
+        assert_eq!(log.len(), 1);
+    }
+
+    #[test]
+    fn dispatch_replace() {
+        let mut d = ApduDispatcher::new();
+        d.register(0xe0, 0x01, |_, _, _| Ok(Vec::from([0x01])));
+        d.register(0xe0, 0x01, |_, _, _| Ok(Vec::from([0x02])));
+
+        assert_eq!(
+            d.dispatch(&mut (), &[0xe0, 0x01, 0x00, 0x00, 0x00]),
+            &[0x02, 0x90, 0x00]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Command APDU framing, encoding request objects with the header, Lc, data and
+//! optional Le fields for transmission to a device.
+
+use encdec::Encode;
+
+use crate::{length::ApduLengths, ApduError, ApduReq};
+
+/// Command APDU encoder, framing [ApduReq] objects as `header | Lc | data | Le`.
+///
+/// Bodies longer than 255 bytes are encoded using extended (3 byte) Lc fields, see [ApduLengths].
+/// Where the request specifies an expected response length ([ApduReq::le]) this is appended
+/// following the data, with the empty Lc omitted for requests without data (ISO 7816-4 case 2).
+/// Header-only requests still include a zero Lc, as expected by Ledger devices.
+///
+/// ```
+/// use ledger_proto::{apdus::AppInfoReq, ApduEncoder};
+///
+/// let mut buff = [0u8; 16];
+/// let n = ApduEncoder::encode(&AppInfoReq {}, &mut buff).unwrap();
+///
+/// assert_eq!(&buff[..n], &[0xb0, 0x01, 0x00, 0x00, 0x00]);
+/// ```
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ApduEncoder;
+
+impl ApduEncoder {
+    /// Compute the length fields for a request
+    pub fn lengths<'a, REQ: ApduReq<'a>>(req: &REQ) -> Result<ApduLengths, ApduError> {
+        ApduLengths::new(req.encode_len()?, req.le())
+    }
+
+    /// Compute the encoded length of a request (header, length fields and data)
+    pub fn encode_len<'a, REQ: ApduReq<'a>>(req: &REQ) -> Result<usize, ApduError> {
+        let lengths = Self::lengths(req)?;
+        Ok(4 + lengths.lc_len() + lengths.lc + lengths.le_len())
+    }
+
+    /// Encode a request into the provided buffer, returning the encoded length
+    /// or [ApduError::InvalidLength] where the buffer is too short
+    pub fn encode<'a, REQ: ApduReq<'a>>(req: &REQ, buff: &mut [u8]) -> Result<usize, ApduError> {
+        let mut index = 0;
+
+        let lengths = Self::lengths(req)?;
+
+        // Check buffer length is reasonable
+        if buff.len() < 4 + lengths.lc_len() + lengths.lc + lengths.le_len() {
+            return Err(ApduError::InvalidLength);
+        }
+
+        // First the header
+        index += req.header().encode(&mut buff[index..])?;
+
+        // Then the data length
+        index += lengths.encode_lc(&mut buff[index..])?;
+
+        // Then the data
+        index += req.encode(&mut buff[index..])?;
+
+        // And finally the expected response length (if specified)
+        index += lengths.encode_le(&mut buff[index..])?;
+
+        Ok(index)
+    }
+
+    /// Encode a request into a newly allocated buffer (enabled with `alloc` feature)
+    #[cfg(feature = "alloc")]
+    pub fn encode_vec<'a, REQ: ApduReq<'a>>(req: &REQ) -> Result<crate::Vec<u8>, ApduError> {
+        let mut buff = alloc::vec![0u8; Self::encode_len(req)?];
+        let n = Self::encode(req, &mut buff)?;
+        buff.truncate(n);
+        Ok(buff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        apdus::{AppInfoReq, GetResponseReq, RunAppReq},
+        GenericApdu,
+    };
+
+    #[test]
+    fn encode_short() {
+        let mut buff = [0u8; 16];
+
+        let n = ApduEncoder::encode(&RunAppReq::new("BTC"), &mut buff).unwrap();
+        assert_eq!(
+            &buff[..n],
+            &[0xe0, 0xd8, 0x00, 0x00, 0x03, 0x42, 0x54, 0x43]
+        );
+        assert_eq!(ApduEncoder::encode_len(&RunAppReq::new("BTC")), Ok(n));
+
+        // Le follows the header directly where there is no data (case 2)
+        let n = ApduEncoder::encode(&GetResponseReq::new(0x10), &mut buff).unwrap();
+        assert_eq!(&buff[..n], &[0x00, 0xc0, 0x00, 0x00, 0x10]);
+
+        // Buffer must fit header, length and data
+        assert_eq!(
+            ApduEncoder::encode(&AppInfoReq {}, &mut buff[..4]),
+            Err(ApduError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn encode_extended() {
+        let req = GenericApdu::build()
+            .cla(0xe0)
+            .ins(0x04)
+            .data([0xaa; 300])
+            .finish();
+
+        assert_eq!(ApduEncoder::encode_len(&req), Ok(307));
+        assert!(ApduEncoder::encode(&req, &mut [0u8; 306]).is_err());
+
+        let buff = ApduEncoder::encode_vec(&req).unwrap();
+        assert_eq!(&buff[..7], &[0xe0, 0x04, 0x00, 0x00, 0x00, 0x01, 0x2c]);
+        assert_eq!(&buff[7..], &[0xaa; 300]);
+
+        // Data exceeding extended lengths is rejected
+        let req = GenericApdu::build().data([0u8; 0x10000]).finish();
+        assert_eq!(ApduEncoder::encode_len(&req), Err(ApduError::InvalidLength));
+    }
+}
@@ -1,12 +1,25 @@
 //! Apdu error information for encoding / decoding etc.
 
 /// APDU error type
-#[derive(Debug, displaydoc::Display)]
+#[derive(Copy, Clone, PartialEq, Debug, displaydoc::Display)]
 #[cfg_attr(feature = "std", derive(thiserror::Error))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ApduError {
     /// Invalid buffer length
     InvalidLength,
 
+    /// Invalid {field} length at offset {offset} (needed {needed} bytes, {available} available)
+    InvalidFieldLength {
+        /// Field being decoded
+        field: &'static str,
+        /// Byte offset of the field in the decoded buffer
+        offset: usize,
+        /// Bytes required to decode the field
+        needed: usize,
+        /// Bytes available from the field offset
+        available: usize,
+    },
+
     /// Invalid Utf8 string encoding
     InvalidUtf8,
 
@@ -17,6 +30,52 @@ pub enum ApduError {
     InvalidEncoding,
 }
 
+/// [core::error::Error] implementation for [ApduError] in `no_std` environments, with
+/// `std::error::Error` derived via `thiserror` where the `std` feature is enabled
+#[cfg(not(feature = "std"))]
+impl core::error::Error for ApduError {}
+
+impl ApduError {
+    /// Check whether this is a length error, with or without field context
+    pub fn is_length(&self) -> bool {
+        matches!(
+            self,
+            ApduError::InvalidLength | ApduError::InvalidFieldLength { .. }
+        )
+    }
+}
+
+/// Fetch `needed` bytes for `field` at `offset`, returning
+/// [ApduError::InvalidFieldLength] where the buffer is too short
+pub(crate) fn field_bytes<'a>(
+    buff: &'a [u8],
+    field: &'static str,
+    offset: usize,
+    needed: usize,
+) -> Result<&'a [u8], ApduError> {
+    buff.get(offset..)
+        .and_then(|b| b.get(..needed))
+        .ok_or(ApduError::InvalidFieldLength {
+            field,
+            offset,
+            needed,
+            available: buff.len().saturating_sub(offset),
+        })
+}
+
+/// Fetch a (single byte) length-prefixed `field` at `offset`, returning the
+/// field data and encoded length
+pub(crate) fn prefixed_field<'a>(
+    buff: &'a [u8],
+    field: &'static str,
+    offset: usize,
+) -> Result<(&'a [u8], usize), ApduError> {
+    let n = field_bytes(buff, field, offset, 1)?[0] as usize;
+    let d = field_bytes(buff, field, offset + 1, n)?;
+
+    Ok((d, 1 + n))
+}
+
 impl From<encdec::Error> for ApduError {
     fn from(value: encdec::Error) -> Self {
         match value {
@@ -25,3 +84,36 @@ impl From<encdec::Error> for ApduError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_errors() {
+        let b = [0x03, 0xaa, 0xbb];
+
+        assert_eq!(field_bytes(&b, "a", 1, 2).unwrap(), &[0xaa, 0xbb]);
+        assert_eq!(
+            prefixed_field(&[0x01, 0x00], "b", 1).unwrap(),
+            (&[] as &[u8], 1)
+        );
+
+        let e = prefixed_field(&b, "name", 0).unwrap_err();
+        assert!(e.is_length());
+        assert!(matches!(
+            e,
+            ApduError::InvalidFieldLength {
+                field: "name",
+                offset: 1,
+                needed: 3,
+                available: 2
+            }
+        ));
+
+        assert!(matches!(
+            field_bytes(&b, "flags", 5, 1),
+            Err(ApduError::InvalidFieldLength { available: 0, .. })
+        ));
+    }
+}
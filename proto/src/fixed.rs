@@ -0,0 +1,162 @@
+//! Fixed-capacity APDU objects (enabled with `heapless` feature), allowing `no_std`
+//! consumers without `alloc` to own request and response data.
+//!
+//! ```
+//! use ledger_proto::{DecodeOwned, GenericApduN};
+//!
+//! // Decode owned response data into a fixed capacity buffer
+//! let (r, _) = GenericApduN::<64>::decode_owned(&[0x01, 0x02, 0x03]).unwrap();
+//! assert_eq!(r.data(), &[0x01, 0x02, 0x03]);
+//!
+//! // Data exceeding the buffer capacity is rejected
+//! assert!(GenericApduN::<2>::decode_owned(&[0x01, 0x02, 0x03]).is_err());
+//! ```
+
+use encdec::{DecodeOwned, Encode};
+
+use crate::{ApduError, ApduHeader, ApduReq};
+
+/// Fixed-capacity generic APDU object, storing up to `N` bytes of data
+/// (see [GenericApdu](crate::GenericApdu) for the `alloc` equivalent)
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+pub struct GenericApduN<const N: usize> {
+    /// Request APDU Header (uses [Default] for incoming / response APDUs)
+    pub header: ApduHeader,
+    /// APDU data
+    pub data: heapless::Vec<u8, N>,
+}
+
+impl<const N: usize> GenericApduN<N> {
+    /// Create a new fixed-capacity APDU, returning [ApduError::InvalidLength]
+    /// where `data` exceeds the capacity `N`
+    pub fn new(header: ApduHeader, data: &[u8]) -> Result<Self, ApduError> {
+        let data = heapless::Vec::from_slice(data).map_err(|_| ApduError::InvalidLength)?;
+        Ok(Self { header, data })
+    }
+
+    /// Fetch APDU data
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Fetch the data capacity
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+/// [ApduReq] implementation for [GenericApduN], exposes internal header
+impl<'a, const N: usize> ApduReq<'a> for GenericApduN<N> {
+    fn header(&self) -> ApduHeader {
+        self.header
+    }
+}
+
+/// [Encode] implementation for [GenericApduN]
+impl<const N: usize> Encode for GenericApduN<N> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.data.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        // Check buffer length
+        if buff.len() < self.data.len() {
+            return Err(ApduError::InvalidLength);
+        }
+        // Copy data
+        buff[..self.data.len()].copy_from_slice(&self.data);
+        // Return write length
+        Ok(self.data.len())
+    }
+}
+
+/// [DecodeOwned] implementation for [GenericApduN], consuming the full buffer
+impl<const N: usize> DecodeOwned for GenericApduN<N> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode_owned(buff: &[u8]) -> Result<(Self::Output, usize), Self::Error> {
+        Ok((Self::new(Default::default(), buff)?, buff.len()))
+    }
+}
+
+/// Convert a [GenericApduN] to a [GenericApdu](crate::GenericApdu)
+#[cfg(feature = "alloc")]
+impl<const N: usize> From<GenericApduN<N>> for crate::GenericApdu {
+    fn from(a: GenericApduN<N>) -> Self {
+        Self {
+            header: a.header,
+            data: a.data.to_vec(),
+        }
+    }
+}
+
+/// Convert a [GenericApdu](crate::GenericApdu) to a [GenericApduN], returning
+/// [ApduError::InvalidLength] where the data exceeds the capacity `N`
+#[cfg(feature = "alloc")]
+impl<const N: usize> TryFrom<crate::GenericApdu> for GenericApduN<N> {
+    type Error = ApduError;
+
+    fn try_from(a: crate::GenericApdu) -> Result<Self, Self::Error> {
+        Self::new(a.header, &a.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generic_apdu_n() {
+        let h = ApduHeader {
+            cla: 0xe0,
+            ins: 0x01,
+            p1: 0x02,
+            p2: 0x03,
+        };
+        let a = GenericApduN::<8>::new(h, &[0xaa, 0xbb]).unwrap();
+        assert_eq!(a.header(), h);
+        assert_eq!(a.capacity(), 8);
+
+        let mut buff = [0u8; 8];
+        assert_eq!(a.encode(&mut buff), Ok(2));
+        assert_eq!(&buff[..2], &[0xaa, 0xbb]);
+        assert_eq!(a.encode(&mut buff[..1]), Err(ApduError::InvalidLength));
+
+        assert_eq!(
+            GenericApduN::<4>::new(h, &[0u8; 5]),
+            Err(ApduError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn generic_apdu_n_decode() {
+        let (a, n) = GenericApduN::<4>::decode_owned(&[0x01, 0x02, 0x03, 0x04]).unwrap();
+        assert_eq!((a.data(), n), (&[0x01, 0x02, 0x03, 0x04][..], 4));
+        assert_eq!(a.header, ApduHeader::default());
+
+        assert_eq!(
+            GenericApduN::<3>::decode_owned(&[0x01, 0x02, 0x03, 0x04]),
+            Err(ApduError::InvalidLength)
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn generic_apdu_n_convert() {
+        let a = crate::GenericApdu::build()
+            .cla(0xb0)
+            .ins(0x01)
+            .data([0x01])
+            .finish();
+
+        let f = GenericApduN::<4>::try_from(a.clone()).unwrap();
+        assert_eq!(crate::GenericApdu::from(f), a);
+
+        let a = crate::GenericApdu::build().data([0u8; 5]).finish();
+        assert!(GenericApduN::<4>::try_from(a).is_err());
+    }
+}
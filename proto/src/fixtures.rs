@@ -0,0 +1,250 @@
+//! Golden APDU vectors for shared APDUs, used by conformance tests to check
+//! encoders and decoders against device responses byte-for-byte.
+//!
+//! Commands contain the full APDU (header, length, data) and responses include
+//! the trailing status word.
+
+/// APDU command / response vector
+#[derive(Copy, Clone, Debug)]
+pub struct Vector {
+    /// Command APDU (header, length, data)
+    pub cmd: &'static [u8],
+    /// Response APDU (data, status word)
+    pub resp: &'static [u8],
+}
+
+impl Vector {
+    /// Fetch response data without the trailing status word
+    pub fn data(&self) -> &'static [u8] {
+        &self.resp[..self.resp.len() - 2]
+    }
+
+    /// Fetch response status word
+    pub fn status(&self) -> u16 {
+        u16::from_be_bytes([
+            self.resp[self.resp.len() - 2],
+            self.resp[self.resp.len() - 1],
+        ])
+    }
+}
+
+/// App info from the dashboard (Nano S Plus, BOLOS 1.1.0), no flags
+pub const APP_INFO_BOLOS: Vector = Vector {
+    cmd: &[0xb0, 0x01, 0x00, 0x00, 0x00],
+    resp: &[
+        0x01, 0x05, 0x42, 0x4f, 0x4c, 0x4f, 0x53, 0x05, 0x31, 0x2e, 0x31, 0x2e, 0x30, 0x90, 0x00,
+    ],
+};
+
+/// App info from the Bitcoin app (Nano X, 2.1.3), with flags
+pub const APP_INFO_BITCOIN: Vector = Vector {
+    cmd: &[0xb0, 0x01, 0x00, 0x00, 0x00],
+    resp: &[
+        0x01, 0x07, 0x42, 0x69, 0x74, 0x63, 0x6f, 0x69, 0x6e, 0x05, 0x32, 0x2e, 0x31, 0x2e, 0x33,
+        0x01, 0x02, 0x90, 0x00,
+    ],
+};
+
+/// App info from the Ethereum app (Stax, 1.10.4), with flags
+pub const APP_INFO_ETHEREUM: Vector = Vector {
+    cmd: &[0xb0, 0x01, 0x00, 0x00, 0x00],
+    resp: &[
+        0x01, 0x08, 0x45, 0x74, 0x68, 0x65, 0x72, 0x65, 0x75, 0x6d, 0x06, 0x31, 0x2e, 0x31, 0x30,
+        0x2e, 0x34, 0x01, 0x00, 0x90, 0x00,
+    ],
+};
+
+/// Device info from the dashboard (Nano S, SE 2.1.0), MCU version is null terminated
+pub const DEVICE_INFO_NANOS: Vector = Vector {
+    cmd: &[0xe0, 0x01, 0x00, 0x00, 0x00],
+    resp: &[
+        0x31, 0x10, 0x00, 0x04, 0x05, 0x32, 0x2e, 0x31, 0x2e, 0x30, 0x04, 0xe6, 0x00, 0x00, 0x00,
+        0x05, 0x31, 0x2e, 0x31, 0x32, 0x00, 0x90, 0x00,
+    ],
+};
+
+/// Device info from the dashboard (Nano X, SE 2.2.3)
+pub const DEVICE_INFO_NANOX: Vector = Vector {
+    cmd: &[0xe0, 0x01, 0x00, 0x00, 0x00],
+    resp: &[
+        0x33, 0x00, 0x00, 0x04, 0x05, 0x32, 0x2e, 0x32, 0x2e, 0x33, 0x04, 0xa6, 0x00, 0x00, 0x00,
+        0x04, 0x32, 0x2e, 0x33, 0x30, 0x90, 0x00,
+    ],
+};
+
+/// Device info from the dashboard (Nano S Plus, SE 1.1.1), with trailing bootloader, hardware and language fields
+pub const DEVICE_INFO_NANOSP: Vector = Vector {
+    cmd: &[0xe0, 0x01, 0x00, 0x00, 0x00],
+    resp: &[
+        0x33, 0x10, 0x00, 0x04, 0x05, 0x31, 0x2e, 0x31, 0x2e, 0x31, 0x04, 0xa6, 0x00, 0x00, 0x00,
+        0x04, 0x35, 0x2e, 0x32, 0x34, 0x04, 0x30, 0x2e, 0x31, 0x31, 0x01, 0x00, 0x01, 0x00, 0x90,
+        0x00,
+    ],
+};
+
+/// Run the Bitcoin app from the dashboard
+pub const RUN_APP_BITCOIN: Vector = Vector {
+    cmd: &[
+        0xe0, 0xd8, 0x00, 0x00, 0x07, 0x42, 0x69, 0x74, 0x63, 0x6f, 0x69, 0x6e,
+    ],
+    resp: &[0x90, 0x00],
+};
+
+/// Exit the running app
+pub const EXIT_APP: Vector = Vector {
+    cmd: &[0xb0, 0xa7, 0x00, 0x00, 0x00],
+    resp: &[0x90, 0x00],
+};
+
+/// First app list request from the dashboard, returning two apps
+pub const APP_LIST_START: Vector = Vector {
+    cmd: &[0xe0, 0xde, 0x00, 0x00, 0x00],
+    resp: &[
+        0x01, 0x4c, 0x00, 0x2d, 0x0a, 0x50, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+        0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11,
+        0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+        0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22,
+        0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x22, 0x07, 0x42, 0x69, 0x74, 0x63,
+        0x6f, 0x69, 0x6e, 0x4d, 0x00, 0x78, 0x0a, 0x50, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33,
+        0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33,
+        0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x44, 0x44, 0x44, 0x44, 0x44,
+        0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44,
+        0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x44, 0x08, 0x45, 0x74,
+        0x68, 0x65, 0x72, 0x65, 0x75, 0x6d, 0x90, 0x00,
+    ],
+};
+
+/// Following app list request from the dashboard, indicating no further apps
+pub const APP_LIST_END: Vector = Vector {
+    cmd: &[0xe0, 0xdf, 0x00, 0x00, 0x00],
+    resp: &[0x90, 0x00],
+};
+
+#[cfg(test)]
+mod tests {
+    use encdec::{Decode, Encode};
+
+    use super::*;
+    use crate::{
+        apdus::{
+            AppFlags, AppInfoReq, AppInfoResp, AppListReq, AppListResp, DeviceInfoReq,
+            DeviceInfoResp, ExitAppReq, RunAppReq,
+        },
+        ApduReq, StatusCode,
+    };
+
+    /// Helper to encode a request APDU with header and length
+    fn encode_req<'a>(req: impl ApduReq<'a>) -> ([u8; 256], usize) {
+        let mut buff = [0u8; 256];
+
+        let mut index = req.header().encode(&mut buff).unwrap();
+        buff[index] = req.encode_len().unwrap() as u8;
+        index += 1;
+        index += req.encode(&mut buff[index..]).unwrap();
+
+        (buff, index)
+    }
+
+    /// Helper to check requests encode to vector commands
+    fn check_req<'a>(v: &Vector, req: impl ApduReq<'a>) {
+        let (buff, n) = encode_req(req);
+        assert_eq!(&buff[..n], v.cmd);
+    }
+
+    /// Helper to decode vector responses, checking the status and that all data is consumed
+    fn decode_resp<'a, T: Decode<'a, Output = T>>(v: &'a Vector) -> T
+    where
+        <T as Decode<'a>>::Error: core::fmt::Debug,
+    {
+        assert_eq!(v.status(), u16::from(StatusCode::Ok));
+
+        let (r, n) = T::decode(v.data()).unwrap();
+        assert_eq!(n, v.data().len());
+
+        r
+    }
+
+    #[test]
+    fn app_info() {
+        for v in [APP_INFO_BOLOS, APP_INFO_BITCOIN, APP_INFO_ETHEREUM] {
+            check_req(&v, AppInfoReq {});
+        }
+
+        let r: AppInfoResp = decode_resp(&APP_INFO_BOLOS);
+        assert_eq!(r, AppInfoResp::new("BOLOS", "1.1.0", AppFlags::empty()));
+
+        let r: AppInfoResp = decode_resp(&APP_INFO_BITCOIN);
+        assert_eq!(r.name, "Bitcoin");
+        assert_eq!(r.version, "2.1.3");
+        assert_eq!(r.flags.bits(), 0x02);
+
+        let r: AppInfoResp = decode_resp(&APP_INFO_ETHEREUM);
+        assert_eq!(r.name, "Ethereum");
+        assert_eq!(r.version, "1.10.4");
+        assert_eq!(r.flags, AppFlags::empty());
+    }
+
+    #[test]
+    fn device_info() {
+        for v in [DEVICE_INFO_NANOS, DEVICE_INFO_NANOX, DEVICE_INFO_NANOSP] {
+            check_req(&v, DeviceInfoReq {});
+        }
+
+        let r: DeviceInfoResp = decode_resp(&DEVICE_INFO_NANOS);
+        assert_eq!(r.target_id, [0x31, 0x10, 0x00, 0x04]);
+        assert_eq!(r.se_version, "2.1.0");
+        assert_eq!(r.flags, &[0xe6, 0x00, 0x00, 0x00]);
+        assert_eq!(r.mcu_version.trim_end_matches('\0'), "1.12");
+
+        let r: DeviceInfoResp = decode_resp(&DEVICE_INFO_NANOX);
+        assert_eq!(
+            r,
+            DeviceInfoResp::new([0x33, 0x00, 0x00, 0x04], "2.2.3", "2.30", &[0xa6, 0, 0, 0])
+        );
+
+        // Trailing fields from newer firmware are decoded where present
+        let v = DEVICE_INFO_NANOSP;
+        let (r, n) = DeviceInfoResp::decode(v.data()).unwrap();
+        assert_eq!(n, v.data().len());
+        assert_eq!(r.target_id, [0x33, 0x10, 0x00, 0x04]);
+        assert_eq!(r.se_version, "1.1.1");
+        assert_eq!(r.mcu_version, "5.24");
+        assert_eq!(r.bootloader_version, "0.11");
+        assert_eq!(
+            (r.hardware_revision, r.language_id),
+            (Some(0x00), Some(0x00))
+        );
+
+        let mut buff = [0u8; 64];
+        let n = r.encode(&mut buff).unwrap();
+        assert_eq!(&buff[..n], v.data());
+    }
+
+    #[test]
+    fn run_exit_app() {
+        check_req(&RUN_APP_BITCOIN, RunAppReq::new("Bitcoin"));
+        assert_eq!(RUN_APP_BITCOIN.status(), u16::from(StatusCode::Ok));
+
+        check_req(&EXIT_APP, ExitAppReq::new());
+        assert_eq!(EXIT_APP.status(), u16::from(StatusCode::Ok));
+    }
+
+    #[test]
+    fn app_list() {
+        check_req(&APP_LIST_START, AppListReq::new(false));
+        check_req(&APP_LIST_END, AppListReq::new(true));
+
+        let r: AppListResp = decode_resp(&APP_LIST_START);
+        let names: Vec<_> = r.iter().map(|a| a.name).collect();
+        assert_eq!(names, &["Bitcoin", "Ethereum"]);
+
+        let a = r.iter().next().unwrap();
+        assert_eq!((a.blocks, a.flags), (0x2d, 0x0a50));
+        assert_eq!(a.hash_code_data, [0x11; 32]);
+        assert_eq!(a.hash, [0x22; 32]);
+
+        // Empty (status only) response indicates the end of the list
+        assert!(APP_LIST_END.data().is_empty());
+        assert_eq!(APP_LIST_END.status(), u16::from(StatusCode::Ok));
+    }
+}
@@ -0,0 +1,237 @@
+//! Annotated hex dumps for encoded APDUs, for use in debug logs and tooling output.
+//!
+//! [CommandDump] labels header, length and data fields of command APDUs, while
+//! [ResponseDump] splits response data from the trailing status word. Data is printed
+//! in rows of 16 bytes with an ASCII column so string fields are readable inline.
+//!
+//! ```
+//! use ledger_proto::fmt::{CommandDump, ResponseDump};
+//!
+//! let c = CommandDump::new(&[0xe0, 0xd8, 0x00, 0x00, 0x03, 0x42, 0x54, 0x43]);
+//! assert_eq!(
+//!     c.to_string(),
+//!     "CLA   e0\n\
+//!      INS   d8\n\
+//!      P1    00\n\
+//!      P2    00\n\
+//!      Lc    03 (3)\n\
+//!      Data  0000: 42 54 43                                         |BTC|"
+//! );
+//!
+//! let r = ResponseDump::new(&[0x6e, 0x00]);
+//! assert_eq!(r.to_string(), "SW    6e00 (APDU class not supported)");
+//! ```
+
+use core::fmt::{Display, Formatter, Result};
+
+use crate::{length::ApduCase, split_status};
+
+/// Data bytes per row
+const ROW_LEN: usize = 16;
+
+/// Annotated hex dump of a command APDU (header, length, data, Le)
+#[derive(Copy, Clone, Debug)]
+pub struct CommandDump<'a>(&'a [u8]);
+
+impl<'a> CommandDump<'a> {
+    /// Create a dump for an encoded command APDU
+    pub fn new(apdu: &'a [u8]) -> Self {
+        Self(apdu)
+    }
+}
+
+impl<'a> Display for CommandDump<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let apdu = self.0;
+
+        // Malformed commands are printed unannotated
+        let case = match ApduCase::classify(apdu) {
+            Ok(c) => c,
+            Err(e) => {
+                write!(f, "APDU  ")?;
+                write_hex(f, apdu)?;
+                return write!(f, " (invalid: {e})");
+            }
+        };
+
+        // Write header fields
+        for (label, v) in ["CLA", "INS", "P1", "P2"].iter().zip(&apdu[..4]) {
+            writeln!(f, "{label:<5} {v:02x}")?;
+        }
+
+        // Compute length field sizes
+        let (lc_len, le_len) = match case {
+            ApduCase::Case1 => (0, 0),
+            // Ledger devices expect Lc to always be present, so a single zero byte
+            // is shown as an empty Lc rather than a maximum Le
+            ApduCase::Case2 {
+                extended: false, ..
+            } if apdu[4] == 0 => (1, 0),
+            ApduCase::Case2 { .. } => (0, apdu.len() - 4),
+            ApduCase::Case3 { extended, lc } | ApduCase::Case4 { extended, lc, .. } => {
+                let n = if extended { 3 } else { 1 };
+                (n, apdu.len() - 4 - n - lc)
+            }
+        };
+
+        // Write Lc, where present
+        let data = case.data(apdu);
+        if lc_len > 0 {
+            write!(f, "{:<5} ", "Lc")?;
+            write_hex(f, &apdu[4..][..lc_len])?;
+            writeln!(f, " ({})", data.len())?;
+        }
+
+        // Write data
+        write!(f, "{:<5} ", "Data")?;
+        write_data(f, data)?;
+
+        // Write Le, where present
+        if let (Some(le), true) = (case.le(), le_len > 0) {
+            write!(f, "\n{:<5} ", "Le")?;
+            write_hex(f, &apdu[apdu.len() - le_len..])?;
+            write!(f, " ({le})")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Annotated hex dump of a response APDU (data, status word)
+#[derive(Copy, Clone, Debug)]
+pub struct ResponseDump<'a>(&'a [u8]);
+
+impl<'a> ResponseDump<'a> {
+    /// Create a dump for an encoded response APDU
+    pub fn new(apdu: &'a [u8]) -> Self {
+        Self(apdu)
+    }
+}
+
+impl<'a> Display for ResponseDump<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (data, status) = match split_status(self.0) {
+            Ok(v) => v,
+            Err(_) => {
+                write!(f, "APDU  ")?;
+                write_hex(f, self.0)?;
+                return write!(f, " (missing status)");
+            }
+        };
+
+        if !data.is_empty() {
+            write!(f, "{:<5} ", "Data")?;
+            write_data(f, data)?;
+            writeln!(f)?;
+        }
+
+        write!(
+            f,
+            "{:<5} {:04x} ({})",
+            "SW",
+            u16::from(status),
+            status.description()
+        )
+    }
+}
+
+/// Write space separated hex bytes
+fn write_hex(f: &mut Formatter<'_>, data: &[u8]) -> Result {
+    for (i, b) in data.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{b:02x}")?;
+    }
+    Ok(())
+}
+
+/// Write data rows with offsets and an ASCII column, continuation rows are
+/// aligned with the first row
+fn write_data(f: &mut Formatter<'_>, data: &[u8]) -> Result {
+    if data.is_empty() {
+        return write!(f, "(empty)");
+    }
+
+    for (i, row) in data.chunks(ROW_LEN).enumerate() {
+        if i > 0 {
+            write!(f, "\n{:<5} ", "")?;
+        }
+
+        write!(f, "{:04x}: ", i * ROW_LEN)?;
+        write_hex(f, row)?;
+
+        // Pad partial rows to align the ASCII column
+        write!(f, "{:w$}  |", "", w = (ROW_LEN - row.len()) * 3)?;
+        for b in row {
+            match b.is_ascii_graphic() || *b == b' ' {
+                true => write!(f, "{}", *b as char)?,
+                false => write!(f, ".")?,
+            }
+        }
+        write!(f, "|")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{APP_INFO_BOLOS, EXIT_APP},
+        ApduError,
+    };
+
+    #[test]
+    fn command_dump() {
+        assert_eq!(
+            CommandDump::new(EXIT_APP.cmd).to_string(),
+            "CLA   b0\nINS   a7\nP1    00\nP2    00\nLc    00 (0)\nData  (empty)"
+        );
+
+        // Case 2 (Le only), case 4 and extended case 4
+        assert_eq!(
+            CommandDump::new(&[0x00, 0xc0, 0x00, 0x00, 0x10]).to_string(),
+            "CLA   00\nINS   c0\nP1    00\nP2    00\nData  (empty)\nLe    10 (16)"
+        );
+        assert_eq!(
+            CommandDump::new(&[0x00, 0xc0, 0x00, 0x00, 0x00, 0x10]).to_string(),
+            "CLA   00\nINS   c0\nP1    00\nP2    00\nLc    00 (0)\nData  (empty)\nLe    10 (16)"
+        );
+        assert_eq!(
+            CommandDump::new(&[0xe0, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0xaa, 0x01, 0x00])
+                .to_string(),
+            "CLA   e0\nINS   02\nP1    00\nP2    00\nLc    00 00 01 (1)\n\
+             Data  0000: aa                                               |.|\n\
+             Le    01 00 (256)"
+        );
+
+        assert_eq!(
+            CommandDump::new(&[0xe0, 0x01]).to_string(),
+            format!("APDU  e0 01 (invalid: {})", ApduError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn response_dump() {
+        let s = ResponseDump::new(APP_INFO_BOLOS.resp).to_string();
+        assert_eq!(
+            s,
+            "Data  0000: 01 05 42 4f 4c 4f 53 05 31 2e 31 2e 30           |..BOLOS.1.1.0|\n\
+             SW    9000 (OK)"
+        );
+
+        let d = [0x41u8; 20];
+        let mut r = d.to_vec();
+        r.extend_from_slice(&[0x90, 0x00]);
+        assert!(ResponseDump::new(&r)
+            .to_string()
+            .contains("\n      0010: 41 41 41 41"));
+
+        assert_eq!(
+            ResponseDump::new(&[0x90]).to_string(),
+            "APDU  90 (missing status)"
+        );
+    }
+}
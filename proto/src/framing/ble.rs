@@ -0,0 +1,252 @@
+//! Ledger BLE framing, MTU sized chunks with command and sequence index headers
+
+use crate::Vec;
+
+use super::{Chunks, FrameError};
+
+/// BLE chunk header length: command, sequence index
+pub const BLE_HEADER_LEN: usize = 3;
+
+/// BLE APDU command
+pub const BLE_CMD_APDU: u8 = 0x05;
+
+/// BLE MTU request command
+pub const BLE_CMD_MTU: u8 = 0x08;
+
+/// BLE continuation command, used for chunks following the first
+const BLE_CMD_CONTINUATION: u8 = 0x03;
+
+/// Encoder, splits a command payload into BLE chunks for the provided MTU
+pub struct BleEncoder<'a> {
+    cmd: u8,
+    mtu: usize,
+    chunks: Chunks<'a>,
+    seq: u16,
+}
+
+impl<'a> BleEncoder<'a> {
+    /// Create an encoder for the provided command and payload
+    pub fn new(cmd: u8, payload: &'a [u8], mtu: usize) -> Self {
+        Self {
+            cmd,
+            mtu,
+            chunks: Chunks::new(payload, mtu.saturating_sub(BLE_HEADER_LEN)),
+            seq: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for BleEncoder<'a> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buff = Vec::with_capacity(self.mtu);
+
+        let cmd = match self.seq == 0 {
+            true => self.cmd,
+            false => BLE_CMD_CONTINUATION,
+        };
+
+        buff.push(cmd); // Command
+        buff.extend_from_slice(&self.seq.to_be_bytes()); // Sequence ID
+
+        if !self.chunks.write_next(&mut buff) {
+            return None;
+        }
+
+        self.seq = self.seq.wrapping_add(1);
+
+        Some(buff)
+    }
+}
+
+/// First chunk header length: command, sequence index, response length
+const BLE_FIRST_HEADER_LEN: usize = BLE_HEADER_LEN + 2;
+
+/// Decoder, reassembles BLE notifications into an APDU response
+#[derive(Clone, Debug, Default)]
+pub struct BleDecoder {
+    /// Expected response length, set on receipt of the first notification
+    len: Option<usize>,
+    /// Next expected sequence index
+    seq: u16,
+    /// Response buffer
+    buff: Vec<u8>,
+}
+
+impl BleDecoder {
+    /// Create a new decoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push a received notification to the decoder, returning the response once complete.
+    ///
+    /// The decoder is reset on completion or error.
+    pub fn push(&mut self, v: &[u8]) -> Result<Option<Vec<u8>>, FrameError> {
+        let r = self.push_inner(v);
+
+        if !matches!(r, Ok(None)) {
+            *self = Self::default();
+        }
+
+        r
+    }
+
+    fn push_inner(&mut self, v: &[u8]) -> Result<Option<Vec<u8>>, FrameError> {
+        let len = match self.len {
+            // First notification, contains the response length
+            None => {
+                // Check response length is reasonable
+                if v.len() < BLE_FIRST_HEADER_LEN {
+                    return Err(FrameError::InvalidLength(v.len()));
+                } else if v[0] != BLE_CMD_APDU {
+                    return Err(FrameError::InvalidCommand(v[0]));
+                }
+
+                // Check sequence index
+                let seq = u16::from_be_bytes([v[1], v[2]]);
+                if seq != 0 {
+                    return Err(FrameError::InvalidSequence(seq));
+                }
+
+                // Read out full response length
+                let len = u16::from_be_bytes([v[3], v[4]]) as usize;
+                if len == 0 {
+                    return Err(FrameError::Empty);
+                }
+
+                // Setup response buffer and add any remaining data
+                self.buff = Vec::with_capacity(len);
+
+                let n = len.min(v.len() - BLE_FIRST_HEADER_LEN);
+                self.buff.extend_from_slice(&v[BLE_FIRST_HEADER_LEN..][..n]);
+
+                self.len = Some(len);
+                self.seq = 1;
+
+                len
+            }
+            // Following notifications, with command and sequence index headers only
+            Some(len) => {
+                // Chunks must contain data to avoid stalling reassembly
+                if v.len() <= BLE_HEADER_LEN {
+                    return Err(FrameError::InvalidLength(v.len()));
+                }
+
+                // Check command tag and sequence index
+                if v[0] != BLE_CMD_APDU && v[0] != BLE_CMD_CONTINUATION {
+                    return Err(FrameError::InvalidCommand(v[0]));
+                }
+                let seq = u16::from_be_bytes([v[1], v[2]]);
+                if seq != self.seq {
+                    return Err(FrameError::InvalidSequence(seq));
+                }
+
+                // Add received data to buffer
+                let n = (len - self.buff.len()).min(v.len() - BLE_HEADER_LEN);
+                self.buff.extend_from_slice(&v[BLE_HEADER_LEN..][..n]);
+
+                self.seq = self.seq.wrapping_add(1);
+
+                len
+            }
+        };
+
+        match self.buff.len() >= len {
+            true => Ok(Some(core::mem::take(&mut self.buff))),
+            false => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ble_encode() {
+        let payload = [0xaa; 40];
+        let chunks: Vec<_> = BleEncoder::new(BLE_CMD_APDU, &payload, 23).collect();
+
+        // 42 bytes (prefix + payload) over 20 byte chunks
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(&chunks[0][..5], &[BLE_CMD_APDU, 0x00, 0x00, 0x00, 40]);
+        assert_eq!(&chunks[1][..3], &[BLE_CMD_CONTINUATION, 0x00, 0x01]);
+        assert_eq!(&chunks[2][..3], &[BLE_CMD_CONTINUATION, 0x00, 0x02]);
+        assert!(chunks.iter().all(|c| c.len() <= 23));
+
+        // Empty payloads still emit the length prefix
+        let chunks: Vec<_> = BleEncoder::new(BLE_CMD_MTU, &[], 23).collect();
+        assert_eq!(&chunks, &[vec![BLE_CMD_MTU, 0x00, 0x00, 0x00, 0x00]]);
+    }
+
+    #[test]
+    fn ble_encode_decode() {
+        for len in [1usize, 18, 19, 20, 21, 100, 255, 256, 300] {
+            let payload: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+            let mut d = BleDecoder::new();
+            let mut resp = None;
+
+            for c in BleEncoder::new(BLE_CMD_APDU, &payload, 23) {
+                assert!(resp.is_none(), "response before final chunk");
+                resp = d.push(&c).unwrap();
+            }
+
+            assert_eq!(resp.as_deref(), Some(&payload[..]), "len: {len}");
+        }
+    }
+
+    #[test]
+    fn ble_decode_continuation() {
+        let mut d = BleDecoder::new();
+
+        // Continuation chunks have 3 byte headers, tagged with the APDU command
+        let mut first = vec![BLE_CMD_APDU, 0x00, 0x00, 0x00, 0x20];
+        first.extend_from_slice(&[0xaa; 18]);
+        assert_eq!(d.push(&first), Ok(None));
+
+        let mut next = vec![BLE_CMD_APDU, 0x00, 0x01];
+        next.extend_from_slice(&[0xbb; 20]);
+        let mut resp = vec![0xaa; 18];
+        resp.extend_from_slice(&[0xbb; 14]);
+        assert_eq!(d.push(&next), Ok(Some(resp)));
+
+        // Out-of-order chunks are rejected
+        assert_eq!(d.push(&first), Ok(None));
+        assert_eq!(
+            d.push(&[BLE_CMD_APDU, 0x00, 0x02, 0xbb]),
+            Err(FrameError::InvalidSequence(2))
+        );
+        assert_eq!(
+            d.push(&[BLE_CMD_APDU, 0x00, 0x01, 0x00, 0x02, 0x90, 0x00]),
+            Err(FrameError::InvalidSequence(1))
+        );
+
+        // As are chunks with unexpected tags
+        assert_eq!(d.push(&first), Ok(None));
+        assert_eq!(
+            d.push(&[BLE_CMD_MTU, 0x00, 0x01, 0xbb]),
+            Err(FrameError::InvalidCommand(BLE_CMD_MTU))
+        );
+    }
+
+    #[test]
+    fn ble_decode() {
+        let mut d = BleDecoder::new();
+        assert_eq!(
+            d.push(&[BLE_CMD_APDU, 0x00, 0x00, 0x00, 0x02, 0x90, 0x00]),
+            Ok(Some(vec![0x90, 0x00]))
+        );
+
+        assert_eq!(
+            d.push(&[0x03, 0x00, 0x00, 0x00, 0x02]),
+            Err(FrameError::InvalidCommand(0x03))
+        );
+        assert_eq!(
+            d.push(&[BLE_CMD_APDU, 0x00, 0x00, 0x00, 0x00]),
+            Err(FrameError::Empty)
+        );
+    }
+}
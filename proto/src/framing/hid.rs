@@ -0,0 +1,403 @@
+//! Ledger HID framing, 64 byte packets with channel, tag and sequence index headers
+
+use crate::Vec;
+
+use super::{Chunks, FrameError};
+
+/// HID packet length (header + data)
+pub const HID_PACKET_LEN: usize = 64;
+
+/// HID packet header length: channel, tag (0x05), sequence index
+pub const HID_HEADER_LEN: usize = 5;
+
+/// Default HID channel ID, used unless otherwise configured
+pub const HID_DEFAULT_CHANNEL: u16 = 0x0101;
+
+/// HID APDU tag
+const HID_TAG: u8 = 0x05;
+
+/// Build the HID channel and tag header prefix
+const fn hid_header(channel: u16) -> [u8; 3] {
+    let c = channel.to_be_bytes();
+    [c[0], c[1], HID_TAG]
+}
+
+/// Encoder, splits an APDU into HID packets for writing.
+///
+/// Packets are prefixed with a zero byte (HID report ID) as expected by `hidapi`.
+pub struct HidEncoder<'a> {
+    chunks: Chunks<'a>,
+    header: [u8; 3],
+    seq: u16,
+}
+
+impl<'a> HidEncoder<'a> {
+    /// Create an encoder for the provided APDU, using the [HID_DEFAULT_CHANNEL]
+    pub fn new(apdu: &'a [u8]) -> Self {
+        Self {
+            chunks: Chunks::new(apdu, HID_PACKET_LEN - HID_HEADER_LEN),
+            header: hid_header(HID_DEFAULT_CHANNEL),
+            seq: 0,
+        }
+    }
+
+    /// Set the HID channel ID for encoded packets
+    pub fn with_channel(mut self, channel: u16) -> Self {
+        self.header = hid_header(channel);
+        self
+    }
+
+    /// Append the next packet to `buff`, returning `false` once all packets have been written.
+    ///
+    /// This allows a single buffer to be reused across packets and commands, where
+    /// iterating allocates each packet.
+    pub fn write_next(&mut self, buff: &mut Vec<u8>) -> bool {
+        let start = buff.len();
+
+        // Zero prefix (report ID)
+        buff.push(0x00);
+
+        // Header channel, tag (0x05), sequence index
+        buff.extend_from_slice(&self.header);
+        buff.extend_from_slice(&self.seq.to_be_bytes());
+
+        // Remaining data
+        if !self.chunks.write_next(buff) {
+            buff.truncate(start);
+            return false;
+        }
+
+        self.seq = self.seq.wrapping_add(1);
+
+        true
+    }
+}
+
+impl<'a> Iterator for HidEncoder<'a> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut packet = Vec::with_capacity(HID_PACKET_LEN + 1);
+
+        match self.write_next(&mut packet) {
+            true => Some(packet),
+            false => None,
+        }
+    }
+}
+
+/// Decoder, reassembles HID packets into an APDU response
+#[derive(Clone, Debug)]
+pub struct HidDecoder {
+    /// Expected channel and tag header prefix
+    header: [u8; 3],
+    /// Expected response length, set on receipt of the first packet
+    len: Option<usize>,
+    /// Next expected sequence index
+    seq: u16,
+    /// Response buffer
+    buff: Vec<u8>,
+    /// Skip foreign and stale packets rather than failing
+    resync: bool,
+}
+
+impl Default for HidDecoder {
+    fn default() -> Self {
+        Self {
+            header: hid_header(HID_DEFAULT_CHANNEL),
+            len: None,
+            seq: 0,
+            buff: Vec::new(),
+            resync: false,
+        }
+    }
+}
+
+impl HidDecoder {
+    /// Create a new decoder, using the [HID_DEFAULT_CHANNEL]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the expected HID channel ID, packets for other channels are rejected
+    pub fn with_channel(mut self, channel: u16) -> Self {
+        self.header = hid_header(channel);
+        self
+    }
+
+    /// Skip packets for other channels and stale packets (for example, left over from
+    /// a previous timed-out exchange) rather than failing, restarting reassembly where
+    /// a new response begins part-way through another
+    pub fn with_resync(mut self) -> Self {
+        self.resync = true;
+        self
+    }
+
+    /// Check whether a response is part-way through reassembly
+    pub fn in_progress(&self) -> bool {
+        self.len.is_some()
+    }
+
+    /// Fetch the number of packets accepted for the response being reassembled,
+    /// allowing callers to distinguish progress from skipped packets
+    pub fn packets(&self) -> u16 {
+        self.seq
+    }
+
+    /// Reset reassembly state, retaining configuration
+    fn reset(&mut self) {
+        *self = Self {
+            header: self.header,
+            resync: self.resync,
+            ..Self::default()
+        };
+    }
+
+    /// Check whether a packet is for another channel or out of date, for skipping
+    /// when resynchronising
+    fn is_stale(&self, packet: &[u8]) -> bool {
+        if packet.len() < HID_HEADER_LEN {
+            return false;
+        }
+        if packet[..2] != self.header[..2] {
+            return true;
+        }
+
+        let seq = u16::from_be_bytes([packet[3], packet[4]]);
+        match self.len {
+            None => seq != 0,
+            Some(_) => seq != 0 && seq < self.seq,
+        }
+    }
+
+    /// Check the channel and tag header for a packet
+    fn check_header(&self, packet: &[u8]) -> Result<(), FrameError> {
+        if packet[..2] != self.header[..2] {
+            return Err(FrameError::InvalidChannel(u16::from_be_bytes([
+                packet[0], packet[1],
+            ])));
+        }
+        if packet[2] != HID_TAG {
+            return Err(FrameError::InvalidHeader);
+        }
+        Ok(())
+    }
+
+    /// Push a received packet (excluding report ID) to the decoder,
+    /// returning the response once complete.
+    ///
+    /// The decoder is reset on completion or error.
+    pub fn push(&mut self, packet: &[u8]) -> Result<Option<Vec<u8>>, FrameError> {
+        if self.resync {
+            if self.is_stale(packet) {
+                return Ok(None);
+            }
+
+            // Restart reassembly where a new response replaces a partial (stale) response
+            if self.len.is_some() && packet.len() >= HID_HEADER_LEN && packet[3..5] == [0, 0] {
+                self.reset();
+            }
+        }
+
+        let r = self.push_inner(packet);
+
+        if !matches!(r, Ok(None)) {
+            self.reset();
+        }
+
+        r
+    }
+
+    fn push_inner(&mut self, packet: &[u8]) -> Result<Option<Vec<u8>>, FrameError> {
+        let len = match self.len {
+            // First packet, contains the response length
+            None => {
+                // Check packet length is valid for following operations
+                if packet.is_empty() {
+                    return Err(FrameError::Empty);
+                } else if packet.len() < HID_HEADER_LEN + 2 {
+                    return Err(FrameError::InvalidLength(packet.len()));
+                }
+
+                // Check header matches expectations
+                self.check_header(packet)?;
+                if packet[3..5] != [0x00, 0x00] {
+                    return Err(FrameError::InvalidHeader);
+                }
+
+                // Parse response length
+                let len = u16::from_be_bytes([packet[5], packet[6]]) as usize;
+
+                // Setup response buffer and add any remaining data
+                self.buff = Vec::with_capacity(len);
+
+                let n = len.min(packet.len() - 7);
+                self.buff.extend_from_slice(&packet[7..][..n]);
+
+                self.len = Some(len);
+                self.seq = 1;
+
+                len
+            }
+            // Following packets
+            Some(len) => {
+                // Packets must contain data to avoid stalling reassembly
+                if packet.len() <= HID_HEADER_LEN {
+                    return Err(FrameError::InvalidLength(packet.len()));
+                }
+
+                // Check header and sequence index
+                self.check_header(packet)?;
+                let seq = u16::from_be_bytes([packet[3], packet[4]]);
+                if seq != self.seq {
+                    return Err(FrameError::InvalidSequence(seq));
+                }
+
+                // Add to response buffer
+                let n = (len - self.buff.len()).min(packet.len() - HID_HEADER_LEN);
+                self.buff.extend_from_slice(&packet[HID_HEADER_LEN..][..n]);
+
+                self.seq = self.seq.wrapping_add(1);
+
+                len
+            }
+        };
+
+        match self.buff.len() >= len {
+            true => Ok(Some(core::mem::take(&mut self.buff))),
+            false => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hid_encode_decode() {
+        for len in [0usize, 1, 57, 58, 59, 60, 200, 255] {
+            let apdu: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+            let packets: Vec<_> = HidEncoder::new(&apdu).collect();
+            assert_eq!(packets.len(), (len + 2).div_ceil(59));
+
+            let mut d = HidDecoder::new();
+            let mut resp = None;
+
+            for (i, p) in packets.iter().enumerate() {
+                assert!(p.len() <= HID_PACKET_LEN + 1);
+                assert!(resp.is_none(), "response before final packet");
+
+                // Strip report ID prior to decode
+                resp = d.push(&p[1..]).unwrap();
+
+                assert_eq!(u16::from_be_bytes([p[4], p[5]]), i as u16);
+            }
+
+            assert_eq!(resp.as_deref(), Some(&apdu[..]), "len: {len}");
+        }
+    }
+
+    #[test]
+    fn hid_encode_write_next() {
+        let apdu = [0xaa; 100];
+        let packets: Vec<_> = HidEncoder::new(&apdu).collect();
+
+        // Packets are appended to existing buffer contents
+        let mut buff = vec![0xff];
+        let mut e = HidEncoder::new(&apdu);
+        while e.write_next(&mut buff) {}
+
+        assert_eq!(buff[0], 0xff);
+        assert_eq!(buff[1..], packets.concat());
+    }
+
+    #[test]
+    fn hid_decode_errors() {
+        let mut d = HidDecoder::new();
+        assert_eq!(d.push(&[]), Err(FrameError::Empty));
+        assert_eq!(d.push(&[0x01, 0x01]), Err(FrameError::InvalidLength(2)));
+        assert_eq!(
+            d.push(&[0x01, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00]),
+            Err(FrameError::InvalidHeader)
+        );
+
+        // Sequence index must increment
+        let mut p = vec![0x01, 0x01, 0x05, 0x00, 0x00, 0x00, 0x80];
+        p.resize(64, 0xaa);
+        assert_eq!(d.push(&p), Ok(None));
+        assert_eq!(
+            d.push(&[0x01, 0x01, 0x05, 0x00, 0x02, 0xaa]),
+            Err(FrameError::InvalidSequence(2))
+        );
+    }
+
+    #[test]
+    fn hid_channel() {
+        let apdu = [0xaa; 100];
+
+        // Packets use the configured channel
+        let packets: Vec<_> = HidEncoder::new(&apdu).with_channel(0x1234).collect();
+        assert!(packets.iter().all(|p| p[1..4] == [0x12, 0x34, 0x05]));
+
+        let mut d = HidDecoder::new().with_channel(0x1234);
+        assert_eq!(d.push(&packets[0][1..]), Ok(None));
+        assert_eq!(d.push(&packets[1][1..]), Ok(Some(apdu.to_vec())));
+
+        // Channel is retained on reset
+        assert_eq!(d.push(&packets[0][1..]), Ok(None));
+        assert_eq!(d.push(&packets[1][1..]), Ok(Some(apdu.to_vec())));
+
+        // Packets for other channels are rejected
+        let mut d = HidDecoder::new();
+        assert_eq!(
+            d.push(&packets[0][1..]),
+            Err(FrameError::InvalidChannel(0x1234))
+        );
+
+        let mut d = HidDecoder::new().with_channel(0x1234);
+        let other: Vec<_> = HidEncoder::new(&apdu).collect();
+        assert_eq!(d.push(&packets[0][1..]), Ok(None));
+        assert_eq!(
+            d.push(&other[1][1..]),
+            Err(FrameError::InvalidChannel(HID_DEFAULT_CHANNEL))
+        );
+    }
+
+    #[test]
+    fn hid_resync() {
+        let (stale, apdu) = ([0xaa; 100], [0xbb; 100]);
+        let stale: Vec<_> = HidEncoder::new(&stale).collect();
+        let foreign: Vec<_> = HidEncoder::new(&apdu).with_channel(0x1234).collect();
+        let packets: Vec<_> = HidEncoder::new(&apdu).collect();
+
+        let mut d = HidDecoder::new().with_resync();
+
+        // Stale continuation and foreign channel packets are skipped
+        assert_eq!(d.push(&stale[1][1..]), Ok(None));
+        assert_eq!(d.push(&foreign[0][1..]), Ok(None));
+        assert_eq!(d.packets(), 0);
+
+        // A new response restarts reassembly of a partial stale response
+        assert_eq!(d.push(&stale[0][1..]), Ok(None));
+        assert_eq!(d.push(&packets[0][1..]), Ok(None));
+
+        // Foreign channel packets are skipped mid-response
+        assert_eq!(d.packets(), 1);
+        assert_eq!(d.push(&foreign[1][1..]), Ok(None));
+        assert_eq!(d.packets(), 1);
+        assert_eq!(d.push(&packets[1][1..]), Ok(Some(apdu.to_vec())));
+
+        // Resync is retained on reset, while invalid packets are still rejected
+        assert_eq!(d.push(&stale[1][1..]), Ok(None));
+        assert_eq!(d.push(&packets[0][1..]), Ok(None));
+        assert_eq!(
+            d.push(&[0x01, 0x01, 0x05, 0x00, 0x05, 0xaa]),
+            Err(FrameError::InvalidSequence(5))
+        );
+        assert_eq!(d.push(&packets[0][1..]), Ok(None));
+        assert_eq!(d.push(&packets[0][1..]), Ok(None));
+        assert_eq!(d.push(&packets[1][1..]), Ok(Some(apdu.to_vec())));
+    }
+}
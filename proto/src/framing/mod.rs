@@ -0,0 +1,101 @@
+//! Sans-IO framing state machines for Ledger HID and BLE transports (enabled with `alloc` feature).
+//!
+//! Encoders split APDUs into transport frames for writing, and decoders accept
+//! received frames one at a time, returning the reassembled response once complete.
+//! These perform no IO so may be driven by any HID / BLE stack.
+//!
+//! ```
+//! use ledger_proto::framing::hid::{HidDecoder, HidEncoder};
+//!
+//! // Split a command into HID packets for writing
+//! let packets: Vec<_> = HidEncoder::new(&[0xb0, 0x01, 0x00, 0x00, 0x00]).collect();
+//! assert_eq!(packets.len(), 1);
+//!
+//! // Feed received packets to the decoder until a response is returned
+//! let mut d = HidDecoder::new();
+//! let resp = d.push(&[0x01, 0x01, 0x05, 0x00, 0x00, 0x00, 0x02, 0x90, 0x00]).unwrap();
+//! assert_eq!(resp.as_deref(), Some(&[0x90, 0x00][..]));
+//! ```
+
+use crate::Vec;
+
+pub mod ble;
+pub mod hid;
+
+/// Framing error type
+#[derive(Copy, Clone, Debug, PartialEq, displaydoc::Display)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum FrameError {
+    /// Empty response
+    Empty,
+
+    /// Invalid frame length {0}
+    InvalidLength(usize),
+
+    /// Unexpected frame header
+    InvalidHeader,
+
+    /// Unexpected frame channel 0x{0:04x}
+    InvalidChannel(u16),
+
+    /// Unexpected frame command 0x{0:02x}
+    InvalidCommand(u8),
+
+    /// Unexpected frame sequence index {0}
+    InvalidSequence(u16),
+}
+
+/// [core::error::Error] implementation for [FrameError] without the `std` feature
+#[cfg(not(feature = "std"))]
+impl core::error::Error for FrameError {}
+
+/// Helper to split a length-prefixed payload into chunks,
+/// without allocating the prefixed payload
+struct Chunks<'a> {
+    prefix: [u8; 2],
+    data: &'a [u8],
+    offset: usize,
+    size: usize,
+}
+
+impl<'a> Chunks<'a> {
+    /// Create a new chunker for `data` with a (truncated) two byte big-endian length prefix
+    fn new(data: &'a [u8], size: usize) -> Self {
+        Self {
+            prefix: (data.len() as u16).to_be_bytes(),
+            data,
+            offset: 0,
+            size: size.max(1),
+        }
+    }
+
+    /// Append the next chunk to `buff`, returning `false` once all data has been written
+    fn write_next(&mut self, buff: &mut Vec<u8>) -> bool {
+        if self.offset >= self.data.len() + 2 {
+            return false;
+        }
+
+        let mut rem = self.size;
+
+        // Write length prefix where required
+        if self.offset < 2 {
+            let p = &self.prefix[self.offset..];
+            let n = p.len().min(rem);
+
+            buff.extend_from_slice(&p[..n]);
+            self.offset += n;
+            rem -= n;
+        }
+
+        // Write data
+        if self.offset >= 2 {
+            let start = self.offset - 2;
+            let end = (start + rem).min(self.data.len());
+
+            buff.extend_from_slice(&self.data[start..end]);
+            self.offset = end + 2;
+        }
+
+        true
+    }
+}
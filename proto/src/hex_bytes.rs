@@ -0,0 +1,55 @@
+//! Serde helpers for borrowed byte fields, serialised as hex strings for
+//! human-readable formats (such as JSON) and raw bytes otherwise.
+//!
+//! Deserialisation borrows from the input, so is only supported by zero-copy
+//! binary formats. Human-readable formats should use owned types
+//! (for example [GenericApdu](crate::GenericApdu) or the `ledger-lib` info objects).
+
+use core::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Serialise a byte slice, as hex where the format is human-readable
+pub fn serialize<S: Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+    match s.is_human_readable() {
+        true => s.collect_str(&Hex(v)),
+        false => s.serialize_bytes(v),
+    }
+}
+
+/// Deserialise a borrowed byte slice
+pub fn deserialize<'de: 'a, 'a, D: Deserializer<'de>>(d: D) -> Result<&'a [u8], D::Error> {
+    <&'de [u8]>::deserialize(d)
+}
+
+/// Hex [Display] helper, avoiding allocation
+struct Hex<'a>(&'a [u8]);
+
+impl<'a> Display for Hex<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for b in self.0 {
+            write!(f, "{b:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::apdus::{AppFlags, AppInfoResp, DeviceInfoResp};
+
+    #[test]
+    fn serde_json_apdus() {
+        let r = DeviceInfoResp::new([0x33, 0x00, 0x00, 0x04], "2.2.3", "2.30", &[0xa6, 0, 0, 0]);
+        let s = serde_json::to_string(&r).unwrap();
+        assert_eq!(
+            s,
+            r#"{"target_id":[51,0,0,4],"se_version":"2.2.3","flags":"a6000000","mcu_version":"2.30"}"#
+        );
+
+        // Borrowed strings deserialise from JSON
+        let r = AppInfoResp::new("Bitcoin", "2.1.3", AppFlags::empty());
+        let s = serde_json::to_string(&r).unwrap();
+        assert_eq!(serde_json::from_str::<AppInfoResp>(&s).unwrap(), r);
+    }
+}
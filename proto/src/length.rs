@@ -0,0 +1,531 @@
+//! APDU length field (Lc / Le) encoding, supporting both short (1 byte Lc / Le)
+//! and extended (3 byte Lc, 2 or 3 byte Le) command APDUs.
+//!
+//! Extended encoding is used where either the data or expected response length exceed
+//! the short limits, in which case both fields are extended as required by ISO 7816-4.
+//!
+//! Encoded body layouts (following the header) by ISO 7816-4 case:
+//!
+//! - Case 1: `00` (Lc is always present for header-only commands, as expected by Ledger devices)
+//! - Case 2S: `Le`, case 2E: `00 Le1 Le2`
+//! - Case 3S: `Lc data`, case 3E: `00 Lc1 Lc2 data`
+//! - Case 4S: `Lc data Le`, case 4E: `00 Lc1 Lc2 data Le1 Le2`
+//!
+//! [ApduCase::classify] may be used to classify and validate raw command APDUs.
+
+use crate::ApduError;
+
+/// Maximum data length for short APDUs (1 byte Lc)
+pub const SHORT_MAX_LEN: usize = u8::MAX as usize;
+
+/// Maximum data length for extended APDUs (3 byte Lc)
+pub const EXTENDED_MAX_LEN: usize = u16::MAX as usize;
+
+/// Command APDU length fields
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ApduLengths {
+    /// Command data length (Lc)
+    pub lc: usize,
+    /// Expected response length (Le), where specified
+    pub le: Option<usize>,
+}
+
+impl ApduLengths {
+    /// Create length fields for the provided data and expected response lengths
+    pub fn new(lc: usize, le: Option<usize>) -> Result<Self, ApduError> {
+        if lc > EXTENDED_MAX_LEN || le.unwrap_or_default() > EXTENDED_MAX_LEN + 1 {
+            return Err(ApduError::InvalidLength);
+        }
+
+        Ok(Self { lc, le })
+    }
+
+    /// Check whether extended length encoding is required
+    pub fn is_extended(&self) -> bool {
+        self.lc > SHORT_MAX_LEN || self.le.unwrap_or_default() > SHORT_MAX_LEN + 1
+    }
+
+    /// Check whether this is a case 2 command (Le without data), omitting the Lc field
+    fn is_case2(&self) -> bool {
+        self.lc == 0 && self.le.is_some()
+    }
+
+    /// Fetch the encoded Lc field length (zero for case 2 commands)
+    pub fn lc_len(&self) -> usize {
+        match (self.is_case2(), self.is_extended()) {
+            (true, _) => 0,
+            (false, true) => 3,
+            (false, false) => 1,
+        }
+    }
+
+    /// Fetch the encoded Le field length (zero where Le is not specified),
+    /// including the leading zero for extended case 2 commands
+    pub fn le_len(&self) -> usize {
+        match (self.le, self.is_extended()) {
+            (None, _) => 0,
+            (Some(_), true) if self.is_case2() => 3,
+            (Some(_), true) => 2,
+            (Some(_), false) => 1,
+        }
+    }
+
+    /// Encode the Lc field (omitted for case 2 commands), returning the number of bytes written
+    pub fn encode_lc(&self, buff: &mut [u8]) -> Result<usize, ApduError> {
+        let n = self.lc_len();
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        match n {
+            // Case 2, no Lc
+            0 => (),
+            // Extended, zero marker then 2 byte big-endian length
+            3 => {
+                buff[0] = 0x00;
+                buff[1..3].copy_from_slice(&(self.lc as u16).to_be_bytes());
+            }
+            // Short, single byte length
+            _ => buff[0] = self.lc as u8,
+        }
+
+        Ok(n)
+    }
+
+    /// Encode the Le field (if specified), returning the number of bytes written.
+    ///
+    /// Maximum lengths (256 for short, 65536 for extended) are encoded as zero, and
+    /// extended case 2 commands (without Lc) are prefixed with a zero marker.
+    pub fn encode_le(&self, buff: &mut [u8]) -> Result<usize, ApduError> {
+        let le = match self.le {
+            Some(v) => v,
+            None => return Ok(0),
+        };
+
+        let n = self.le_len();
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        match n {
+            // Extended case 2, zero marker then 2 byte big-endian length
+            3 => {
+                buff[0] = 0x00;
+                buff[1..3].copy_from_slice(&(le as u16).to_be_bytes());
+            }
+            // Extended, 2 byte big-endian length
+            2 => buff[..2].copy_from_slice(&(le as u16).to_be_bytes()),
+            // Short, single byte length
+            _ => buff[0] = le as u8,
+        }
+
+        Ok(n)
+    }
+}
+
+/// Decode the Lc field from the body of a command APDU (following the header),
+/// returning the data length and the length of the Lc field.
+///
+/// A zero first byte indicates an extended Lc only where the following length matches
+/// the remaining body, otherwise this is a short Lc of zero.
+pub fn decode_lc(body: &[u8]) -> Result<(usize, usize), ApduError> {
+    match body {
+        [] => Err(ApduError::InvalidLength),
+        [0x00, a, b, data @ ..] if u16::from_be_bytes([*a, *b]) as usize == data.len() => {
+            Ok((data.len(), 3))
+        }
+        [n, ..] => Ok((*n as usize, 1)),
+    }
+}
+
+/// ISO 7816-4 command APDU cases, see [ApduCase::classify]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ApduCase {
+    /// Case 1, header only (no data or expected response)
+    Case1,
+    /// Case 2, expected response length (Le) only
+    Case2 {
+        /// Expected response length
+        le: usize,
+        /// Extended length encoding
+        extended: bool,
+    },
+    /// Case 3, command data (Lc) only
+    Case3 {
+        /// Command data length
+        lc: usize,
+        /// Extended length encoding
+        extended: bool,
+    },
+    /// Case 4, command data (Lc) and expected response length (Le)
+    Case4 {
+        /// Command data length
+        lc: usize,
+        /// Expected response length
+        le: usize,
+        /// Extended length encoding
+        extended: bool,
+    },
+}
+
+impl ApduCase {
+    /// Classify an encoded command APDU (header, optional Lc / data, optional Le),
+    /// checking Lc and Le are consistent with the frame length.
+    ///
+    /// Zero Le values are decoded as the maximum (256 for short, 65536 for extended) lengths.
+    ///
+    /// Note that Ledger devices expect Lc to always be present, so header-only requests
+    /// encoded with a zero Lc (eg. by `ledger-lib`) are classified as [ApduCase::Case2].
+    pub fn classify(apdu: &[u8]) -> Result<Self, ApduError> {
+        let body = match apdu.get(4..) {
+            Some(b) => b,
+            None => return Err(ApduError::InvalidLength),
+        };
+
+        let short_le = |v: u8| match v {
+            0 => SHORT_MAX_LEN + 1,
+            v => v as usize,
+        };
+        let extended_le = |a: u8, b: u8| match u16::from_be_bytes([a, b]) {
+            0 => EXTENDED_MAX_LEN + 1,
+            v => v as usize,
+        };
+
+        match body {
+            // Header only
+            [] => Ok(Self::Case1),
+            // Short Le
+            [le] => Ok(Self::Case2 {
+                le: short_le(*le),
+                extended: false,
+            }),
+            // Extended Le
+            [0x00, a, b] => Ok(Self::Case2 {
+                le: extended_le(*a, *b),
+                extended: true,
+            }),
+            // Extended Lc, with optional extended Le
+            [0x00, a, b, rest @ ..] => {
+                let lc = u16::from_be_bytes([*a, *b]) as usize;
+                if lc == 0 {
+                    return Err(ApduError::InvalidEncoding);
+                }
+
+                match rest.len().checked_sub(lc) {
+                    Some(0) => Ok(Self::Case3 { lc, extended: true }),
+                    Some(2) => Ok(Self::Case4 {
+                        lc,
+                        le: extended_le(rest[lc], rest[lc + 1]),
+                        extended: true,
+                    }),
+                    Some(_) => Err(ApduError::InvalidEncoding),
+                    None => Err(ApduError::InvalidFieldLength {
+                        field: "data",
+                        offset: 7,
+                        needed: lc,
+                        available: rest.len(),
+                    }),
+                }
+            }
+            // Short Lc, with optional short Le
+            [lc, rest @ ..] => {
+                let lc = *lc as usize;
+
+                match rest.len().checked_sub(lc) {
+                    Some(0) => Ok(Self::Case3 {
+                        lc,
+                        extended: false,
+                    }),
+                    Some(1) => Ok(Self::Case4 {
+                        lc,
+                        le: short_le(rest[lc]),
+                        extended: false,
+                    }),
+                    Some(_) => Err(ApduError::InvalidEncoding),
+                    None => Err(ApduError::InvalidFieldLength {
+                        field: "data",
+                        offset: 5,
+                        needed: lc,
+                        available: rest.len(),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Fetch the command data length (Lc), zero where not present
+    pub fn lc(&self) -> usize {
+        match self {
+            Self::Case3 { lc, .. } | Self::Case4 { lc, .. } => *lc,
+            _ => 0,
+        }
+    }
+
+    /// Fetch the expected response length (Le), where present
+    pub fn le(&self) -> Option<usize> {
+        match self {
+            Self::Case2 { le, .. } | Self::Case4 { le, .. } => Some(*le),
+            _ => None,
+        }
+    }
+
+    /// Check whether the command uses extended length encoding
+    pub fn is_extended(&self) -> bool {
+        match self {
+            Self::Case1 => false,
+            Self::Case2 { extended, .. }
+            | Self::Case3 { extended, .. }
+            | Self::Case4 { extended, .. } => *extended,
+        }
+    }
+
+    /// Fetch the command data from the classified APDU
+    pub fn data<'a>(&self, apdu: &'a [u8]) -> &'a [u8] {
+        let offset = match self.is_extended() {
+            true => 7,
+            false => 5,
+        };
+
+        apdu.get(offset..)
+            .and_then(|d| d.get(..self.lc()))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_lengths() {
+        let l = ApduLengths::new(255, Some(256)).unwrap();
+        assert!(!l.is_extended());
+
+        let mut buff = [0xffu8; 3];
+        assert_eq!(l.encode_lc(&mut buff).unwrap(), 1);
+        assert_eq!(buff[0], 0xff);
+
+        assert_eq!(l.encode_le(&mut buff).unwrap(), 1);
+        assert_eq!(buff[0], 0x00);
+    }
+
+    #[test]
+    fn extended_lengths() {
+        let l = ApduLengths::new(0x0123, Some(0x10000)).unwrap();
+        assert!(l.is_extended());
+        assert_eq!((l.lc_len(), l.le_len()), (3, 2));
+
+        let mut buff = [0xffu8; 3];
+        assert_eq!(l.encode_lc(&mut buff).unwrap(), 3);
+        assert_eq!(buff, [0x00, 0x01, 0x23]);
+
+        assert_eq!(l.encode_le(&mut buff).unwrap(), 2);
+        assert_eq!(&buff[..2], &[0x00, 0x00]);
+
+        // Extended Le forces extended Lc
+        let l = ApduLengths::new(1, Some(512)).unwrap();
+        assert_eq!((l.lc_len(), l.le_len()), (3, 2));
+
+        // No Lc without data, extended Le gains the zero marker
+        let l = ApduLengths::new(0, Some(512)).unwrap();
+        assert_eq!((l.lc_len(), l.le_len()), (0, 3));
+
+        assert!(ApduLengths::new(EXTENDED_MAX_LEN + 1, None).is_err());
+    }
+
+    /// Encode `header | Lc | data | Le` using [ApduLengths], returning the encoded length
+    fn encode(buff: &mut [u8], data: &[u8], le: Option<usize>) -> usize {
+        let l = ApduLengths::new(data.len(), le).unwrap();
+
+        buff[..4].copy_from_slice(&[0xe0, 0x01, 0x00, 0x00]);
+        let mut index = 4 + l.encode_lc(&mut buff[4..]).unwrap();
+        buff[index..][..data.len()].copy_from_slice(data);
+        index += data.len();
+        index += l.encode_le(&mut buff[index..]).unwrap();
+
+        assert_eq!(index, 4 + l.lc_len() + data.len() + l.le_len());
+        index
+    }
+
+    /// Golden vector, containing data, Le, the expected encoded fields preceding
+    /// and following the data, and the expected classification
+    type Golden<'a> = (&'a [u8], Option<usize>, &'a [u8], &'a [u8], ApduCase);
+
+    #[test]
+    fn golden_cases() {
+        let ext = [0xaa; 300];
+
+        let tests: &[Golden] = &[
+            // Case 1, zero Lc retained for Ledger devices
+            (
+                &[],
+                None,
+                &[0x00],
+                &[],
+                ApduCase::Case2 {
+                    le: 256,
+                    extended: false,
+                },
+            ),
+            // Case 2S, Le only
+            (
+                &[],
+                Some(0x20),
+                &[],
+                &[0x20],
+                ApduCase::Case2 {
+                    le: 0x20,
+                    extended: false,
+                },
+            ),
+            (
+                &[],
+                Some(256),
+                &[],
+                &[0x00],
+                ApduCase::Case2 {
+                    le: 256,
+                    extended: false,
+                },
+            ),
+            // Case 2E, zero marker then 2 byte Le
+            (
+                &[],
+                Some(0x0200),
+                &[],
+                &[0x00, 0x02, 0x00],
+                ApduCase::Case2 {
+                    le: 0x0200,
+                    extended: true,
+                },
+            ),
+            (
+                &[],
+                Some(0x10000),
+                &[],
+                &[0x00, 0x00, 0x00],
+                ApduCase::Case2 {
+                    le: 0x10000,
+                    extended: true,
+                },
+            ),
+            // Case 3E, zero marker then 2 byte Lc and data
+            (
+                &ext,
+                None,
+                &[0x00, 0x01, 0x2c],
+                &[],
+                ApduCase::Case3 {
+                    lc: 300,
+                    extended: true,
+                },
+            ),
+            // Case 4E, 2 byte Le follows data without marker
+            (
+                &ext,
+                Some(0x0200),
+                &[0x00, 0x01, 0x2c],
+                &[0x02, 0x00],
+                ApduCase::Case4 {
+                    lc: 300,
+                    le: 0x0200,
+                    extended: true,
+                },
+            ),
+            (
+                &[0xaa],
+                Some(0x0200),
+                &[0x00, 0x00, 0x01],
+                &[0x02, 0x00],
+                ApduCase::Case4 {
+                    lc: 1,
+                    le: 0x0200,
+                    extended: true,
+                },
+            ),
+        ];
+
+        for (data, le, pre, post, case) in tests {
+            let mut buff = [0u8; 512];
+            let n = encode(&mut buff, data, *le);
+            let a = &buff[..n];
+
+            let (body, rest) = a[4..].split_at(pre.len());
+            let (d, l) = rest.split_at(data.len());
+            assert_eq!((body, d, l), (*pre, *data, *post), "encoding for {case:?}");
+            assert_eq!(&ApduCase::classify(a).unwrap(), case);
+        }
+    }
+
+    #[test]
+    fn decode_lengths() {
+        assert_eq!(decode_lc(&[0x00]).unwrap(), (0, 1));
+        assert_eq!(decode_lc(&[0x02, 0xaa, 0xbb]).unwrap(), (2, 1));
+        assert_eq!(decode_lc(&[0x00, 0x00, 0x01, 0xaa]).unwrap(), (1, 3));
+        assert!(decode_lc(&[]).is_err());
+
+        let mut body = [0u8; 3 + 300];
+        ApduLengths::new(300, None)
+            .unwrap()
+            .encode_lc(&mut body)
+            .unwrap();
+        assert_eq!(decode_lc(&body).unwrap(), (300, 3));
+    }
+
+    #[test]
+    fn classify_cases() {
+        let h = [0xe0, 0x01, 0x00, 0x00];
+        let c = |b: &[u8]| ApduCase::classify(&[&h[..], b].concat());
+
+        assert_eq!(c(&[]).unwrap(), ApduCase::Case1);
+        assert_eq!(
+            c(&[0x00]).unwrap(),
+            ApduCase::Case2 {
+                le: 256,
+                extended: false
+            }
+        );
+        assert_eq!(
+            c(&[0x00, 0x01, 0x00]).unwrap(),
+            ApduCase::Case2 {
+                le: 256,
+                extended: true
+            }
+        );
+        assert_eq!(
+            c(&[0x02, 0xaa, 0xbb]).unwrap(),
+            ApduCase::Case3 {
+                lc: 2,
+                extended: false
+            }
+        );
+        assert_eq!(
+            c(&[0x01, 0xaa, 0x20]).unwrap(),
+            ApduCase::Case4 {
+                lc: 1,
+                le: 32,
+                extended: false
+            }
+        );
+        assert_eq!(
+            c(&[0x00, 0x00, 0x01, 0xaa, 0x00, 0x00]).unwrap(),
+            ApduCase::Case4 {
+                lc: 1,
+                le: 65536,
+                extended: true
+            }
+        );
+
+        let a = [&h[..], &[0x01, 0xaa, 0x20]].concat();
+        let r = ApduCase::classify(&a).unwrap();
+        assert_eq!((r.lc(), r.le()), (1, Some(32)));
+        assert_eq!(r.data(&a), &[0xaa]);
+
+        // Malformed frames
+        assert!(ApduCase::classify(&h[..3]).is_err());
+        assert!(c(&[0x03, 0xaa]).unwrap_err().is_length());
+        assert!(c(&[0x01, 0xaa, 0x00, 0x00]).is_err());
+        assert!(c(&[0x00, 0x00, 0x00, 0xaa]).is_err());
+        assert!(c(&[0x00, 0x00, 0x02, 0xaa]).is_err());
+    }
+}
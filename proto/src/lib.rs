@@ -35,6 +35,20 @@
 //! }
 //! ```
 //!
+//! With the `derive` feature, [ApduStatic] can also be derived using `#[apdu(...)]` attributes,
+//! with optional `p1` / `p2` expressions evaluated against `self`:
+//!
+//! ```
+//! # #[cfg(feature = "derive")] {
+//! use ledger_proto::{ApduStatic, ApduError, Encode, DecodeOwned};
+//!
+//! #[derive(Clone, Debug, PartialEq, Encode, DecodeOwned, ApduStatic)]
+//! #[encdec(error = "ApduError")]
+//! #[apdu(cla = 0xb0, ins = 0x01)]
+//! pub struct AppInfoReq {}
+//! # }
+//! ```
+//!
 //! Manual response APDU implementation
 //!
 //! ```
@@ -114,17 +128,59 @@ use alloc::vec::Vec;
 
 pub use encdec::{Decode, DecodeOwned, EncDec, Encode};
 
+#[cfg(feature = "derive")]
+pub use ledger_proto_derive::ApduStatic;
+
 mod error;
 pub use error::ApduError;
+pub(crate) use error::{field_bytes, prefixed_field};
 
 pub mod apdus;
 
 mod status;
-pub use status::StatusCode;
+pub use status::{AppStatus, ParseStatusError, Status, StatusCode};
+
+#[cfg(feature = "serde")]
+mod hex_bytes;
+
+pub mod length;
+
+mod encoder;
+pub use encoder::ApduEncoder;
+
+pub mod fmt;
+
+mod bip32;
+pub use bip32::{Bip32Error, Bip32Path, HARDENED, MAX_BIP32_DEPTH};
+
+mod lv;
+pub use lv::{Lv, LvStr};
+
+mod chunked;
+pub use chunked::{ChunkMarkers, ChunkedReq, ReqChunk};
+
+mod resp;
+pub use resp::{split_status, ApduResp, STATUS_LEN};
+
+#[cfg(feature = "alloc")]
+pub mod framing;
+
+#[cfg(feature = "ledger_apdu")]
+mod compat;
+
+#[cfg(feature = "scp")]
+pub mod scp;
+
+pub mod testing;
+
+#[cfg(test)]
+mod fixtures;
 
 /// APDU command header
-#[derive(Copy, Clone, PartialEq, Debug, Default, Encode, DecodeOwned)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default, Encode, DecodeOwned)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[encdec(error = "ApduError")]
 pub struct ApduHeader {
     /// Class ID
@@ -173,12 +229,23 @@ pub trait ApduStatic {
     fn p2(&self) -> u8 {
         0
     }
+
+    /// Fetch the expected response length (Le), if required (defaults to `None` if not extended)
+    fn le(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Generic APDU request trait
 pub trait ApduReq<'a>: EncDec<'a, ApduError> {
     /// Fetch the [ApduHeader] for a given APDU request
     fn header(&self) -> ApduHeader;
+
+    /// Fetch the expected response length (Le), appended to the encoded request
+    /// where specified (defaults to `None`)
+    fn le(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Blanket [ApduReq] impl for [ApduStatic] types
@@ -191,6 +258,10 @@ impl<'a, T: EncDec<'a, ApduError> + ApduStatic> ApduReq<'a> for T {
             p2: self.p2(),
         }
     }
+
+    fn le(&self) -> Option<usize> {
+        ApduStatic::le(self)
+    }
 }
 
 /// Generic APDU base trait, auto-implemented where `T: EncDec<'a, ApduError>`
@@ -200,8 +271,10 @@ pub trait ApduBase<'a>: EncDec<'a, ApduError> {}
 impl<'a, T: EncDec<'a, ApduError>> ApduBase<'a> for T {}
 
 /// Generic APDU object (enabled with `alloc` feature), prefer use of strict APDU types where possible
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[cfg(feature = "alloc")]
 pub struct GenericApdu {
     /// Request APDU Header (uses [Default] for incoming / response APDUs)
@@ -211,6 +284,32 @@ pub struct GenericApdu {
     pub data: Vec<u8>,
 }
 
+#[cfg(feature = "alloc")]
+mod builder;
+#[cfg(feature = "alloc")]
+pub use builder::GenericApduBuilder;
+
+mod borrowed;
+pub use borrowed::GenericApduRef;
+
+#[cfg(feature = "heapless")]
+mod fixed;
+#[cfg(feature = "heapless")]
+pub use fixed::GenericApduN;
+
+#[cfg(feature = "alloc")]
+mod dispatch;
+#[cfg(feature = "alloc")]
+pub use dispatch::{ApduDispatcher, Handler, HandlerResult};
+
+#[cfg(feature = "schema")]
+pub mod schema;
+
+#[cfg(feature = "alloc")]
+mod transcript;
+#[cfg(feature = "alloc")]
+pub use transcript::{Transcript, TranscriptEntry, TranscriptError};
+
 /// [ApduReq] implementation for [GenericApdu], exposes internal header
 #[cfg(feature = "alloc")]
 impl<'a> ApduReq<'a> for GenericApdu {
@@ -260,21 +359,106 @@ impl DecodeOwned for GenericApdu {
 }
 
 #[cfg(test)]
-pub(crate) mod tests {
+mod tests {
     use super::*;
-    use encdec::EncDec;
+    use crate::testing::encode_decode;
+
+    /// Round-trip [arbitrary::Arbitrary] APDU objects generated from pseudo-random data
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_encode_decode() {
+        use crate::apdus::*;
+        use arbitrary::{Arbitrary, Unstructured};
+
+        // Objects with header-carried parameters (eg. [AppListReq]) are not included
+        // as these are not round-tripped via the APDU body.
+        //
+        // Generate, encode (skipping objects that can not be encoded, eg. oversized fields) and decode
+        macro_rules! check {
+            ($data:expr, $($t:ty),*) => {$(
+                let mut buff = [0u8; 1024];
+                if let Ok(a) = <$t>::arbitrary(&mut Unstructured::new($data)) {
+                    if a.encode(&mut buff).is_ok() {
+                        encode_decode(&mut buff, a);
+                    }
+                }
+            )*};
+        }
+
+        let mut data = [0u8; 512];
+        let mut x = 0x2545_f491u32;
+        for _ in 0..256 {
+            for d in data.iter_mut() {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                *d = x as u8;
+            }
+
+            check!(
+                &data,
+                ApduHeader,
+                AppInfoReq,
+                AppInfoResp,
+                DeviceInfoReq,
+                DeviceInfoResp,
+                AppData,
+                AppListResp,
+                RunAppReq,
+                ExitAppReq,
+                WalletIdReq,
+                WalletIdResp,
+                GetBatteryStatusResp,
+                ValidateTargetIdReq,
+                InitAuthReq,
+                InitAuthResp,
+                DeviceCert,
+                EndorsementKeyResp,
+                CommitEndorsementReq,
+                GetMemoryInfoReq,
+                MemoryInfoResp,
+                McuVersionReq,
+                McuVersionResp,
+                McuValidateReq,
+                McuResetReq,
+                CreateAppReq,
+                SelectSegmentReq,
+                LoadSegmentReq,
+                FlushReq,
+                CommitAppReq,
+                DeleteAppReq,
+                DeleteAppByHashReq,
+                Tlv
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn generic_apdu_keys() {
+        use std::collections::HashMap;
 
-    /// Helper to test round-trip encode / decode for APDUS
-    pub fn encode_decode<'a, A: EncDec<'a, ApduError> + PartialEq>(buff: &'a mut [u8], a: A) {
-        // Test encoding
-        let n = a.encode(buff).unwrap();
+        let a = GenericApdu::build()
+            .cla(0xe0)
+            .ins(0x01)
+            .data([0xaa])
+            .finish();
+        let b = GenericApdu::build()
+            .cla(0xe0)
+            .ins(0x01)
+            .data([0xbb])
+            .finish();
 
-        // Test decoding
-        let (a1, n1) = A::decode(&buff[..n]).unwrap();
+        // Requests are usable as replay map keys
+        let mut m = HashMap::new();
+        m.insert(a.clone(), [0x90, 0x00]);
+        m.insert(b.clone(), [0x6a, 0x80]);
+        assert_eq!(m.get(&a), Some(&[0x90, 0x00]));
+        assert_eq!(m.get(&b), Some(&[0x6a, 0x80]));
 
-        // Compare results
-        assert_eq!(n1, n);
-        assert_eq!(a1, a);
+        // As are headers, for dispatch tables
+        let h: HashMap<_, _> = [(a.header, "a")].into_iter().collect();
+        assert_eq!(h.get(&b.header), Some(&"a"));
     }
 
     #[test]
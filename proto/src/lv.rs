@@ -0,0 +1,155 @@
+//! Length-prefixed value helpers, for the single byte length-prefixed fields
+//! used throughout Ledger APDUs.
+//!
+//! ```
+//! use ledger_proto::{Decode, Encode, Lv, LvStr};
+//!
+//! let mut buff = [0u8; 16];
+//! let n = LvStr("BTC").encode(&mut buff).unwrap();
+//! assert_eq!(&buff[..n], &[0x03, 0x42, 0x54, 0x43]);
+//!
+//! let (v, m) = LvStr::decode(&buff[..n]).unwrap();
+//! assert_eq!((v.0, m), ("BTC", 4));
+//!
+//! // Values exceeding the available data are rejected
+//! assert!(Lv::decode(&[0x02, 0xaa]).is_err());
+//! ```
+
+use core::ops::Deref;
+
+use encdec::{Decode, Encode};
+
+use crate::{prefixed_field, ApduError};
+
+/// Length-prefixed bytes, encoded as a single byte length followed by the value
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Lv<'a>(
+    #[cfg_attr(feature = "serde", serde(borrow, with = "crate::hex_bytes"))] pub &'a [u8],
+);
+
+impl<'a> Deref for Lv<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a> From<&'a [u8]> for Lv<'a> {
+    fn from(v: &'a [u8]) -> Self {
+        Self(v)
+    }
+}
+
+impl<'a> Encode for Lv<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        if self.0.len() > u8::MAX as usize {
+            return Err(ApduError::InvalidLength);
+        }
+
+        Ok(1 + self.0.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.encode_len()?;
+        if buff.len() < n {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[0] = self.0.len() as u8;
+        buff[1..n].copy_from_slice(self.0);
+
+        Ok(n)
+    }
+}
+
+impl<'a> Decode<'a> for Lv<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let (v, n) = prefixed_field(buff, "value", 0)?;
+        Ok((Self(v), n))
+    }
+}
+
+/// Length-prefixed UTF-8 string, encoded as a single byte length followed by the value
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct LvStr<'a>(pub &'a str);
+
+impl<'a> Deref for LvStr<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a> From<&'a str> for LvStr<'a> {
+    fn from(v: &'a str) -> Self {
+        Self(v)
+    }
+}
+
+impl<'a> Encode for LvStr<'a> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Lv(self.0.as_bytes()).encode_len()
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        Lv(self.0.as_bytes()).encode(buff)
+    }
+}
+
+impl<'a> Decode<'a> for LvStr<'a> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let (v, n) = Lv::decode(buff)?;
+        let s = core::str::from_utf8(v.0).map_err(|_| ApduError::InvalidUtf8)?;
+        Ok((Self(s), n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::encode_decode;
+
+    #[test]
+    fn lv() {
+        let mut buff = [0u8; 300];
+        encode_decode(&mut buff, Lv(&[0xaa, 0xbb]));
+        encode_decode(&mut buff, Lv(&[]));
+        encode_decode(&mut buff, LvStr("Bitcoin"));
+
+        // Values are limited to 255 bytes
+        assert_eq!(Lv(&[0u8; 255]).encode_len(), Ok(256));
+        assert_eq!(Lv(&[0u8; 256]).encode_len(), Err(ApduError::InvalidLength));
+        assert_eq!(
+            Lv(&[0xaa]).encode(&mut buff[..1]),
+            Err(ApduError::InvalidLength)
+        );
+
+        assert!(Lv::decode(&[]).unwrap_err().is_length());
+        assert!(Lv::decode(&[0x03, 0x01, 0x02]).unwrap_err().is_length());
+        assert_eq!(LvStr::decode(&[0x01, 0xff]), Err(ApduError::InvalidUtf8));
+
+        // Trailing data is not consumed
+        let (v, n) = LvStr::decode(&[0x02, 0x41, 0x42, 0x43]).unwrap();
+        assert_eq!((&*v, n), ("AB", 3));
+    }
+}
@@ -0,0 +1,138 @@
+//! Response wrapper pairing decoded response APDUs with their trailing status word
+
+use core::fmt::Debug;
+
+use encdec::{Decode, Encode};
+
+use crate::{ApduError, AppStatus, Status, StatusCode};
+
+/// Status word length
+pub const STATUS_LEN: usize = 2;
+
+/// Response APDU with the trailing status word, allowing warning statuses
+/// (for example `0x63xx`) to be observed alongside response data
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct ApduResp<T> {
+    /// Decoded response body
+    pub body: T,
+    /// Raw status word
+    pub sw: u16,
+}
+
+impl<T> ApduResp<T> {
+    /// Create a new response with the provided body and status word
+    pub fn new(body: T, sw: u16) -> Self {
+        Self { body, sw }
+    }
+
+    /// Fetch the [StatusCode] for the response
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from(self.sw)
+    }
+
+    /// Resolve the response status, preferring application-specific codes (see [AppStatus])
+    pub fn app_status<A: AppStatus>(&self) -> Status<A> {
+        A::resolve(self.sw)
+    }
+
+    /// Check whether the response status is OK (`0x9000`)
+    pub fn is_ok(&self) -> bool {
+        self.sw == u16::from(StatusCode::Ok)
+    }
+
+    /// Unwrap the response body, discarding the status
+    pub fn into_body(self) -> T {
+        self.body
+    }
+}
+
+/// Split a raw response into body and trailing status word, returning
+/// [ApduError::InvalidLength] where the response is too short to contain a status.
+///
+/// Unrecognised status words map to [StatusCode::Unknown], use `u16::from(status)`
+/// to fetch the raw value.
+pub fn split_status(buff: &[u8]) -> Result<(&[u8], StatusCode), ApduError> {
+    if buff.len() < STATUS_LEN {
+        return Err(ApduError::InvalidLength);
+    }
+
+    let (body, sw) = buff.split_at(buff.len() - STATUS_LEN);
+
+    Ok((body, StatusCode::from(u16::from_be_bytes([sw[0], sw[1]]))))
+}
+
+/// [Encode] implementation for [ApduResp], writes the body followed by the status word
+impl<T: Encode<Error = ApduError> + Debug> Encode for ApduResp<T> {
+    type Error = ApduError;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(self.body.encode_len()? + STATUS_LEN)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let n = self.body.encode(buff)?;
+        if buff.len() < n + STATUS_LEN {
+            return Err(ApduError::InvalidLength);
+        }
+
+        buff[n..][..STATUS_LEN].copy_from_slice(&self.sw.to_be_bytes());
+
+        Ok(n + STATUS_LEN)
+    }
+}
+
+/// [Decode] implementation for [ApduResp], decodes the body preceding the trailing status word
+impl<'a, T: Decode<'a, Output = T, Error = ApduError> + Debug> Decode<'a> for ApduResp<T> {
+    type Output = Self;
+
+    type Error = ApduError;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let (body, status) = split_status(buff)?;
+        let (body, _) = T::decode(body)?;
+
+        Ok((
+            Self {
+                body,
+                sw: u16::from(status),
+            },
+            buff.len(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apdus::WalletIdResp;
+
+    #[test]
+    fn split_status_word() {
+        assert_eq!(
+            split_status(&[0xaa, 0x90, 0x00]).unwrap(),
+            (&[0xaa][..], StatusCode::Ok)
+        );
+        assert_eq!(
+            split_status(&[0x63, 0xc2]).unwrap(),
+            (&[][..], StatusCode::Unknown(0x63c2))
+        );
+        assert!(split_status(&[0x90]).is_err());
+    }
+
+    #[test]
+    fn encode_decode_resp() {
+        let r = ApduResp::new(WalletIdResp::new(&[0xab; 4]), 0x6300);
+        assert!(!r.is_ok());
+        assert_eq!(r.status(), StatusCode::GpAuthFailed);
+
+        let mut buff = [0u8; 16];
+        let n = r.encode(&mut buff).unwrap();
+        assert_eq!(&buff[..n], &[0xab, 0xab, 0xab, 0xab, 0x63, 0x00]);
+
+        let (d, _) = ApduResp::<WalletIdResp>::decode(&buff[..n]).unwrap();
+        assert_eq!(d, r);
+    }
+}
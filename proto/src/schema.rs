@@ -0,0 +1,498 @@
+//! Declarative APDU schemas (enabled with the `schema` feature), describing APDU
+//! fields at runtime for encoding and decoding [DynamicApdu] objects without typed structs.
+//!
+//! Schemas implement [serde::Deserialize] so may be loaded from JSON, YAML or any other
+//! serde-supported format.
+//!
+//! ```
+//! use ledger_proto::schema::{ApduSchema, DynamicApdu, FieldValue};
+//!
+//! let s: ApduSchema = serde_json::from_str(r#"{
+//!     "name": "get_address",
+//!     "cla": 224,
+//!     "ins": 2,
+//!     "p1": 1,
+//!     "request": [
+//!         { "name": "path", "type": "bip32" }
+//!     ],
+//!     "response": [
+//!         { "name": "public_key", "type": "prefixed_bytes" },
+//!         { "name": "address", "type": "prefixed_string" }
+//!     ]
+//! }"#).unwrap();
+//!
+//! // Encode a request
+//! let req = DynamicApdu::new().with("path", FieldValue::Str("m/44'/60'/0'/0/0".into()));
+//! let apdu = s.encode_request(&req).unwrap();
+//! assert_eq!((apdu.header.ins, apdu.header.p1), (0x02, 0x01));
+//! assert_eq!(apdu.data.len(), 21);
+//!
+//! // Decode a response
+//! let resp = s.decode_response(&[0x02, 0xaa, 0xbb, 0x02, b'0', b'x']).unwrap();
+//! assert_eq!(resp.get("public_key"), Some(&FieldValue::Bytes(vec![0xaa, 0xbb])));
+//! assert_eq!(resp.get("address"), Some(&FieldValue::Str("0x".into())));
+//! ```
+
+use alloc::string::{String, ToString};
+
+use encdec::{DecodeOwned, Encode};
+
+use crate::{ApduError, ApduHeader, Bip32Path, GenericApdu, Vec};
+
+/// APDU schema, describing the header and request / response fields for a command
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ApduSchema {
+    /// Command name
+    pub name: String,
+    /// Class ID
+    pub cla: u8,
+    /// Instruction ID
+    pub ins: u8,
+    /// Parameter 1 (defaults to `0`)
+    #[serde(default)]
+    pub p1: u8,
+    /// Parameter 2 (defaults to `0`)
+    #[serde(default)]
+    pub p2: u8,
+    /// Request fields, encoded in order
+    #[serde(default)]
+    pub request: Vec<FieldSchema>,
+    /// Response fields, decoded in order (excluding the status word)
+    #[serde(default)]
+    pub response: Vec<FieldSchema>,
+}
+
+/// APDU field schema
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FieldSchema {
+    /// Field name
+    pub name: String,
+    /// Field type
+    #[serde(flatten)]
+    pub kind: FieldKind,
+}
+
+/// APDU field types, integers are big-endian unless `little_endian` is set
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FieldKind {
+    /// Single byte integer
+    U8,
+    /// Two byte integer
+    U16 {
+        /// Little-endian encoding
+        #[serde(default)]
+        little_endian: bool,
+    },
+    /// Four byte integer
+    U32 {
+        /// Little-endian encoding
+        #[serde(default)]
+        little_endian: bool,
+    },
+    /// Eight byte integer
+    U64 {
+        /// Little-endian encoding
+        #[serde(default)]
+        little_endian: bool,
+    },
+    /// Fixed length bytes, or all remaining bytes where no length is specified
+    Bytes {
+        /// Fixed length in bytes
+        #[serde(default)]
+        len: Option<usize>,
+    },
+    /// Bytes with a single byte length prefix
+    PrefixedBytes,
+    /// Fixed length UTF-8 string, or all remaining bytes where no length is specified
+    String {
+        /// Fixed length in bytes
+        #[serde(default)]
+        len: Option<usize>,
+    },
+    /// UTF-8 string with a single byte length prefix
+    PrefixedString,
+    /// BIP32 derivation path (see [Bip32Path])
+    Bip32,
+}
+
+/// Dynamic APDU field values
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum FieldValue {
+    /// Integer value
+    Int(u64),
+    /// String value, also used for BIP32 paths
+    Str(String),
+    /// Byte value
+    Bytes(Vec<u8>),
+}
+
+/// Dynamic APDU object, containing named field values for use with an [ApduSchema]
+#[derive(Clone, Debug, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub struct DynamicApdu {
+    fields: Vec<(String, FieldValue)>,
+}
+
+/// Schema encode / decode errors
+#[derive(Clone, PartialEq, Debug, displaydoc::Display)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum SchemaError {
+    /// Missing value for field {0}
+    MissingField(String),
+
+    /// Invalid value for field {0}
+    InvalidValue(String),
+
+    /// Invalid length for field {0}
+    InvalidLength(String),
+
+    /// Invalid UTF8 for field {0}
+    InvalidUtf8(String),
+
+    /// Trailing data ({0} bytes)
+    TrailingData(usize),
+
+    /// APDU encode / decode error: {0}
+    Apdu(ApduError),
+}
+
+/// [core::error::Error] implementation for [SchemaError] without the `std` feature
+#[cfg(not(feature = "std"))]
+impl core::error::Error for SchemaError {}
+
+impl From<ApduError> for SchemaError {
+    fn from(e: ApduError) -> Self {
+        Self::Apdu(e)
+    }
+}
+
+impl DynamicApdu {
+    /// Create a new empty dynamic APDU
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a field value
+    pub fn with(mut self, name: &str, value: FieldValue) -> Self {
+        self.fields.push((name.to_string(), value));
+        self
+    }
+
+    /// Fetch a field value by name
+    pub fn get(&self, name: &str) -> Option<&FieldValue> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// Iterate over field names and values
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &FieldValue)> {
+        self.fields.iter().map(|(n, v)| (n.as_str(), v))
+    }
+}
+
+impl ApduSchema {
+    /// Fetch the [ApduHeader] for this command
+    pub fn header(&self) -> ApduHeader {
+        ApduHeader {
+            cla: self.cla,
+            ins: self.ins,
+            p1: self.p1,
+            p2: self.p2,
+        }
+    }
+
+    /// Encode a request from the provided field values
+    pub fn encode_request(&self, values: &DynamicApdu) -> Result<GenericApdu, SchemaError> {
+        Ok(GenericApdu {
+            header: self.header(),
+            data: encode_fields(&self.request, values)?,
+        })
+    }
+
+    /// Decode a request body to field values
+    pub fn decode_request(&self, buff: &[u8]) -> Result<DynamicApdu, SchemaError> {
+        decode_fields(&self.request, buff)
+    }
+
+    /// Encode a response body (excluding the status word) from the provided field values
+    pub fn encode_response(&self, values: &DynamicApdu) -> Result<Vec<u8>, SchemaError> {
+        encode_fields(&self.response, values)
+    }
+
+    /// Decode a response body (excluding the status word) to field values
+    pub fn decode_response(&self, buff: &[u8]) -> Result<DynamicApdu, SchemaError> {
+        decode_fields(&self.response, buff)
+    }
+}
+
+/// Encode field values using the provided field schemas
+pub fn encode_fields(fields: &[FieldSchema], values: &DynamicApdu) -> Result<Vec<u8>, SchemaError> {
+    let mut buff = Vec::new();
+
+    for f in fields {
+        let v = values
+            .get(&f.name)
+            .ok_or_else(|| SchemaError::MissingField(f.name.clone()))?;
+        let invalid = || SchemaError::InvalidValue(f.name.clone());
+        let invalid_len = || SchemaError::InvalidLength(f.name.clone());
+
+        // Integer helper, checking the value fits the field
+        let int = |max: u64| match v {
+            FieldValue::Int(i) if *i <= max => Ok(*i),
+            _ => Err(invalid()),
+        };
+
+        match &f.kind {
+            FieldKind::U8 => buff.push(int(u8::MAX as u64)? as u8),
+            FieldKind::U16 { little_endian } => {
+                let i = int(u16::MAX as u64)? as u16;
+                buff.extend_from_slice(&endian(i.to_be_bytes(), *little_endian));
+            }
+            FieldKind::U32 { little_endian } => {
+                let i = int(u32::MAX as u64)? as u32;
+                buff.extend_from_slice(&endian(i.to_be_bytes(), *little_endian));
+            }
+            FieldKind::U64 { little_endian } => {
+                let i = int(u64::MAX)?;
+                buff.extend_from_slice(&endian(i.to_be_bytes(), *little_endian));
+            }
+            FieldKind::Bytes { len } | FieldKind::String { len } => {
+                let d = match (&f.kind, v) {
+                    (FieldKind::Bytes { .. }, FieldValue::Bytes(b)) => b.as_slice(),
+                    (FieldKind::String { .. }, FieldValue::Str(s)) => s.as_bytes(),
+                    _ => return Err(invalid()),
+                };
+                if len.is_some_and(|n| n != d.len()) {
+                    return Err(invalid_len());
+                }
+                buff.extend_from_slice(d);
+            }
+            FieldKind::PrefixedBytes | FieldKind::PrefixedString => {
+                let d = match (&f.kind, v) {
+                    (FieldKind::PrefixedBytes, FieldValue::Bytes(b)) => b.as_slice(),
+                    (FieldKind::PrefixedString, FieldValue::Str(s)) => s.as_bytes(),
+                    _ => return Err(invalid()),
+                };
+                if d.len() > u8::MAX as usize {
+                    return Err(invalid_len());
+                }
+                buff.push(d.len() as u8);
+                buff.extend_from_slice(d);
+            }
+            FieldKind::Bip32 => {
+                let p: Bip32Path = match v {
+                    FieldValue::Str(s) => s.parse().map_err(|_| invalid())?,
+                    _ => return Err(invalid()),
+                };
+
+                let mut b = [0u8; 1 + 4 * crate::MAX_BIP32_DEPTH];
+                let n = p.encode(&mut b)?;
+                buff.extend_from_slice(&b[..n]);
+            }
+        }
+    }
+
+    Ok(buff)
+}
+
+/// Decode field values using the provided field schemas
+pub fn decode_fields(fields: &[FieldSchema], buff: &[u8]) -> Result<DynamicApdu, SchemaError> {
+    let mut values = DynamicApdu::new();
+    let mut index = 0;
+
+    for f in fields {
+        let invalid_len = || SchemaError::InvalidLength(f.name.clone());
+        let invalid_utf8 = || SchemaError::InvalidUtf8(f.name.clone());
+
+        // Fetch `n` bytes from the current index
+        let take = |n: usize| {
+            buff.get(index..)
+                .and_then(|b| b.get(..n))
+                .ok_or_else(invalid_len)
+        };
+
+        let (v, n) = match &f.kind {
+            FieldKind::U8 => (FieldValue::Int(take(1)?[0] as u64), 1),
+            FieldKind::U16 { little_endian } => {
+                let b = endian(take(2)?.try_into().unwrap(), *little_endian);
+                (FieldValue::Int(u16::from_be_bytes(b) as u64), 2)
+            }
+            FieldKind::U32 { little_endian } => {
+                let b = endian(take(4)?.try_into().unwrap(), *little_endian);
+                (FieldValue::Int(u32::from_be_bytes(b) as u64), 4)
+            }
+            FieldKind::U64 { little_endian } => {
+                let b = endian(take(8)?.try_into().unwrap(), *little_endian);
+                (FieldValue::Int(u64::from_be_bytes(b)), 8)
+            }
+            FieldKind::Bytes { len } | FieldKind::String { len } => {
+                let d = take(len.unwrap_or(buff.len() - index))?;
+                let v = match &f.kind {
+                    FieldKind::String { .. } => FieldValue::Str(
+                        core::str::from_utf8(d)
+                            .map_err(|_| invalid_utf8())?
+                            .to_string(),
+                    ),
+                    _ => FieldValue::Bytes(d.to_vec()),
+                };
+                (v, d.len())
+            }
+            FieldKind::PrefixedBytes | FieldKind::PrefixedString => {
+                let n = take(1)?[0] as usize;
+                let d = buff
+                    .get(index + 1..)
+                    .and_then(|b| b.get(..n))
+                    .ok_or_else(invalid_len)?;
+                let v = match &f.kind {
+                    FieldKind::PrefixedString => FieldValue::Str(
+                        core::str::from_utf8(d)
+                            .map_err(|_| invalid_utf8())?
+                            .to_string(),
+                    ),
+                    _ => FieldValue::Bytes(d.to_vec()),
+                };
+                (v, 1 + n)
+            }
+            FieldKind::Bip32 => {
+                let (p, n) = Bip32Path::decode_owned(&buff[index..])?;
+                (FieldValue::Str(p.to_string()), n)
+            }
+        };
+
+        values.fields.push((f.name.clone(), v));
+        index += n;
+    }
+
+    if index < buff.len() {
+        return Err(SchemaError::TrailingData(buff.len() - index));
+    }
+
+    Ok(values)
+}
+
+/// Helper to swap big-endian bytes to little-endian where required
+fn endian<const N: usize>(mut b: [u8; N], little_endian: bool) -> [u8; N] {
+    if little_endian {
+        b.reverse();
+    }
+    b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+name: sign
+cla: 0xe0
+ins: 0x04
+p2: 0x01
+request:
+  - name: path
+    type: bip32
+  - name: amount
+    type: u64
+  - name: chain_id
+    type: u32
+    little_endian: true
+  - name: memo
+    type: prefixed_string
+  - name: payload
+    type: bytes
+response:
+  - name: v
+    type: u8
+  - name: r
+    type: bytes
+    len: 4
+"#;
+
+    #[test]
+    fn schema_yaml() {
+        let s: ApduSchema = serde_yaml::from_str(SCHEMA).unwrap();
+        assert_eq!(
+            s.header(),
+            ApduHeader {
+                cla: 0xe0,
+                ins: 0x04,
+                p1: 0,
+                p2: 1
+            }
+        );
+        assert_eq!(
+            s.request[2].kind,
+            FieldKind::U32 {
+                little_endian: true
+            }
+        );
+
+        // JSON round-trip
+        let j = serde_json::to_string(&s).unwrap();
+        assert_eq!(serde_json::from_str::<ApduSchema>(&j).unwrap(), s);
+    }
+
+    #[test]
+    fn schema_encode_decode() {
+        let s: ApduSchema = serde_yaml::from_str(SCHEMA).unwrap();
+
+        let req = DynamicApdu::new()
+            .with("path", FieldValue::Str("m/44'/1'".into()))
+            .with("amount", FieldValue::Int(0x0102))
+            .with("chain_id", FieldValue::Int(1))
+            .with("memo", FieldValue::Str("hi".into()))
+            .with("payload", FieldValue::Bytes(vec![0xaa, 0xbb]));
+
+        let apdu = s.encode_request(&req).unwrap();
+        assert_eq!(
+            &apdu.data[..9],
+            &[0x02, 0x80, 0x00, 0x00, 0x2c, 0x80, 0x00, 0x00, 0x01]
+        );
+        assert_eq!(&apdu.data[9..17], &[0, 0, 0, 0, 0, 0, 0x01, 0x02]);
+        assert_eq!(
+            &apdu.data[17..],
+            &[0x01, 0, 0, 0, 0x02, b'h', b'i', 0xaa, 0xbb]
+        );
+
+        assert_eq!(s.decode_request(&apdu.data).unwrap(), req);
+
+        let resp = DynamicApdu::new()
+            .with("v", FieldValue::Int(27))
+            .with("r", FieldValue::Bytes(vec![1, 2, 3, 4]));
+        let b = s.encode_response(&resp).unwrap();
+        assert_eq!(s.decode_response(&b).unwrap(), resp);
+    }
+
+    #[test]
+    fn schema_errors() {
+        let s: ApduSchema = serde_yaml::from_str(SCHEMA).unwrap();
+
+        let resp = DynamicApdu::new().with("v", FieldValue::Int(27));
+        assert_eq!(
+            s.encode_response(&resp),
+            Err(SchemaError::MissingField("r".into()))
+        );
+
+        let resp = resp.with("r", FieldValue::Bytes(vec![1, 2, 3]));
+        assert_eq!(
+            s.encode_response(&resp),
+            Err(SchemaError::InvalidLength("r".into()))
+        );
+
+        let resp = DynamicApdu::new()
+            .with("v", FieldValue::Int(256))
+            .with("r", FieldValue::Bytes(vec![1, 2, 3, 4]));
+        assert_eq!(
+            s.encode_response(&resp),
+            Err(SchemaError::InvalidValue("v".into()))
+        );
+
+        assert_eq!(
+            s.decode_response(&[27, 1, 2]),
+            Err(SchemaError::InvalidLength("r".into()))
+        );
+        assert_eq!(
+            s.decode_response(&[27, 1, 2, 3, 4, 5]),
+            Err(SchemaError::TrailingData(1))
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Secure channel APDU wrapping, following a completed [ScpHandshake](super::ScpHandshake)
+
+use aes::{
+    cipher::{generic_array::GenericArray, BlockDecryptMut, BlockEncryptMut, KeyIvInit},
+    Aes128,
+};
+use k256::{elliptic_curve::sec1::ToEncodedPoint, NonZeroScalar, ProjectivePoint};
+use sha2::{Digest, Sha256};
+
+use super::ScpError;
+use crate::{ApduReq, GenericApdu, Vec};
+
+/// AES block length
+const BLOCK_LEN: usize = 16;
+
+/// Truncated MAC length, appended to wrapped data
+const MAC_LEN: usize = 14;
+
+/// Padding marker, appended to data prior to zero-padding to the block length
+const PAD_MARKER: u8 = 0x80;
+
+/// Session key derivation index for the encryption key
+const ENC_KEY_INDEX: u32 = 0;
+
+/// Session key derivation index for the MAC key
+const MAC_KEY_INDEX: u32 = 1;
+
+/// Established secure channel, wrapping (encrypting and authenticating) APDU bodies.
+///
+/// Data is padded (`0x80` followed by zeroes to the block length), encrypted with
+/// AES-128-CBC, then authenticated with an AES-128-CBC-MAC over the ciphertext, truncated
+/// to 14 bytes. IVs are chained across all wrapped and unwrapped messages so each
+/// side of the channel must process every message in order.
+#[derive(Clone, PartialEq)]
+pub struct ScpChannel {
+    enc_key: [u8; 16],
+    mac_key: [u8; 16],
+    enc_iv: [u8; 16],
+    mac_iv: [u8; 16],
+}
+
+/// [core::fmt::Debug] implementation for [ScpChannel], omitting session keys
+impl core::fmt::Debug for ScpChannel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ScpChannel").finish_non_exhaustive()
+    }
+}
+
+impl ScpChannel {
+    /// Create a new channel from the ECDH shared secret (SHA-256 of the compressed
+    /// shared point), deriving encryption and MAC session keys
+    pub fn new(secret: &[u8; 32]) -> Self {
+        Self {
+            enc_key: derive_key(secret, ENC_KEY_INDEX),
+            mac_key: derive_key(secret, MAC_KEY_INDEX),
+            enc_iv: [0u8; 16],
+            mac_iv: [0u8; 16],
+        }
+    }
+
+    /// Wrap data for transmission, returning the encrypted data and MAC
+    pub fn wrap(&mut self, data: &[u8]) -> Vec<u8> {
+        // Pad data to the block length
+        let mut b = data.to_vec();
+        b.push(PAD_MARKER);
+        b.resize(b.len().next_multiple_of(BLOCK_LEN), 0);
+
+        // Encrypt, then compute the MAC over the ciphertext
+        self.enc_iv = cbc_encrypt(&self.enc_key, &self.enc_iv, &mut b);
+        self.mac_iv = cbc_mac(&self.mac_key, &self.mac_iv, &b);
+
+        b.extend_from_slice(&self.mac_iv[BLOCK_LEN - MAC_LEN..]);
+        b
+    }
+
+    /// Unwrap received data, checking the MAC and returning the decrypted data.
+    ///
+    /// Empty data (for example, responses containing only a status word) is returned unchanged.
+    pub fn unwrap(&mut self, data: &[u8]) -> Result<Vec<u8>, ScpError> {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Check data contains whole blocks and a MAC
+        let n = data.len().wrapping_sub(MAC_LEN);
+        if data.len() < BLOCK_LEN + MAC_LEN || !n.is_multiple_of(BLOCK_LEN) {
+            return Err(ScpError::InvalidLength(data.len()));
+        }
+        let (c, mac) = data.split_at(n);
+
+        // Check MAC prior to decryption
+        let mac_iv = cbc_mac(&self.mac_key, &self.mac_iv, c);
+        if mac_iv[BLOCK_LEN - MAC_LEN..] != *mac {
+            return Err(ScpError::InvalidMac);
+        }
+
+        // Decrypt data
+        let mut b = c.to_vec();
+        let enc_iv = cbc_decrypt(&self.enc_key, &self.enc_iv, &mut b);
+
+        // Strip padding
+        let p = match b.iter().rposition(|v| *v != 0) {
+            Some(i) if b[i] == PAD_MARKER => i,
+            _ => return Err(ScpError::InvalidPadding),
+        };
+        b.truncate(p);
+
+        self.enc_iv = enc_iv;
+        self.mac_iv = mac_iv;
+
+        Ok(b)
+    }
+
+    /// Wrap the body of an APDU request, returning a [GenericApdu] with the
+    /// original (cleartext) header
+    pub fn wrap_req<'a, REQ: ApduReq<'a>>(&mut self, req: &REQ) -> Result<GenericApdu, ScpError> {
+        let mut buff = alloc::vec![0u8; req.encode_len()?];
+        let n = req.encode(&mut buff)?;
+
+        Ok(GenericApdu {
+            header: req.header(),
+            data: self.wrap(&buff[..n]),
+        })
+    }
+}
+
+/// Derive a session key from the ECDH shared secret.
+///
+/// A private scalar is computed as `SHA-256(index || retry || secret)` (retrying until
+/// valid), with the key taken from `SHA-256` of the corresponding uncompressed public key.
+fn derive_key(secret: &[u8; 32], index: u32) -> [u8; 16] {
+    let mut retry = 0u8;
+
+    let d: NonZeroScalar = loop {
+        let h = Sha256::new()
+            .chain_update(index.to_be_bytes())
+            .chain_update([retry])
+            .chain_update(secret)
+            .finalize();
+
+        if let Some(d) = Option::from(NonZeroScalar::from_repr(h)) {
+            break d;
+        }
+        retry = retry.wrapping_add(1);
+    };
+
+    let p = (ProjectivePoint::GENERATOR * *d)
+        .to_affine()
+        .to_encoded_point(false);
+
+    let mut k = [0u8; 16];
+    k.copy_from_slice(&Sha256::digest(p.as_bytes())[..16]);
+    k
+}
+
+/// Encrypt block-aligned data in place, returning the final ciphertext block (next IV)
+fn cbc_encrypt(key: &[u8; 16], iv: &[u8; 16], data: &mut [u8]) -> [u8; 16] {
+    let mut c = cbc::Encryptor::<Aes128>::new(key.into(), iv.into());
+    for b in data.chunks_exact_mut(BLOCK_LEN) {
+        c.encrypt_block_mut(GenericArray::from_mut_slice(b));
+    }
+    last_block(data, iv)
+}
+
+/// Decrypt block-aligned data in place, returning the final ciphertext block (next IV)
+fn cbc_decrypt(key: &[u8; 16], iv: &[u8; 16], data: &mut [u8]) -> [u8; 16] {
+    let next = last_block(data, iv);
+    let mut c = cbc::Decryptor::<Aes128>::new(key.into(), iv.into());
+    for b in data.chunks_exact_mut(BLOCK_LEN) {
+        c.decrypt_block_mut(GenericArray::from_mut_slice(b));
+    }
+    next
+}
+
+/// Compute the CBC-MAC of block-aligned data, returning the final block
+fn cbc_mac(key: &[u8; 16], iv: &[u8; 16], data: &[u8]) -> [u8; 16] {
+    let mut b = data.to_vec();
+    cbc_encrypt(key, iv, &mut b)
+}
+
+/// Fetch the final block of data, or `iv` where data is empty
+fn last_block(data: &[u8], iv: &[u8; 16]) -> [u8; 16] {
+    let mut b = *iv;
+    if let Some(l) = data.rchunks_exact(BLOCK_LEN).next() {
+        b.copy_from_slice(l);
+    }
+    b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apdus::RunAppReq;
+
+    #[test]
+    fn wrap_unwrap() {
+        let mut host = ScpChannel::new(&[0x11; 32]);
+        let mut device = host.clone();
+
+        // Data is padded to whole blocks with an appended MAC
+        for (d, n) in [(&[][..], 16), (&[0xaa; 15][..], 16), (&[0xbb; 16][..], 32)] {
+            let w = host.wrap(d);
+            assert_eq!(w.len(), n + MAC_LEN);
+            assert_eq!(device.unwrap(&w).unwrap(), d);
+        }
+
+        // IVs are chained, so repeated data is wrapped differently
+        let (a, b) = (host.wrap(&[0x01]), host.wrap(&[0x01]));
+        assert_ne!(a, b);
+        assert_eq!(device.unwrap(&a).unwrap(), [0x01]);
+        assert_eq!(device.unwrap(&b).unwrap(), [0x01]);
+
+        // Responses use the same (chained) channel state
+        let r = device.wrap(b"OK");
+        assert_eq!(host.unwrap(&r).unwrap(), b"OK");
+        assert!(host.unwrap(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unwrap_errors() {
+        let mut host = ScpChannel::new(&[0x22; 32]);
+        let mut device = host.clone();
+
+        let mut w = host.wrap(&[0x01, 0x02, 0x03]);
+        assert_eq!(
+            device.unwrap(&w[..w.len() - 1]),
+            Err(ScpError::InvalidLength(w.len() - 1))
+        );
+        assert_eq!(device.unwrap(&[0u8; 14]), Err(ScpError::InvalidLength(14)));
+
+        // Modified data fails MAC validation without updating channel state
+        w[0] ^= 0x01;
+        assert_eq!(device.unwrap(&w), Err(ScpError::InvalidMac));
+        w[0] ^= 0x01;
+        assert_eq!(device.unwrap(&w).unwrap(), [0x01, 0x02, 0x03]);
+
+        // Channels with mismatched keys fail
+        let w = host.wrap(&[0x01]);
+        assert_eq!(
+            ScpChannel::new(&[0x33; 32]).unwrap(&w),
+            Err(ScpError::InvalidMac)
+        );
+    }
+
+    #[test]
+    fn wrap_req() {
+        let mut host = ScpChannel::new(&[0x44; 32]);
+        let mut device = host.clone();
+
+        let a = host.wrap_req(&RunAppReq::new("BTC")).unwrap();
+        assert_eq!((a.header.cla, a.header.ins), (0xe0, 0xd8));
+        assert_eq!(a.data.len(), BLOCK_LEN + MAC_LEN);
+        assert_eq!(device.unwrap(&a.data).unwrap(), b"BTC");
+    }
+
+    #[test]
+    fn session_keys() {
+        let c = ScpChannel::new(&[0x55; 32]);
+        assert_ne!(c.enc_key, c.mac_key);
+        assert_eq!(c, ScpChannel::new(&[0x55; 32]));
+        assert_ne!(c, ScpChannel::new(&[0x56; 32]));
+    }
+}
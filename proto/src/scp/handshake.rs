@@ -0,0 +1,423 @@
+//! Secure channel handshake state machine, authenticating host and device certificate
+//! chains and establishing an [ScpChannel]
+
+use k256::{
+    ecdsa::{signature::Signer, signature::Verifier, Signature, SigningKey, VerifyingKey},
+    elliptic_curve::sec1::ToEncodedPoint,
+    PublicKey, SecretKey,
+};
+use sha2::{Digest, Sha256};
+
+use super::{ScpChannel, ScpError};
+use crate::{
+    apdus::{DeviceCert, GetCertReq, InitAuthReq, InitAuthResp, MutualAuthReq},
+    Vec,
+};
+
+/// Signed data role for the host signer certificate
+const ROLE_SIGNER: u8 = 0x01;
+
+/// Signed data role for the device certificate
+const ROLE_DEVICE: u8 = 0x02;
+
+/// Signed data role for the host ephemeral certificate
+const ROLE_HOST_EPHEMERAL: u8 = 0x11;
+
+/// Signed data role for the device ephemeral certificate
+const ROLE_DEVICE_EPHEMERAL: u8 = 0x12;
+
+/// Host signer, used to certify the host ephemeral key
+#[derive(Clone, Debug)]
+pub struct HostSigner {
+    key: SigningKey,
+    signature: Signature,
+}
+
+impl HostSigner {
+    /// Create a new host signer from a signing key and the issuer signature over
+    /// the signer certificate (see [HostSigner::signed_data])
+    pub fn new(key: SigningKey, signature: Signature) -> Self {
+        Self { key, signature }
+    }
+
+    /// Create a self-signed host signer, accepted by devices in development
+    /// (custom CA) configurations
+    pub fn self_signed(key: SigningKey) -> Self {
+        let signature = key.sign(&Self::signed_data(key.verifying_key()));
+        Self { key, signature }
+    }
+
+    /// Fetch the data signed by the issuer to certify a signer public key
+    pub fn signed_data(public_key: &VerifyingKey) -> Vec<u8> {
+        let p = public_key.to_encoded_point(false);
+
+        let mut d = Vec::with_capacity(1 + p.len());
+        d.push(ROLE_SIGNER);
+        d.extend_from_slice(p.as_bytes());
+        d
+    }
+}
+
+/// Handshake states
+#[derive(Clone, Debug)]
+enum State {
+    /// Awaiting [InitAuthResp]
+    Init,
+    /// Awaiting the device certificate
+    DeviceCert { device_nonce: [u8; 8] },
+    /// Awaiting the device ephemeral certificate
+    DeviceEphemeral {
+        device_nonce: [u8; 8],
+        device_key: VerifyingKey,
+    },
+    /// Awaiting mutual authentication
+    MutualAuth { device_ephemeral: PublicKey },
+}
+
+/// Secure channel handshake, validating the device certificate chain against
+/// an issuer key and deriving the session [ScpChannel].
+///
+/// Handshake steps must be performed in order, with the corresponding APDUs
+/// exchanged with the device between each step:
+/// 1. [ScpHandshake::init_auth_req] / [ScpHandshake::handle_init_auth]
+/// 2. [ScpHandshake::host_certs], submitted via [ValidateCertReq](crate::apdus::ValidateCertReq)
+/// 3. [ScpHandshake::get_cert_req] / [ScpHandshake::handle_device_cert], for both device certificates
+/// 4. [ScpHandshake::mutual_auth_req] then [ScpHandshake::finish]
+#[derive(Clone, Debug)]
+pub struct ScpHandshake {
+    issuer: VerifyingKey,
+    signer: HostSigner,
+    ephemeral: SecretKey,
+    host_nonce: [u8; 8],
+    state: State,
+}
+
+impl ScpHandshake {
+    /// Create a new handshake using the provided host ephemeral key and nonce.
+    ///
+    /// `issuer` is the public key used to validate the device certificate, selected
+    /// by the device target and batch serial (see [InitAuthResp]). Ephemeral keys and
+    /// nonces must be freshly generated for each handshake.
+    pub fn new(
+        issuer: VerifyingKey,
+        signer: HostSigner,
+        ephemeral: SecretKey,
+        host_nonce: [u8; 8],
+    ) -> Self {
+        Self {
+            issuer,
+            signer,
+            ephemeral,
+            host_nonce,
+            state: State::Init,
+        }
+    }
+
+    /// Fetch the [InitAuthReq] for the handshake
+    pub fn init_auth_req(&self) -> InitAuthReq {
+        InitAuthReq::new(self.host_nonce)
+    }
+
+    /// Handle the [InitAuthResp], storing the device nonce
+    pub fn handle_init_auth(&mut self, resp: &InitAuthResp) -> Result<(), ScpError> {
+        match self.state {
+            State::Init => {
+                self.state = State::DeviceCert {
+                    device_nonce: resp.device_nonce,
+                };
+                Ok(())
+            }
+            _ => Err(ScpError::InvalidState),
+        }
+    }
+
+    /// Fetch the encoded host signer and ephemeral certificates, to be submitted
+    /// in order via [ValidateCertReq](crate::apdus::ValidateCertReq) (with the final
+    /// ephemeral certificate flagged as `last`)
+    pub fn host_certs(&self) -> Result<[Vec<u8>; 2], ScpError> {
+        let device_nonce = match &self.state {
+            State::DeviceCert { device_nonce } => device_nonce,
+            _ => return Err(ScpError::InvalidState),
+        };
+
+        // Signer certificate, issued by the host issuer
+        let signer_key = self.signer.key.verifying_key().to_encoded_point(false);
+        let signer_cert = encode_cert(signer_key.as_bytes(), &self.signer.signature);
+
+        // Ephemeral certificate, signed by the host signer and bound to the session nonces
+        let ephemeral_key = self.ephemeral.public_key().to_encoded_point(false);
+
+        let mut d = Vec::with_capacity(17 + ephemeral_key.len());
+        d.push(ROLE_HOST_EPHEMERAL);
+        d.extend_from_slice(&self.host_nonce);
+        d.extend_from_slice(device_nonce);
+        d.extend_from_slice(ephemeral_key.as_bytes());
+
+        let signature: Signature = self.signer.key.sign(&d);
+        let ephemeral_cert = encode_cert(ephemeral_key.as_bytes(), &signature);
+
+        Ok([signer_cert, ephemeral_cert])
+    }
+
+    /// Fetch the [GetCertReq] for the next expected device certificate
+    pub fn get_cert_req(&self) -> Result<GetCertReq, ScpError> {
+        match self.state {
+            State::DeviceCert { .. } => Ok(GetCertReq::new(false)),
+            State::DeviceEphemeral { .. } => Ok(GetCertReq::new(true)),
+            _ => Err(ScpError::InvalidState),
+        }
+    }
+
+    /// Handle a device certificate, validating the signature against the issuer
+    /// (for the device certificate) or the device key (for the ephemeral certificate)
+    pub fn handle_device_cert(&mut self, cert: &DeviceCert) -> Result<(), ScpError> {
+        if cert.is_empty() {
+            return Err(ScpError::InvalidCertificate);
+        }
+
+        let public_key =
+            PublicKey::from_sec1_bytes(cert.public_key).map_err(|_| ScpError::InvalidKey)?;
+
+        match &self.state {
+            State::DeviceCert { device_nonce } => {
+                let d = [&[ROLE_DEVICE][..], cert.header, cert.public_key].concat();
+                verify(&self.issuer, &d, cert.signature)?;
+
+                self.state = State::DeviceEphemeral {
+                    device_nonce: *device_nonce,
+                    device_key: public_key.into(),
+                };
+            }
+            State::DeviceEphemeral {
+                device_nonce,
+                device_key,
+            } => {
+                let d = [
+                    &[ROLE_DEVICE_EPHEMERAL][..],
+                    device_nonce,
+                    &self.host_nonce,
+                    cert.public_key,
+                ]
+                .concat();
+                verify(device_key, &d, cert.signature)?;
+
+                self.state = State::MutualAuth {
+                    device_ephemeral: public_key,
+                };
+            }
+            _ => return Err(ScpError::InvalidState),
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the [MutualAuthReq] once device certificates have been validated
+    pub fn mutual_auth_req(&self) -> Result<MutualAuthReq, ScpError> {
+        match self.state {
+            State::MutualAuth { .. } => Ok(MutualAuthReq::new()),
+            _ => Err(ScpError::InvalidState),
+        }
+    }
+
+    /// Complete the handshake following a successful [MutualAuthReq], deriving the
+    /// shared secret and returning the established [ScpChannel]
+    pub fn finish(self) -> Result<ScpChannel, ScpError> {
+        let device_ephemeral = match self.state {
+            State::MutualAuth { device_ephemeral } => device_ephemeral,
+            _ => return Err(ScpError::InvalidState),
+        };
+
+        Ok(ScpChannel::new(&shared_secret(
+            &self.ephemeral,
+            &device_ephemeral,
+        )))
+    }
+}
+
+/// Compute the ECDH shared secret, SHA-256 of the compressed shared point
+fn shared_secret(secret: &SecretKey, public: &PublicKey) -> [u8; 32] {
+    let p = (public.to_projective() * *secret.to_nonzero_scalar()).to_affine();
+    Sha256::digest(p.to_encoded_point(true).as_bytes()).into()
+}
+
+/// Encode a host certificate as length-prefixed public key and (DER) signature fields
+fn encode_cert(public_key: &[u8], signature: &Signature) -> Vec<u8> {
+    let s = signature.to_der();
+
+    let mut c = Vec::with_capacity(2 + public_key.len() + s.len());
+    c.push(public_key.len() as u8);
+    c.extend_from_slice(public_key);
+    c.push(s.len() as u8);
+    c.extend_from_slice(s.as_bytes());
+    c
+}
+
+/// Verify a (DER) certificate signature, accepting non-normalised signatures
+/// as issued by devices
+fn verify(key: &VerifyingKey, data: &[u8], signature: &[u8]) -> Result<(), ScpError> {
+    let s = Signature::from_der(signature).map_err(|_| ScpError::InvalidSignature)?;
+    let s = s.normalize_s().unwrap_or(s);
+
+    key.verify(data, &s).map_err(|_| ScpError::InvalidSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apdus::{DeviceCert, InitAuthResp};
+
+    /// Device side of the handshake for testing
+    struct Device {
+        device_key: SigningKey,
+        ephemeral: SecretKey,
+        nonce: [u8; 8],
+    }
+
+    /// Split an encoded host certificate into public key and signature fields
+    fn decode_cert(c: &[u8]) -> (&[u8], &[u8]) {
+        let (k, n) = crate::prefixed_field(c, "public_key", 0).unwrap();
+        let (s, _) = crate::prefixed_field(c, "signature", n).unwrap();
+        (k, s)
+    }
+
+    fn key(v: u8) -> SigningKey {
+        SigningKey::from_bytes(&[v; 32].into()).unwrap()
+    }
+
+    fn setup() -> (ScpHandshake, Device, Vec<u8>, Signature) {
+        let issuer = key(0x01);
+        let device_key = key(0x02);
+
+        // Device certificate, issued by the issuer
+        let header = [0x00, 0x00, 0x00, 0x01];
+        let device_pub = device_key.verifying_key().to_encoded_point(false);
+        let d = [&[ROLE_DEVICE][..], &header, device_pub.as_bytes()].concat();
+        let sig: Signature = issuer.sign(&d);
+
+        let hs = ScpHandshake::new(
+            *issuer.verifying_key(),
+            HostSigner::self_signed(key(0x03)),
+            SecretKey::from_bytes(&[0x04; 32].into()).unwrap(),
+            [0xaa; 8],
+        );
+        let dev = Device {
+            device_key,
+            ephemeral: SecretKey::from_bytes(&[0x05; 32].into()).unwrap(),
+            nonce: [0xbb; 8],
+        };
+
+        (hs, dev, [&header[..], device_pub.as_bytes()].concat(), sig)
+    }
+
+    #[test]
+    fn handshake() {
+        let (mut hs, dev, cert, sig) = setup();
+
+        // Exchange nonces
+        assert_eq!(hs.init_auth_req(), InitAuthReq::new([0xaa; 8]));
+        assert_eq!(hs.host_certs(), Err(ScpError::InvalidState));
+        hs.handle_init_auth(&InitAuthResp {
+            batch_serial: [0x00, 0x00, 0x00, 0x01],
+            device_nonce: dev.nonce,
+        })
+        .unwrap();
+
+        // Host certificates are signed by the signer and bound to the session nonces
+        let [signer_cert, ephemeral_cert] = hs.host_certs().unwrap();
+        let (k, s) = decode_cert(&signer_cert);
+        let signer = VerifyingKey::from_sec1_bytes(k).unwrap();
+        verify(&signer, &HostSigner::signed_data(&signer), s).unwrap();
+
+        let (k, s) = decode_cert(&ephemeral_cert);
+        let d = [&[ROLE_HOST_EPHEMERAL][..], &[0xaa; 8], &dev.nonce, k].concat();
+        verify(&signer, &d, s).unwrap();
+        let host_ephemeral = PublicKey::from_sec1_bytes(k).unwrap();
+
+        // Device certificate
+        assert_eq!(hs.get_cert_req(), Ok(GetCertReq::new(false)));
+        let der = sig.to_der();
+        hs.handle_device_cert(&DeviceCert::new(&cert[..4], &cert[4..], der.as_bytes()))
+            .unwrap();
+
+        // Device ephemeral certificate, signed by the device key
+        assert_eq!(hs.get_cert_req(), Ok(GetCertReq::new(true)));
+        let eph_pub = dev.ephemeral.public_key().to_encoded_point(false);
+        let d = [
+            &[ROLE_DEVICE_EPHEMERAL][..],
+            &dev.nonce,
+            &[0xaa; 8],
+            eph_pub.as_bytes(),
+        ]
+        .concat();
+        let s: Signature = dev.device_key.sign(&d);
+        hs.handle_device_cert(&DeviceCert::new(
+            &[],
+            eph_pub.as_bytes(),
+            s.to_der().as_bytes(),
+        ))
+        .unwrap();
+
+        // Commit and check both sides derive the same channel
+        assert_eq!(hs.mutual_auth_req(), Ok(MutualAuthReq::new()));
+        let mut host = hs.finish().unwrap();
+        let mut device = ScpChannel::new(&shared_secret(&dev.ephemeral, &host_ephemeral));
+
+        let w = host.wrap(&[0x01, 0x02]);
+        assert_eq!(device.unwrap(&w).unwrap(), [0x01, 0x02]);
+    }
+
+    #[test]
+    fn handshake_invalid_certs() {
+        let (mut hs, dev, cert, sig) = setup();
+
+        assert_eq!(hs.get_cert_req(), Err(ScpError::InvalidState));
+        hs.handle_init_auth(&InitAuthResp {
+            batch_serial: [0x00; 4],
+            device_nonce: dev.nonce,
+        })
+        .unwrap();
+
+        let der = sig.to_der();
+
+        // Modified device certificates are rejected
+        let mut header = cert[..4].to_vec();
+        header[0] ^= 0x01;
+        assert_eq!(
+            hs.handle_device_cert(&DeviceCert::new(&header, &cert[4..], der.as_bytes())),
+            Err(ScpError::InvalidSignature)
+        );
+        assert_eq!(
+            hs.handle_device_cert(&DeviceCert::new(&cert[..4], &cert[5..], der.as_bytes())),
+            Err(ScpError::InvalidKey)
+        );
+        assert_eq!(
+            hs.handle_device_cert(&DeviceCert::default()),
+            Err(ScpError::InvalidCertificate)
+        );
+
+        // Ephemeral certificates not bound to the session nonces are rejected
+        hs.handle_device_cert(&DeviceCert::new(&cert[..4], &cert[4..], der.as_bytes()))
+            .unwrap();
+
+        let eph_pub = dev.ephemeral.public_key().to_encoded_point(false);
+        let d = [
+            &[ROLE_DEVICE_EPHEMERAL][..],
+            &[0xcc; 8],
+            &[0xaa; 8],
+            eph_pub.as_bytes(),
+        ]
+        .concat();
+        let s: Signature = dev.device_key.sign(&d);
+        assert_eq!(
+            hs.handle_device_cert(&DeviceCert::new(
+                &[],
+                eph_pub.as_bytes(),
+                s.to_der().as_bytes()
+            )),
+            Err(ScpError::InvalidSignature)
+        );
+
+        assert_eq!(hs.mutual_auth_req(), Err(ScpError::InvalidState));
+        assert_eq!(hs.finish().unwrap_err(), ScpError::InvalidState);
+    }
+}
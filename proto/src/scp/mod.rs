@@ -0,0 +1,88 @@
+//! Sans-IO secure channel protocol (SCP) for the Ledger manager (enabled with `scp` feature).
+//!
+//! The secure channel authenticates the host and device via certificate chains
+//! over ephemeral secp256k1 keys, then wraps APDU bodies using AES-128-CBC with
+//! a CBC-MAC, as required for application install and firmware tooling.
+//!
+//! [ScpHandshake] drives the authentication flow (see [genuine](crate::apdus::InitAuthReq)
+//! APDUs), producing an [ScpChannel] for wrapping commands and unwrapping responses.
+//! No IO is performed, so these may be used with any transport.
+//!
+//! ```no_run
+//! # fn exchange(_req: &[u8]) -> Vec<u8> { unimplemented!() }
+//! use ledger_proto::{
+//!     apdus::{DeviceCert, GetCertReq, InitAuthResp, MutualAuthReq, ValidateCertReq},
+//!     scp::{k256::ecdsa::{SigningKey, VerifyingKey}, k256::SecretKey, HostSigner, ScpHandshake},
+//!     ApduEncoder, Decode,
+//! };
+//!
+//! # let (issuer, signer, ephemeral, nonce): (VerifyingKey, SigningKey, SecretKey, [u8; 8]) = unimplemented!();
+//! let mut hs = ScpHandshake::new(issuer, HostSigner::self_signed(signer), ephemeral, nonce);
+//!
+//! // Exchange nonces
+//! let r = exchange(&ApduEncoder::encode_vec(&hs.init_auth_req()).unwrap());
+//! hs.handle_init_auth(&InitAuthResp::decode(&r).unwrap().0).unwrap();
+//!
+//! // Submit host certificates
+//! let [signer_cert, ephemeral_cert] = hs.host_certs().unwrap();
+//! exchange(&ApduEncoder::encode_vec(&ValidateCertReq::new(&signer_cert, false)).unwrap());
+//! exchange(&ApduEncoder::encode_vec(&ValidateCertReq::new(&ephemeral_cert, true)).unwrap());
+//!
+//! // Fetch and validate device certificates
+//! for next in [false, true] {
+//!     let r = exchange(&ApduEncoder::encode_vec(&GetCertReq::new(next)).unwrap());
+//!     hs.handle_device_cert(&DeviceCert::decode(&r).unwrap().0).unwrap();
+//! }
+//!
+//! // Commit the channel and wrap subsequent APDUs
+//! exchange(&ApduEncoder::encode_vec(&MutualAuthReq::new()).unwrap());
+//! let channel = hs.finish().unwrap();
+//! ```
+
+/// Re-export of [k256] for key types used by the secure channel
+pub use k256;
+
+mod channel;
+pub use channel::ScpChannel;
+
+mod handshake;
+pub use handshake::{HostSigner, ScpHandshake};
+
+/// Secure channel error type
+#[derive(Copy, Clone, Debug, PartialEq, displaydoc::Display)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum ScpError {
+    /// Unexpected handshake state
+    InvalidState,
+
+    /// Invalid public key encoding
+    InvalidKey,
+
+    /// Invalid certificate encoding
+    InvalidCertificate,
+
+    /// Certificate signature verification failed
+    InvalidSignature,
+
+    /// Invalid wrapped data length {0}
+    InvalidLength(usize),
+
+    /// Wrapped data MAC mismatch
+    InvalidMac,
+
+    /// Invalid wrapped data padding
+    InvalidPadding,
+
+    /// APDU encoding failed: {0}
+    Apdu(crate::ApduError),
+}
+
+/// [core::error::Error] implementation for [ScpError] without the `std` feature
+#[cfg(not(feature = "std"))]
+impl core::error::Error for ScpError {}
+
+impl From<crate::ApduError> for ScpError {
+    fn from(e: crate::ApduError) -> Self {
+        Self::Apdu(e)
+    }
+}
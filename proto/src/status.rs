@@ -1,9 +1,91 @@
-/// Device status codes (two bytes, trailing response data)
-///
-/// Replicated from: https://github.com/LedgerHQ/ledger-live/blob/develop/libs/ledgerjs/packages/errors/src/index.ts#L212
-#[derive(Copy, Clone, Debug, displaydoc::Display, num_enum::TryFromPrimitive)]
-#[repr(u16)]
-pub enum StatusCode {
+/// Define [StatusCode] variants, generating name and description lookups from the
+/// variant identifiers and doc comments
+macro_rules! status_codes {
+    ($(#[doc = $doc:literal] $name:ident = $code:literal,)*) => {
+        /// Device status codes (two bytes, trailing response data)
+        ///
+        /// Replicated from: https://github.com/LedgerHQ/ledger-live/blob/develop/libs/ledgerjs/packages/errors/src/index.ts#L212
+        ///
+        /// Conversions from `u16` are infallible, with unrecognised codes mapped to [StatusCode::Unknown].
+        /// Use `u16::from(code)` to fetch the raw status word.
+        ///
+        /// Codes are displayed with their description and raw value, parsed from variant names
+        /// (or `0x`-prefixed hex values), and serialised as the numeric status word.
+        ///
+        /// ```
+        /// use ledger_proto::StatusCode;
+        ///
+        /// let s = StatusCode::ClaNotSupported;
+        /// assert_eq!(s.to_string(), "APDU class not supported (0x6e00)");
+        /// assert_eq!("ClaNotSupported".parse(), Ok(s));
+        /// assert_eq!("0x1234".parse(), Ok(StatusCode::Unknown(0x1234)));
+        /// ```
+        #[derive(
+            Copy,
+            Clone,
+            Debug,
+            PartialEq,
+            Eq,
+            Hash,
+            num_enum::FromPrimitive,
+            num_enum::IntoPrimitive,
+        )]
+        #[cfg_attr(
+            feature = "serde",
+            derive(serde::Serialize, serde::Deserialize),
+            serde(from = "u16", into = "u16")
+        )]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+        #[repr(u16)]
+        #[non_exhaustive]
+        pub enum StatusCode {
+            $(
+                #[doc = $doc]
+                $name = $code,
+            )*
+            /// Unrecognised status
+            #[num_enum(catch_all)]
+            Unknown(u16),
+        }
+
+        impl StatusCode {
+            /// Fetch the status code name (the variant identifier)
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Self::$name => stringify!($name),)*
+                    Self::Unknown(_) => "Unknown",
+                }
+            }
+
+            /// Fetch a human-readable description of the status code
+            pub fn description(&self) -> &'static str {
+                match self {
+                    $(Self::$name => $doc.trim_start(),)*
+                    Self::Unknown(_) => "Unrecognised status",
+                }
+            }
+        }
+
+        /// Parse a [StatusCode] from a variant name or `0x`-prefixed hex status word
+        impl core::str::FromStr for StatusCode {
+            type Err = ParseStatusError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $(stringify!($name) => Ok(Self::$name),)*
+                    _ => s
+                        .strip_prefix("0x")
+                        .and_then(|v| u16::from_str_radix(v, 16).ok())
+                        .map(Self::from)
+                        .ok_or(ParseStatusError),
+                }
+            }
+        }
+    };
+}
+
+status_codes! {
     /// Access condition not fulfilled
     AccessConditionNotFulfilled = 0x9804,
     /// Algorithm not supported
@@ -82,4 +164,319 @@ pub enum StatusCode {
     UserRefusedOnDevice = 0x5501,
     /// Not enough space
     NotEnoughSpace = 0x5102,
+    /// Not enough space (alternate)
+    NotEnoughSpace2 = 0x5103,
+    /// Application not found or invalid context
+    AppNotFoundOrInvalidContext = 0x5123,
+    /// Application not found
+    AppNotFound = 0x6807,
+    /// Data invalid
+    DataInvalid = 0x6984,
+    /// AES key generation failed
+    GenAesKeyFailed = 0x5419,
+    /// Internal crypto operation failed
+    InternalCryptoOperationFailed = 0x541a,
+    /// Internal AES CMAC computation failed
+    InternalComputeAesCmacFailed = 0x541b,
+    /// Application storage encryption failed
+    EncryptAppStorageFailed = 0x541c,
+    /// PIN not set
+    PinNotSet = 0x5502,
+    /// Incorrect target ID
+    IncorrectTargetId = 0x6484,
+    /// Invalid backup state
+    InvalidBackupState = 0x6642,
+    /// Invalid restore state
+    InvalidRestoreState = 0x6643,
+    /// Invalid application name length
+    InvalidAppNameLength = 0x670a,
+    /// Invalid backup length
+    InvalidBackupLength = 0x6733,
+    /// Invalid chunk length
+    InvalidChunkLength = 0x6734,
+    /// Invalid backup header
+    InvalidBackupHeader = 0x684a,
+}
+
+/// [core::fmt::Display] implementation for [StatusCode], description followed by the raw status word
+/// (with remaining attempts for `0x63cX` PIN statuses)
+impl core::fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let sw = u16::from(*self);
+        match self.pin_remaining_attempts() {
+            Some(n) => write!(f, "{n} PIN attempts remaining (0x{sw:04x})"),
+            None => write!(f, "{} (0x{sw:04x})", self.description()),
+        }
+    }
+}
+
+/// Unrecognised status code name
+#[derive(Copy, Clone, Debug, PartialEq, displaydoc::Display)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ParseStatusError;
+
+/// [core::error::Error] implementation for [ParseStatusError] without the `std` feature
+#[cfg(not(feature = "std"))]
+impl core::error::Error for ParseStatusError {}
+
+/// Status categories, allowing callers to select retry / UX behaviour
+/// without matching on individual codes
+impl StatusCode {
+    /// Check whether the status indicates success (`0x9000`)
+    pub fn is_success(&self) -> bool {
+        *self == Self::Ok
+    }
+
+    /// Check whether the status is an ISO 7816-4 warning (`0x62XX` / `0x63XX`),
+    /// where response data may still be returned
+    pub fn is_warning(&self) -> bool {
+        matches!(u16::from(*self) >> 8, 0x62 | 0x63)
+    }
+
+    /// Fetch the remaining PIN attempts for `0x63cX` statuses, encoded in the low nibble.
+    ///
+    /// Only `0x63c0` maps to [StatusCode::PinRemainingAttempts], with other counts
+    /// decoded as [StatusCode::Unknown].
+    pub fn pin_remaining_attempts(&self) -> Option<u8> {
+        let sw = u16::from(*self);
+        match sw & 0xfff0 {
+            0x63c0 => Some((sw & 0x000f) as u8),
+            _ => None,
+        }
+    }
+
+    /// Check whether the status indicates the device is locked (PIN entry required)
+    ///
+    /// Some firmware versions report a locked device via [StatusCode::SecurityStatusNotSatisfied].
+    pub fn is_locked(&self) -> bool {
+        matches!(self, Self::LockedDevice | Self::SecurityStatusNotSatisfied)
+    }
+
+    /// Check whether the status requires user action on the device (unlocking,
+    /// onboarding, or a request refused by the user), after which the request
+    /// may be retried
+    pub fn is_user_action(&self) -> bool {
+        self.is_locked()
+            || matches!(
+                self,
+                Self::UserRefusedOnDevice
+                    | Self::ConditionsOfUseNotSatisfied
+                    | Self::DeviceNotOnboarded
+                    | Self::DeviceNotOnboarded2
+                    | Self::PinNotSet
+            )
+    }
+
+    /// Check whether the status indicates a fatal device error, where retrying
+    /// the request is not expected to succeed
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            Self::Halted
+                | Self::TechnicalProblem
+                | Self::MemoryProblem
+                | Self::CodeBlocked
+                | Self::GenAesKeyFailed
+                | Self::InternalCryptoOperationFailed
+                | Self::InternalComputeAesCmacFailed
+                | Self::EncryptAppStorageFailed
+        )
+    }
+}
+
+/// Application-specific status codes, resolved ahead of shared [StatusCode]
+/// values via [Status].
+///
+/// This allows application SDKs built on `ledger-proto` to surface their own
+/// status word meanings.
+///
+/// ```
+/// use ledger_proto::{AppStatus, Status, StatusCode};
+///
+/// #[derive(Copy, Clone, PartialEq, Debug)]
+/// enum EthStatus {
+///     InvalidPath,
+/// }
+///
+/// impl TryFrom<u16> for EthStatus {
+///     type Error = ();
+///
+///     fn try_from(sw: u16) -> Result<Self, ()> {
+///         match sw {
+///             0x6a15 => Ok(Self::InvalidPath),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// impl AppStatus for EthStatus {}
+///
+/// assert_eq!(EthStatus::resolve(0x6a15), Status::App(EthStatus::InvalidPath));
+/// assert_eq!(EthStatus::resolve(0x6985), Status::Shared(StatusCode::ConditionsOfUseNotSatisfied));
+/// ```
+pub trait AppStatus: TryFrom<u16> {
+    /// Resolve a status word, trying application codes before falling back to [StatusCode]
+    fn resolve(sw: u16) -> Status<Self>
+    where
+        Self: Sized,
+    {
+        Status::from(sw)
+    }
+}
+
+/// Status word resolved to an application-specific ([AppStatus]) or shared ([StatusCode]) value
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Status<A> {
+    /// Application-specific status
+    App(A),
+    /// Shared status (unrecognised codes map to [StatusCode::Unknown])
+    Shared(StatusCode),
+}
+
+impl<A> Status<A> {
+    /// Fetch the application-specific status, if matched
+    pub fn app(&self) -> Option<&A> {
+        match self {
+            Self::App(a) => Some(a),
+            Self::Shared(_) => None,
+        }
+    }
+
+    /// Fetch the shared status, if no application-specific status matched
+    pub fn shared(&self) -> Option<StatusCode> {
+        match self {
+            Self::App(_) => None,
+            Self::Shared(s) => Some(*s),
+        }
+    }
+}
+
+impl<A: AppStatus> From<u16> for Status<A> {
+    fn from(sw: u16) -> Self {
+        match A::try_from(sw) {
+            Ok(a) => Self::App(a),
+            Err(_) => Self::Shared(StatusCode::from(sw)),
+        }
+    }
+}
+
+impl<A: core::fmt::Display> core::fmt::Display for Status<A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::App(a) => write!(f, "{a}"),
+            Self::Shared(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_conversions() {
+        assert_eq!(StatusCode::from(0x9000), StatusCode::Ok);
+        assert_eq!(u16::from(StatusCode::LockedDevice), 0x5515);
+
+        assert_eq!(StatusCode::from(0x1234), StatusCode::Unknown(0x1234));
+        assert_eq!(u16::from(StatusCode::Unknown(0x1234)), 0x1234);
+    }
+
+    #[test]
+    fn status_display_parse() {
+        let s = StatusCode::LockedDevice;
+        assert_eq!(s.name(), "LockedDevice");
+        assert_eq!(s.description(), "Device locked");
+        assert_eq!(s.to_string(), "Device locked (0x5515)");
+        assert_eq!(
+            StatusCode::Unknown(0x1234).to_string(),
+            "Unrecognised status (0x1234)"
+        );
+
+        assert_eq!("LockedDevice".parse(), Ok(s));
+        assert_eq!("0x9000".parse(), Ok(StatusCode::Ok));
+        assert_eq!("0x1234".parse(), Ok(StatusCode::Unknown(0x1234)));
+        assert_eq!("Locked".parse::<StatusCode>(), Err(ParseStatusError));
+        assert_eq!("0x12345".parse::<StatusCode>(), Err(ParseStatusError));
+
+        // Names round-trip for all known codes
+        for sw in 0..=u16::MAX {
+            let s = StatusCode::from(sw);
+            if !matches!(s, StatusCode::Unknown(_)) {
+                assert_eq!(s.name().parse(), Ok(s));
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn status_serde() {
+        for s in [StatusCode::Ok, StatusCode::Unknown(0x1234)] {
+            let v = serde_json::to_string(&s).unwrap();
+            assert_eq!(v, u16::from(s).to_string());
+            assert_eq!(serde_json::from_str::<StatusCode>(&v).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn status_categories() {
+        assert!(StatusCode::Ok.is_success());
+        assert!(!StatusCode::from(0x6985).is_success());
+
+        assert!(StatusCode::from(0x6310).is_warning());
+        assert!(StatusCode::GpAuthFailed.is_warning());
+        assert!(!StatusCode::IncorrectData.is_warning());
+
+        assert_eq!(
+            StatusCode::PinRemainingAttempts.pin_remaining_attempts(),
+            Some(0)
+        );
+        assert_eq!(StatusCode::from(0x63c2).pin_remaining_attempts(), Some(2));
+        assert_eq!(StatusCode::from(0x63cf).pin_remaining_attempts(), Some(15));
+        assert_eq!(StatusCode::from(0x63d2).pin_remaining_attempts(), None);
+        assert_eq!(StatusCode::GpAuthFailed.pin_remaining_attempts(), None);
+        assert_eq!(
+            StatusCode::from(0x63c2).to_string(),
+            "2 PIN attempts remaining (0x63c2)"
+        );
+
+        assert!(StatusCode::LockedDevice.is_locked());
+        assert!(StatusCode::LockedDevice.is_user_action());
+        assert!(StatusCode::UserRefusedOnDevice.is_user_action());
+        assert!(!StatusCode::InsNotSupported.is_user_action());
+
+        assert!(StatusCode::TechnicalProblem.is_fatal());
+        assert!(!StatusCode::LockedDevice.is_fatal());
+        assert!(!StatusCode::Unknown(0x1234).is_fatal());
+    }
+
+    #[test]
+    fn app_status() {
+        #[derive(Copy, Clone, PartialEq, Debug)]
+        struct Custom(u8);
+
+        impl TryFrom<u16> for Custom {
+            type Error = ();
+
+            fn try_from(sw: u16) -> Result<Self, ()> {
+                match sw >> 8 {
+                    0xb0 => Ok(Self(sw as u8)),
+                    _ => Err(()),
+                }
+            }
+        }
+
+        impl AppStatus for Custom {}
+
+        let s = Custom::resolve(0xb012);
+        assert_eq!((s.app(), s.shared()), (Some(&Custom(0x12)), None));
+
+        let s = Status::<Custom>::from(0x5515);
+        assert_eq!(s.shared(), Some(StatusCode::LockedDevice));
+        assert_eq!(
+            Status::<Custom>::from(0x1234).shared(),
+            Some(StatusCode::Unknown(0x1234))
+        );
+    }
 }
@@ -0,0 +1,203 @@
+//! APDU test helpers, for validating encoders and decoders against known-good
+//! (golden) captures.
+//!
+//! Mismatches panic with a hex diff of the expected and actual encodings, marking
+//! the first differing byte. The [assert_golden!](crate::assert_golden) macro wraps
+//! these helpers with a stack buffer of [BUFF_LEN] bytes:
+//!
+//! ```
+//! use ledger_proto::{assert_golden, apdus::{AppInfoReq, AppInfoResp, AppFlags}};
+//!
+//! // Full request capture (header, length, data)
+//! assert_golden!(req: AppInfoReq {}, &[0xb0, 0x01, 0x00, 0x00, 0x00]);
+//!
+//! // Full response capture (data, status word)
+//! assert_golden!(
+//!     resp: AppInfoResp::new("Ethereum", "1.10.4", AppFlags::empty()),
+//!     &[
+//!         0x01, 0x08, 0x45, 0x74, 0x68, 0x65, 0x72, 0x65, 0x75, 0x6d, 0x06, 0x31, 0x2e,
+//!         0x31, 0x30, 0x2e, 0x34, 0x01, 0x00, 0x90, 0x00,
+//!     ]
+//! );
+//! ```
+
+use core::fmt::{Display, Formatter};
+
+use encdec::{EncDec, Encode};
+
+use crate::{split_status, ApduEncoder, ApduError, ApduReq};
+
+/// Buffer length used by [assert_golden!](crate::assert_golden), call the helper
+/// functions directly with a larger buffer for oversized APDUs
+pub const BUFF_LEN: usize = 4096;
+
+/// Helper to test round-trip encode / decode for APDUs
+pub fn encode_decode<'a, A: EncDec<'a, ApduError> + PartialEq>(buff: &'a mut [u8], a: A) {
+    // Test encoding
+    let n = a.encode(buff).unwrap();
+
+    // Test decoding
+    let (a1, n1) = A::decode(&buff[..n]).unwrap();
+
+    // Compare results
+    assert_eq!(n1, n);
+    assert_eq!(a1, a);
+}
+
+/// Assert an APDU body encodes to the expected bytes
+pub fn assert_encoded<E: Encode<Error = ApduError>>(buff: &mut [u8], a: &E, expected: &[u8]) {
+    let n = match a.encode(buff) {
+        Ok(n) => n,
+        Err(e) => panic!("APDU encoding failed: {e:?} (object: {a:?})"),
+    };
+
+    check_bytes(expected, &buff[..n]);
+}
+
+/// Assert an APDU body encodes to, and decodes from, the expected bytes
+pub fn assert_golden<'a, A: EncDec<'a, ApduError> + PartialEq>(
+    buff: &mut [u8],
+    a: &A,
+    expected: &'a [u8],
+) {
+    // Check encoding
+    assert_encoded(buff, a, expected);
+
+    // Check decoding
+    let (a1, n) = match A::decode(expected) {
+        Ok(v) => v,
+        Err(e) => panic!("APDU decoding failed: {e:?} (data: {})", Hex(expected)),
+    };
+
+    assert_eq!(n, expected.len(), "APDU decode length mismatch");
+    assert_eq!(&a1, a, "APDU decode mismatch");
+}
+
+/// Assert a request APDU encodes to the expected command (header, length, data
+/// and Le where specified).
+///
+/// This checks encoding only, as request objects with header-carried parameters
+/// can not be recovered from the APDU body.
+pub fn assert_golden_req<'a, A: ApduReq<'a>>(buff: &mut [u8], a: &A, expected: &[u8]) {
+    let n = match ApduEncoder::encode(a, buff) {
+        Ok(n) => n,
+        Err(e) => panic!("APDU encoding failed: {e:?} (object: {a:?})"),
+    };
+
+    check_bytes(expected, &buff[..n]);
+}
+
+/// Assert a response APDU encodes to, and decodes from, the expected response
+/// (data and status word), with an OK (`0x9000`) status
+pub fn assert_golden_resp<'a, A: EncDec<'a, ApduError> + PartialEq>(
+    buff: &mut [u8],
+    a: &A,
+    expected: &'a [u8],
+) {
+    let (data, status) = match split_status(expected) {
+        Ok(v) => v,
+        Err(_) => panic!("APDU response missing status (data: {})", Hex(expected)),
+    };
+
+    assert!(
+        status.is_success(),
+        "APDU response status {:#06x}",
+        u16::from(status)
+    );
+
+    assert_golden(buff, a, data);
+}
+
+/// Assert APDU objects match golden captures, using a stack buffer of [BUFF_LEN] bytes.
+///
+/// - `assert_golden!(a, bytes)` checks an APDU body, see [testing::assert_golden](crate::testing::assert_golden)
+/// - `assert_golden!(req: a, bytes)` checks a full command, see [testing::assert_golden_req](crate::testing::assert_golden_req)
+/// - `assert_golden!(resp: a, bytes)` checks a full response, see [testing::assert_golden_resp](crate::testing::assert_golden_resp)
+#[macro_export]
+macro_rules! assert_golden {
+    (req: $a:expr, $expected:expr) => {
+        $crate::testing::assert_golden_req(&mut [0u8; $crate::testing::BUFF_LEN], &$a, $expected)
+    };
+    (resp: $a:expr, $expected:expr) => {
+        $crate::testing::assert_golden_resp(&mut [0u8; $crate::testing::BUFF_LEN], &$a, $expected)
+    };
+    ($a:expr, $expected:expr) => {
+        $crate::testing::assert_golden(&mut [0u8; $crate::testing::BUFF_LEN], &$a, $expected)
+    };
+}
+
+/// Compare encoded bytes, panicking with a hex diff on mismatch
+fn check_bytes(expected: &[u8], actual: &[u8]) {
+    let offset = match expected.iter().zip(actual).position(|(e, a)| e != a) {
+        Some(i) => i,
+        None if expected.len() != actual.len() => expected.len().min(actual.len()),
+        None => return,
+    };
+
+    panic!(
+        "APDU encoding mismatch at offset {offset} (expected {} bytes, got {})\n  expected: {}\n    actual: {}\n            {:>w$}",
+        expected.len(),
+        actual.len(),
+        Hex(expected),
+        Hex(actual),
+        "^^",
+        w = offset * 3 + 2,
+    );
+}
+
+/// Space separated hex display helper
+struct Hex<'a>(&'a [u8]);
+
+impl<'a> Display for Hex<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{b:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        apdus::{AppFlags, AppInfoReq, AppInfoResp, ExitAppReq, GetResponseReq},
+        fixtures::{APP_INFO_BITCOIN, EXIT_APP},
+    };
+
+    #[test]
+    fn golden_vectors() {
+        assert_golden!(req: AppInfoReq {}, APP_INFO_BITCOIN.cmd);
+        assert_golden!(
+            resp: AppInfoResp::new("Bitcoin", "2.1.3", AppFlags::from_bits_retain(0x02)),
+            APP_INFO_BITCOIN.resp
+        );
+        assert_golden!(req: ExitAppReq {}, EXIT_APP.cmd);
+
+        // Le is appended to requests
+        assert_golden!(req: GetResponseReq::new(0x10), &[0x00, 0xc0, 0x00, 0x00, 0x10]);
+    }
+
+    #[test]
+    #[should_panic(expected = "mismatch at offset 3")]
+    fn golden_mismatch() {
+        assert_golden!(
+            AppInfoResp::new("Bitcoin", "2.1.3", AppFlags::empty()),
+            &[0x01, 0x07, 0x42, 0x00]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "status 0x6e00")]
+    fn golden_status() {
+        assert_golden!(resp: AppInfoReq {}, &[0x6e, 0x00]);
+    }
+
+    #[test]
+    fn hex_diff() {
+        assert_eq!(format!("{}", Hex(&[0xb0, 0x01, 0x00])), "b0 01 00");
+    }
+}
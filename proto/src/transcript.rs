@@ -0,0 +1,262 @@
+//! APDU transcripts (enabled with `alloc` feature), ordered sequences of command /
+//! response exchanges using the common hex transcript format:
+//!
+//! ```text
+//! => b001000000
+//! <= 0105424f4c4f5305312e312e309000
+//! ```
+//!
+//! Commands contain the full APDU (header, length, data) and responses include the
+//! trailing status word. Blank lines and comments (starting with `#` or `//`) are ignored
+//! when parsing.
+//!
+//! ```
+//! use ledger_proto::Transcript;
+//!
+//! let t: Transcript = "=> b001000000\n<= 6e00".parse().unwrap();
+//! assert_eq!(t.entries()[0].status, 0x6e00);
+//!
+//! assert_eq!(t.to_string(), "=> b001000000\n<= 6e00\n");
+//! ```
+
+use core::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{builder::parse_hex, split_status, Vec};
+
+/// Command prefix for transcript lines
+pub const COMMAND_PREFIX: &str = "=>";
+
+/// Response prefix for transcript lines
+pub const RESPONSE_PREFIX: &str = "<=";
+
+/// Transcript entry, containing a command and the associated response
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TranscriptEntry {
+    /// Command APDU (header, length, data)
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub command: Vec<u8>,
+    /// Response data (excluding status word)
+    #[cfg_attr(feature = "serde", serde(with = "hex::serde"))]
+    pub response: Vec<u8>,
+    /// Response status word
+    pub status: u16,
+}
+
+impl TranscriptEntry {
+    /// Create a transcript entry from a command and a raw response (including the status word)
+    pub fn new(command: &[u8], response: &[u8]) -> Result<Self, TranscriptError> {
+        let (data, status) = split_status(response).map_err(|_| TranscriptError::MissingStatus)?;
+
+        Ok(Self {
+            command: command.to_vec(),
+            response: data.to_vec(),
+            status: u16::from(status),
+        })
+    }
+
+    /// Fetch the raw response (including the status word)
+    pub fn raw_response(&self) -> Vec<u8> {
+        let mut r = self.response.clone();
+        r.extend_from_slice(&self.status.to_be_bytes());
+        r
+    }
+}
+
+/// APDU transcript, an ordered sequence of [TranscriptEntry] exchanges
+#[derive(Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transcript {
+    entries: Vec<TranscriptEntry>,
+}
+
+/// Transcript parsing errors, line numbers start from 1
+#[derive(Copy, Clone, PartialEq, Debug, displaydoc::Display)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
+pub enum TranscriptError {
+    /// Invalid hex on line {0}
+    InvalidHex(usize),
+
+    /// Unrecognised line {0} (expected `=>` or `<=` prefix)
+    InvalidLine(usize),
+
+    /// Command on line {0} without a response
+    MissingResponse(usize),
+
+    /// Response on line {0} without a command
+    UnexpectedResponse(usize),
+
+    /// Response missing status word
+    MissingStatus,
+}
+
+/// [core::error::Error] implementation for [TranscriptError] without the `std` feature
+#[cfg(not(feature = "std"))]
+impl core::error::Error for TranscriptError {}
+
+impl Transcript {
+    /// Create a new empty transcript
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an entry to the transcript
+    pub fn push(&mut self, entry: TranscriptEntry) {
+        self.entries.push(entry);
+    }
+
+    /// Fetch transcript entries
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// Iterate over transcript entries
+    pub fn iter(&self) -> core::slice::Iter<'_, TranscriptEntry> {
+        self.entries.iter()
+    }
+
+    /// Check whether the transcript is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Fetch the number of transcript entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+impl From<Vec<TranscriptEntry>> for Transcript {
+    fn from(entries: Vec<TranscriptEntry>) -> Self {
+        Self { entries }
+    }
+}
+
+impl<'a> IntoIterator for &'a Transcript {
+    type Item = &'a TranscriptEntry;
+
+    type IntoIter = core::slice::Iter<'a, TranscriptEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Parse a transcript from the hex transcript format
+impl FromStr for Transcript {
+    type Err = TranscriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = Vec::new();
+        let mut command: Option<(usize, Vec<u8>)> = None;
+
+        for (i, line) in s.lines().enumerate() {
+            let n = i + 1;
+            let line = line.trim();
+
+            // Skip blank lines and comments
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+                continue;
+            }
+
+            // Parse line prefix and hex data (ignoring whitespace)
+            let (is_command, data) = if let Some(d) = line.strip_prefix(COMMAND_PREFIX) {
+                (true, d)
+            } else if let Some(d) = line.strip_prefix(RESPONSE_PREFIX) {
+                (false, d)
+            } else {
+                return Err(TranscriptError::InvalidLine(n));
+            };
+
+            let data: alloc::string::String = data.chars().filter(|c| !c.is_whitespace()).collect();
+            let data = parse_hex(&data).map_err(|_| TranscriptError::InvalidHex(n))?;
+
+            match (is_command, command.take()) {
+                (true, None) => command = Some((n, data)),
+                (true, Some((c, _))) => return Err(TranscriptError::MissingResponse(c)),
+                (false, Some((_, cmd))) => entries.push(TranscriptEntry::new(&cmd, &data)?),
+                (false, None) => return Err(TranscriptError::UnexpectedResponse(n)),
+            }
+        }
+
+        if let Some((c, _)) = command {
+            return Err(TranscriptError::MissingResponse(c));
+        }
+
+        Ok(Self { entries })
+    }
+}
+
+/// Emit a transcript in the hex transcript format (one line per command / response)
+impl Display for Transcript {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for e in &self.entries {
+            write!(f, "{COMMAND_PREFIX} ")?;
+            for b in &e.command {
+                write!(f, "{b:02x}")?;
+            }
+            writeln!(f)?;
+
+            write!(f, "{RESPONSE_PREFIX} ")?;
+            for b in &e.response {
+                write!(f, "{b:02x}")?;
+            }
+            writeln!(f, "{:04x}", e.status)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{APP_INFO_BOLOS, DEVICE_INFO_NANOS};
+
+    #[test]
+    fn transcript_round_trip() {
+        let mut t = Transcript::new();
+        for v in [APP_INFO_BOLOS, DEVICE_INFO_NANOS] {
+            t.push(TranscriptEntry::new(v.cmd, v.resp).unwrap());
+        }
+        assert_eq!(t.entries()[0].status, 0x9000);
+        assert_eq!(t.entries()[1].raw_response(), DEVICE_INFO_NANOS.resp);
+
+        let s = t.to_string();
+        assert!(s.starts_with("=> b001000000\n<= 0105424f4c4f5305312e312e309000\n"));
+        assert_eq!(s.parse::<Transcript>().unwrap(), t);
+    }
+
+    #[test]
+    fn transcript_parse() {
+        let t: Transcript =
+            "# app info\n\n=> B0 01 00 00 00\n<= 6E 00\n// exit\n=>b0a7000000\n<=9000"
+                .parse()
+                .unwrap();
+        assert_eq!(t.len(), 2);
+        assert_eq!(t.entries()[0].command, &[0xb0, 0x01, 0x00, 0x00, 0x00]);
+        assert_eq!(t.entries()[0].response, &[] as &[u8]);
+        assert_eq!(t.entries()[1].status, 0x9000);
+    }
+
+    #[test]
+    fn transcript_errors() {
+        let p = |s: &str| s.parse::<Transcript>().unwrap_err();
+
+        assert_eq!(p("=> zz\n<= 9000"), TranscriptError::InvalidHex(1));
+        assert_eq!(p("-> b0\n<= 9000"), TranscriptError::InvalidLine(1));
+        assert_eq!(
+            p("=> b0\n=> b0\n<= 9000"),
+            TranscriptError::MissingResponse(1)
+        );
+        assert_eq!(
+            p("=> b0\n<= 9000\n=> b0"),
+            TranscriptError::MissingResponse(3)
+        );
+        assert_eq!(p("\n<= 9000"), TranscriptError::UnexpectedResponse(2));
+        assert_eq!(p("=> b0\n<= 90"), TranscriptError::MissingStatus);
+    }
+}